@@ -0,0 +1,295 @@
+// Copyright © 2023 Rak Laptudirm <rak@laptudirm.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fmt;
+
+use crate::chess::{Board, Color, Move, Outcome};
+use crate::engine::{Engine, SearchInfo, SearchLimits, Signals};
+use crate::pgn::Game;
+use crate::search::{self, Score, SearchEngine};
+
+/// AnnotatorOptions are the options of the analysis of a game.
+#[derive(Clone)]
+pub struct AnnotatorOptions {
+    /// limits are the limits of the search of each position.
+    pub limits: SearchLimits,
+
+    /// inaccuracy, mistake and blunder are the least centipawn losses of
+    /// moves which are judged as inaccuracies, mistakes and blunders.
+    pub inaccuracy: Score,
+    pub mistake: Score,
+    pub blunder: Score,
+}
+
+impl Default for AnnotatorOptions {
+    fn default() -> Self {
+        AnnotatorOptions {
+            limits: SearchLimits {
+                depth: Some(10),
+                ..SearchLimits::default()
+            },
+            inaccuracy: 50,
+            mistake: 100,
+            blunder: 300,
+        }
+    }
+}
+
+/// MAX_SCORE is the score which the scores of positions are clamped to when
+/// computing centipawn losses, so that missing a mate and missing a large
+/// material win are judged similarly.
+const MAX_SCORE: Score = 1000;
+
+/// Judgement is the judgement of a move which lost some of the advantage
+/// of the side which played it.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Judgement {
+    Inaccuracy,
+    Mistake,
+    Blunder,
+}
+
+impl Judgement {
+    /// nag returns the Numeric Annotation Glyph of the Judgement, which is
+    /// `?!` for inaccuracies, `?` for mistakes and `??` for blunders.
+    pub fn nag(self) -> u8 {
+        match self {
+            Judgement::Inaccuracy => 6,
+            Judgement::Mistake => 2,
+            Judgement::Blunder => 4,
+        }
+    }
+}
+
+impl fmt::Display for Judgement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Judgement::Inaccuracy => "inaccuracy",
+                Judgement::Mistake => "mistake",
+                Judgement::Blunder => "blunder",
+            }
+        )
+    }
+}
+
+/// MoveAnalysis is the analysis of a move of a game.
+#[derive(Clone)]
+pub struct MoveAnalysis {
+    /// chessmove is the move which was played.
+    pub chessmove: Move,
+
+    /// side is the side which played the move.
+    pub side: Color,
+
+    /// best_line is the best line found in the position before the move,
+    /// starting with the best move.
+    pub best_line: Vec<Move>,
+
+    /// best_score is the score of the position before the move, and score
+    /// is the score of the position after it, both from the point of view
+    /// of the side which played the move.
+    pub best_score: Score,
+    pub score: Score,
+
+    /// loss is the number of centipawns the move lost compared to the best
+    /// move, with the scores clamped to a thousand centipawns either way.
+    pub loss: Score,
+
+    /// judgement is the judgement of the move by its loss, if it is bad
+    /// enough to be judged.
+    pub judgement: Option<Judgement>,
+}
+
+impl MoveAnalysis {
+    /// best_move returns the best move in the position before the move, or
+    /// Move::NULL if none was found.
+    pub fn best_move(&self) -> Move {
+        self.best_line.first().copied().unwrap_or(Move::NULL)
+    }
+
+    /// accuracy returns the accuracy of the move, from 0 to 100 percent,
+    /// by the drop in the winning chances of the side which played it, like
+    /// the accuracy of Lichess.
+    pub fn accuracy(&self) -> f64 {
+        let drop = win_chance(self.best_score) - win_chance(self.score);
+        (103.1668 * (-0.04354 * drop.max(0.0)).exp() - 3.1669).clamp(0.0, 100.0)
+    }
+}
+
+/// PlayerStats are the statistics of the moves of a side in a game.
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+pub struct PlayerStats {
+    pub moves: usize,
+    pub inaccuracies: usize,
+    pub mistakes: usize,
+    pub blunders: usize,
+
+    /// average_loss is the average centipawn loss of the moves.
+    pub average_loss: f64,
+
+    /// accuracy is the average accuracy of the moves, from 0 to 100 percent.
+    pub accuracy: f64,
+}
+
+impl fmt::Display for PlayerStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} inaccuracies, {} mistakes, {} blunders, {:.0} average centipawn loss, {:.1}% accuracy",
+            self.inaccuracies, self.mistakes, self.blunders, self.average_loss, self.accuracy
+        )
+    }
+}
+
+/// GameAnalysis is the analysis of the moves of a game's mainline.
+#[derive(Clone, Default)]
+pub struct GameAnalysis {
+    /// moves are the analyses of the moves, in the order they were played.
+    pub moves: Vec<MoveAnalysis>,
+}
+
+impl GameAnalysis {
+    /// stats returns the statistics of the moves played by the given side.
+    pub fn stats(&self, side: Color) -> PlayerStats {
+        let mut stats = PlayerStats::default();
+        let (mut loss, mut accuracy) = (0, 0.0);
+
+        for analysis in self.moves.iter().filter(|analysis| analysis.side == side) {
+            stats.moves += 1;
+            loss += analysis.loss;
+            accuracy += analysis.accuracy();
+
+            match analysis.judgement {
+                Some(Judgement::Inaccuracy) => stats.inaccuracies += 1,
+                Some(Judgement::Mistake) => stats.mistakes += 1,
+                Some(Judgement::Blunder) => stats.blunders += 1,
+                None => {}
+            }
+        }
+
+        if stats.moves > 0 {
+            stats.average_loss = loss as f64 / stats.moves as f64;
+            stats.accuracy = accuracy / stats.moves as f64;
+        }
+
+        stats
+    }
+}
+
+/// Annotator analyzes the moves of games by searching the positions before
+/// and after each of them, and judging them by the centipawns they lose.
+pub struct Annotator {
+    options: AnnotatorOptions,
+    engine: SearchEngine,
+    signals: Signals,
+}
+
+impl Annotator {
+    /// new creates an Annotator with the given options.
+    pub fn new(options: AnnotatorOptions) -> Annotator {
+        Annotator {
+            options,
+            engine: SearchEngine::new(),
+            signals: Signals::new(),
+        }
+    }
+
+    /// analyze analyzes the moves of the given Game's mainline.
+    pub fn analyze(&mut self, game: &Game) -> GameAnalysis {
+        self.engine.new_game();
+
+        let mut board = game.start_board();
+        let (mut best_score, mut best_line) = self.search(&mut board);
+
+        let mut analysis = GameAnalysis::default();
+        for node in game.mainline() {
+            let side = board.side_to_move();
+            board.make_move(node.chessmove);
+
+            let (next_score, next_line) = self.search(&mut board);
+
+            // Playing the best move can't lose anything, even if the deeper
+            // search after it finds that it is worse than it seemed.
+            let score = -next_score;
+            let loss = if best_line.first() == Some(&node.chessmove) {
+                0
+            } else {
+                (best_score.clamp(-MAX_SCORE, MAX_SCORE) - score.clamp(-MAX_SCORE, MAX_SCORE))
+                    .max(0)
+            };
+
+            analysis.moves.push(MoveAnalysis {
+                chessmove: node.chessmove,
+                side,
+                best_line,
+                best_score,
+                score,
+                loss,
+                judgement: self.judge(loss),
+            });
+
+            (best_score, best_line) = (next_score, next_line);
+        }
+
+        analysis
+    }
+
+    /// judge returns the Judgement of a move which lost the given number of
+    /// centipawns, if it is bad enough to be judged.
+    fn judge(&self, loss: Score) -> Option<Judgement> {
+        if loss >= self.options.blunder {
+            Some(Judgement::Blunder)
+        } else if loss >= self.options.mistake {
+            Some(Judgement::Mistake)
+        } else if loss >= self.options.inaccuracy {
+            Some(Judgement::Inaccuracy)
+        } else {
+            None
+        }
+    }
+
+    /// search returns the score of the position on the Board from the point
+    /// of view of the side to move, along with its best line. Positions
+    /// where the game is over are scored by their outcome.
+    fn search(&mut self, board: &mut Board) -> (Score, Vec<Move>) {
+        match board.outcome() {
+            Some(Outcome::Win(winner)) if winner == board.side_to_move() => {
+                return (search::mate_in(0), Vec::new())
+            }
+            Some(Outcome::Win(_)) => return (search::mated_in(0), Vec::new()),
+            Some(Outcome::Draw) => return (search::DRAW, Vec::new()),
+            None => {}
+        }
+
+        let mut last = (search::DRAW, Vec::new());
+        self.signals.reset(false);
+        self.engine.search(
+            board.clone(),
+            self.options.limits,
+            &self.signals,
+            &mut |info: &SearchInfo| last = (info.score, info.pv.clone()),
+        );
+
+        last
+    }
+}
+
+/// win_chance returns the winning chances of a side with the given score,
+/// from 0 to 100 percent, by the model of Lichess.
+fn win_chance(score: Score) -> f64 {
+    let score = score.clamp(-MAX_SCORE, MAX_SCORE) as f64;
+    50.0 + 50.0 * (2.0 / (1.0 + (-0.00368208 * score).exp()) - 1.0)
+}
@@ -0,0 +1,8 @@
+// Non-namespaced modules.
+mod annotator;
+mod output;
+
+// Make the contents of the non-namespaced
+// modules public, so they can be accessed
+// without their parent namespace.
+pub use self::annotator::*;
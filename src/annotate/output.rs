@@ -0,0 +1,104 @@
+// Copyright © 2023 Rak Laptudirm <rak@laptudirm.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::chess::{Color, Move};
+use crate::pgn::{Eval, Game, MoveNode};
+use crate::search::{self, Score};
+
+use super::{GameAnalysis, MoveAnalysis};
+
+impl GameAnalysis {
+    /// annotate returns a copy of the given Game, which is the game that was
+    /// analyzed, with the analysis written into it like Lichess does: every
+    /// move gets an `[%eval]` annotation, and judged moves get a NAG, a
+    /// comment with their judgement, and a variation with the best line.
+    pub fn annotate(&self, game: &Game) -> Game {
+        let mut game = game.clone();
+        if game.tag("Annotator").is_none() {
+            game.tags
+                .push((String::from("Annotator"), String::from("mess")));
+        }
+
+        let mut board = game.start_board();
+        let mut nodes = game.moves_mut();
+        for analysis in &self.moves {
+            let Some(node) = nodes.first_mut() else {
+                break;
+            };
+
+            node.eval = eval(analysis);
+
+            if let Some(judgement) = analysis.judgement {
+                if !node.nags.contains(&judgement.nag()) {
+                    node.nags.push(judgement.nag());
+                }
+
+                let best_move = analysis.best_move();
+                if best_move != Move::NULL {
+                    let name = judgement.to_string();
+                    node.comments.push(format!(
+                        "{}{}. {} was best.",
+                        name[..1].to_uppercase(),
+                        &name[1..],
+                        board.to_san(best_move)
+                    ));
+
+                    nodes.push(line(&analysis.best_line));
+                }
+            }
+
+            board.make_move(analysis.chessmove);
+            nodes = &mut nodes[0].children;
+        }
+
+        game
+    }
+}
+
+/// eval returns the `[%eval]` annotation of the position after the analyzed
+/// move, from White's point of view, or None if the game is over after it.
+fn eval(analysis: &MoveAnalysis) -> Option<Eval> {
+    // Mates are counted in moves of the side to move after the move, which
+    // is the opponent of the side which played it.
+    let score: Score = -analysis.score;
+    let white_score = match analysis.side {
+        Color::White => -score,
+        _ => score,
+    };
+
+    if !search::is_mate(score) {
+        return Some(Eval::Centipawns(white_score));
+    }
+
+    match search::mate_moves(score) {
+        0 => None,
+        moves if analysis.side == Color::White => Some(Eval::Mate(-moves)),
+        moves => Some(Eval::Mate(moves)),
+    }
+}
+
+/// line returns the node of the first of the given moves, which is followed
+/// by the nodes of the rest of them.
+fn line(moves: &[Move]) -> MoveNode {
+    let mut nodes = moves
+        .iter()
+        .rev()
+        .map(|&chessmove| MoveNode::new(chessmove));
+    let mut first = nodes.next().expect("line should have at least one move");
+    for mut node in nodes {
+        node.children.push(first);
+        first = node;
+    }
+
+    first
+}
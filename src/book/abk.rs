@@ -0,0 +1,212 @@
+// Copyright © 2023 Rak Laptudirm <rak@laptudirm.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{fs::File, io, path::Path};
+
+use memmap2::Mmap;
+
+use crate::chess::{castling, Board, Move, MoveFlag, Piece, Square};
+
+use super::BookMove;
+
+/// Book is an Arena opening book which has been memory mapped from a file.
+///
+/// Unlike Polyglot books, Arena books store a tree of move sequences from
+/// the standard starting position instead of a table of position hashes,
+/// so a Board is looked up by replaying the moves made on it.
+pub struct Book {
+    data: Mmap,
+}
+
+/// Entry is a single move node in an Arena book's move tree.
+struct Entry {
+    source: Square,
+    target: Square,
+    promotion: Piece,
+
+    games: u32,
+    wins: u32,
+    losses: u32,
+
+    next_move: usize,
+    next_sibling: usize,
+}
+
+impl Book {
+    /// ENTRY_SIZE is the size of a single book entry in bytes.
+    const ENTRY_SIZE: usize = 28;
+
+    /// ROOT is the index of the first move from the starting position. The
+    /// entries before it are taken up by the book's header.
+    const ROOT: usize = 900;
+
+    /// open memory maps the Arena book at the given path.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Book> {
+        let file = File::open(path)?;
+
+        // Safety: the book file is only ever read from, and is expected to
+        // not be modified by other processes while it is mapped.
+        let data = unsafe { Mmap::map(&file)? };
+
+        if data.len() < (Book::ROOT + 1) * Book::ENTRY_SIZE {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "arena book is too small to contain a move tree",
+            ));
+        }
+
+        Ok(Book { data })
+    }
+
+    /// len returns the number of entries in the book, including the header.
+    pub fn len(&self) -> usize {
+        self.data.len() / Book::ENTRY_SIZE
+    }
+
+    /// is_empty checks if the book has no entries.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// probe returns the book moves for the current position on the board.
+    /// The Board needs to have been set up from the standard starting
+    /// position, since its move history is used to find the position.
+    /// Each move is weighted by the results it scored, counting a win as
+    /// two points and a draw as one. Moves which are not legal in the
+    /// position are skipped.
+    pub fn probe(&self, board: &mut Board) -> Vec<BookMove> {
+        // Walk down the move tree along the moves made on the Board.
+        let mut node = Book::ROOT;
+        for chessmove in board.move_history() {
+            let (source, target) = abk_squares(chessmove);
+            let promotion = promotion(chessmove);
+
+            let mut sibling = node;
+            loop {
+                let Some(entry) = self.entry(sibling) else {
+                    return Vec::new();
+                };
+
+                if entry.source == source && entry.target == target && entry.promotion == promotion
+                {
+                    node = entry.next_move;
+                    break;
+                }
+
+                sibling = entry.next_sibling;
+            }
+        }
+
+        let legal_moves = board.generate_legal_moves();
+
+        let mut book_moves = Vec::new();
+        let mut sibling = node;
+        while let Some(entry) = self.entry(sibling) {
+            sibling = entry.next_sibling;
+
+            let chessmove = legal_moves.iter().copied().find(|&chessmove| {
+                abk_squares(chessmove) == (entry.source, entry.target)
+                    && promotion(chessmove) == entry.promotion
+            });
+
+            if let Some(chessmove) = chessmove {
+                let draws = entry.games.saturating_sub(entry.wins + entry.losses);
+                let weight = entry.wins as u64 * 2 + draws as u64;
+
+                book_moves.push(BookMove {
+                    chessmove,
+                    weight: weight.min(u16::MAX as u64) as u16,
+                    learn: 0,
+                });
+            }
+        }
+
+        book_moves
+    }
+
+    /// entry decodes the book entry at the given index, if it is a valid
+    /// move tree entry.
+    fn entry(&self, index: usize) -> Option<Entry> {
+        if index < Book::ROOT || index >= self.len() {
+            return None;
+        }
+
+        let bytes = &self.data[index * Book::ENTRY_SIZE..(index + 1) * Book::ENTRY_SIZE];
+
+        // Arena entries are stored in little-endian byte order.
+        let u32_at =
+            |offset: usize| u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap());
+        let index_at = |offset: usize| {
+            let index = i32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap());
+            usize::try_from(index).unwrap_or(0)
+        };
+
+        // Promotions are encoded as 1 = rook, 2 = knight, 3 = bishop, and
+        // 4 = queen, and are negated for Black's promotions.
+        let promotion = match (bytes[2] as i8).unsigned_abs() {
+            1 => Piece::Rook,
+            2 => Piece::Knight,
+            3 => Piece::Bishop,
+            4 => Piece::Queen,
+            _ => Piece::None,
+        };
+
+        Some(Entry {
+            source: abk_square(bytes[0])?,
+            target: abk_square(bytes[1])?,
+            promotion,
+
+            games: u32_at(4),
+            wins: u32_at(8),
+            losses: u32_at(12),
+
+            next_move: index_at(20),
+            next_sibling: index_at(24),
+        })
+    }
+}
+
+/// abk_square converts an Arena square index, which counts up from a1, into
+/// a Square, which counts down from a8.
+fn abk_square(index: u8) -> Option<Square> {
+    if index as usize >= Square::N {
+        return None;
+    }
+
+    Some(Square::from(index ^ 0b111_000))
+}
+
+/// abk_squares returns the source and target squares of the given Move in
+/// the Arena encoding, where castling moves are encoded as the king moving
+/// to its target square instead of capturing its own rook.
+fn abk_squares(chessmove: Move) -> (Square, Square) {
+    let source = chessmove.source();
+    let target = chessmove.target();
+
+    if chessmove.flags() == MoveFlag::Castle {
+        let (king_target, _) = castling::SideColor::from_sqs(source, target).get_targets();
+        return (source, king_target);
+    }
+
+    (source, target)
+}
+
+/// promotion returns the promotion piece of the given Move, or Piece::None
+/// if the Move is not a promotion.
+fn promotion(chessmove: Move) -> Piece {
+    if chessmove.flags() == MoveFlag::Promotion {
+        chessmove.promot()
+    } else {
+        Piece::None
+    }
+}
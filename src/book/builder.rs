@@ -0,0 +1,195 @@
+// Copyright © 2023 Rak Laptudirm <rak@laptudirm.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{collections::HashMap, io};
+
+use crate::chess::{Board, Color, Move};
+use crate::pgn::{GameResult, Reader};
+
+use super::polyglot;
+
+/// Builder collects the moves played in a set of games and builds a Polyglot
+/// opening book out of them. Each book move is weighted by the results that
+/// it scored for the side which played it.
+pub struct Builder {
+    min_games: u32,
+    min_rating: u16,
+    max_plys: usize,
+    weights: [u64; 3],
+
+    stats: HashMap<(u64, u16), MoveStats>,
+}
+
+/// MoveStats stores the results a book move scored for the side to move.
+#[derive(Copy, Clone, Default)]
+struct MoveStats {
+    wins: u32,
+    draws: u32,
+    losses: u32,
+}
+
+impl MoveStats {
+    fn games(&self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+}
+
+impl Default for Builder {
+    fn default() -> Self {
+        Builder::new()
+    }
+}
+
+impl Builder {
+    /// new creates a Builder which adds every move from the first 32 plys of
+    /// each game to the book, with wins weighted 2, draws 1, and losses 0.
+    pub fn new() -> Builder {
+        Builder {
+            min_games: 1,
+            min_rating: 0,
+            max_plys: 32,
+            weights: [2, 1, 0],
+
+            stats: HashMap::new(),
+        }
+    }
+
+    /// min_games sets the minimum number of games a move needs to have been
+    /// played in for it to be added to the book.
+    pub fn min_games(mut self, games: u32) -> Builder {
+        self.min_games = games;
+        self
+    }
+
+    /// min_rating sets the minimum rating a player needs to have for their
+    /// moves to be added to the book. Players without a rating are treated
+    /// as having a rating of zero.
+    pub fn min_rating(mut self, rating: u16) -> Builder {
+        self.min_rating = rating;
+        self
+    }
+
+    /// max_plys sets the number of plys from the start of each game which
+    /// are added to the book.
+    pub fn max_plys(mut self, plys: usize) -> Builder {
+        self.max_plys = plys;
+        self
+    }
+
+    /// result_weights sets how much a win, a draw, and a loss for the side
+    /// which played a move add to the move's weight.
+    pub fn result_weights(mut self, win: u16, draw: u16, loss: u16) -> Builder {
+        self.weights = [win as u64, draw as u64, loss as u64];
+        self
+    }
+
+    /// add_game adds the moves of a game played from the given Board to the
+    /// book. The winner is Color::None if the game was drawn, and the player
+    /// ratings are indexed by their Colors.
+    pub fn add_game(
+        &mut self,
+        mut board: Board,
+        moves: &[Move],
+        winner: Color,
+        ratings: [u16; Color::N],
+    ) {
+        for &chessmove in moves.iter().take(self.max_plys) {
+            let stm = board.side_to_move();
+
+            if ratings[stm as usize] >= self.min_rating {
+                let key = polyglot::hash(board.position());
+                let stats = self
+                    .stats
+                    .entry((key, polyglot::encode_move(chessmove)))
+                    .or_default();
+
+                match winner {
+                    Color::None => stats.draws += 1,
+                    _ if winner == stm => stats.wins += 1,
+                    _ => stats.losses += 1,
+                }
+            }
+
+            board.make_move(chessmove);
+        }
+    }
+
+    /// add_pgn adds every game with a known result from the given PGN text to
+    /// the book, and returns the number of games added. Games which fail to
+    /// parse are skipped.
+    pub fn add_pgn(&mut self, pgn: &str) -> usize {
+        let mut added = 0;
+
+        for game in Reader::new(pgn).flatten() {
+            if game.result == GameResult::Unknown {
+                continue;
+            }
+
+            let rating = |name| game.tag(name).and_then(|elo| elo.parse().ok()).unwrap_or(0);
+            let ratings = [rating("WhiteElo"), rating("BlackElo")];
+
+            self.add_game(
+                game.start_board(),
+                &game.mainline_moves(),
+                game.result.winner(),
+                ratings,
+            );
+            added += 1;
+        }
+
+        added
+    }
+
+    /// write writes the book to the given writer in the Polyglot format, and
+    /// returns the number of entries written. Moves played in fewer than the
+    /// minimum number of games or with a zero weight are left out.
+    pub fn write<W: io::Write>(&self, mut writer: W) -> io::Result<usize> {
+        let mut entries: Vec<(u64, u16, u64)> = self
+            .stats
+            .iter()
+            .filter(|(_, stats)| stats.games() >= self.min_games)
+            .map(|(&(key, raw_move), stats)| {
+                let weight = stats.wins as u64 * self.weights[0]
+                    + stats.draws as u64 * self.weights[1]
+                    + stats.losses as u64 * self.weights[2];
+                (key, raw_move, weight)
+            })
+            .filter(|&(_, _, weight)| weight > 0)
+            .collect();
+
+        // Polyglot books are sorted by key, and the moves of each position
+        // are conventionally sorted by decreasing weight.
+        entries.sort_unstable_by(|a, b| a.0.cmp(&b.0).then(b.2.cmp(&a.2)).then(a.1.cmp(&b.1)));
+
+        for position in entries.chunk_by(|a, b| a.0 == b.0) {
+            // Scale down the weights of a position's moves if they are too
+            // large to fit in a Polyglot entry, while keeping their ratios.
+            let max_weight = position[0].2;
+
+            for &(key, raw_move, weight) in position {
+                let weight = if max_weight > u16::MAX as u64 {
+                    (weight * u16::MAX as u64 / max_weight).max(1)
+                } else {
+                    weight
+                };
+
+                writer.write_all(&key.to_be_bytes())?;
+                writer.write_all(&raw_move.to_be_bytes())?;
+                writer.write_all(&(weight as u16).to_be_bytes())?;
+                writer.write_all(&0u32.to_be_bytes())?;
+            }
+        }
+
+        Ok(entries.len())
+    }
+}
@@ -0,0 +1,32 @@
+// Copyright © 2023 Rak Laptudirm <rak@laptudirm.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::chess::Move;
+
+// Opening book formats.
+pub mod polyglot;
+
+#[cfg(feature = "abk")]
+pub mod abk;
+
+// Opening book tooling.
+pub mod builder;
+pub mod openings;
+
+/// BookMove is a move found in an opening book, along with its weight.
+#[derive(Copy, Clone)]
+pub struct BookMove {
+    pub chessmove: Move,
+    pub weight: u16,
+    pub learn: u32,
+}
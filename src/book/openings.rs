@@ -0,0 +1,215 @@
+// Copyright © 2023 Rak Laptudirm <rak@laptudirm.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{fs, io, path::Path, str::FromStr};
+
+use crate::chess::{Board, Position};
+use crate::pgn;
+
+/// Order is the order the positions of an OpeningSuite are used in.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Order {
+    /// Sequential uses the positions in the order they were loaded in.
+    Sequential,
+
+    /// Random uses the positions in a random order decided by the given
+    /// seed, and shuffles them again each time all of them have been used.
+    Random(u64),
+}
+
+/// OpeningSuite is a set of opening positions which games are started from,
+/// like in engine matches or self-play. It is an endless iterator over its
+/// positions, which starts over once all of them have been used.
+#[derive(Clone)]
+pub struct OpeningSuite {
+    positions: Vec<Position>,
+    order: Order,
+    next: usize,
+    rng: u64,
+}
+
+impl OpeningSuite {
+    /// new creates an OpeningSuite with the given positions, which are used
+    /// in the given order. The suite needs to have at least one position.
+    pub fn new(positions: Vec<Position>, order: Order) -> OpeningSuite {
+        assert!(!positions.is_empty(), "opening suites need a position");
+
+        let mut suite = OpeningSuite {
+            positions,
+            order,
+            next: 0,
+            rng: 0,
+        };
+
+        if let Order::Random(seed) = order {
+            // Xorshift generators can't be seeded with zero.
+            suite.rng = seed | 1;
+            suite.shuffle();
+        }
+
+        suite
+    }
+
+    /// load loads the opening positions from the file at the given path,
+    /// which is parsed as a PGN file if it has a pgn extension, and as a
+    /// list of FENs or EPDs otherwise. The positions of PGN games are the
+    /// ones after the given number of plys of their mainlines, or after
+    /// the whole mainline if it is shorter or no limit is given.
+    pub fn load<P: AsRef<Path>>(
+        path: P,
+        max_plys: Option<usize>,
+        order: Order,
+    ) -> io::Result<OpeningSuite> {
+        let path = path.as_ref();
+        let contents = fs::read_to_string(path)?;
+
+        let is_pgn = path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("pgn"));
+        let positions = if is_pgn {
+            parse_pgn(&contents, max_plys)?
+        } else {
+            parse_fens(&contents)?
+        };
+
+        if positions.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "no opening positions found",
+            ));
+        }
+
+        Ok(OpeningSuite::new(positions, order))
+    }
+
+    /// len returns the number of positions in the OpeningSuite.
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    /// is_empty checks if the OpeningSuite has no positions, which is never
+    /// the case.
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    /// positions returns the positions of the OpeningSuite, in the order
+    /// they are currently being used in.
+    pub fn positions(&self) -> &[Position] {
+        &self.positions
+    }
+
+    /// shuffle shuffles the positions with a Fisher-Yates shuffle.
+    fn shuffle(&mut self) {
+        for i in (1..self.positions.len()).rev() {
+            let j = (self.random() % (i as u64 + 1)) as usize;
+            self.positions.swap(i, j);
+        }
+    }
+
+    /// random returns the next number from the xorshift generator.
+    fn random(&mut self) -> u64 {
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 7;
+        self.rng ^= self.rng << 17;
+        self.rng
+    }
+}
+
+impl Default for OpeningSuite {
+    /// default returns an OpeningSuite with only the standard starting
+    /// position.
+    fn default() -> Self {
+        let start = Board::startpos();
+        OpeningSuite::new(vec![*start.position()], Order::Sequential)
+    }
+}
+
+impl Iterator for OpeningSuite {
+    type Item = Position;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.next == self.positions.len() {
+            self.next = 0;
+            if let Order::Random(_) = self.order {
+                self.shuffle();
+            }
+        }
+
+        self.next += 1;
+        Some(self.positions[self.next - 1])
+    }
+}
+
+/// parse_fens parses a list of positions with one FEN or EPD per line, where
+/// the operations of EPDs are ignored. Empty lines and lines starting with
+/// a `#` are skipped.
+fn parse_fens(list: &str) -> io::Result<Vec<Position>> {
+    let mut positions = Vec::new();
+    for (number, line) in list.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let invalid = || {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("invalid position on line {}", number + 1),
+            )
+        };
+
+        // EPDs don't have the move counters of FENs, and have operations
+        // after their position fields instead.
+        let fields: Vec<&str> = line.split(';').next().unwrap().split_whitespace().collect();
+        let is_fen =
+            fields.len() == 6 && fields[4..].iter().all(|field| field.parse::<u16>().is_ok());
+        let fen = match fields.get(..4) {
+            Some(_) if is_fen => fields.join(" "),
+            Some(position) => format!("{} 0 1", position.join(" ")),
+            None => return Err(invalid()),
+        };
+
+        let board = Board::from_str(&fen).map_err(|_| invalid())?;
+        positions.push(*board.position());
+    }
+
+    Ok(positions)
+}
+
+/// parse_pgn parses the positions after at most the given number of plys of
+/// the mainlines of the games in the given PGN.
+fn parse_pgn(pgn: &str, max_plys: Option<usize>) -> io::Result<Vec<Position>> {
+    let mut positions = Vec::new();
+    for (number, game) in pgn::Reader::new(pgn).enumerate() {
+        let game = game.map_err(|err| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("invalid game {}: {err}", number + 1),
+            )
+        })?;
+
+        let mut board = game.start_board();
+        for chessmove in game
+            .mainline_moves()
+            .into_iter()
+            .take(max_plys.unwrap_or(usize::MAX))
+        {
+            board.make_move(chessmove);
+        }
+
+        positions.push(*board.position());
+    }
+
+    Ok(positions)
+}
@@ -0,0 +1,417 @@
+// Copyright © 2023 Rak Laptudirm <rak@laptudirm.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+    io::{self, BufRead},
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use crate::chess::{Board, Color, Move, Outcome};
+use crate::engine::{Engine, SearchInfo, SearchLimits, Signals, TimeControl};
+use crate::pgn::GameResult;
+use crate::search;
+use crate::uci::format_move;
+
+/// Client runs the Chess Engine Communication Protocol, used by XBoard and
+/// WinBoard, for an Engine over the standard input and output, so that it
+/// can be used by older chess GUIs and internet chess server interfaces.
+pub struct Client<E: Engine> {
+    // The engine is moved into the search thread while a search is going
+    // on, and is given back by the thread along with the move it played.
+    engine: Option<E>,
+    search: Option<JoinHandle<(E, Option<Move>)>>,
+    signals: Arc<Signals>,
+
+    // Searches which are aborted don't play their best move.
+    abort: Arc<AtomicBool>,
+
+    board: Board,
+
+    // The color the engine is playing, or Color::None in force mode, where
+    // the engine only keeps track of the moves without playing any.
+    engine_color: Color,
+
+    // The time control of the game, with the clocks of the engine and its
+    // opponent, which are updated by the GUI before each of its searches.
+    time_control: Option<TimeControl>,
+    clocks: [Option<Duration>; 2],
+    depth: Option<u32>,
+
+    // Whether the engine's thinking output is sent to the GUI.
+    post: bool,
+}
+
+impl<E: Engine> Client<E> {
+    /// new creates a Client for the given Engine.
+    pub fn new(engine: E) -> Client<E> {
+        Client {
+            engine: Some(engine),
+            search: None,
+            signals: Arc::new(Signals::new()),
+            abort: Arc::new(AtomicBool::new(false)),
+
+            board: Board::startpos(),
+
+            engine_color: Color::Black,
+
+            time_control: None,
+            clocks: [None; 2],
+            depth: None,
+
+            post: false,
+        }
+    }
+
+    /// run runs the protocol loop until the `quit` command is received or
+    /// the standard input is closed.
+    pub fn run(mut self) {
+        for line in io::stdin().lock().lines() {
+            let Ok(line) = line else {
+                break;
+            };
+
+            // Take back the engine and play its move if its search has ended.
+            if self.search.as_ref().is_some_and(JoinHandle::is_finished) {
+                self.join_search();
+            }
+
+            let tokens: Vec<&str> = line.split_whitespace().collect();
+            let Some((&command, args)) = tokens.split_first() else {
+                continue;
+            };
+
+            match command {
+                "protover" => {
+                    let engine = self.engine();
+                    println!("feature myname=\"{}\"", engine.name());
+                    println!("feature setboard=1 usermove=1 ping=1 playother=1");
+                    println!("feature sigint=0 sigterm=0 colors=0 reuse=1 analyze=0");
+                    println!("feature done=1");
+                }
+
+                "new" => {
+                    self.abort_search();
+                    self.engine().new_game();
+
+                    self.board = Board::startpos();
+                    self.engine_color = Color::Black;
+                    self.depth = None;
+                    if let Some(TimeControl::MoveTime(_)) = self.time_control {
+                        self.time_control = None;
+                    }
+                }
+
+                "setboard" => {
+                    self.abort_search();
+                    match Board::from_str(&args.join(" ")) {
+                        Ok(board) => self.board = board,
+                        Err(err) => println!("tellusererror Illegal position: {err}"),
+                    }
+                }
+
+                "force" => {
+                    self.abort_search();
+                    self.engine_color = Color::None;
+                }
+
+                "go" => {
+                    self.abort_search();
+                    self.engine_color = self.board.side_to_move();
+                    self.start_search();
+                }
+
+                "playother" => {
+                    self.abort_search();
+                    self.engine_color = !self.board.side_to_move();
+                }
+
+                "usermove" => {
+                    self.abort_search();
+
+                    let user_move = args.first().copied().unwrap_or_default();
+                    match parse_move(&mut self.board, user_move) {
+                        Some(chessmove) => {
+                            self.board.make_move(chessmove);
+                            if self.board.side_to_move() == self.engine_color {
+                                self.start_search();
+                            }
+                        }
+
+                        None => println!("Illegal move: {user_move}"),
+                    }
+                }
+
+                "undo" => {
+                    self.abort_search();
+                    self.undo_moves(1);
+                }
+
+                "remove" => {
+                    self.abort_search();
+                    self.undo_moves(2);
+                }
+
+                "level" => {
+                    if let [mps, base, inc] = args {
+                        let base = parse_base_time(base);
+                        self.time_control = Some(TimeControl::new(
+                            mps.parse().unwrap_or(0),
+                            base,
+                            parse_seconds(inc),
+                        ));
+                        self.clocks = [Some(base); 2];
+                    }
+                }
+
+                "st" => {
+                    self.time_control = args
+                        .first()
+                        .map(|secs| TimeControl::MoveTime(parse_seconds(secs)));
+                }
+
+                "sd" => self.depth = args.first().and_then(|depth| depth.parse().ok()),
+
+                // Clock times are given in centiseconds.
+                "time" | "otim" => {
+                    let Some(centis) = args.first().and_then(|time| time.parse::<i64>().ok())
+                    else {
+                        continue;
+                    };
+
+                    let clock = (command == "otim") as usize;
+                    self.clocks[clock] = Some(Duration::from_millis(centis.max(0) as u64 * 10));
+                }
+
+                "?" => self.stop_search(),
+
+                "post" => self.post = true,
+                "nopost" => self.post = false,
+
+                "ping" => println!("pong {}", args.first().copied().unwrap_or_default()),
+
+                "quit" => break,
+
+                // Unknown commands and commands for unsupported features,
+                // like `hard` and `random`, are ignored.
+                _ => {}
+            }
+        }
+
+        self.abort_search();
+    }
+
+    /// engine returns the Client's Engine, aborting any ongoing search
+    /// to get it back from the search thread.
+    fn engine(&mut self) -> &mut E {
+        self.abort_search();
+        self.engine.as_mut().unwrap()
+    }
+
+    /// undo_moves takes back the given number of moves, if they have been
+    /// made since the starting position was set.
+    fn undo_moves(&mut self, count: usize) {
+        for _ in 0..count {
+            if self.board.last_move().is_some() {
+                self.board.undo_move();
+            }
+        }
+    }
+
+    /// start_search starts searching the current position on a separate
+    /// thread, which plays the best move once the search is over.
+    fn start_search(&mut self) {
+        let mut engine = self.engine.take().unwrap();
+
+        let mut board = self.board.clone();
+        let mut limits = match self.time_control {
+            Some(control) => {
+                let mut time = [Duration::ZERO; Color::N];
+                time[self.engine_color as usize] = self.clocks[0].unwrap_or(control.base());
+                time[!self.engine_color as usize] = self.clocks[1].unwrap_or(control.base());
+
+                control.search_limits(time, board.plys() as u32 / 2)
+            }
+
+            None => SearchLimits::default(),
+        };
+
+        limits.depth = self.depth;
+
+        let signals = Arc::clone(&self.signals);
+        let abort = Arc::clone(&self.abort);
+        signals.reset(false);
+        abort.store(false, Ordering::Relaxed);
+
+        let post = self.post;
+
+        self.search = Some(thread::spawn(move || {
+            let mut report = |info: &SearchInfo| {
+                if post {
+                    println!("{}", format_thinking(info));
+                }
+            };
+
+            let best_move = engine
+                .search(board.clone(), limits, &signals, &mut report)
+                .chessmove;
+            if abort.load(Ordering::Relaxed) || best_move == Move::NULL {
+                return (engine, None);
+            }
+
+            println!("move {}", format_move(best_move));
+
+            board.make_move(best_move);
+            if let Some(result) = game_result(&mut board) {
+                println!("{result}");
+            }
+
+            (engine, Some(best_move))
+        }));
+    }
+
+    /// stop_search stops the ongoing search, if any, making it play its
+    /// best move immediately.
+    fn stop_search(&mut self) {
+        if self.search.is_some() {
+            self.signals.stop();
+            self.join_search();
+        }
+    }
+
+    /// abort_search stops the ongoing search, if any, without playing its
+    /// best move, unless it was already played before the search stopped.
+    fn abort_search(&mut self) {
+        self.abort.store(true, Ordering::Relaxed);
+        self.stop_search();
+    }
+
+    /// join_search waits for the ongoing search to end, takes back the Engine
+    /// from the search thread, and makes the move it played on the Board.
+    fn join_search(&mut self) {
+        let search = self.search.take().expect("join search: no ongoing search");
+        let (engine, best_move) = search.join().expect("join search: search thread panicked");
+
+        self.engine = Some(engine);
+        if let Some(best_move) = best_move {
+            self.board.make_move(best_move);
+        }
+    }
+}
+
+/// parse_move finds the legal move on the Board described by the given move
+/// in coordinate notation, where castling is written as the king's move.
+fn parse_move(board: &mut Board, user_move: &str) -> Option<Move> {
+    board
+        .generate_legal_moves()
+        .into_iter()
+        .find(|&chessmove| format_move(chessmove) == user_move)
+}
+
+/// parse_seconds parses a possibly fractional number of seconds.
+fn parse_seconds(seconds: &str) -> Duration {
+    let seconds: f64 = seconds.parse().unwrap_or(0.0);
+    Duration::from_secs_f64(seconds.max(0.0))
+}
+
+/// parse_base_time parses the base time of a `level` command, which is
+/// given either in minutes, or in minutes and seconds separated by a colon.
+fn parse_base_time(base: &str) -> Duration {
+    let (minutes, seconds) = base.split_once(':').unwrap_or((base, "0"));
+    let minutes: u64 = minutes.parse().unwrap_or(0);
+    Duration::from_secs(minutes * 60) + parse_seconds(seconds)
+}
+
+/// format_thinking formats the given SearchInfo as a line of thinking output,
+/// where forced mates are scored as 100000 plus the number of moves to mate.
+fn format_thinking(info: &SearchInfo) -> String {
+    let score = if search::is_mate(info.score) {
+        let moves = search::mate_moves(info.score);
+        moves.signum() * 100000 + moves
+    } else {
+        info.score
+    };
+
+    let pv: Vec<String> = info
+        .pv
+        .iter()
+        .map(|&chessmove| format_move(chessmove))
+        .collect();
+
+    format!(
+        "{} {score} {} {} {}",
+        info.depth,
+        info.time.as_millis() / 10,
+        info.nodes,
+        pv.join(" ")
+    )
+}
+
+/// game_result returns the result command for the position on the Board if
+/// the game is over in it by the rules of the Board's Variant, or None if it
+/// isn't. The comment after the result gives the reason the game ended.
+fn game_result(board: &mut Board) -> Option<String> {
+    let outcome = board.outcome()?;
+    let has_legal_moves = board.has_legal_moves();
+
+    let reason = match outcome {
+        Outcome::Win(Color::White) if board.is_check() && !has_legal_moves => "White mates",
+        Outcome::Win(_) if board.is_check() && !has_legal_moves => "Black mates",
+        Outcome::Win(Color::White) => "White wins",
+        Outcome::Win(_) => "Black wins",
+        Outcome::Draw if !has_legal_moves => "Stalemate",
+        Outcome::Draw if board.is_50_move_draw() => "Draw by fifty move rule",
+        Outcome::Draw => "Draw",
+    };
+
+    Some(format!("{} {{{reason}}}", GameResult::from(outcome)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use crate::chess::variant;
+
+    use super::*;
+
+    #[test]
+    fn game_result_follows_the_outcome() {
+        let results = [
+            ("7k/8/6K1/8/8/8/8/5Q2 b - - 0 80", None),
+            ("7k/6Q1/6K1/8/8/8/8/8 b - - 0 80", Some("1-0 {White mates}")),
+            (
+                "7k/5Q2/6K1/8/8/8/8/8 b - - 0 80",
+                Some("1/2-1/2 {Stalemate}"),
+            ),
+            (
+                "7k/8/6K1/8/8/8/8/5Q2 b - - 100 80",
+                Some("1/2-1/2 {Draw by fifty move rule}"),
+            ),
+        ];
+
+        for (fen, expected) in results {
+            let mut board = Board::from_str(fen).unwrap();
+            assert_eq!(game_result(&mut board).as_deref(), expected, "{fen}");
+        }
+
+        let fen = "7k/8/8/8/3K4/8/8/8 b - - 0 1";
+        let mut board = Board::from_fen(fen, &variant::KingOfTheHill).unwrap();
+        assert_eq!(game_result(&mut board).as_deref(), Some("1-0 {White wins}"));
+    }
+}
@@ -20,8 +20,7 @@ use crate::chess;
 use crate::util::type_macros;
 
 use super::Color;
-use super::File;
-use super::Rank;
+use super::Direction;
 
 /// BitBoard represents a set of squares as a 64 bit bitset.
 #[derive(Copy, Clone, PartialEq, Eq, FromPrimitive)]
@@ -65,34 +64,32 @@ impl BitBoard {
 
     pub fn up(self, us: Color) -> Self {
         match us {
-            Color::White => self.north(),
-            Color::Black => self.south(),
             Color::None => self,
+            _ => self.shift(Direction::up(us)),
         }
     }
 
     pub fn down(self, us: Color) -> Self {
         match us {
-            Color::White => self.south(),
-            Color::Black => self.north(),
             Color::None => self,
+            _ => self.shift(Direction::down(us)),
         }
     }
 
     pub fn north(self) -> BitBoard {
-        (self & !BitBoard::rank(Rank::Eighth)) >> BitBoard(8)
+        self.shift(Direction::North)
     }
 
     pub fn south(self) -> BitBoard {
-        (self & !BitBoard::rank(Rank::First)) << BitBoard(8)
+        self.shift(Direction::South)
     }
 
     pub fn east(self) -> BitBoard {
-        (self & !BitBoard::file(File::H)) << BitBoard(1)
+        self.shift(Direction::East)
     }
 
     pub fn west(self) -> BitBoard {
-        (self & !BitBoard::file(File::A)) >> BitBoard(1)
+        self.shift(Direction::West)
     }
 
     pub fn reverse(self) -> BitBoard {
@@ -253,8 +250,7 @@ impl ops::Sub<chess::Square> for BitBoard {
 impl fmt::Display for BitBoard {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let mut string_rep = String::from("");
-        for square in 0..chess::Square::N {
-            let square = chess::Square::from(square);
+        for square in chess::Square::iter() {
             string_rep += if self.contains(square) { "1 " } else { "0 " };
 
             if square.file() == chess::File::H {
@@ -11,22 +11,29 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::{fmt, str::FromStr};
+use std::{error::Error, fmt, ops::ControlFlow, str::FromStr};
 
-use crate::chess::{zobrist, BitBoard, Color, ColoredPiece, File, Move, MoveFlag, Piece, Square};
+use crate::chess::{
+    zobrist, BitBoard, Color, ColoredPiece, Direction, File, Move, MoveFlag, MoveList, Outcome,
+    Piece, Square,
+};
 
-use super::{castling, moves, Mailbox, Rank, FEN};
+use super::{
+    castling, cuckoo, moves, variant, variant::Variant, FENParseError, IntegrityError, Mailbox,
+    Position, PositionError, Rank, FEN,
+};
+
+#[cfg(feature = "fixed-buffers")]
+use crate::util::array_vec::ArrayVec;
 
 use colored::Colorize;
 
+#[derive(Clone)]
 pub struct Board {
-    // 8x8 mailbox board representation for
-    // fast piece square lookup.
-    mailbox: Mailbox,
+    // The current position on the board.
+    pos: Position,
 
-    // BitBoard board representation.
-    color_bbs: [BitBoard; Color::N],
-    piece_bbs: [BitBoard; Piece::N],
+    // Occupancy BitBoards from the side to move's perspective.
     friends: BitBoard,
     enemies: BitBoard,
     occupied: BitBoard,
@@ -35,19 +42,9 @@ pub struct Board {
     pub checkers: BitBoard,
     pub check_nm: u32,
 
-    // Position metadata.
-    side_to_mv: Color,
-    pub plys_count: u16,
-    draw_clock: u8,
-    enp_target: Square,
-
-    // Game metadata.
-    is_fischer_random: bool,
-    castling_square_info: castling::Info,
-
-    hash: zobrist::Hash,
-
-    pub history: [BoardState; 1024],
+    // Irreversible info of the previous positions, one
+    // entry for each move made on the board, in order.
+    history: History,
 
     // Move generation specific info.
     pub check_mask: BitBoard,
@@ -55,9 +52,22 @@ pub struct Board {
     pub pin_mask_d: BitBoard,
     targets: BitBoard,
     threats: BitBoard,
-    move_list: Vec<Move>,
+
+    // The squares attacked by the side not to move, if they have been
+    // generated since the position changed. They are kept in the history
+    // too, so that they don't need to be generated again after an undo.
+    cached_threats: Option<BitBoard>,
+}
+
+// Boards are equal if their positions are, regardless of how they got there.
+impl PartialEq for Board {
+    fn eq(&self, other: &Self) -> bool {
+        self.same_position(other)
+    }
 }
 
+impl Eq for Board {}
+
 impl fmt::Display for Board {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         const THEME: (&str, &str, &str) = (
@@ -69,13 +79,9 @@ impl fmt::Display for Board {
         let board = self;
         let mut string_rep = String::from(" ");
 
-        let last_move = if board.plys_count >= 1 {
-            board.history[board.plys_count as usize - 1].played_move
-        } else {
-            Move::NULL
-        };
+        let last_move = board.last_move().unwrap_or(Move::NULL);
 
-        for (square, piece) in board.mailbox.0.into_iter().enumerate() {
+        for (square, piece) in board.pos.mailbox.0.into_iter().enumerate() {
             let square = Square::from(square);
 
             let square_rep = match piece.piece() {
@@ -103,7 +109,7 @@ impl fmt::Display for Board {
             };
 
             if !board.checkers.is_empty()
-                && piece == ColoredPiece::new(Piece::King, board.side_to_mv)
+                && piece == ColoredPiece::new(Piece::King, board.pos.side_to_mv)
             {
                 square_color = "red";
             } else if last_move != Move::NULL
@@ -131,15 +137,31 @@ impl fmt::Display for Board {
             "{}\nfen: {}\nkey: {}\ncheckers: {}\n",
             string_rep,
             FEN::from(board),
-            board.hash,
+            board.pos.hash,
             checkers
         )
     }
 }
 
+/// History is the list of the states of the previous positions on a Board.
+/// With the fixed-buffers feature, it is stored inline with a fixed capacity
+/// of Board::HISTORY_CAPACITY moves, so that making moves never allocates.
+#[cfg(not(feature = "fixed-buffers"))]
+type History = Vec<BoardState>;
+#[cfg(feature = "fixed-buffers")]
+type History = ArrayVec<BoardState, { Board::HISTORY_CAPACITY }>;
+
+/// MoveSink is a function which the move generator passes each of the moves
+/// it generates to. The move generator stops as soon as the function breaks.
+trait MoveSink: FnMut(Move) -> ControlFlow<()> {}
+
+impl<F: FnMut(Move) -> ControlFlow<()>> MoveSink for F {}
+
+/// BoardState is the state of a previous position on a Board which can't be
+/// recovered after a move is made in it, along with the move which was made.
 #[derive(Clone, Copy, Default)]
 pub struct BoardState {
-    pub played_move: Move,
+    played_move: Move,
     captured_piece: ColoredPiece,
 
     castling_r: castling::Rights,
@@ -147,26 +169,108 @@ pub struct BoardState {
     draw_clock: u8,
 
     hash: zobrist::Hash,
+    threats: Option<BitBoard>,
+}
+
+impl BoardState {
+    /// played_move returns the move which was made in the position.
+    pub fn played_move(&self) -> Move {
+        self.played_move
+    }
+
+    /// captured_piece returns the piece captured by the move made in the
+    /// position, if any. Castling moves, which are encoded as the king
+    /// capturing its own rook, don't capture anything.
+    pub fn captured_piece(&self) -> Option<ColoredPiece> {
+        match self.played_move.flags() {
+            MoveFlag::Castle => None,
+
+            // The pawn captured by en passant isn't on the target square. It
+            // is white's if the target square is on white's side of the board.
+            MoveFlag::EnPassant => {
+                let color = if self.played_move.target().rank() == Rank::Third {
+                    Color::White
+                } else {
+                    Color::Black
+                };
+
+                Some(ColoredPiece::new(Piece::Pawn, color))
+            }
+
+            _ => match self.captured_piece {
+                ColoredPiece::None => None,
+                piece => Some(piece),
+            },
+        }
+    }
+
+    /// castling_rights returns the castling rights of the position.
+    pub fn castling_rights(&self) -> castling::Rights {
+        self.castling_r
+    }
+
+    /// en_passant_target returns the en passant target square of the
+    /// position, which is Square::None if it doesn't have one.
+    pub fn en_passant_target(&self) -> Square {
+        self.enp_target
+    }
+
+    /// draw_clock returns the number of plys since the last capture or pawn
+    /// move before the position.
+    pub fn draw_clock(&self) -> u8 {
+        self.draw_clock
+    }
+
+    /// hash returns the zobrist hash of the position.
+    pub fn hash(&self) -> zobrist::Hash {
+        self.hash
+    }
+}
+
+/// BoardParseError is the reason a FEN string couldn't be parsed into a
+/// Board: either the string isn't valid FEN, or its position is illegal.
+#[derive(Debug)]
+pub enum BoardParseError {
+    InvalidFen(FENParseError),
+    IllegalPosition(PositionError),
 }
 
+impl fmt::Display for BoardParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BoardParseError::InvalidFen(err) => write!(f, "invalid fen: {err}"),
+            BoardParseError::IllegalPosition(err) => write!(f, "illegal position: {err}"),
+        }
+    }
+}
+
+impl Error for BoardParseError {}
+
 impl FromStr for Board {
-    type Err = ();
+    type Err = BoardParseError;
 
+    /// from_str parses the given FEN string into a standard chess Board. The
+    /// move counters may be left out of the FEN, and the word `startpos` is
+    /// accepted for the standard starting position.
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match FEN::from_str(s) {
-            Ok(fen) => Ok(Board::from(fen)),
-            Err(_) => Err(()),
+        match s.trim() {
+            "startpos" => Ok(Board::startpos()),
+            fen => Board::from_fen(fen, &variant::Standard),
         }
     }
 }
 
 impl From<FEN> for Board {
     fn from(fen: FEN) -> Self {
+        Board::from(Position::from(fen))
+    }
+}
+
+impl From<Position> for Board {
+    fn from(position: Position) -> Self {
         let mut board = Board {
-            mailbox: fen.position,
+            pos: position,
 
-            piece_bbs: [BitBoard::EMPTY; Piece::N],
-            color_bbs: [BitBoard::EMPTY; Color::N],
             friends: BitBoard::EMPTY,
             enemies: BitBoard::EMPTY,
             occupied: BitBoard::EMPTY,
@@ -174,23 +278,10 @@ impl From<FEN> for Board {
             checkers: BitBoard::EMPTY,
             check_nm: 0,
 
-            side_to_mv: fen.side_to_move,
-            plys_count: (fen.full_move_count - 1) * 2 + fen.side_to_move as u16,
-            draw_clock: fen.half_move_clock,
-            enp_target: fen.en_pass_square,
-
-            is_fischer_random: false,
-            hash: zobrist::castling_rights_key(fen.castling_rights),
-            castling_square_info: castling::Info::from_squares(
-                Square::E1,
-                File::H,
-                File::A,
-                Square::E8,
-                File::H,
-                File::A,
-            ),
-
-            history: [BoardState::default(); 1024],
+            #[cfg(not(feature = "fixed-buffers"))]
+            history: Vec::with_capacity(Board::HISTORY_CAPACITY),
+            #[cfg(feature = "fixed-buffers")]
+            history: History::new(),
 
             check_mask: BitBoard::EMPTY,
             pin_mask_l: BitBoard::EMPTY,
@@ -199,85 +290,218 @@ impl From<FEN> for Board {
             targets: BitBoard::EMPTY,
             threats: BitBoard::EMPTY,
 
-            move_list: Vec::new(),
+            cached_threats: None,
         };
 
-        for (square, piece) in board.mailbox.0.iter().enumerate() {
-            let piece = *piece;
-
-            if piece == ColoredPiece::None {
-                continue;
-            }
-
-            let square = Square::from(square);
-
-            board.piece_bbs[piece.piece() as usize].insert(square);
-            board.color_bbs[piece.color() as usize].insert(square);
+        board.friends = board.color_bb(board.pos.side_to_mv);
+        board.enemies = board.color_bb(!board.pos.side_to_mv);
+        board.occupied = board.friends | board.enemies;
 
-            board.hash ^= zobrist::piece_square_key(piece, square);
-        }
+        board.generate_check_masks();
 
-        if board.side_to_mv == Color::Black {
-            board.hash ^= zobrist::side_to_move_key();
-        }
+        board
+    }
+}
 
-        if board.enp_target != Square::None {
-            board.hash ^= zobrist::en_passant_key(board.enp_target);
-        }
+impl Default for Board {
+    fn default() -> Self {
+        Board::startpos()
+    }
+}
 
-        board.friends = board.color_bb(board.side_to_mv);
-        board.enemies = board.color_bb(!board.side_to_mv);
-        board.occupied = board.friends | board.enemies;
+impl Board {
+    /// startpos returns a Board with the standard starting position, which
+    /// is built directly rather than by parsing its FEN, STARTPOS_FEN.
+    pub fn startpos() -> Board {
+        Board::from(FEN {
+            position: Mailbox::STARTPOS,
+            side_to_move: Color::White,
+            castling: castling::Info::standard(castling::Rights::from(Color::White) + Color::Black),
+            en_pass_square: Square::None,
+            half_move_clock: 0,
+            full_move_count: 1,
+        })
+    }
 
-        board.generate_check_masks();
+    /// from_fen parses the given FEN string into a Board whose game is played
+    /// by the given Variant, if the position is legal by its rules.
+    pub fn from_fen(fen: &str, variant: &'static dyn Variant) -> Result<Board, BoardParseError> {
+        let fen = FEN::from_str(fen).map_err(BoardParseError::InvalidFen)?;
 
+        let mut board = Board::from(fen);
+        board.set_variant(variant);
         board
+            .pos
+            .validate()
+            .map_err(BoardParseError::IllegalPosition)?;
+
+        Ok(board)
     }
 }
 
 impl Board {
+    /// MAX_PHASE is the phase of the game with all the pieces of the
+    /// starting position on the Board.
+    pub const MAX_PHASE: u8 = 24;
+
+    /// HISTORY_CAPACITY is the number of moves a Board has space for in
+    /// its history before it has to reallocate it. Games can be longer,
+    /// unless the history has a fixed capacity.
+    pub const HISTORY_CAPACITY: usize = 1024;
+
+    /// position returns a reference to the current position on the Board,
+    /// which can be cloned to take a snapshot of it.
+    pub fn position(&self) -> &Position {
+        &self.pos
+    }
+
+    /// last_move returns the last move made on the Board, if any.
+    pub fn last_move(&self) -> Option<Move> {
+        self.history.last().map(|state| state.played_move)
+    }
+
+    /// captured_on_last_move returns the piece captured by the last move made
+    /// on the Board, if any moves have been made and it was a capture.
+    pub fn captured_on_last_move(&self) -> Option<ColoredPiece> {
+        self.history.last()?.captured_piece()
+    }
+
+    /// state_at returns the state of the position before the move with the
+    /// given index was made, where the first move made on the Board since it
+    /// was created has the index zero, or None if not that many moves have
+    /// been made on the Board.
+    pub fn state_at(&self, index: usize) -> Option<&BoardState> {
+        self.history.get(index)
+    }
+
+    /// move_history returns an iterator over the moves made on the Board
+    /// since it was created, from the first move to the last one.
+    pub fn move_history(&self) -> impl Iterator<Item = Move> + '_ {
+        self.history.iter().map(|state| state.played_move)
+    }
+
+    /// hash_history returns an iterator over the zobrist hashes of the
+    /// previous positions on the Board, from the oldest to the latest. The
+    /// hash of the current position is not included.
+    pub fn hash_history(&self) -> impl DoubleEndedIterator<Item = zobrist::Hash> + '_ {
+        self.history.iter().map(|state| state.hash)
+    }
+
+    /// hash returns the zobrist hash of the current position on the Board.
+    #[inline(always)]
+    pub fn hash(&self) -> zobrist::Hash {
+        self.pos.hash
+    }
+
+    /// pawn_hash returns the zobrist hash of only the pawns on the Board.
+    #[inline(always)]
+    pub fn pawn_hash(&self) -> zobrist::Hash {
+        self.pos.pawn_hash
+    }
+
+    /// material_hash returns the zobrist hash of the number of each piece
+    /// on the Board.
+    #[inline(always)]
+    pub fn material_hash(&self) -> zobrist::Hash {
+        self.pos.material_hash
+    }
+
+    /// phase returns the phase of the game on the Board, which goes from
+    /// MAX_PHASE with all the pieces on the board down to zero with only
+    /// pawns and kings left. Promotions may take it over MAX_PHASE, so it
+    /// is capped there.
+    #[inline(always)]
+    pub fn phase(&self) -> u8 {
+        self.pos.phase.min(Board::MAX_PHASE)
+    }
+
     pub fn mailbox(&self) -> Mailbox {
-        self.mailbox
+        self.pos.mailbox()
     }
 
     pub fn side_to_move(&self) -> Color {
-        self.side_to_mv
+        self.pos.side_to_move()
     }
 
     pub fn en_passant_target(&self) -> Square {
-        self.enp_target
+        self.pos.en_passant_target()
+    }
+
+    pub fn castling_rights(&self) -> castling::Rights {
+        self.pos.castling_rights()
+    }
+
+    /// validate checks the integrity of the internal state of the Board: that
+    /// its piece representations agree with each other, that each side has
+    /// as many kings as its Variant allows, and that its incrementally updated
+    /// hashes and occupancies are what they would be if computed from scratch.
+    /// Boards should always be valid, so it is meant for fuzzing and for
+    /// debug assertions after moves are made or taken back.
+    pub fn validate(&self) -> Result<(), IntegrityError> {
+        self.pos.validate_integrity()?;
+
+        let stm = self.side_to_move();
+        if self.friends != self.color_bb(stm)
+            || self.enemies != self.color_bb(!stm)
+            || self.occupied != (self.friends | self.enemies)
+        {
+            return Err(IntegrityError::OccupancyMismatch);
+        }
+
+        if self.hash() != zobrist::compute(self) {
+            return Err(IntegrityError::HashMismatch);
+        }
+
+        if self
+            .cached_threats
+            .is_some_and(|threats| threats != self.generate_threats())
+        {
+            return Err(IntegrityError::ThreatsMismatch);
+        }
+
+        Ok(())
+    }
+
+    /// same_position checks if the given Board has the same position as this
+    /// one, ignoring the move counters and the history of either Board. See
+    /// Position::same_position for what makes two positions the same.
+    pub fn same_position(&self, other: &Board) -> bool {
+        self.pos.same_position(&other.pos)
     }
 
     pub fn plys(&self) -> u16 {
-        self.plys_count
+        self.pos.plys()
     }
 
     pub fn draw_clock(&self) -> u8 {
-        self.draw_clock
+        self.pos.draw_clock()
     }
 
     #[inline(always)]
     pub fn colored_piece_bb(&self, piece: ColoredPiece) -> BitBoard {
-        self.piece_color_bb(piece.piece(), piece.color())
+        self.pos.colored_piece_bb(piece)
     }
 
     #[inline(always)]
     pub fn piece_color_bb(&self, piece: Piece, color: Color) -> BitBoard {
-        self.piece_bb(piece) & self.color_bb(color)
+        self.pos.piece_color_bb(piece, color)
     }
 
     #[inline(always)]
     pub fn piece_bb(&self, piece: Piece) -> BitBoard {
-        self.piece_bbs[piece as usize]
+        self.pos.piece_bb(piece)
     }
 
     #[inline(always)]
     pub fn color_bb(&self, color: Color) -> BitBoard {
-        self.color_bbs[color as usize]
+        self.pos.color_bb(color)
     }
 
-    pub fn const_color_bb<const color: Color>(&self) -> BitBoard {
-        self.color_bbs[color as usize]
+    /// const_color_bb returns the BitBoard of the pieces of the Color with
+    /// the given const generic index, as used by the specialized movegen.
+    #[inline(always)]
+    pub fn const_color_bb<const COLOR: usize>(&self) -> BitBoard {
+        self.pos.color_bbs[COLOR]
     }
 
     #[inline(always)]
@@ -287,48 +511,75 @@ impl Board {
 
     #[inline(always)]
     pub fn is_fischer_random(&self) -> bool {
-        self.is_fischer_random
+        self.pos.is_fischer_random()
+    }
+
+    /// variant returns the Variant whose rules the game on the Board is
+    /// played by.
+    #[inline(always)]
+    pub fn variant(&self) -> &'static dyn Variant {
+        self.pos.variant
+    }
+
+    /// set_variant sets the Variant whose rules the game on the Board is
+    /// played by, which should be done before any moves are made on it.
+    /// Setting a Variant without castling removes all of the castling
+    /// rights of the position.
+    pub fn set_variant(&mut self, variant: &'static dyn Variant) {
+        self.pos.variant = variant;
+
+        if !variant.can_castle() {
+            let rights = self.pos.castling_square_info.rights;
+            self.pos.hash ^= zobrist::castling_rights_key(rights);
+            self.pos.castling_square_info.rights = castling::Rights(0);
+            self.pos.hash ^= zobrist::castling_rights_key(castling::Rights(0));
+        }
+
+        self.generate_check_masks();
     }
 }
 
 impl Board {
     #[inline(always)]
     pub fn piece_at(&self, at: Square) -> ColoredPiece {
-        self.mailbox.0[at as usize]
+        self.pos.piece_at(at)
     }
 
     #[inline(always)]
     pub fn insert_piece(&mut self, square: Square, piece: ColoredPiece) {
-        self.mailbox.0[square as usize] = piece;
-
-        self.piece_bbs[piece.piece() as usize].insert(square);
-        self.color_bbs[piece.color() as usize].insert(square);
-
-        self.hash ^= zobrist::piece_square_key(piece, square);
+        self.pos.insert_piece(square, piece);
+        self.cached_threats = None;
     }
 
     #[inline(always)]
     pub fn remove_piece(&mut self, square: Square) {
-        let piece: ColoredPiece = self.mailbox.0[square as usize];
-        self.mailbox.0[square as usize] = ColoredPiece::None;
-
-        self.piece_bbs[piece.piece() as usize].remove(square);
-        self.color_bbs[piece.color() as usize].remove(square);
-
-        self.hash ^= zobrist::piece_square_key(piece, square);
+        self.pos.remove_piece(square);
+        self.cached_threats = None;
     }
 
     #[inline(always)]
     pub fn is_check(&self) -> bool {
         !self.checkers.is_empty()
     }
+
+    /// is_capture checks if the given move captures a piece on the Board.
+    /// Castling moves, which are encoded as the king capturing its own
+    /// rook, aren't captures.
+    #[inline(always)]
+    pub fn is_capture(&self, chessmove: Move) -> bool {
+        match chessmove.flags() {
+            MoveFlag::Castle => false,
+            MoveFlag::EnPassant => true,
+            _ => self.piece_at(chessmove.target()) != ColoredPiece::None,
+        }
+    }
 }
 
 /// Functions for various different terminal checks.
 impl Board {
     #[inline(always)]
     pub fn is_mated(&mut self) -> bool {
-        self.is_check() && self.generate_legal_moves().is_empty()
+        self.is_check() && !self.has_legal_moves()
     }
 
     #[inline(always)]
@@ -336,131 +587,141 @@ impl Board {
         self.is_50_move_draw()
     }
 
-    #[inline(always)]
-    pub fn is_50_move_draw(&mut self) -> bool {
-        self.draw_clock >= 100
-            && (self.checkers.is_empty() || !self.generate_legal_moves().is_empty())
-    }
-}
-
-impl Board {
-    pub fn make_move(&mut self, chessmove: Move) {
-        let board = self;
-
-        let source = chessmove.source();
-        let target = chessmove.target();
+    /// upcoming_repetition checks if the side to move has a move which
+    /// repeats a previous position, which means that it can force a draw by
+    /// repetition. Positions at or before the given ply from the root of a
+    /// search are only considered if they were already repeated once, since
+    /// they are part of the game's history rather than of the search tree.
+    pub fn upcoming_repetition(&self, ply: usize) -> bool {
+        // Only positions since the last irreversible move can be repeated,
+        // and a repeating move needs three plys between the positions.
+        let end = (self.pos.draw_clock as usize).min(self.history.len());
+        if end < 3 {
+            return false;
+        }
 
-        let flag = chessmove.flags();
+        for i in (3..=end).step_by(2) {
+            let index = self.history.len() - i;
+            let previous = self.history[index].hash;
 
-        let source_piece = board.piece_at(source);
-        let target_piece = board.piece_at(target);
+            // The move which leads to the previous position, if there is one,
+            // is the move whose key is the difference between the hashes.
+            let Some(chessmove) = cuckoo::find(self.pos.hash ^ previous) else {
+                continue;
+            };
 
-        let is_capture = target_piece != ColoredPiece::None;
+            let (source, target) = (chessmove.source(), chessmove.target());
+            if !(BitBoard::between(source, target) & self.occupied).is_empty() {
+                continue;
+            }
 
-        if board.history[board.plys_count as usize].hash != board.hash {
-            board.history[board.plys_count as usize] = BoardState {
-                played_move: chessmove,
-                captured_piece: target_piece,
+            if ply > i {
+                return true;
+            }
 
-                castling_r: board.castling_square_info.rights,
-                enp_target: board.enp_target,
-                draw_clock: board.draw_clock,
-                hash: board.hash,
+            // Repetitions of positions before the root need the move to be
+            // made by the side to move, rather than to lead to the current
+            // position, and the position to have already been repeated.
+            let piece = match self.piece_at(source) {
+                ColoredPiece::None => self.piece_at(target),
+                piece => piece,
             };
-        } else {
-            board.history[board.plys_count as usize].played_move = chessmove;
-            board.history[board.plys_count as usize].captured_piece = target_piece;
-        }
-
-        board.remove_piece(source); // Remove the moving piece.
 
-        // Update draw clock. Reset it on an irreversible move.
-        board.draw_clock = if is_capture || source_piece.is(Piece::Pawn) {
-            0
-        } else {
-            board.draw_clock + 1
-        };
-
-        // Reset en passant square, if any.
-        if board.enp_target != Square::None {
-            board.hash ^= zobrist::en_passant_key(board.enp_target);
-            board.enp_target = Square::None;
+            if piece.color() == self.pos.side_to_mv
+                && self.history[..index]
+                    .iter()
+                    .any(|state| state.hash == previous)
+            {
+                return true;
+            }
         }
 
-        // Do castling rights updates, if any.
-        board.castling_square_info.rights =
-            board.castling_square_info.rights - board.castling_square_info.get_updates(source);
-        board.castling_square_info.rights =
-            board.castling_square_info.rights - board.castling_square_info.get_updates(target);
+        false
+    }
 
-        // Remove the captured piece, if any.
-        if is_capture {
-            board.remove_piece(target);
-        }
+    /// is_threefold_repetition checks if the current position on the Board
+    /// has occurred at least three times, counting the current occurrence.
+    pub fn is_threefold_repetition(&self) -> bool {
+        self.history
+            .iter()
+            .rev()
+            .take(self.pos.draw_clock as usize)
+            .skip(1)
+            .step_by(2)
+            .filter(|state| state.hash == self.pos.hash)
+            .count()
+            >= 2
+    }
 
-        match flag {
-            MoveFlag::Promotion => {
-                let promotion = ColoredPiece::new(chessmove.promot(), board.side_to_mv);
-                board.insert_piece(target, promotion);
-            }
+    /// is_insufficient_material checks if neither side has enough material
+    /// on the Board to ever win the game by the rules of its Variant.
+    pub fn is_insufficient_material(&self) -> bool {
+        self.pos.variant.is_insufficient_material(self)
+    }
 
-            MoveFlag::Castle => {
-                let (king_target, rook_target) =
-                    castling::SideColor::from_sqs(source, target).get_targets();
+    #[inline(always)]
+    pub fn is_50_move_draw(&mut self) -> bool {
+        self.pos.draw_clock >= 100 && (self.checkers.is_empty() || self.has_legal_moves())
+    }
 
-                // The king has already been removed from it's source square by the
-                // general move source square clearing done before, and the rook has
-                // already been removed by the captured piece clearing done before.
+    /// outcome returns the Outcome of the game on the Board by the rules of
+    /// its Variant if the game is over, or None if it isn't.
+    pub fn outcome(&mut self) -> Option<Outcome> {
+        self.pos.variant.outcome(self)
+    }
+}
 
-                board.insert_piece(king_target, source_piece); // Insert King.
-                board.insert_piece(rook_target, target_piece); // Insert Rook.
-            }
+impl Board {
+    pub fn make_move(&mut self, chessmove: Move) {
+        let board = self;
 
-            MoveFlag::EnPassant => {
-                // Make the en passant capture.
-                board.remove_piece(target.down(board.side_to_mv));
-                board.insert_piece(target, source_piece);
-            }
+        let target_piece = board.piece_at(chessmove.target());
 
-            MoveFlag::Normal => {
-                // Move the piece to the target square.
-                board.insert_piece(target, source_piece);
-
-                // Update en passant target on a double pawn push.
-                if source_piece.is(Piece::Pawn) {
-                    // Calculate the en passant capture square.
-                    let ep_target = target.down(board.side_to_mv);
-
-                    if target.distance(source) == 2
-                    // Only set the en passant square if the pawn can be captured
-                    // by en passant. This increases the number of tt hits we get.
-                    && !moves::pawn_attacks(ep_target, board.side_to_mv)
-                    .is_disjoint(board.piece_color_bb(Piece::Pawn, !board.side_to_mv))
-                    {
-                        // The en passant target square is below
-                        // the pawn's square after the double push.
-                        board.enp_target = ep_target;
-                        board.hash ^= zobrist::en_passant_key(board.enp_target);
-                    }
-                }
-            }
-        }
+        // Save the irreversible state of the position so that the move can
+        // be undone later. The history grows with the game, so there is no
+        // limit on the number of moves that can be made on a Board.
+        board.history.push(BoardState {
+            played_move: chessmove,
+            captured_piece: target_piece,
 
-        board.plys_count += 1;
-        board.side_to_mv = !board.side_to_mv;
-        board.hash ^= zobrist::side_to_move_key();
+            castling_r: board.pos.castling_square_info.rights,
+            enp_target: board.pos.enp_target,
+            draw_clock: board.pos.draw_clock,
+            hash: board.pos.hash,
+            threats: board.cached_threats,
+        });
 
-        board.friends = board.color_bb(board.side_to_mv);
-        board.enemies = board.color_bb(!board.side_to_mv);
+        board.pos.apply_move(chessmove);
+        board.cached_threats = None;
+
+        board.friends = board.color_bb(board.pos.side_to_mv);
+        board.enemies = board.color_bb(!board.pos.side_to_mv);
         board.occupied = board.friends | board.enemies;
 
         board.generate_check_masks();
+
+        #[cfg(feature = "debug-hash")]
+        board.verify_hash("make move");
     }
 
+    /// undo_move takes back the last move made on the Board. It panics if
+    /// no moves have been made on the Board since it was created.
     pub fn undo_move(&mut self) {
+        // The move being taken back was made by the side not to move.
+        match self.pos.side_to_mv {
+            Color::White => self.undo_move_for::<{ Color::Black as usize }>(),
+            _ => self.undo_move_for::<{ Color::White as usize }>(),
+        }
+    }
+
+    /// undo_move_for takes back the last move made on the Board, which has
+    /// to have been made by the given side.
+    #[inline(always)]
+    fn undo_move_for<const STM: usize>(&mut self) {
         let board = self;
+        let stm = Color::from_index(STM);
 
-        let previous_state = board.history[(board.plys_count - 1) as usize];
+        let previous_state = board.history.pop().expect("undo move: no moves to undo");
 
         let chessmove = previous_state.played_move;
 
@@ -473,8 +734,8 @@ impl Board {
         let mut source_piece = target_piece;
 
         // Switch side.
-        board.plys_count -= 1;
-        board.side_to_mv = !board.side_to_mv;
+        board.pos.plys_count -= 1;
+        board.pos.side_to_mv = stm;
 
         match flag {
             MoveFlag::Castle => {
@@ -491,10 +752,7 @@ impl Board {
                 board.remove_piece(target);
 
                 // Put back the pawn captured by en passant.
-                board.insert_piece(
-                    target.down(board.side_to_mv),
-                    ColoredPiece::new(Piece::Pawn, !board.side_to_mv),
-                )
+                board.insert_piece(target.down(stm), ColoredPiece::new(Piece::Pawn, !stm))
             }
 
             MoveFlag::Promotion => {
@@ -514,18 +772,36 @@ impl Board {
         board.insert_piece(source, source_piece);
 
         // Reset irreversible info from previous state.
-        board.enp_target = previous_state.enp_target;
-        board.castling_square_info.rights = previous_state.castling_r;
-        board.draw_clock = previous_state.draw_clock;
+        board.pos.enp_target = previous_state.enp_target;
+        board.pos.castling_square_info.rights = previous_state.castling_r;
+        board.pos.draw_clock = previous_state.draw_clock;
 
         // Zobrist hash is reversible, but it is easier to reset.
-        board.hash = previous_state.hash;
+        board.pos.hash = previous_state.hash;
+        board.cached_threats = previous_state.threats;
 
-        board.friends = board.color_bb(board.side_to_mv);
-        board.enemies = board.color_bb(!board.side_to_mv);
+        board.friends = board.color_bb(board.pos.side_to_mv);
+        board.enemies = board.color_bb(!board.pos.side_to_mv);
         board.occupied = board.friends | board.enemies;
 
         board.generate_check_masks();
+
+        #[cfg(feature = "debug-hash")]
+        board.verify_hash("undo move");
+    }
+
+    /// verify_hash panics if the incrementally updated zobrist hash of the
+    /// Board doesn't match the one computed from scratch, naming the given
+    /// operation as the one which got it wrong.
+    #[cfg(feature = "debug-hash")]
+    fn verify_hash(&self, operation: &str) {
+        let expected = zobrist::compute(self);
+        assert!(
+            self.pos.hash == expected,
+            "{operation}: incremental hash {} doesn't match computed hash {expected} in {}",
+            self.pos.hash,
+            FEN::from(self),
+        );
     }
 }
 
@@ -533,9 +809,25 @@ impl Board {
     fn generate_check_masks(&mut self) {
         let board = self;
 
+        // Kings without royalty can't be checked.
+        if !board.pos.variant.has_royal_king() {
+            board.checkers = BitBoard::EMPTY;
+            board.check_nm = 0;
+            board.check_mask = BitBoard::UNIVERSE;
+            return;
+        }
+
         // Get our king's bitboard.
         let king = (board.piece_bb(Piece::King) & board.friends).lsb();
 
+        // A side without a king, like the horde in Horde, can't be checked.
+        if king == Square::None {
+            board.checkers = BitBoard::EMPTY;
+            board.check_nm = 0;
+            board.check_mask = BitBoard::UNIVERSE;
+            return;
+        }
+
         // Exclude king from blocker masks to allow x-raying.
         let blockers = board.occupied() & !BitBoard::from(king);
 
@@ -547,7 +839,7 @@ impl Board {
         let q = board.piece_bb(Piece::Queen) & board.enemies;
 
         // Get opponent's checking pieces.
-        let checking_p = p & moves::pawn_attacks(king, board.side_to_mv);
+        let checking_p = p & moves::pawn_attacks(king, board.pos.side_to_mv);
         let checking_n = n & moves::knight(king);
         let checking_b = (b | q) & moves::bishop(king, blockers);
         let checking_r = (r | q) & moves::rook(king, blockers);
@@ -574,6 +866,13 @@ impl Board {
         // Get our king's bitboard.
         let king = (board.piece_bb(Piece::King) & board.friends).lsb();
 
+        // Nothing can be pinned to a missing king.
+        if king == Square::None {
+            board.pin_mask_l = BitBoard::EMPTY;
+            board.pin_mask_d = BitBoard::EMPTY;
+            return;
+        }
+
         // Get opponent's sliding pieces bitboards.
         let b = board.piece_bb(Piece::Bishop) & board.enemies;
         let r = board.piece_bb(Piece::Rook) & board.enemies;
@@ -610,20 +909,34 @@ impl Board {
         }
     }
 
-    fn generate_threats(&mut self) {
-        let board = self;
-        let xtm = !board.side_to_mv;
+    /// threats returns the squares attacked by the side not to move. The
+    /// side to move's king doesn't block the attacks of sliding pieces, so
+    /// it can't move away from them along their lines either. They are
+    /// cached once generated until the position changes.
+    pub fn threats(&self) -> BitBoard {
+        self.cached_threats
+            .unwrap_or_else(|| self.generate_threats())
+    }
 
-        board.threats = BitBoard::EMPTY;
+    /// update_threats generates the threats of the position into the cache
+    /// and the move generation bitboards, unless they are cached already.
+    fn update_threats(&mut self) {
+        let threats = self.threats();
+        self.cached_threats = Some(threats);
+        self.threats = threats;
+    }
 
-        let pawns = board.piece_color_bb(Piece::Pawn, xtm);
-        for pawn in pawns {
-            board.threats |= moves::pawn_attacks(pawn, xtm);
-        }
+    fn generate_threats(&self) -> BitBoard {
+        let board = self;
+        let xtm = !board.pos.side_to_mv;
+
+        // Pawns attacks can be generated for all the pawns at once.
+        let pawns = board.piece_color_bb(Piece::Pawn, xtm).up(xtm);
+        let mut threats = pawns.east() | pawns.west();
 
         let knights = board.piece_color_bb(Piece::Knight, xtm);
         for knight in knights {
-            board.threats |= moves::knight(knight);
+            threats |= moves::knight(knight);
         }
 
         // Exclude king from blocker masks to allow x-raying.
@@ -631,47 +944,178 @@ impl Board {
 
         let bishops = board.piece_color_bb(Piece::Bishop, xtm);
         for bishop in bishops {
-            board.threats |= moves::bishop(bishop, blockers);
+            threats |= moves::bishop(bishop, blockers);
         }
 
         let rooks = board.piece_color_bb(Piece::Rook, xtm);
         for rook in rooks {
-            board.threats |= moves::rook(rook, blockers);
+            threats |= moves::rook(rook, blockers);
         }
 
         let queens = board.piece_color_bb(Piece::Queen, xtm);
         for queen in queens {
-            board.threats |= moves::queen(queen, blockers);
+            threats |= moves::queen(queen, blockers);
+        }
+
+        for king in board.piece_color_bb(Piece::King, xtm) {
+            threats |= moves::king(king);
         }
 
-        board.threats |= moves::king(board.piece_color_bb(Piece::King, xtm).lsb())
+        threats
     }
 }
 
 // Implementation of the Board's legal move generation.
 impl Board {
-    pub fn generate_legal_moves(&mut self) -> Vec<Move> {
+    pub fn generate_legal_moves(&mut self) -> MoveList {
         self.generate_moves::<true, true>()
     }
 
-    pub fn generate_quiet_moves(&mut self) -> Vec<Move> {
+    pub fn generate_quiet_moves(&mut self) -> MoveList {
         self.generate_moves::<true, false>()
     }
 
-    pub fn generate_noisy_moves(&mut self) -> Vec<Move> {
+    pub fn generate_noisy_moves(&mut self) -> MoveList {
         self.generate_moves::<false, true>()
     }
 
+    /// for_each_legal_move calls the given function with each of the legal
+    /// moves on the Board, until it breaks. The moves are passed to it as
+    /// soon as they are generated, so no list of them is built, and the
+    /// move generator stops as soon as the function breaks. Variants with a
+    /// move filter stage still need to generate all of the moves first.
+    pub fn for_each_legal_move(&mut self, mut f: impl FnMut(Move) -> ControlFlow<()>) {
+        if self.pos.variant.has_move_filter() {
+            for chessmove in self.generate_legal_moves() {
+                if f(chessmove).is_break() {
+                    break;
+                }
+            }
+
+            return;
+        }
+
+        let _ = self.generate_moves_for::<true, true>(&mut f);
+    }
+
+    /// has_legal_moves checks if the side to move has any legal moves.
+    pub fn has_legal_moves(&mut self) -> bool {
+        let mut has_moves = false;
+        self.for_each_legal_move(|_| {
+            has_moves = true;
+            ControlFlow::Break(())
+        });
+
+        has_moves
+    }
+
+    /// destinations returns the squares the piece on the given square can
+    /// legally move to, which is empty if it isn't a piece of the side to
+    /// move. Castling moves target the square of the castling rook, like in
+    /// the moves themselves.
+    pub fn destinations(&mut self, from: Square) -> BitBoard {
+        let mut targets = BitBoard::EMPTY;
+        self.for_each_legal_move(|chessmove| {
+            if chessmove.source() == from {
+                targets |= BitBoard::from(chessmove.target());
+            }
+
+            ControlFlow::Continue(())
+        });
+
+        targets
+    }
+
+    /// legal_moves_from returns the legal moves of the piece on the given
+    /// square, with a move for each of the pieces a pawn can promote to. It
+    /// is empty if the square doesn't have a piece of the side to move.
+    pub fn legal_moves_from(&mut self, from: Square) -> MoveList {
+        let mut moves = MoveList::new();
+        self.for_each_legal_move(|chessmove| {
+            if chessmove.source() == from {
+                moves.push(chessmove);
+            }
+
+            ControlFlow::Continue(())
+        });
+
+        moves
+    }
+
+    /// generate_moves generates the legal moves of the given kinds into a
+    /// new MoveList. The list is kept out of the Board, so that the Board
+    /// stays small and cheap to copy.
     #[inline(always)]
-    fn generate_moves<const GEN_QUIET: bool, const GEN_NOISY: bool>(&mut self) -> Vec<Move> {
+    fn generate_moves<const GEN_QUIET: bool, const GEN_NOISY: bool>(&mut self) -> MoveList {
         let board = self;
+        let mut moves = MoveList::new();
+        let mut push = |chessmove| {
+            moves.push(chessmove);
+            ControlFlow::Continue(())
+        };
 
-        // Clear the move-list, but reuse it's memory.
-        board.move_list.truncate(0);
+        // Variants with a move filter stage need all of the moves to filter
+        // them, so they are split into quiet and noisy moves afterwards.
+        if board.pos.variant.has_move_filter() {
+            let _ = board.generate_moves_for::<true, true>(&mut push);
+            board.pos.variant.filter_moves(board, &mut moves);
+
+            // Captures and queen promotions are noisy, while all other
+            // moves are quiet, like in the search's move ordering.
+            moves.retain(|&chessmove| {
+                let is_noisy = board.is_capture(chessmove)
+                    || (chessmove.is_promotion() && chessmove.promot() == Piece::Queen);
+
+                if is_noisy {
+                    GEN_NOISY
+                } else {
+                    GEN_QUIET
+                }
+            });
 
-        // Generate move generation bitboards.
-        board.generate_threats();
-        board.generate_pin_masks();
+            return moves;
+        }
+
+        let _ = board.generate_moves_for::<GEN_QUIET, GEN_NOISY>(&mut push);
+        moves
+    }
+
+    /// generate_moves_for generates the unfiltered moves of the given kinds
+    /// with the move generator specialized for the side to move, so that the
+    /// generator doesn't need to branch on the side to move at every step.
+    #[inline(always)]
+    fn generate_moves_for<const GEN_QUIET: bool, const GEN_NOISY: bool>(
+        &mut self,
+        sink: &mut impl MoveSink,
+    ) -> ControlFlow<()> {
+        match self.pos.side_to_mv {
+            Color::White => self
+                .generate_unfiltered_moves::<{ Color::White as usize }, GEN_QUIET, GEN_NOISY>(sink),
+            _ => self
+                .generate_unfiltered_moves::<{ Color::Black as usize }, GEN_QUIET, GEN_NOISY>(sink),
+        }
+    }
+
+    /// generate_unfiltered_moves generates the moves which are legal before
+    /// the move filter stage of the Board's Variant into the given MoveSink,
+    /// for the given side to move, which has to be the Board's side to move.
+    #[inline(always)]
+    fn generate_unfiltered_moves<const STM: usize, const GEN_QUIET: bool, const GEN_NOISY: bool>(
+        &mut self,
+        sink: &mut impl MoveSink,
+    ) -> ControlFlow<()> {
+        let board = self;
+
+        // Generate move generation bitboards. Nothing is pinned to or
+        // attacks a king without royalty, which can move like any piece.
+        if board.pos.variant.has_royal_king() {
+            board.update_threats();
+            board.generate_pin_masks();
+        } else {
+            board.threats = BitBoard::EMPTY;
+            board.pin_mask_l = BitBoard::EMPTY;
+            board.pin_mask_d = BitBoard::EMPTY;
+        }
 
         board.targets = BitBoard::EMPTY;
         if GEN_QUIET {
@@ -682,54 +1126,143 @@ impl Board {
         }
 
         // King moves can always be legal.
-        board.generate_king_moves();
+        board.generate_king_moves::<STM>(sink)?;
 
         // If the king is in double check, only
         // king moves can possibly be legal.
         if board.check_nm < 2 {
-            board.generate_pawn_moves::<GEN_QUIET, GEN_NOISY>();
+            board.generate_pawn_moves::<STM, GEN_QUIET, GEN_NOISY>(sink)?;
 
-            board.generate_knight_moves();
-            board.generate_bishop_moves();
-            board.generate_rook_moves();
+            board.generate_knight_moves::<STM>(sink)?;
+            board.generate_bishop_moves::<STM>(sink)?;
+            board.generate_rook_moves::<STM>(sink)?;
 
             if GEN_QUIET {
-                board.generate_castling_moves()
+                board.generate_castling_moves::<STM>(sink)?;
             }
         }
 
-        board.move_list.clone()
+        ControlFlow::Continue(())
     }
 }
 
 impl Board {
     #[inline(always)]
-    fn generate_pawn_moves<const GEN_QUIET: bool, const GEN_NOISY: bool>(&mut self) {
-        let pawns = self.piece_color_bb(Piece::Pawn, self.side_to_mv) - self.pin_mask_d;
+    fn generate_pawn_moves<const STM: usize, const GEN_QUIET: bool, const GEN_NOISY: bool>(
+        &mut self,
+        sink: &mut impl MoveSink,
+    ) -> ControlFlow<()> {
+        let stm = Color::from_index(STM);
+        let pawns = self.piece_bb(Piece::Pawn) & self.const_color_bb::<STM>();
+
+        // Diagonally pinned pawns can't push, and
+        // laterally pinned pawns can't capture.
+        let pushers = pawns - self.pin_mask_d;
+        let capturers = pawns - self.pin_mask_l;
+
+        let up = Direction::up(stm);
+
+        let pinned = pushers & self.pin_mask_l;
+        let unpinned = pushers ^ pinned;
+
+        let pinned_pushed = pinned.shift(up) & self.pin_mask_l;
+        let unpinned_pushed = unpinned.shift(up);
+
+        self.serialize_pawn_push::<STM, GEN_QUIET, GEN_NOISY>(
+            pinned_pushed + unpinned_pushed,
+            sink,
+        )?;
+
+        let pinned = capturers & self.pin_mask_d;
+        let unpinned = capturers ^ pinned;
+
+        let (up_east, up_west) = if stm == Color::White {
+            (Direction::NorthEast, Direction::NorthWest)
+        } else {
+            (Direction::SouthEast, Direction::SouthWest)
+        };
+
+        for direction in [up_east, up_west] {
+            let pinned_captures = pinned.shift(direction) & self.pin_mask_d;
+            let unpinned_captures = unpinned.shift(direction);
+
+            self.serialize_pawn_captures::<STM, GEN_QUIET, GEN_NOISY>(
+                pinned_captures + unpinned_captures,
+                direction,
+                sink,
+            )?;
+        }
+
+        if GEN_NOISY && self.pos.enp_target != Square::None {
+            self.generate_en_passant_moves::<STM>(capturers, sink)?;
+        }
+
+        ControlFlow::Continue(())
+    }
+
+    #[inline(always)]
+    fn generate_en_passant_moves<const STM: usize>(
+        &mut self,
+        capturers: BitBoard,
+        sink: &mut impl MoveSink,
+    ) -> ControlFlow<()> {
+        let stm = Color::from_index(STM);
+        let ep_target = self.pos.enp_target;
+        let captured = ep_target.down(stm);
+
+        // An en passant capture can only resolve a check by capturing
+        // the checking pawn or by blocking a sliding piece's attack.
+        if !self.check_mask.contains(ep_target) && !self.check_mask.contains(captured) {
+            return ControlFlow::Continue(());
+        }
 
-        let pinned = pawns & self.pin_mask_l;
-        let unpinned = pawns ^ pinned;
+        // En passant captures can only expose a royal king.
+        let king = self.piece_color_bb(Piece::King, stm).lsb();
+        let has_king = king != Square::None && self.pos.variant.has_royal_king();
+
+        let enemy_l = (self.piece_bb(Piece::Rook) | self.piece_bb(Piece::Queen)) & self.enemies;
+        let enemy_d = (self.piece_bb(Piece::Bishop) | self.piece_bb(Piece::Queen)) & self.enemies;
+
+        for pawn in moves::pawn_attacks(ep_target, !stm) & capturers {
+            // Both the capturing and the captured pawn leave their squares,
+            // which can expose the king in ways the pin masks don't catch,
+            // so check for sliding attacks on the resulting occupancy.
+            let occupied = self.occupied - pawn - captured + ep_target;
+            if has_king
+                && (!moves::rook(king, occupied).is_disjoint(enemy_l)
+                    || !moves::bishop(king, occupied).is_disjoint(enemy_d))
+            {
+                continue;
+            }
 
-        let pinned_pushed = pinned.up(self.side_to_mv) & self.pin_mask_l;
-        let unpinned_pushed = unpinned.up(self.side_to_mv);
+            sink(Move::new(pawn, ep_target, MoveFlag::EnPassant))?;
+        }
 
-        self.serialize_pawn_push::<GEN_QUIET, GEN_NOISY>(pinned_pushed + unpinned_pushed);
+        ControlFlow::Continue(())
     }
 
     #[inline(always)]
-    fn generate_knight_moves(&mut self) {
-        let knights = self.piece_color_bb(Piece::Knight, self.side_to_mv)
+    fn generate_knight_moves<const STM: usize>(
+        &mut self,
+        sink: &mut impl MoveSink,
+    ) -> ControlFlow<()> {
+        let knights = (self.piece_bb(Piece::Knight) & self.const_color_bb::<STM>())
             - (self.pin_mask_l | self.pin_mask_d);
 
         for knight in knights {
-            self.serialize_moves(knight, moves::knight(knight));
+            self.serialize_moves(knight, moves::knight(knight), sink)?;
         }
+
+        ControlFlow::Continue(())
     }
 
     #[inline(always)]
-    fn generate_bishop_moves(&mut self) {
-        let bishops = (self.piece_color_bb(Piece::Bishop, self.side_to_mv)
-            | self.piece_color_bb(Piece::Queen, self.side_to_mv))
+    fn generate_bishop_moves<const STM: usize>(
+        &mut self,
+        sink: &mut impl MoveSink,
+    ) -> ControlFlow<()> {
+        let bishops = ((self.piece_bb(Piece::Bishop) | self.piece_bb(Piece::Queen))
+            & self.const_color_bb::<STM>())
             - self.pin_mask_l;
 
         let pinned = bishops & self.pin_mask_d;
@@ -739,154 +1272,295 @@ impl Board {
             self.serialize_moves(
                 bishop,
                 moves::bishop(bishop, self.occupied()) & self.pin_mask_d,
-            );
+                sink,
+            )?;
         }
 
         for bishop in unpinned {
-            self.serialize_moves(bishop, moves::bishop(bishop, self.occupied()));
+            self.serialize_moves(bishop, moves::bishop(bishop, self.occupied()), sink)?;
         }
+
+        ControlFlow::Continue(())
     }
 
     #[inline(always)]
-    fn generate_rook_moves(&mut self) {
-        let rooks = (self.piece_color_bb(Piece::Rook, self.side_to_mv)
-            | self.piece_color_bb(Piece::Queen, self.side_to_mv))
+    fn generate_rook_moves<const STM: usize>(
+        &mut self,
+        sink: &mut impl MoveSink,
+    ) -> ControlFlow<()> {
+        let rooks = ((self.piece_bb(Piece::Rook) | self.piece_bb(Piece::Queen))
+            & self.const_color_bb::<STM>())
             - self.pin_mask_d;
 
         let pinned = rooks & self.pin_mask_l;
         let unpinned = rooks ^ pinned;
 
         for rook in pinned {
-            self.serialize_moves(rook, moves::rook(rook, self.occupied()) & self.pin_mask_l);
+            self.serialize_moves(
+                rook,
+                moves::rook(rook, self.occupied()) & self.pin_mask_l,
+                sink,
+            )?;
         }
 
         for rook in unpinned {
-            self.serialize_moves(rook, moves::rook(rook, self.occupied()));
+            self.serialize_moves(rook, moves::rook(rook, self.occupied()), sink)?;
         }
+
+        ControlFlow::Continue(())
     }
 
     #[inline(always)]
-    fn generate_king_moves(&mut self) {
-        let king = self.piece_color_bb(Piece::King, self.side_to_mv).lsb();
-        self.serialize_king_moves(king, moves::king(king));
+    fn generate_king_moves<const STM: usize>(
+        &mut self,
+        sink: &mut impl MoveSink,
+    ) -> ControlFlow<()> {
+        for king in self.piece_bb(Piece::King) & self.const_color_bb::<STM>() {
+            self.serialize_king_moves(king, moves::king(king), sink)?;
+        }
+
+        ControlFlow::Continue(())
     }
 
     #[inline(always)]
-    fn generate_castling_moves(&mut self) {
+    fn generate_castling_moves<const STM: usize>(
+        &mut self,
+        sink: &mut impl MoveSink,
+    ) -> ControlFlow<()> {
         let board = self;
+        let stm = Color::from_index(STM);
+
+        let king = (board.piece_bb(Piece::King) & board.const_color_bb::<STM>()).lsb();
+
+        let castling_info = &board.pos.castling_square_info;
+
+        // Enemy rooks and queens on the back rank which are hidden behind
+        // the castling rook, which is possible in Chess960, check the king
+        // on its target square once the rook has moved out of the way.
+        let sliders = (board.piece_bb(Piece::Rook) | board.piece_bb(Piece::Queen)) & board.enemies;
+        let is_exposed = |side: castling::SideColor| {
+            let (king_target, rook_target) = side.get_targets();
+            let blockers =
+                (board.occupied - BitBoard::from(king) - BitBoard::from(castling_info.rook(side)))
+                    | BitBoard::from(rook_target);
+            !moves::rook(king_target, blockers).is_disjoint(sliders)
+        };
 
-        // Other pieces in the castling path or attacking the
-        // castling path block the king's ability to castle.
-        let castling_blockers = board.occupied + board.threats;
-
-        let king = board.piece_color_bb(Piece::King, board.side_to_mv).lsb();
+        // Other pieces in the castling path or attacks on the
+        // king's path block the king's ability to castle.
+        let can_castle = |side| {
+            castling_info.rights.has(side)
+                && castling_info.path(side).is_disjoint(board.occupied)
+                && castling_info.king_path(side).is_disjoint(board.threats)
+                && !is_exposed(side)
+        };
 
-        let castling_info = &board.castling_square_info;
+        let a_side = castling::SideColor(stm, castling::Side::A);
+        let h_side = castling::SideColor(stm, castling::Side::H);
 
-        let a_side = castling::SideColor(board.side_to_mv, castling::Side::A);
-        if board.castling_square_info.rights.has(a_side)
-            && castling_info.path(a_side).is_disjoint(castling_blockers)
-        {
-            board.move_list.push(Move::new(
+        if can_castle(a_side) {
+            sink(Move::new(
                 king,
                 castling_info.rook(a_side),
                 MoveFlag::Castle,
-            ));
+            ))?;
         }
 
-        let h_side = castling::SideColor(board.side_to_mv, castling::Side::H);
-        if board.castling_square_info.rights.has(h_side)
-            && castling_info.path(h_side).is_disjoint(castling_blockers)
-        {
-            board.move_list.push(Move::new(
+        if can_castle(h_side) {
+            sink(Move::new(
                 king,
                 castling_info.rook(h_side),
                 MoveFlag::Castle,
-            ));
+            ))?;
         }
+
+        ControlFlow::Continue(())
     }
 }
 
 impl Board {
     #[inline(always)]
-    fn serialize_moves(&mut self, source: Square, targets: BitBoard) {
+    fn serialize_moves(
+        &self,
+        source: Square,
+        targets: BitBoard,
+        sink: &mut impl MoveSink,
+    ) -> ControlFlow<()> {
         let targets = targets & self.targets & self.check_mask;
 
         for target in targets {
-            self.move_list
-                .push(Move::new(source, target, MoveFlag::Normal));
+            sink(Move::new(source, target, MoveFlag::Normal))?;
         }
+
+        ControlFlow::Continue(())
     }
 
     #[inline(always)]
-    fn serialize_pawn_push<const GEN_QUIET: bool, const GEN_NOISY: bool>(
-        &mut self,
+    fn serialize_pawn_push<const STM: usize, const GEN_QUIET: bool, const GEN_NOISY: bool>(
+        &self,
         targets: BitBoard,
-    ) {
+        sink: &mut impl MoveSink,
+    ) -> ControlFlow<()> {
+        let stm = Color::from_index(STM);
         let pushes = (targets & self.check_mask) - self.occupied;
 
-        let promos = pushes & BitBoard::rank(Rank::Eighth.relative(self.side_to_mv));
+        let promos = pushes & BitBoard::rank(Rank::Eighth.relative(stm));
         let pushes = pushes - promos;
 
         for pawn in promos {
             // Queen Promotions are noisy moves.
             if GEN_NOISY {
-                self.move_list.push(Move::new_with_promotion(
-                    pawn.down(self.side_to_mv),
-                    pawn,
-                    Piece::Queen,
-                ));
+                sink(Move::new_with_promotion(pawn.down(stm), pawn, Piece::Queen))?;
             }
 
             // Knight, Bishop, and Rook promotions are quiet moves.
             if GEN_QUIET {
-                self.move_list.push(Move::new_with_promotion(
-                    pawn.down(self.side_to_mv),
+                sink(Move::new_with_promotion(
+                    pawn.down(stm),
                     pawn,
                     Piece::Knight,
-                ));
-                self.move_list.push(Move::new_with_promotion(
-                    pawn.down(self.side_to_mv),
-                    pawn,
-                    Piece::Rook,
-                ));
-                self.move_list.push(Move::new_with_promotion(
-                    pawn.down(self.side_to_mv),
+                ))?;
+                sink(Move::new_with_promotion(pawn.down(stm), pawn, Piece::Rook))?;
+                sink(Move::new_with_promotion(
+                    pawn.down(stm),
                     pawn,
                     Piece::Bishop,
-                ));
+                ))?;
             }
         }
 
         if GEN_QUIET {
             for pawn in pushes {
-                self.move_list.push(Move::new(
-                    pawn.down(self.side_to_mv),
-                    pawn,
-                    MoveFlag::Normal,
-                ));
+                sink(Move::new(pawn.down(stm), pawn, MoveFlag::Normal))?;
             }
 
-            let double = targets & BitBoard::rank(Rank::Third.relative(self.side_to_mv));
-            let double = (double.up(self.side_to_mv) & self.check_mask) - self.occupied;
+            // Double pushes need the single push square to be empty too.
+            let up = Direction::up(stm);
+            let double_ranks = self.pos.variant.double_push_ranks(stm).shift(up);
+
+            let double = (targets - self.occupied) & double_ranks;
+            let double = double.shift(Direction::up(stm));
+            let double = (double & self.check_mask) - self.occupied;
 
             for pawn in double {
-                self.move_list.push(Move::new(
-                    pawn.down(self.side_to_mv).down(self.side_to_mv),
-                    pawn,
-                    MoveFlag::Normal,
-                ));
+                sink(Move::new(pawn.down(stm).down(stm), pawn, MoveFlag::Normal))?;
             }
         }
+
+        ControlFlow::Continue(())
+    }
+
+    #[inline(always)]
+    fn serialize_pawn_captures<const STM: usize, const GEN_QUIET: bool, const GEN_NOISY: bool>(
+        &self,
+        targets: BitBoard,
+        direction: Direction,
+        sink: &mut impl MoveSink,
+    ) -> ControlFlow<()> {
+        let stm = Color::from_index(STM);
+        let captures = targets & self.enemies & self.check_mask;
+
+        let promos = captures & BitBoard::rank(Rank::Eighth.relative(stm));
+        let captures = captures - promos;
+
+        for target in promos {
+            let source = Square::from(target as i8 - direction.offset());
+
+            // Queen Promotions are noisy moves.
+            if GEN_NOISY {
+                sink(Move::new_with_promotion(source, target, Piece::Queen))?;
+            }
+
+            // Knight, Bishop, and Rook promotions are quiet moves.
+            if GEN_QUIET {
+                sink(Move::new_with_promotion(source, target, Piece::Knight))?;
+                sink(Move::new_with_promotion(source, target, Piece::Rook))?;
+                sink(Move::new_with_promotion(source, target, Piece::Bishop))?;
+            }
+        }
+
+        if GEN_NOISY {
+            for target in captures {
+                let source = Square::from(target as i8 - direction.offset());
+                sink(Move::new(source, target, MoveFlag::Normal))?;
+            }
+        }
+
+        ControlFlow::Continue(())
     }
 
     #[inline(always)]
-    fn serialize_king_moves(&mut self, source: Square, targets: BitBoard) {
+    fn serialize_king_moves(
+        &self,
+        source: Square,
+        targets: BitBoard,
+        sink: &mut impl MoveSink,
+    ) -> ControlFlow<()> {
         let targets = (targets & self.targets) - self.threats;
 
         for target in targets {
-            self.move_list
-                .push(Move::new(source, target, MoveFlag::Normal));
+            sink(Move::new(source, target, MoveFlag::Normal))?;
+        }
+
+        ControlFlow::Continue(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::chess::STARTPOS_FEN;
+
+    use super::*;
+
+    const FENS: [&str; 4] = [
+        STARTPOS_FEN,
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+        "7k/5Q2/6K1/8/8/8/8/8 b - - 0 80",
+    ];
+
+    #[test]
+    fn for_each_legal_move_visits_the_legal_moves() {
+        for fen in FENS {
+            let mut board = Board::from_str(fen).unwrap();
+
+            let mut moves = Vec::new();
+            board.for_each_legal_move(|chessmove| {
+                moves.push(chessmove);
+                ControlFlow::Continue(())
+            });
+
+            assert_eq!(moves, board.generate_legal_moves().to_vec(), "{fen}");
+            assert_eq!(board.has_legal_moves(), !moves.is_empty(), "{fen}");
+        }
+    }
+
+    #[test]
+    fn for_each_legal_move_stops_at_the_first_break() {
+        for fen in FENS {
+            let mut board = Board::from_str(fen).unwrap();
+
+            let mut calls = 0;
+            board.for_each_legal_move(|_| {
+                calls += 1;
+                ControlFlow::Break(())
+            });
+
+            let expected = board.generate_legal_moves().len().min(1);
+            assert_eq!(calls, expected, "{fen}");
         }
     }
+
+    #[test]
+    fn chess960_castling_checks_the_king_target_behind_the_rook() {
+        // The rook on b1 hides the king's target square from the rook on
+        // a1 until it castles, so castling would leave the king in check.
+        let castle = Move::new(Square::D1, Square::B1, MoveFlag::Castle);
+
+        let mut board = Board::from_str("4k3/8/8/8/8/8/8/rR1K4 w B - 0 1").unwrap();
+        assert!(!board.generate_legal_moves().contains(&castle));
+
+        let mut board = Board::from_str("4k3/8/8/8/8/8/8/nR1K4 w B - 0 1").unwrap();
+        assert!(board.generate_legal_moves().contains(&castle));
+    }
 }
@@ -12,7 +12,7 @@
 // limitations under the License.
 
 use super::{BitBoard, Color, File, Rank, Square};
-use std::ops;
+use std::{error::Error, fmt, ops, str::FromStr};
 
 #[derive(Copy, Clone, PartialEq, Eq, Default)]
 pub struct Rights(pub u8);
@@ -20,19 +20,89 @@ pub struct Rights(pub u8);
 impl Rights {
     pub const N: usize = 16;
 
-    pub const WH: Rights = Rights(SideColor(Color::White, Side::H).bit_offset() as u8);
-    pub const WA: Rights = Rights(SideColor(Color::White, Side::A).bit_offset() as u8);
-    pub const BH: Rights = Rights(SideColor(Color::Black, Side::H).bit_offset() as u8);
-    pub const BA: Rights = Rights(SideColor(Color::Black, Side::A).bit_offset() as u8);
+    pub const WH: Rights = Rights(1 << SideColor(Color::White, Side::H).bit_offset());
+    pub const WA: Rights = Rights(1 << SideColor(Color::White, Side::A).bit_offset());
+    pub const BH: Rights = Rights(1 << SideColor(Color::Black, Side::H).bit_offset());
+    pub const BA: Rights = Rights(1 << SideColor(Color::Black, Side::A).bit_offset());
 
     pub fn has(self, side: SideColor) -> bool {
         self.0 >> side.bit_offset() & 1 != 0
     }
 }
 
+impl fmt::Display for Rights {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if *self == Rights(0) {
+            return write!(f, "-");
+        }
+
+        let mut string_rep = String::from("");
+
+        if self.has(SideColor(Color::White, Side::H)) {
+            string_rep += "K";
+        }
+        if self.has(SideColor(Color::White, Side::A)) {
+            string_rep += "Q";
+        }
+        if self.has(SideColor(Color::Black, Side::H)) {
+            string_rep += "k";
+        }
+        if self.has(SideColor(Color::Black, Side::A)) {
+            string_rep += "q";
+        }
+
+        write!(f, "{string_rep}")
+    }
+}
+
+#[derive(Debug)]
+pub struct RightsParseError;
+
+impl fmt::Display for RightsParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "castling rights should be - or some of KQkq, without repeats"
+        )
+    }
+}
+
+impl Error for RightsParseError {}
+
+impl FromStr for Rights {
+    type Err = RightsParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "-" {
+            return Ok(Rights(0));
+        }
+
+        let mut rights = Rights(0);
+        for ident in s.chars() {
+            let right = match ident {
+                'K' => Rights::WH,
+                'Q' => Rights::WA,
+                'k' => Rights::BH,
+                'q' => Rights::BA,
+                _ => return Err(RightsParseError),
+            };
+
+            // Repeated rights are not allowed.
+            if rights.0 & right.0 != 0 {
+                return Err(RightsParseError);
+            }
+
+            rights = rights + right;
+        }
+
+        Ok(rights)
+    }
+}
+
 impl From<Color> for Rights {
     fn from(color: Color) -> Self {
-        Rights((1 << (4 + 1)) << color as u16)
+        // Each color has two consecutive rights bits.
+        Rights(0b11 << (color as usize * Color::N))
     }
 }
 
@@ -86,7 +156,7 @@ impl ops::Sub<Color> for Rights {
     }
 }
 
-#[derive(Copy, Clone, PartialEq, Eq)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
 pub struct SideColor(pub Color, pub Side);
 
 impl SideColor {
@@ -118,7 +188,7 @@ impl SideColor {
     }
 }
 
-#[derive(Copy, Clone, PartialEq, Eq)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
 #[rustfmt::skip]
 pub enum Side {
     H, A,
@@ -134,15 +204,29 @@ impl Side {
     }
 }
 
+/// Info contains the castling rights of a position along with the squares
+/// which castling depends on. It is a part of every Position, so the rights
+/// updates of each square are worked out from the king and rook squares when
+/// they are needed, instead of being kept in a table of every square.
+#[derive(Clone, Copy)]
 pub struct Info {
     pub rights: Rights,
+    kings: [Square; Color::N],
     rooks: [Square; SideColor::N],
     paths: [BitBoard; SideColor::N],
-    rights_masks: [Rights; Square::N],
+    king_paths: [BitBoard; SideColor::N],
 }
 
 impl Info {
-    //pub fn from_pos_and_str() -> Info {}
+    /// standard returns the Info of a standard chess position with the given
+    /// castling rights, where the kings start on the e-file and the rooks on
+    /// the a-file and the h-file.
+    pub fn standard(rights: Rights) -> Info {
+        let mut info =
+            Info::from_squares(Square::E1, File::H, File::A, Square::E8, File::H, File::A);
+        info.rights = rights;
+        info
+    }
 
     #[rustfmt::skip]
     pub fn from_squares(
@@ -151,51 +235,104 @@ impl Info {
     ) -> Info {
         let mut info = Info {
             rights: Rights(0),
+            kings: [w_king, b_king],
             rooks: [Square::default(); SideColor::N],
             paths: [BitBoard::default(); SideColor::N],
-            rights_masks: [Rights::default(); Square::N],
+            king_paths: [BitBoard::default(); SideColor::N],
         };
 
-        // Get the bit offsets/indexes of each side-color.
-        let wh = SideColor(Color::White, Side::H).bit_offset();
-        let wa = SideColor(Color::White, Side::A).bit_offset();
-        let bh = SideColor(Color::Black, Side::H).bit_offset();
-        let ba = SideColor(Color::Black, Side::A).bit_offset();
-
         // Initialize the rook square table.
-        info.rooks[wh] = Square::new(w_rook_h, Rank::First);
-        info.rooks[wa] = Square::new(w_rook_a, Rank::First);
-        info.rooks[bh] = Square::new(b_rook_h, Rank::Eighth);
-        info.rooks[ba] = Square::new(b_rook_a, Rank::Eighth);
-
-        // Initialize the castling path table.
-        info.paths[wh] = BitBoard::between(w_king, info.rooks[wh]) | BitBoard::from(w_king);
-        info.paths[wa] = BitBoard::between(w_king, info.rooks[wa]) | BitBoard::from(w_king);
-        info.paths[bh] = BitBoard::between(b_king, info.rooks[bh]) | BitBoard::from(b_king);
-        info.paths[ba] = BitBoard::between(b_king, info.rooks[ba]) | BitBoard::from(b_king);
-
-        // Initialize the rights update for the king's squares.
-        info.rights_masks[w_king as usize] = Rights::WH + Rights::WA;
-        info.rights_masks[b_king as usize] = Rights::BH + Rights::BA;
-
-        // Initialize the rights update for the rook's squares.
-        info.rights_masks[w_rook_h as usize] = Rights::WH;
-        info.rights_masks[w_rook_a as usize] = Rights::WA;
-        info.rights_masks[b_rook_h as usize] = Rights::BH;
-        info.rights_masks[b_rook_a as usize] = Rights::BA;
+        let sides = [
+            (SideColor(Color::White, Side::H), w_king, Square::new(w_rook_h, Rank::First)),
+            (SideColor(Color::White, Side::A), w_king, Square::new(w_rook_a, Rank::First)),
+            (SideColor(Color::Black, Side::H), b_king, Square::new(b_rook_h, Rank::Eighth)),
+            (SideColor(Color::Black, Side::A), b_king, Square::new(b_rook_a, Rank::Eighth)),
+        ];
+
+        // The king or the rook may already be on its target square in
+        // Chess960, and there are no squares between a square and itself.
+        let between = |source, target| {
+            if source == target {
+                BitBoard::EMPTY
+            } else {
+                BitBoard::between(source, target)
+            }
+        };
+
+        for (side, king, rook) in sides {
+            let offset = side.bit_offset();
+            let (king_target, rook_target) = side.get_targets();
+
+            info.rooks[offset] = rook;
+
+            // The squares the king moves through, which must not be attacked.
+            info.king_paths[offset] = between(king, king_target)
+                | BitBoard::from(king) | BitBoard::from(king_target);
+
+            // The squares the king and the rook move through, which must be
+            // empty, ignoring the castling king and rook themselves.
+            info.paths[offset] = (info.king_paths[offset]
+                | between(rook, rook_target) | BitBoard::from(rook_target))
+                - BitBoard::from(king) - BitBoard::from(rook);
+        }
 
         info
     }
 
+    /// get_updates returns the castling rights which are lost when a piece
+    /// moves from or to the given square: both rights of a color if it is
+    /// that color's king square, and the right of a side if it is that side's
+    /// rook square.
+    #[inline(always)]
     pub fn get_updates(&self, square: Square) -> Rights {
-        self.rights_masks[square as usize]
+        let mut updates = Rights(0);
+
+        for (offset, &rook) in self.rooks.iter().enumerate() {
+            if rook == square {
+                updates.0 |= 1 << offset;
+            }
+        }
+
+        for (color, &king) in self.kings.iter().enumerate() {
+            if king == square {
+                updates = updates + Rights::from(Color::from(color));
+            }
+        }
+
+        updates
+    }
+
+    /// king returns the square the king of the given color castles from.
+    pub fn king(&self, color: Color) -> Square {
+        self.kings[color as usize]
     }
 
     pub fn rook(&self, side: SideColor) -> Square {
         self.rooks[side.bit_offset()]
     }
 
+    /// is_standard checks if every castling right is for a king and a rook
+    /// on their standard squares, which is false for most Chess960 setups.
+    pub fn is_standard(&self) -> bool {
+        let standard = Info::standard(self.rights);
+        [Color::White, Color::Black].into_iter().all(|color| {
+            [SideColor(color, Side::H), SideColor(color, Side::A)]
+                .into_iter()
+                .filter(|&side| self.rights.has(side))
+                .all(|side| {
+                    self.king(color) == standard.king(color)
+                        && self.rook(side) == standard.rook(side)
+                })
+        })
+    }
+
+    /// path returns the squares which need to be empty for castling.
     pub fn path(&self, side: SideColor) -> BitBoard {
         self.paths[side.bit_offset()]
     }
+
+    /// king_path returns the squares which need to be unattacked for castling.
+    pub fn king_path(&self, side: SideColor) -> BitBoard {
+        self.king_paths[side.bit_offset()]
+    }
 }
@@ -11,14 +11,14 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::{fmt::Display, ops, str::FromStr};
+use std::{error::Error, fmt::Display, ops, str::FromStr};
 
 use crate::util::type_macros;
 
 use num_derive::FromPrimitive;
 use num_traits::FromPrimitive;
 
-#[derive(Copy, Clone, PartialEq, Eq, Default, FromPrimitive)]
+#[derive(Copy, Clone, PartialEq, Eq, Default, Debug, FromPrimitive)]
 #[rustfmt::skip]
 pub enum Color {
     White, Black, #[default] None,
@@ -40,6 +40,18 @@ type_macros::impl_from_integer_for_enum! {
 
 impl Color {
     pub const N: usize = 2;
+
+    /// from_index returns the Color with the given index, which is either
+    /// that of White or that of Black. It is a const fn so that the Color
+    /// of a const generic color index is known at compile time.
+    #[inline(always)]
+    pub const fn from_index(index: usize) -> Color {
+        if index == Color::White as usize {
+            Color::White
+        } else {
+            Color::Black
+        }
+    }
 }
 
 impl ops::Not for Color {
@@ -49,11 +61,23 @@ impl ops::Not for Color {
     }
 }
 
+#[derive(Debug)]
 pub enum ColorParseError {
     StringTooLong,
     StringFormatInvalid,
 }
 
+impl Display for ColorParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ColorParseError::StringTooLong => write!(f, "color should be a single character"),
+            ColorParseError::StringFormatInvalid => write!(f, "color should be either w or b"),
+        }
+    }
+}
+
+impl Error for ColorParseError {}
+
 impl Display for Color {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
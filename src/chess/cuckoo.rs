@@ -0,0 +1,154 @@
+// Copyright © 2023 Rak Laptudirm <rak@laptudirm.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::OnceLock;
+
+use super::{moves, zobrist, BitBoard, ColoredPiece, Move, MoveFlag, Piece, Square};
+
+/// N is the number of slots in the cuckoo table, which is large enough to
+/// hold the 3668 reversible moves of the non-pawn pieces.
+const N: usize = 8192;
+
+/// Table is a cuckoo hash table of the reversible moves of every non-pawn
+/// piece on an empty board, keyed by the difference they make to the hash
+/// of a position: the keys of the piece on its source and target squares
+/// and the side to move key. Each key is stored in one of the two slots
+/// given by its hash functions, which makes lookups cheap.
+struct Table {
+    keys: [zobrist::Hash; N],
+    moves: [Move; N],
+}
+
+/// TABLE is the cuckoo table, which is generated the first time it is used.
+static TABLE: OnceLock<Box<Table>> = OnceLock::new();
+
+/// find returns the reversible move which changes the hash of a position by
+/// the given key, if there is one.
+pub fn find(key: zobrist::Hash) -> Option<Move> {
+    let table = TABLE.get_or_init(generate);
+
+    [h1(key), h2(key)]
+        .into_iter()
+        .find(|&slot| table.keys[slot] == key)
+        .map(|slot| table.moves[slot])
+}
+
+/// generate generates the cuckoo table.
+fn generate() -> Box<Table> {
+    let mut table = Box::new(Table {
+        keys: [zobrist::Hash::default(); N],
+        moves: [Move::NULL; N],
+    });
+
+    for piece in 0..ColoredPiece::N {
+        let piece = ColoredPiece::from(piece);
+
+        for source in 0..Square::N {
+            let source = Square::from(source);
+            let attacks = match piece.piece() {
+                Piece::Pawn => continue,
+                Piece::Knight => moves::knight(source),
+                Piece::Bishop => moves::bishop(source, BitBoard::EMPTY),
+                Piece::Rook => moves::rook(source, BitBoard::EMPTY),
+                Piece::Queen => moves::queen(source, BitBoard::EMPTY),
+                _ => moves::king(source),
+            };
+
+            // Each move is stored once, from its lower square to its higher
+            // square, since moves and their reverses have the same key.
+            for target in attacks {
+                if target as usize <= source as usize {
+                    continue;
+                }
+
+                let mut chessmove = Move::new(source, target, MoveFlag::Normal);
+                let mut key = zobrist::piece_square_key(piece, source)
+                    ^ zobrist::piece_square_key(piece, target)
+                    ^ zobrist::side_to_move_key();
+
+                // Insert the move into its first slot, and keep moving the
+                // move it displaces into its other slot until a slot is empty.
+                let mut slot = h1(key);
+                loop {
+                    std::mem::swap(&mut table.keys[slot], &mut key);
+                    std::mem::swap(&mut table.moves[slot], &mut chessmove);
+
+                    if chessmove == Move::NULL {
+                        break;
+                    }
+
+                    slot = if slot == h1(key) { h2(key) } else { h1(key) };
+                }
+            }
+        }
+    }
+
+    table
+}
+
+/// h1 is the first hash function of the cuckoo table.
+fn h1(key: zobrist::Hash) -> usize {
+    (u64::from(key) & (N as u64 - 1)) as usize
+}
+
+/// h2 is the second hash function of the cuckoo table.
+fn h2(key: zobrist::Hash) -> usize {
+    (u64::from(key) >> 16 & (N as u64 - 1)) as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::chess::{Board, Color};
+
+    use super::*;
+
+    fn play(moves: &[&str]) -> Board {
+        let mut board = Board::startpos();
+        board.apply_uci_moves(moves, false).unwrap();
+        board
+    }
+
+    #[test]
+    fn table_holds_every_reversible_move() {
+        let table = TABLE.get_or_init(generate);
+        let moves = table.moves.iter().filter(|&&m| m != Move::NULL).count();
+        assert_eq!(moves, 3668);
+
+        let knight = ColoredPiece::new(Piece::Knight, Color::White);
+        let key = zobrist::piece_square_key(knight, Square::G1)
+            ^ zobrist::piece_square_key(knight, Square::F3)
+            ^ zobrist::side_to_move_key();
+        assert_eq!(
+            find(key),
+            Some(Move::new(Square::F3, Square::G1, MoveFlag::Normal))
+        );
+    }
+
+    #[test]
+    fn upcoming_repetitions() {
+        // Black can repeat the starting position with Ng8.
+        let board = play(&["g1f3", "g8f6", "f3g1"]);
+        assert!(board.upcoming_repetition(4));
+
+        // Before the root, the position needs to have been repeated already.
+        assert!(!board.upcoming_repetition(0));
+        let board = play(&["g1f3", "g8f6", "f3g1", "f6g8", "g1f3", "g8f6", "f3g1"]);
+        assert!(board.upcoming_repetition(0));
+
+        // No single move leads back to an earlier position, and pawn moves
+        // can't be taken back.
+        assert!(!play(&["g1f3", "g8f6"]).upcoming_repetition(4));
+        assert!(!play(&["g1f3", "g8f6", "b1c3"]).upcoming_repetition(4));
+        assert!(!play(&["g1f3", "g8f6", "f3g1", "e7e5"]).upcoming_repetition(4));
+    }
+}
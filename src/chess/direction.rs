@@ -0,0 +1,162 @@
+// Copyright © 2023 Rak Laptudirm <rak@laptudirm.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::{BitBoard, Color, Square};
+
+/// Direction represents a direction on the chess board along which Squares
+/// and BitBoards can be shifted. North is towards the eighth rank and East
+/// is towards the h-file, as seen from White's side of the board.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[rustfmt::skip]
+pub enum Direction {
+    // Orthogonal directions.
+    North, South, East, West,
+
+    // Diagonal directions.
+    NorthEast, NorthWest, SouthEast, SouthWest,
+
+    // Knight jumps.
+    NorthNorthEast, NorthNorthWest, SouthSouthEast, SouthSouthWest,
+    EastNorthEast, EastSouthEast, WestNorthWest, WestSouthWest,
+}
+
+impl Direction {
+    /// N is the number of different directions.
+    pub const N: usize = 16;
+
+    /// up returns the direction in which the pawns of the given color push.
+    #[inline(always)]
+    pub fn up(color: Color) -> Direction {
+        if color == Color::White {
+            Direction::North
+        } else {
+            Direction::South
+        }
+    }
+
+    /// down returns the direction opposite to the one in which the pawns of
+    /// the given color push.
+    #[inline(always)]
+    pub fn down(color: Color) -> Direction {
+        if color == Color::White {
+            Direction::South
+        } else {
+            Direction::North
+        }
+    }
+
+    /// offset returns the change in square index on moving in the Direction.
+    #[inline(always)]
+    pub const fn offset(self) -> i8 {
+        let (file, rank) = self.deltas();
+
+        // Square indexes increase towards the east and the south.
+        file - rank * 8
+    }
+
+    /// deltas returns the change in file and rank on moving in the Direction.
+    /// Rank deltas are positive towards the north.
+    #[rustfmt::skip]
+    pub const fn deltas(self) -> (i8, i8) {
+        match self {
+            Direction::North => ( 0,  1),
+            Direction::South => ( 0, -1),
+            Direction::East  => ( 1,  0),
+            Direction::West  => (-1,  0),
+
+            Direction::NorthEast => ( 1,  1),
+            Direction::NorthWest => (-1,  1),
+            Direction::SouthEast => ( 1, -1),
+            Direction::SouthWest => (-1, -1),
+
+            Direction::NorthNorthEast => ( 1,  2),
+            Direction::NorthNorthWest => (-1,  2),
+            Direction::SouthSouthEast => ( 1, -2),
+            Direction::SouthSouthWest => (-1, -2),
+            Direction::EastNorthEast  => ( 2,  1),
+            Direction::EastSouthEast  => ( 2, -1),
+            Direction::WestNorthWest  => (-2,  1),
+            Direction::WestSouthWest  => (-2, -1),
+        }
+    }
+
+    /// source_mask returns a BitBoard of all the squares which stay on the
+    /// board when they are shifted in the Direction.
+    #[inline(always)]
+    pub const fn source_mask(self) -> BitBoard {
+        BitBoard(SOURCE_MASKS[self as usize])
+    }
+}
+
+impl BitBoard {
+    /// shift moves every Square in the BitBoard one step in the given
+    /// Direction. Squares which would fall off the board are dropped.
+    #[inline(always)]
+    pub fn shift(self, direction: Direction) -> BitBoard {
+        let bb = self.0 & direction.source_mask().0;
+        let offset = direction.offset();
+
+        if offset > 0 {
+            BitBoard(bb << offset)
+        } else {
+            BitBoard(bb >> -offset)
+        }
+    }
+}
+
+impl Square {
+    /// shift returns the Square one step away in the given Direction, or
+    /// None if the step would take it off the board.
+    #[inline(always)]
+    pub fn shift(self, direction: Direction) -> Option<Square> {
+        if self == Square::None || !direction.source_mask().contains(self) {
+            return None;
+        }
+
+        Some(Square::from(self as i8 + direction.offset()))
+    }
+}
+
+const fn source_mask(direction: Direction) -> u64 {
+    let (file_delta, rank_delta) = direction.deltas();
+
+    let mut mask = 0;
+    let mut square = 0;
+    while square < Square::N as i8 {
+        // Ranks are indexed from the eighth rank downwards.
+        let file = square % 8 + file_delta;
+        let rank = square / 8 - rank_delta;
+
+        if file >= 0 && file < 8 && rank >= 0 && rank < 8 {
+            mask |= 1 << square;
+        }
+
+        square += 1;
+    }
+
+    mask
+}
+
+#[rustfmt::skip]
+const SOURCE_MASKS: [u64; Direction::N] = [
+    source_mask(Direction::North), source_mask(Direction::South),
+    source_mask(Direction::East), source_mask(Direction::West),
+
+    source_mask(Direction::NorthEast), source_mask(Direction::NorthWest),
+    source_mask(Direction::SouthEast), source_mask(Direction::SouthWest),
+
+    source_mask(Direction::NorthNorthEast), source_mask(Direction::NorthNorthWest),
+    source_mask(Direction::SouthSouthEast), source_mask(Direction::SouthSouthWest),
+    source_mask(Direction::EastNorthEast), source_mask(Direction::EastSouthEast),
+    source_mask(Direction::WestNorthWest), source_mask(Direction::WestSouthWest),
+];
@@ -11,17 +11,26 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::{fmt::Display, num::ParseIntError, str::FromStr};
+use std::{error::Error, fmt::Display, num::ParseIntError, str::FromStr};
 
 use super::{
-    castling, Board, Color, ColorParseError, Mailbox, MailboxParseErr, Square, SquareParseError,
+    castling, Board, Color, ColorParseError, ColoredPiece, File, Mailbox, MailboxParseErr, Piece,
+    Position, Rank, Square, SquareParseError,
 };
 
+use castling::{Side, SideColor};
+
+/// STARTPOS_FEN is the FEN of the standard starting position.
+pub const STARTPOS_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
 // rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1
+//
+// The castling rights may name the files of the castling rooks instead of
+// their sides, like in Shredder-FEN and X-FEN, for Chess960 positions.
 pub struct FEN {
     pub position: Mailbox,
     pub side_to_move: Color,
-    pub castling_rights: castling::Rights,
+    pub castling: castling::Info,
     pub en_pass_square: Square,
     pub half_move_clock: u8,
     pub full_move_count: u16,
@@ -34,28 +43,40 @@ impl FEN {
     const EN_PASS_OFFSET: usize = 3;
     const HALF_MV_OFFSET: usize = 4;
     const FULL_MV_OFFSET: usize = 5;
+
+    /// MAX_FULL_MOVE_COUNT is the largest full move count a FEN may have.
+    /// It leaves plenty of headroom for the game to continue before its
+    /// ply count overflows.
+    pub const MAX_FULL_MOVE_COUNT: u16 = 16384;
 }
 
-impl From<&Board> for FEN {
-    fn from(board: &Board) -> Self {
+impl From<&Position> for FEN {
+    fn from(position: &Position) -> Self {
         FEN {
-            position: board.mailbox(),
-            side_to_move: board.side_to_move(),
-            castling_rights: castling::Rights::BA,
-            en_pass_square: board.en_passant_target(),
-            half_move_clock: board.draw_clock(),
-            full_move_count: board.plys() / 2 + 1,
+            position: position.mailbox(),
+            side_to_move: position.side_to_move(),
+            castling: position.castling_square_info,
+            en_pass_square: position.en_passant_target(),
+            half_move_clock: position.draw_clock(),
+            full_move_count: position.plys() / 2 + 1,
         }
     }
 }
 
+impl From<&Board> for FEN {
+    fn from(board: &Board) -> Self {
+        FEN::from(board.position())
+    }
+}
+
 impl Display for FEN {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "{} {} cas {} {} {}",
+            "{} {} {} {} {} {}",
             self.position,
             self.side_to_move,
+            format_castling(&self.castling, &self.position),
             self.en_pass_square,
             self.half_move_clock,
             self.full_move_count
@@ -63,16 +84,45 @@ impl Display for FEN {
     }
 }
 
+#[derive(Debug)]
 pub enum FENParseError {
     WrongFieldNumber,
     MailboxParseError(MailboxParseErr),
     SideToMoveParseError(ColorParseError),
-    CastlingParseError,
+    CastlingParseError(castling::RightsParseError),
     EnPassantSqParseError(SquareParseError),
     HalfMoveClockParseError(ParseIntError),
     FullMoveClockParseError(ParseIntError),
+    FullMoveCountOutOfRange(u16),
+}
+
+impl Display for FENParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FENParseError::WrongFieldNumber => write!(f, "fen should have 4 to 6 fields"),
+            FENParseError::MailboxParseError(err) => write!(f, "invalid piece placement: {err}"),
+            FENParseError::SideToMoveParseError(err) => write!(f, "invalid side to move: {err}"),
+            FENParseError::CastlingParseError(err) => write!(f, "invalid castling rights: {err}"),
+            FENParseError::EnPassantSqParseError(err) => {
+                write!(f, "invalid en passant square: {err}")
+            }
+            FENParseError::HalfMoveClockParseError(err) => {
+                write!(f, "invalid half move clock: {err}")
+            }
+            FENParseError::FullMoveClockParseError(err) => {
+                write!(f, "invalid full move count: {err}")
+            }
+            FENParseError::FullMoveCountOutOfRange(count) => write!(
+                f,
+                "full move count {count} should be between 1 and {}",
+                FEN::MAX_FULL_MOVE_COUNT
+            ),
+        }
+    }
 }
 
+impl Error for FENParseError {}
+
 impl FromStr for FEN {
     type Err = FENParseError;
 
@@ -80,8 +130,9 @@ impl FromStr for FEN {
         // Split fen into it's fields along the whitespace.
         let fields: Vec<&str> = s.split_whitespace().collect();
 
-        // Verify the presence of the 6 fen fields.
-        if fields.len() != 6 {
+        // Verify the presence of the fen fields. Many tools leave out the
+        // move counters, which default to those of a new game if missing.
+        if !(4..=6).contains(&fields.len()) {
             return Err(FENParseError::WrongFieldNumber);
         }
 
@@ -97,6 +148,12 @@ impl FromStr for FEN {
             Err(err) => return Err(FENParseError::SideToMoveParseError(err)),
         };
 
+        // Parse castling rights.
+        let castling = match parse_castling(fields[FEN::CASTLINGOFFSET], &position) {
+            Ok(info) => info,
+            Err(err) => return Err(FENParseError::CastlingParseError(err)),
+        };
+
         // Parse en passant target square.
         let en_pass_square = match Square::from_str(fields[FEN::EN_PASS_OFFSET]) {
             Ok(target) => target,
@@ -104,27 +161,280 @@ impl FromStr for FEN {
         };
 
         // Parse half move clock.
-        let half_move_clock = match str::parse::<u8>(fields[FEN::HALF_MV_OFFSET]) {
-            Ok(half_move_clock) => half_move_clock,
-            Err(err) => return Err(FENParseError::HalfMoveClockParseError(err)),
+        let half_move_clock = match fields.get(FEN::HALF_MV_OFFSET).map(|f| f.parse::<u8>()) {
+            None => 0,
+            Some(Ok(half_move_clock)) => half_move_clock,
+            Some(Err(err)) => return Err(FENParseError::HalfMoveClockParseError(err)),
         };
 
         // Parse full move count.
-        let full_move_count = match str::parse::<u16>(fields[FEN::FULL_MV_OFFSET]) {
-            Ok(full_move_count) => full_move_count,
-            Err(err) => return Err(FENParseError::FullMoveClockParseError(err)),
+        let full_move_count = match fields.get(FEN::FULL_MV_OFFSET).map(|f| f.parse::<u16>()) {
+            None => 1,
+            Some(Ok(full_move_count)) => full_move_count,
+            Some(Err(err)) => return Err(FENParseError::FullMoveClockParseError(err)),
         };
 
+        if !(1..=FEN::MAX_FULL_MOVE_COUNT).contains(&full_move_count) {
+            return Err(FENParseError::FullMoveCountOutOfRange(full_move_count));
+        }
+
         Ok(FEN {
             position,
             side_to_move,
-            castling_rights: castling::Rights::WH
-                + castling::Rights::WA
-                + castling::Rights::BH
-                + castling::Rights::BA,
+            castling,
             en_pass_square,
             half_move_clock,
             full_move_count,
         })
     }
 }
+
+/// parse_castling parses the castling rights of a FEN with the given piece
+/// placement. A right is either the side it castles to, as `KQkq`, which
+/// castles with the outermost rook on that side of the king, or the file of
+/// the castling rook, as `A` to `H` for white and `a` to `h` for black.
+/// Kings which aren't on their back rank castle from the e-file, and sides
+/// without a rook castle with the rook on the a-file or the h-file, so that
+/// those rights are rejected when the position is validated.
+fn parse_castling(
+    field: &str,
+    mailbox: &Mailbox,
+) -> Result<castling::Info, castling::RightsParseError> {
+    if field == "-" {
+        return Ok(castling::Info::standard(castling::Rights(0)));
+    }
+
+    let kings = [Color::White, Color::Black].map(|color| {
+        let king = ColoredPiece::new(Piece::King, color);
+        File::iter()
+            .map(|file| Square::new(file, Rank::First.relative(color)))
+            .find(|&square| mailbox.0[square as usize] == king)
+            .unwrap_or(Square::E1.relative(color))
+    });
+
+    let mut rights = castling::Rights(0);
+    let mut files = [[File::H, File::A]; Color::N];
+    for ident in field.chars() {
+        let color = if ident.is_ascii_uppercase() {
+            Color::White
+        } else {
+            Color::Black
+        };
+
+        let king = kings[color as usize];
+        let (side, file) = match ident.to_ascii_lowercase() {
+            'k' => (Side::H, outermost_rook(mailbox, color, king, Side::H)),
+            'q' => (Side::A, outermost_rook(mailbox, color, king, Side::A)),
+            ident @ 'a'..='h' => {
+                let file = File::from(ident as u8 - b'a');
+                if file == king.file() {
+                    return Err(castling::RightsParseError);
+                }
+
+                let rook = Square::new(file, Rank::First.relative(color));
+                (Side::from_sqs(king, rook), Some(file))
+            }
+            _ => return Err(castling::RightsParseError),
+        };
+
+        // Repeated rights are not allowed.
+        let side_color = SideColor(color, side);
+        if rights.has(side_color) {
+            return Err(castling::RightsParseError);
+        }
+
+        rights = rights + side_color;
+        if let Some(file) = file {
+            files[color as usize][side as usize] = file;
+        }
+    }
+
+    let [white, black] = files;
+    let mut info = castling::Info::from_squares(
+        kings[0],
+        white[Side::H as usize],
+        white[Side::A as usize],
+        kings[1],
+        black[Side::H as usize],
+        black[Side::A as usize],
+    );
+    info.rights = rights;
+
+    Ok(info)
+}
+
+/// format_castling formats the castling rights of a FEN with the given piece
+/// placement like X-FEN does: rights which castle with the outermost rook on
+/// their side of the king are written as `KQkq`, and the others are written
+/// as the file of their rook, so standard positions always use `KQkq`.
+fn format_castling(info: &castling::Info, mailbox: &Mailbox) -> String {
+    let mut field = String::new();
+    for color in [Color::White, Color::Black] {
+        for side in [Side::H, Side::A] {
+            let side_color = SideColor(color, side);
+            if !info.rights.has(side_color) {
+                continue;
+            }
+
+            let file = info.rook(side_color).file();
+            let ident = match outermost_rook(mailbox, color, info.king(color), side) {
+                Some(outermost) if outermost != file => (b'a' + file as u8) as char,
+                _ if side == Side::H => 'k',
+                _ => 'q',
+            };
+
+            field.push(match color {
+                Color::White => ident.to_ascii_uppercase(),
+                _ => ident,
+            });
+        }
+    }
+
+    if field.is_empty() {
+        field.push('-');
+    }
+
+    field
+}
+
+/// outermost_rook returns the file of the rook of the given color which is
+/// the furthest from its king on the given side of it on their back rank.
+fn outermost_rook(mailbox: &Mailbox, color: Color, king: Square, side: Side) -> Option<File> {
+    let rook = ColoredPiece::new(Piece::Rook, color);
+    let is_rook =
+        |file: &File| mailbox.0[Square::new(*file, Rank::First.relative(color)) as usize] == rook;
+
+    match side {
+        Side::H => File::iter()
+            .filter(|&file| file > king.file())
+            .filter(is_rook)
+            .last(),
+        Side::A => File::iter()
+            .take_while(|&file| file < king.file())
+            .find(is_rook),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_move_counters_default_to_a_new_game() {
+        let fen = FEN::from_str("8/8/8/8/8/8/8/K6k b - -").unwrap();
+        assert_eq!((fen.half_move_clock, fen.full_move_count), (0, 1));
+        assert_eq!(Position::from(fen).plys(), 1);
+
+        let fen = FEN::from_str("8/8/8/8/8/8/8/K6k w - - 7").unwrap();
+        assert_eq!((fen.half_move_clock, fen.full_move_count), (7, 1));
+
+        assert!(matches!(
+            FEN::from_str("8/8/8/8/8/8/8/K6k w -"),
+            Err(FENParseError::WrongFieldNumber)
+        ));
+        assert!(matches!(
+            FEN::from_str("8/8/8/8/8/8/8/K6k w - - 0 1 0"),
+            Err(FENParseError::WrongFieldNumber)
+        ));
+    }
+
+    #[test]
+    fn zero_move_counters() {
+        let fen = FEN::from_str("8/8/8/8/8/8/8/K6k w - - 0 1").unwrap();
+        assert_eq!(fen.half_move_clock, 0);
+
+        assert!(matches!(
+            FEN::from_str("8/8/8/8/8/8/8/K6k w - - 0 0"),
+            Err(FENParseError::FullMoveCountOutOfRange(0))
+        ));
+    }
+
+    #[test]
+    fn startpos_keyword_parses_as_a_board() {
+        let board = Board::from_str("startpos").unwrap();
+        assert_eq!(FEN::from(&board).to_string(), STARTPOS_FEN);
+    }
+
+    #[test]
+    fn full_move_count_is_range_checked() {
+        let fen = FEN::from_str("8/8/8/8/8/8/8/K6k b - - 0 16384").unwrap();
+        assert_eq!(Position::from(fen).plys(), 32767);
+
+        for count in ["0", "16385", "60000"] {
+            let fen = format!("8/8/8/8/8/8/8/K6k w - - 0 {count}");
+            assert!(matches!(
+                FEN::from_str(&fen),
+                Err(FENParseError::FullMoveCountOutOfRange(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn position_saturates_unchecked_full_move_counts() {
+        let mut fen = FEN::from_str("8/8/8/8/8/8/8/K6k b - - 0 1").unwrap();
+
+        fen.full_move_count = 0;
+        assert_eq!(Position::from(fen).plys(), 1);
+
+        let mut fen = FEN::from_str("8/8/8/8/8/8/8/K6k b - - 0 1").unwrap();
+        fen.full_move_count = 60000;
+        assert_eq!(Position::from(fen).plys(), u16::MAX);
+    }
+
+    #[test]
+    fn chess960_castling_rights() {
+        // Shredder-FEN rights are written back like in X-FEN, which only
+        // names the file of a rook if it isn't the outermost one.
+        let fen =
+            FEN::from_str("bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9")
+                .unwrap();
+        assert_eq!(fen.castling.king(Color::White), Square::G1);
+        assert_eq!(
+            fen.castling.rook(SideColor(Color::White, Side::A)),
+            Square::F1
+        );
+        assert_eq!(
+            fen.castling.rook(SideColor(Color::Black, Side::H)),
+            Square::H8
+        );
+        assert!(!fen.castling.is_standard());
+        assert_eq!(
+            fen.to_string(),
+            "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w KQkq - 2 9"
+        );
+
+        let board = Board::from_str(&fen.to_string()).unwrap();
+        assert!(board.is_fischer_random());
+
+        // An inner rook is named by its file.
+        let fen = FEN::from_str("1r2k3/8/8/8/8/8/8/RR2K3 w Bb - 0 1").unwrap();
+        assert_eq!(
+            fen.castling.rook(SideColor(Color::White, Side::A)),
+            Square::B1
+        );
+        assert_eq!(fen.to_string(), "1r2k3/8/8/8/8/8/8/RR2K3 w Bq - 0 1");
+
+        let fen = FEN::from_str("1r2k3/8/8/8/8/8/8/RR2K3 w Q - 0 1").unwrap();
+        assert_eq!(
+            fen.castling.rook(SideColor(Color::White, Side::A)),
+            Square::A1
+        );
+
+        // Standard positions keep their usual rights.
+        let fen =
+            FEN::from_str("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w HAha - 0 1").unwrap();
+        assert!(fen.castling.is_standard());
+        assert_eq!(fen.to_string(), STARTPOS_FEN);
+
+        for rights in ["KK", "HK", "E", "x", "KQkqK"] {
+            let fen = format!("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w {rights} - 0 1");
+            assert!(
+                matches!(
+                    FEN::from_str(&fen),
+                    Err(FENParseError::CastlingParseError(_))
+                ),
+                "{rights}"
+            );
+        }
+    }
+}
@@ -11,15 +11,33 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::{fmt, str::FromStr};
+use std::{error::Error, fmt, str::FromStr};
 
-use colored::Colorize;
+use super::{ColoredPiece, File, Rank, Square};
 
-use super::{Color, ColoredPiece, File, Piece, Rank, Square};
-
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq)]
 pub struct Mailbox(pub [ColoredPiece; Square::N]);
 
+impl Mailbox {
+    /// STARTPOS is the Mailbox of the standard starting position.
+    #[rustfmt::skip]
+    pub const STARTPOS: Mailbox = {
+        use ColoredPiece::*;
+        const NO: ColoredPiece = ColoredPiece::None;
+
+        Mailbox([
+            BlackRook, BlackKnight, BlackBishop, BlackQueen, BlackKing, BlackBishop, BlackKnight, BlackRook,
+            BlackPawn, BlackPawn,   BlackPawn,   BlackPawn,  BlackPawn, BlackPawn,   BlackPawn,   BlackPawn,
+            NO,        NO,          NO,          NO,         NO,        NO,          NO,          NO,
+            NO,        NO,          NO,          NO,         NO,        NO,          NO,          NO,
+            NO,        NO,          NO,          NO,         NO,        NO,          NO,          NO,
+            NO,        NO,          NO,          NO,         NO,        NO,          NO,          NO,
+            WhitePawn, WhitePawn,   WhitePawn,   WhitePawn,  WhitePawn, WhitePawn,   WhitePawn,   WhitePawn,
+            WhiteRook, WhiteKnight, WhiteBishop, WhiteQueen, WhiteKing, WhiteBishop, WhiteKnight, WhiteRook,
+        ])
+    };
+}
+
 #[derive(Debug)]
 pub enum MailboxParseErr {
     JumpTooLong,
@@ -28,6 +46,21 @@ pub enum MailboxParseErr {
     TooManyFields,
 }
 
+impl fmt::Display for MailboxParseErr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MailboxParseErr::JumpTooLong => write!(f, "empty squares run past the end of a rank"),
+            MailboxParseErr::InvalidPieceIdent => {
+                write!(f, "pieces should be one of pnbrqk, in upper case for white")
+            }
+            MailboxParseErr::FileDataIncomplete => write!(f, "a rank has less than 8 squares"),
+            MailboxParseErr::TooManyFields => write!(f, "there are more than 8 ranks"),
+        }
+    }
+}
+
+impl Error for MailboxParseErr {}
+
 impl FromStr for Mailbox {
     type Err = MailboxParseErr;
 
@@ -127,6 +160,11 @@ impl fmt::Display for Mailbox {
             }
         }
 
+        // Flush the empty squares at the end of the first rank.
+        if empty_counter > 0 {
+            string_rep += &empty_counter.to_string();
+        }
+
         write!(f, "{string_rep}")
     }
 }
@@ -0,0 +1,125 @@
+// Copyright © 2023 Rak Laptudirm <rak@laptudirm.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fmt;
+
+use super::{Board, Color, Piece};
+
+/// MaterialKey is a summary of the material in a position, which contains the
+/// number of pieces of each kind that each side has, regardless of where
+/// they are. It is useful for recognizing specific endgames.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Default)]
+pub struct MaterialKey {
+    counts: [[u8; Piece::N]; Color::N],
+}
+
+impl MaterialKey {
+    /// count returns the number of pieces of the given kind and Color.
+    pub fn count(&self, piece: Piece, color: Color) -> u32 {
+        self.counts[color as usize][piece as usize] as u32
+    }
+
+    /// material returns the total material value of the pieces of the given
+    /// Color, by the conventional values of the pieces.
+    pub fn material(&self, color: Color) -> i32 {
+        self.counts[color as usize]
+            .iter()
+            .enumerate()
+            .map(|(piece, &count)| Piece::from(piece).value() * count as i32)
+            .sum()
+    }
+
+    /// mirrored returns the MaterialKey with the material of the two sides
+    /// swapped around.
+    pub fn mirrored(&self) -> MaterialKey {
+        let [white, black] = self.counts;
+        MaterialKey {
+            counts: [black, white],
+        }
+    }
+}
+
+impl From<&Board> for MaterialKey {
+    fn from(board: &Board) -> Self {
+        let mut key = MaterialKey::default();
+        for color in [Color::White, Color::Black] {
+            for piece in 0..Piece::N {
+                let piece = Piece::from(piece);
+                key.counts[color as usize][piece as usize] = board.count(piece, color) as u8;
+            }
+        }
+
+        key
+    }
+}
+
+impl fmt::Display for MaterialKey {
+    /// fmt formats the MaterialKey in the usual endgame notation, with the
+    /// pieces of each side from the most valuable to the least valuable and
+    /// the white pieces first, like KRPvKR.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        const PIECES: [(Piece, char); Piece::N] = [
+            (Piece::King, 'K'),
+            (Piece::Queen, 'Q'),
+            (Piece::Rook, 'R'),
+            (Piece::Bishop, 'B'),
+            (Piece::Knight, 'N'),
+            (Piece::Pawn, 'P'),
+        ];
+
+        for (i, color) in [Color::White, Color::Black].into_iter().enumerate() {
+            if i > 0 {
+                write!(f, "v")?;
+            }
+
+            for (piece, ident) in PIECES {
+                for _ in 0..self.count(piece, color) {
+                    write!(f, "{ident}")?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Board {
+    /// count returns the number of pieces of the given kind and Color on
+    /// the Board.
+    #[inline(always)]
+    pub fn count(&self, piece: Piece, color: Color) -> u32 {
+        self.piece_color_bb(piece, color).popcnt()
+    }
+
+    /// material returns the total material value of the pieces of the given
+    /// Color on the Board, by the conventional values of the pieces.
+    pub fn material(&self, color: Color) -> i32 {
+        (0..Piece::N)
+            .map(Piece::from)
+            .map(|piece| piece.value() * self.count(piece, color) as i32)
+            .sum()
+    }
+
+    /// has_non_pawn_material checks if the given Color has any pieces other
+    /// than its pawns and king on the Board. Positions without them are the
+    /// ones where zugzwang is common.
+    pub fn has_non_pawn_material(&self, color: Color) -> bool {
+        let pawns_and_kings = self.piece_bb(Piece::Pawn) | self.piece_bb(Piece::King);
+        !(self.color_bb(color) & !pawns_and_kings).is_empty()
+    }
+
+    /// material_key returns the MaterialKey of the position on the Board.
+    pub fn material_key(&self) -> MaterialKey {
+        MaterialKey::from(self)
+    }
+}
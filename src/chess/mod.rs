@@ -1,17 +1,27 @@
 // Namespaced modules.
 pub mod castling;
 pub mod moves;
+pub mod variant;
 pub mod zobrist;
 
 // Non-namespaced modules.
 mod bitboard;
 mod board;
 mod color;
+mod cuckoo;
+mod direction;
 mod fen;
 mod mailbox;
+mod material;
 mod r#move;
+mod outcome;
+mod packed;
 mod piece;
+mod position;
+mod san;
+mod see;
 mod square;
+mod validation;
 
 // Make the contents of the non-namespaced
 // modules public, so they can be accessed
@@ -19,8 +29,15 @@ mod square;
 pub use self::bitboard::*;
 pub use self::board::*;
 pub use self::color::*;
+pub use self::direction::*;
 pub use self::fen::*;
 pub use self::mailbox::*;
+pub use self::material::*;
+pub use self::outcome::*;
+pub use self::packed::*;
 pub use self::piece::*;
+pub use self::position::*;
 pub use self::r#move::*;
+pub use self::san::*;
 pub use self::square::*;
+pub use self::validation::*;
@@ -13,13 +13,22 @@
 
 use std::fmt;
 
-use crate::chess;
+use crate::chess::{self, castling};
+use crate::util::array_vec::ArrayVec;
 use crate::util::type_macros;
 
 use num_derive::FromPrimitive;
 use num_traits::FromPrimitive;
 
-#[derive(Copy, Clone, PartialEq, Default)]
+/// MAX_MOVES is the capacity of a MoveList, which is more than the number
+/// of legal moves in any position.
+pub const MAX_MOVES: usize = 256;
+
+/// MoveList is a list of moves which can hold all of the legal moves of any
+/// position. It is stored inline, so building one doesn't allocate.
+pub type MoveList = ArrayVec<Move, MAX_MOVES>;
+
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Default, Debug)]
 pub struct Move(u16);
 
 impl Move {
@@ -87,6 +96,44 @@ impl Move {
     pub fn flags(self) -> MoveFlag {
         MoveFlag::from(((self.0 >> Move::MVFLAG_OFFSET) & Move::MVFLAG_MASK) as u8)
     }
+
+    /// is_promotion checks if the Move is a pawn promotion.
+    #[inline(always)]
+    pub fn is_promotion(self) -> bool {
+        self.flags() == MoveFlag::Promotion
+    }
+
+    /// is_castle checks if the Move is a castling move.
+    #[inline(always)]
+    pub fn is_castle(self) -> bool {
+        self.flags() == MoveFlag::Castle
+    }
+
+    /// is_en_passant checks if the Move is an en passant capture.
+    #[inline(always)]
+    pub fn is_en_passant(self) -> bool {
+        self.flags() == MoveFlag::EnPassant
+    }
+
+    /// to_uci formats the Move in the UCI long algebraic notation. Castling
+    /// moves are written as the king capturing its own rook in Chess960, and
+    /// as the king moving two squares otherwise. Promotions have a lower case
+    /// suffix with the promotion piece, and the null move is written as 0000.
+    pub fn to_uci(self, chess960: bool) -> String {
+        if self == Move::NULL {
+            return String::from("0000");
+        }
+
+        match self.flags() {
+            MoveFlag::Castle if !chess960 => {
+                let side = castling::SideColor::from_sqs(self.source(), self.target());
+                let (king_target, _) = side.get_targets();
+                format!("{}{king_target}", self.source())
+            }
+
+            _ => self.to_string(),
+        }
+    }
 }
 
 #[derive(Copy, Clone, PartialEq, Eq, Default, FromPrimitive)]
@@ -111,6 +158,16 @@ type_macros::impl_from_integer_for_enum! {
 
 impl fmt::Display for Move {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}{}", self.source(), self.target())
+        write!(f, "{}{}", self.source(), self.target())?;
+
+        if self.is_promotion() {
+            write!(f, "{}", PROMOTION_IDENTS[self.promot() as usize] as char)?;
+        }
+
+        Ok(())
     }
 }
+
+/// PROMOTION_IDENTS contains the lower case identifiers of each Piece used as
+/// the suffixes of promotions, indexed by the Piece.
+const PROMOTION_IDENTS: [u8; chess::Piece::N] = *b"pnbrqk";
@@ -0,0 +1,35 @@
+// Copyright © 2023 Rak Laptudirm <rak@laptudirm.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::Color;
+
+/// Outcome is the outcome of a finished game.
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    /// Win is a game won by the given Color.
+    Win(Color),
+
+    /// Draw is a drawn game.
+    Draw,
+}
+
+impl Outcome {
+    /// winner returns the Color which won the game, or Color::None if the
+    /// game was drawn.
+    pub fn winner(self) -> Color {
+        match self {
+            Outcome::Win(color) => color,
+            Outcome::Draw => Color::None,
+        }
+    }
+}
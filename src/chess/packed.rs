@@ -0,0 +1,296 @@
+// Copyright © 2023 Rak Laptudirm <rak@laptudirm.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{error::Error, fmt};
+
+use super::castling::{self, Side, SideColor};
+use super::{Board, Color, ColoredPiece, Mailbox, Piece, Position, PositionError, Square, FEN};
+
+/// PackedBoard is a compact binary encoding of a position, for storing large
+/// numbers of them, like in training datasets. It is made up of the occupancy
+/// of the position, a nibble for each of the at most 32 pieces in the order
+/// of the occupancy, the side to move and en passant square, the halfmove
+/// clock, and the fullmove number. Rooks which can still castle are encoded
+/// as separate pieces. This is the same as the position part of marlinflow's
+/// marlinformat, so squares are numbered from a1 to h8.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct PackedBoard {
+    occupancy: u64,
+    pieces: u128,
+    stm_en_passant: u8,
+    draw_clock: u8,
+    full_moves: u16,
+}
+
+impl PackedBoard {
+    /// SIZE is the size of a PackedBoard's byte encoding.
+    pub const SIZE: usize = 28;
+
+    /// to_bytes returns the byte encoding of the PackedBoard, whose multi-byte
+    /// fields are little endian.
+    pub fn to_bytes(&self) -> [u8; PackedBoard::SIZE] {
+        let mut bytes = [0; PackedBoard::SIZE];
+        bytes[0..8].copy_from_slice(&self.occupancy.to_le_bytes());
+        bytes[8..24].copy_from_slice(&self.pieces.to_le_bytes());
+        bytes[24] = self.stm_en_passant;
+        bytes[25] = self.draw_clock;
+        bytes[26..28].copy_from_slice(&self.full_moves.to_le_bytes());
+        bytes
+    }
+
+    /// from_bytes decodes a PackedBoard from its byte encoding. The encoded
+    /// position isn't checked until the PackedBoard is unpacked.
+    pub fn from_bytes(bytes: [u8; PackedBoard::SIZE]) -> PackedBoard {
+        PackedBoard {
+            occupancy: u64::from_le_bytes(bytes[0..8].try_into().unwrap()),
+            pieces: u128::from_le_bytes(bytes[8..24].try_into().unwrap()),
+            stm_en_passant: bytes[24],
+            draw_clock: bytes[25],
+            full_moves: u16::from_le_bytes(bytes[26..28].try_into().unwrap()),
+        }
+    }
+
+    /// unpack decodes the Position encoded in the PackedBoard, which can be
+    /// turned into a Board with Board::from. Only standard chess positions
+    /// can be encoded, so the castling rooks are on their usual squares.
+    pub fn unpack(&self) -> Result<Position, PackedBoardError> {
+        if self.occupancy.count_ones() > 32 {
+            return Err(PackedBoardError::TooManyPieces);
+        }
+
+        let mut mailbox = Mailbox([ColoredPiece::None; Square::N]);
+        let mut rights = castling::Rights(0);
+
+        let mut occupancy = self.occupancy;
+        let mut index = 0;
+        while occupancy != 0 {
+            let square = Square::from((occupancy.trailing_zeros() ^ 56) as usize);
+            occupancy &= occupancy - 1;
+
+            let nibble = (self.pieces >> (index * 4)) as u8 & 0xF;
+            index += 1;
+
+            let color = if nibble & 8 == 0 {
+                Color::White
+            } else {
+                Color::Black
+            };
+
+            let piece = match nibble & 7 {
+                UNMOVED_ROOK => {
+                    rights = rights + castling_side(square, color)?;
+                    Piece::Rook
+                }
+
+                code if code < Piece::N as u8 => Piece::from(code as usize),
+                _ => return Err(PackedBoardError::InvalidPiece(square)),
+            };
+
+            mailbox.0[square as usize] = ColoredPiece::new(piece, color);
+        }
+
+        let side_to_move = if self.stm_en_passant >> 7 == 0 {
+            Color::White
+        } else {
+            Color::Black
+        };
+
+        let en_passant = match self.stm_en_passant & 0x7F {
+            NO_SQUARE => Square::None,
+            square if square < NO_SQUARE => Square::from((square ^ 56) as usize),
+            _ => return Err(PackedBoardError::InvalidEnPassant),
+        };
+
+        let position = Position::from(FEN {
+            position: mailbox,
+            side_to_move,
+            castling: castling::Info::standard(rights),
+            en_pass_square: en_passant,
+            half_move_clock: self.draw_clock,
+            full_move_count: self.full_moves.clamp(1, FEN::MAX_FULL_MOVE_COUNT),
+        });
+
+        position
+            .validate()
+            .map_err(PackedBoardError::IllegalPosition)?;
+        Ok(position)
+    }
+}
+
+impl From<&Position> for PackedBoard {
+    fn from(position: &Position) -> Self {
+        // Rooks which can still castle are encoded as unmoved rooks.
+        let rights = position.castling_rights();
+        let unmoved_rooks: Vec<Square> = [Color::White, Color::Black]
+            .into_iter()
+            .flat_map(|color| [SideColor(color, Side::H), SideColor(color, Side::A)])
+            .filter(|&side| rights.has(side))
+            .map(|side| position.castling_square_info.rook(side))
+            .collect();
+
+        let mut squares: Vec<Square> = position.occupied().into_iter().collect();
+        squares.sort_unstable_by_key(|&square| square as u8 ^ 56);
+
+        let mut occupancy = 0;
+        let mut pieces = 0;
+        for (index, square) in squares.into_iter().enumerate() {
+            let piece = position.piece_at(square);
+            let code = if unmoved_rooks.contains(&square) {
+                UNMOVED_ROOK
+            } else {
+                piece.piece() as u8
+            };
+
+            let code = code | ((piece.color() == Color::Black) as u8) << 3;
+
+            occupancy |= 1 << (square as u8 ^ 56);
+            pieces |= (code as u128) << (index * 4);
+        }
+
+        let en_passant = match position.en_passant_target() {
+            Square::None => NO_SQUARE,
+            square => square as u8 ^ 56,
+        };
+
+        PackedBoard {
+            occupancy,
+            pieces,
+            stm_en_passant: ((position.side_to_move() == Color::Black) as u8) << 7 | en_passant,
+            draw_clock: position.draw_clock(),
+            full_moves: position.plys() / 2 + 1,
+        }
+    }
+}
+
+impl From<&Board> for PackedBoard {
+    fn from(board: &Board) -> Self {
+        PackedBoard::from(board.position())
+    }
+}
+
+/// PackedBoardError is the reason a PackedBoard couldn't be unpacked.
+#[derive(Debug)]
+pub enum PackedBoardError {
+    TooManyPieces,
+    InvalidPiece(Square),
+    InvalidCastlingRook(Square),
+    InvalidEnPassant,
+    IllegalPosition(PositionError),
+}
+
+impl fmt::Display for PackedBoardError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PackedBoardError::TooManyPieces => write!(f, "more than 32 pieces"),
+            PackedBoardError::InvalidPiece(square) => write!(f, "invalid piece on {square}"),
+            PackedBoardError::InvalidCastlingRook(square) => {
+                write!(f, "unmoved rook on {square} can't castle")
+            }
+            PackedBoardError::InvalidEnPassant => write!(f, "invalid en passant square"),
+            PackedBoardError::IllegalPosition(err) => write!(f, "illegal position: {err}"),
+        }
+    }
+}
+
+impl Error for PackedBoardError {}
+
+/// UNMOVED_ROOK is the piece code of the rooks which can still castle.
+const UNMOVED_ROOK: u8 = 6;
+
+/// NO_SQUARE is the square code of a missing en passant square.
+const NO_SQUARE: u8 = 64;
+
+/// castling_side returns the side the given Color's unmoved rook on the
+/// given Square castles to, which must be one of its corner squares.
+fn castling_side(square: Square, color: Color) -> Result<SideColor, PackedBoardError> {
+    let side = if square == Square::H1.relative(color) {
+        Side::H
+    } else if square == Square::A1.relative(color) {
+        Side::A
+    } else {
+        return Err(PackedBoardError::InvalidCastlingRook(square));
+    };
+
+    Ok(SideColor(color, side))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    #[rustfmt::skip]
+    const POSITIONS: [&str; 6] = [
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+        "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w Kq f6 0 3",
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 b - - 37 62",
+    ];
+
+    #[test]
+    fn pack_unpack_round_trips() {
+        for fen in POSITIONS {
+            let board = Board::from_str(fen).unwrap();
+            let packed = PackedBoard::from(&board);
+
+            let position = PackedBoard::from_bytes(packed.to_bytes()).unpack().unwrap();
+            assert_eq!(FEN::from(&position).to_string(), fen);
+            assert_eq!(position.hash(), board.position().hash(), "{fen}");
+        }
+    }
+
+    #[test]
+    fn startpos_encoding() {
+        let packed = PackedBoard::from(&Board::startpos());
+        let bytes = packed.to_bytes();
+
+        assert_eq!(bytes[0..8], 0xFFFF00000000FFFF_u64.to_le_bytes());
+        // Unmoved white rook, white knight, and so on from a1.
+        assert_eq!(bytes[8], 0x16);
+        assert_eq!(bytes[24..28], [64, 0, 1, 0]);
+    }
+
+    #[test]
+    fn corrupt_encodings_are_errors() {
+        let mut bytes = PackedBoard::from(&Board::startpos()).to_bytes();
+        bytes[8] = 0x76;
+        assert!(matches!(
+            PackedBoard::from_bytes(bytes).unpack(),
+            Err(PackedBoardError::InvalidPiece(Square::B1))
+        ));
+
+        let mut bytes = PackedBoard::from(&Board::startpos()).to_bytes();
+        bytes[8] = 0x66;
+        assert!(matches!(
+            PackedBoard::from_bytes(bytes).unpack(),
+            Err(PackedBoardError::InvalidCastlingRook(Square::B1))
+        ));
+
+        let mut bytes = PackedBoard::from(&Board::startpos()).to_bytes();
+        bytes[24] = 65;
+        assert!(matches!(
+            PackedBoard::from_bytes(bytes).unpack(),
+            Err(PackedBoardError::InvalidEnPassant)
+        ));
+
+        let mut bytes = PackedBoard::from(&Board::startpos()).to_bytes();
+        bytes[0..8].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert!(matches!(
+            PackedBoard::from_bytes(bytes).unpack(),
+            Err(PackedBoardError::TooManyPieces)
+        ));
+    }
+}
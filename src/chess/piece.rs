@@ -34,7 +34,7 @@ impl ColoredPiece {
 
     #[inline(always)]
     pub fn new(piece: Piece, color: chess::Color) -> ColoredPiece {
-        ColoredPiece::from(color as usize * 2 + piece as usize)
+        ColoredPiece::from(color as usize * Piece::N + piece as usize)
     }
 
     #[inline(always)]
@@ -83,6 +83,29 @@ pub enum Piece {
 
 impl Piece {
     pub const N: usize = 6;
+
+    /// value returns the conventional material value of the Piece in
+    /// centipawns. Kings can't be exchanged, so they have no value.
+    pub const fn value(self) -> i32 {
+        match self {
+            Piece::Pawn => 100,
+            Piece::Knight | Piece::Bishop => 300,
+            Piece::Rook => 500,
+            Piece::Queen => 900,
+            _ => 0,
+        }
+    }
+
+    /// phase returns how much a single piece of this kind on the board
+    /// contributes to the phase of the game. Pawns and kings don't.
+    pub const fn phase(self) -> u8 {
+        match self {
+            Piece::Knight | Piece::Bishop => 1,
+            Piece::Rook => 2,
+            Piece::Queen => 4,
+            _ => 0,
+        }
+    }
 }
 
 type_macros::impl_from_integer_for_enum! {
@@ -0,0 +1,417 @@
+// Copyright © 2023 Rak Laptudirm <rak@laptudirm.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fmt;
+
+use super::castling::{Side, SideColor};
+use super::variant::{self, Variant};
+use super::{
+    castling, moves, zobrist, BitBoard, Board, Color, ColoredPiece, Mailbox, Move, MoveFlag, Piece,
+    Rank, Square, FEN,
+};
+
+/// Position is an immutable snapshot of a chess position: the piece placement
+/// and the metadata needed to continue the game from it. Unlike a Board, it
+/// has no game history or move generation scratch space, which makes it a
+/// plain value of a few hundred bytes that can be freely copied and shared
+/// between threads. Moves can be made on copies of it with make_move.
+#[derive(Clone, Copy)]
+pub struct Position {
+    // 8x8 mailbox board representation for
+    // fast piece square lookup.
+    pub(crate) mailbox: Mailbox,
+
+    // BitBoard board representation.
+    pub(crate) color_bbs: [BitBoard; Color::N],
+    pub(crate) piece_bbs: [BitBoard; Piece::N],
+
+    // Position metadata.
+    pub(crate) side_to_mv: Color,
+    pub(crate) plys_count: u16,
+    pub(crate) draw_clock: u8,
+    pub(crate) enp_target: Square,
+
+    // Game metadata.
+    pub(crate) variant: &'static dyn Variant,
+    pub(crate) is_fischer_random: bool,
+    pub(crate) castling_square_info: castling::Info,
+
+    pub(crate) hash: zobrist::Hash,
+
+    // Zobrist hash of only the pawns in the position.
+    pub(crate) pawn_hash: zobrist::Hash,
+
+    // Zobrist hash of the number of each piece in the position.
+    pub(crate) material_hash: zobrist::Hash,
+
+    // Sum of the phase of each piece in the position, which isn't capped.
+    pub(crate) phase: u8,
+}
+
+impl PartialEq for Position {
+    fn eq(&self, other: &Self) -> bool {
+        self.same_position(other)
+    }
+}
+
+impl Eq for Position {}
+
+// Boards own their scratch space and may be moved between threads, while
+// Positions are immutable snapshots which may also be shared between them.
+const fn assert_send<T: Send>() {}
+const fn assert_sync<T: Sync>() {}
+const _: () = assert_send::<Board>();
+const _: () = assert_send::<Position>();
+const _: () = assert_sync::<Position>();
+
+impl From<FEN> for Position {
+    fn from(fen: FEN) -> Self {
+        let mut position = Position {
+            mailbox: fen.position,
+
+            piece_bbs: [BitBoard::EMPTY; Piece::N],
+            color_bbs: [BitBoard::EMPTY; Color::N],
+
+            side_to_mv: fen.side_to_move,
+            plys_count: fen
+                .full_move_count
+                .saturating_sub(1)
+                .saturating_mul(2)
+                .saturating_add(fen.side_to_move as u16),
+            draw_clock: fen.half_move_clock,
+            enp_target: fen.en_pass_square,
+
+            variant: &variant::Standard,
+            is_fischer_random: !fen.castling.is_standard(),
+            hash: zobrist::castling_rights_key(fen.castling.rights),
+            pawn_hash: zobrist::Hash::default(),
+            material_hash: zobrist::Hash::default(),
+            phase: 0,
+            castling_square_info: fen.castling,
+        };
+
+        for (square, piece) in position.mailbox.0.iter().enumerate() {
+            let piece = *piece;
+
+            if piece == ColoredPiece::None {
+                continue;
+            }
+
+            let square = Square::from(square);
+
+            let count = position.colored_piece_bb(piece).popcnt();
+            position.material_hash ^= zobrist::material_key(piece, count);
+            position.phase += piece.piece().phase();
+
+            position.piece_bbs[piece.piece() as usize].insert(square);
+            position.color_bbs[piece.color() as usize].insert(square);
+
+            position.hash ^= zobrist::piece_square_key(piece, square);
+            if piece.piece() == Piece::Pawn {
+                position.pawn_hash ^= zobrist::piece_square_key(piece, square);
+            }
+        }
+
+        if position.side_to_mv == Color::Black {
+            position.hash ^= zobrist::side_to_move_key();
+        }
+
+        if position.enp_target != Square::None {
+            position.hash ^= zobrist::en_passant_key(position.enp_target);
+        }
+
+        position
+    }
+}
+
+impl From<&Board> for Position {
+    fn from(board: &Board) -> Self {
+        *board.position()
+    }
+}
+
+impl Position {
+    /// make_move returns the Position after the given legal move is made in
+    /// this one, which is left as it is. Positions are small plain values,
+    /// so this is a cheap way to look at what a move leads to without the
+    /// history and the move generation state of a Board.
+    pub fn make_move(&self, chessmove: Move) -> Position {
+        let mut position = *self;
+        position.apply_move(chessmove);
+        position
+    }
+
+    /// apply_move makes the given legal move in the Position.
+    pub(crate) fn apply_move(&mut self, chessmove: Move) {
+        match self.side_to_mv {
+            Color::White => self.apply_move_for::<{ Color::White as usize }>(chessmove),
+            _ => self.apply_move_for::<{ Color::Black as usize }>(chessmove),
+        }
+    }
+
+    /// apply_move_for makes the given legal move in the Position, with the
+    /// given side to move, which has to be the Position's side to move.
+    #[inline(always)]
+    fn apply_move_for<const STM: usize>(&mut self, chessmove: Move) {
+        let stm = Color::from_index(STM);
+
+        let source = chessmove.source();
+        let target = chessmove.target();
+
+        let flag = chessmove.flags();
+
+        let source_piece = self.piece_at(source);
+        let target_piece = self.piece_at(target);
+
+        // Castling moves are encoded as the king capturing its own rook,
+        // so they need to be excluded from the actual captures.
+        let is_capture = target_piece != ColoredPiece::None && flag != MoveFlag::Castle;
+
+        self.remove_piece(source); // Remove the moving piece.
+
+        // Update draw clock. Reset it on an irreversible move.
+        self.draw_clock = if is_capture || source_piece.is(Piece::Pawn) {
+            0
+        } else {
+            self.draw_clock + 1
+        };
+
+        // Reset en passant square, if any.
+        if self.enp_target != Square::None {
+            self.hash ^= zobrist::en_passant_key(self.enp_target);
+            self.enp_target = Square::None;
+        }
+
+        // Do castling rights updates, if any.
+        let old_rights = self.castling_square_info.rights;
+        self.castling_square_info.rights = old_rights
+            - self.castling_square_info.get_updates(source)
+            - self.castling_square_info.get_updates(target);
+
+        if self.castling_square_info.rights != old_rights {
+            self.hash ^= zobrist::castling_rights_key(old_rights);
+            self.hash ^= zobrist::castling_rights_key(self.castling_square_info.rights);
+        }
+
+        // Remove the captured piece or the castling rook, if any.
+        if target_piece != ColoredPiece::None {
+            self.remove_piece(target);
+        }
+
+        match flag {
+            MoveFlag::Promotion => {
+                let promotion = ColoredPiece::new(chessmove.promot(), stm);
+                self.insert_piece(target, promotion);
+            }
+
+            MoveFlag::Castle => {
+                let (king_target, rook_target) =
+                    castling::SideColor::from_sqs(source, target).get_targets();
+
+                // The king has already been removed from it's source square by the
+                // general move source square clearing done before, and the rook has
+                // already been removed by the captured piece clearing done before.
+
+                self.insert_piece(king_target, source_piece); // Insert King.
+                self.insert_piece(rook_target, target_piece); // Insert Rook.
+            }
+
+            MoveFlag::EnPassant => {
+                // Make the en passant capture.
+                self.remove_piece(target.down(stm));
+                self.insert_piece(target, source_piece);
+            }
+
+            MoveFlag::Normal => {
+                // Move the piece to the target square.
+                self.insert_piece(target, source_piece);
+
+                // Update en passant target on a double pawn push.
+                if source_piece.is(Piece::Pawn) {
+                    // Calculate the en passant capture square.
+                    let ep_target = target.down(stm);
+
+                    if target.distance(source) == 2
+                    // Double pushes from the first rank in Horde can't be
+                    // captured by en passant.
+                    && source.rank() == Rank::Second.relative(stm)
+                    // Only set the en passant square if the pawn can be captured
+                    // by en passant. This increases the number of tt hits we get.
+                    && !moves::pawn_attacks(ep_target, stm)
+                    .is_disjoint(self.piece_color_bb(Piece::Pawn, !stm))
+                    {
+                        // The en passant target square is below
+                        // the pawn's square after the double push.
+                        self.enp_target = ep_target;
+                        self.hash ^= zobrist::en_passant_key(self.enp_target);
+                    }
+                }
+            }
+        }
+
+        self.plys_count += 1;
+        self.side_to_mv = !stm;
+        self.hash ^= zobrist::side_to_move_key();
+    }
+
+    #[inline(always)]
+    pub(crate) fn insert_piece(&mut self, square: Square, piece: ColoredPiece) {
+        let count = self.colored_piece_bb(piece).popcnt();
+        self.material_hash ^= zobrist::material_key(piece, count);
+        self.phase += piece.piece().phase();
+
+        self.mailbox.0[square as usize] = piece;
+
+        self.piece_bbs[piece.piece() as usize].insert(square);
+        self.color_bbs[piece.color() as usize].insert(square);
+
+        let key = zobrist::piece_square_key(piece, square);
+        self.hash ^= key;
+        if piece.piece() == Piece::Pawn {
+            self.pawn_hash ^= key;
+        }
+    }
+
+    #[inline(always)]
+    pub(crate) fn remove_piece(&mut self, square: Square) {
+        let piece: ColoredPiece = self.mailbox.0[square as usize];
+        self.mailbox.0[square as usize] = ColoredPiece::None;
+
+        self.piece_bbs[piece.piece() as usize].remove(square);
+        self.color_bbs[piece.color() as usize].remove(square);
+
+        let key = zobrist::piece_square_key(piece, square);
+        self.hash ^= key;
+        if piece.piece() == Piece::Pawn {
+            self.pawn_hash ^= key;
+        }
+
+        let count = self.colored_piece_bb(piece).popcnt();
+        self.material_hash ^= zobrist::material_key(piece, count);
+        self.phase -= piece.piece().phase();
+    }
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", FEN::from(self))
+    }
+}
+
+impl Position {
+    pub fn mailbox(&self) -> Mailbox {
+        self.mailbox
+    }
+
+    pub fn side_to_move(&self) -> Color {
+        self.side_to_mv
+    }
+
+    pub fn en_passant_target(&self) -> Square {
+        self.enp_target
+    }
+
+    pub fn castling_rights(&self) -> castling::Rights {
+        self.castling_square_info.rights
+    }
+
+    pub fn hash(&self) -> zobrist::Hash {
+        self.hash
+    }
+
+    /// same_position checks if the given Position is the same as this one:
+    /// the same pieces on the same squares, the same side to move, and the
+    /// same castling and en passant rights. Unlike comparing their hashes,
+    /// this can't give false positives. The move counters are ignored.
+    pub fn same_position(&self, other: &Position) -> bool {
+        let rights = self.castling_rights();
+
+        // Castling rights are only the same if they are with the same rooks,
+        // which may be on different squares in fischer random chess.
+        let same_rooks = [Color::White, Color::Black]
+            .into_iter()
+            .flat_map(|color| [SideColor(color, Side::H), SideColor(color, Side::A)])
+            .filter(|&side| rights.has(side))
+            .all(|side| {
+                self.castling_square_info.rook(side) == other.castling_square_info.rook(side)
+            });
+
+        self.mailbox == other.mailbox
+            && self.side_to_mv == other.side_to_mv
+            && self.enp_target == other.enp_target
+            && rights == other.castling_rights()
+            && same_rooks
+    }
+
+    /// pawn_hash returns the zobrist hash of only the pawns in the position,
+    /// which is the same for all positions with the same pawn structure.
+    pub fn pawn_hash(&self) -> zobrist::Hash {
+        self.pawn_hash
+    }
+
+    /// material_hash returns the zobrist hash of the number of each piece in
+    /// the position, which is the same for all positions with the same
+    /// material, regardless of where the pieces are.
+    pub fn material_hash(&self) -> zobrist::Hash {
+        self.material_hash
+    }
+
+    pub fn plys(&self) -> u16 {
+        self.plys_count
+    }
+
+    pub fn draw_clock(&self) -> u8 {
+        self.draw_clock
+    }
+
+    #[inline(always)]
+    pub fn piece_at(&self, at: Square) -> ColoredPiece {
+        self.mailbox.0[at as usize]
+    }
+
+    #[inline(always)]
+    pub fn colored_piece_bb(&self, piece: ColoredPiece) -> BitBoard {
+        self.piece_color_bb(piece.piece(), piece.color())
+    }
+
+    #[inline(always)]
+    pub fn piece_color_bb(&self, piece: Piece, color: Color) -> BitBoard {
+        self.piece_bb(piece) & self.color_bb(color)
+    }
+
+    #[inline(always)]
+    pub fn piece_bb(&self, piece: Piece) -> BitBoard {
+        self.piece_bbs[piece as usize]
+    }
+
+    #[inline(always)]
+    pub fn color_bb(&self, color: Color) -> BitBoard {
+        self.color_bbs[color as usize]
+    }
+
+    #[inline(always)]
+    pub fn occupied(&self) -> BitBoard {
+        self.color_bb(Color::White) | self.color_bb(Color::Black)
+    }
+
+    #[inline(always)]
+    pub fn is_fischer_random(&self) -> bool {
+        self.is_fischer_random
+    }
+
+    /// variant returns the Variant whose rules the position is played by.
+    #[inline(always)]
+    pub fn variant(&self) -> &'static dyn Variant {
+        self.variant
+    }
+}
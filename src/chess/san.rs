@@ -0,0 +1,638 @@
+// Copyright © 2023 Rak Laptudirm <rak@laptudirm.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{error::Error, fmt, str::FromStr};
+
+use super::{castling, Board, Color, File, Move, MoveFlag, Piece, Rank, Square};
+
+impl Board {
+    /// parse_san finds the legal move on the Board which is described by the
+    /// given Standard Algebraic Notation string. It returns None if the
+    /// string doesn't describe exactly one legal move. Check and annotation
+    /// suffixes like `+`, `#`, `!` and `?` are ignored.
+    pub fn parse_san(&mut self, san: &str) -> Option<Move> {
+        let san = san.trim_end_matches(['+', '#', '!', '?']);
+
+        let legal_moves = self.generate_legal_moves();
+
+        // Castling moves are encoded as the king capturing its own rook, so
+        // the side is found from the relative positions of the two squares.
+        let castling_side = match san {
+            "O-O" | "0-0" => Some(castling::Side::H),
+            "O-O-O" | "0-0-0" => Some(castling::Side::A),
+            _ => None,
+        };
+
+        if let Some(side) = castling_side {
+            return legal_moves.into_iter().find(|chessmove| {
+                chessmove.flags() == MoveFlag::Castle
+                    && castling::Side::from_sqs(chessmove.source(), chessmove.target()) == side
+            });
+        }
+
+        let mut san = san.as_bytes();
+
+        // Parse the promotion suffix, if any.
+        let mut promotion = Piece::None;
+        if let Some((&last, rest)) = san.split_last() {
+            if let Some(piece) = san_piece(last) {
+                promotion = piece;
+                san = rest.strip_suffix(b"=").unwrap_or(rest);
+            }
+        }
+
+        // Parse the moving piece, which is a pawn if it is not specified.
+        let mut piece = Piece::Pawn;
+        if let Some((&first, rest)) = san.split_first() {
+            if let Some(moving) = san_piece(first) {
+                piece = moving;
+                san = rest;
+            }
+        }
+
+        // The target square is always the last part of the move.
+        if san.len() < 2 {
+            return None;
+        }
+
+        let (disambiguation, target) = san.split_at(san.len() - 2);
+        let target = Square::from_str(std::str::from_utf8(target).ok()?).ok()?;
+
+        // Parse the source file and rank used for disambiguation, if any.
+        let mut source_file = File::None;
+        let mut source_rank = Rank::None;
+        for &ident in disambiguation {
+            match ident {
+                b'a'..=b'h' => source_file = File::from(ident - b'a'),
+                b'1'..=b'8' => source_rank = Rank::from(b'8' - ident),
+                b'x' | b'-' => {}
+                _ => return None,
+            }
+        }
+
+        let mut matches = legal_moves.into_iter().filter(|chessmove| {
+            let source = chessmove.source();
+
+            let promot = if chessmove.flags() == MoveFlag::Promotion {
+                chessmove.promot()
+            } else {
+                Piece::None
+            };
+
+            chessmove.flags() != MoveFlag::Castle
+                && chessmove.target() == target
+                && promot == promotion
+                && self.piece_at(source).is(piece)
+                && (source_file == File::None || source.file() == source_file)
+                && (source_rank == Rank::None || source.rank() == source_rank)
+        });
+
+        let chessmove = matches.next()?;
+
+        // Ambiguous moves don't describe a single legal move.
+        if matches.next().is_some() {
+            return None;
+        }
+
+        Some(chessmove)
+    }
+
+    /// to_san converts the given legal move on the Board into its Standard
+    /// Algebraic Notation, including the check or checkmate suffix.
+    pub fn to_san(&mut self, chessmove: Move) -> String {
+        self.to_styled_san(chessmove, SanStyle::Letters)
+    }
+
+    /// to_figurine_san converts the given legal move on the Board into its
+    /// Figurine Algebraic Notation, which is Standard Algebraic Notation with
+    /// the pieces written as the Unicode chess symbols of the moving side.
+    pub fn to_figurine_san(&mut self, chessmove: Move) -> String {
+        self.to_styled_san(chessmove, SanStyle::Figurines)
+    }
+
+    /// to_styled_san converts the given legal move on the Board into its
+    /// Standard Algebraic Notation, with the pieces written in the given style.
+    pub fn to_styled_san(&mut self, chessmove: Move, style: SanStyle) -> String {
+        let source = chessmove.source();
+        let target = chessmove.target();
+
+        let mut san = String::new();
+
+        if chessmove.flags() == MoveFlag::Castle {
+            san += match castling::Side::from_sqs(source, target) {
+                castling::Side::H => "O-O",
+                castling::Side::A => "O-O-O",
+            };
+        } else {
+            let piece = self.piece_at(source).piece();
+            let is_capture = self.is_capture(chessmove);
+
+            if piece == Piece::Pawn {
+                // Pawn captures are disambiguated by their source file.
+                if is_capture {
+                    san += &source.file().to_string();
+                }
+            } else {
+                san.push(style.symbol(self.side_to_move(), piece));
+
+                // Find the other moves of the same piece type to the same
+                // target square, which need to be disambiguated from.
+                let others: Vec<Square> = self
+                    .generate_legal_moves()
+                    .into_iter()
+                    .filter(|other| {
+                        other.flags() != MoveFlag::Castle
+                            && other.target() == target
+                            && other.source() != source
+                            && self.piece_at(other.source()).is(piece)
+                    })
+                    .map(|other| other.source())
+                    .collect();
+
+                if !others.is_empty() {
+                    if others.iter().all(|other| other.file() != source.file()) {
+                        san += &source.file().to_string();
+                    } else if others.iter().all(|other| other.rank() != source.rank()) {
+                        san += &source.rank().to_string();
+                    } else {
+                        san += &source.to_string();
+                    }
+                }
+            }
+
+            if is_capture {
+                san.push('x');
+            }
+
+            san += &target.to_string();
+
+            if chessmove.flags() == MoveFlag::Promotion {
+                san.push('=');
+                san.push(style.symbol(self.side_to_move(), chessmove.promot()));
+            }
+        }
+
+        san.extend(self.check_suffix(chessmove));
+        san
+    }
+
+    /// parse_lan finds the legal move on the Board which is described by the
+    /// given Long Algebraic Notation string, like `Ng1-f3` or `e7xd8=Q`. It
+    /// returns None if the string doesn't describe a legal move. The capture
+    /// separator may be left out, but it has to be right if it is given.
+    /// Check and annotation suffixes are ignored.
+    pub fn parse_lan(&mut self, lan: &str) -> Option<Move> {
+        let lan = lan.trim_end_matches(['+', '#', '!', '?']);
+
+        // Castling is written the same way in both algebraic notations.
+        if lan.starts_with(['O', '0']) {
+            return self.parse_san(lan);
+        }
+
+        let mut lan = lan.as_bytes();
+
+        // Parse the promotion suffix, if any.
+        let mut promotion = Piece::None;
+        if let Some((&last, rest)) = lan.split_last() {
+            if let Some(piece) = san_piece(last) {
+                promotion = piece;
+                lan = rest.strip_suffix(b"=").unwrap_or(rest);
+            }
+        }
+
+        // Parse the moving piece, which is a pawn if it is not specified.
+        let mut piece = Piece::Pawn;
+        if let Some((&first, rest)) = lan.split_first() {
+            if let Some(moving) = san_piece(first) {
+                piece = moving;
+                lan = rest;
+            }
+        }
+
+        let (source, separator, target) = match *lan {
+            [s1, s2, separator @ (b'-' | b'x'), t1, t2] => ([s1, s2], Some(separator), [t1, t2]),
+            [s1, s2, t1, t2] => ([s1, s2], None, [t1, t2]),
+            _ => return None,
+        };
+
+        let source = Square::from_str(std::str::from_utf8(&source).ok()?).ok()?;
+        let target = Square::from_str(std::str::from_utf8(&target).ok()?).ok()?;
+
+        let chessmove = self.generate_legal_moves().into_iter().find(|chessmove| {
+            let promot = if chessmove.flags() == MoveFlag::Promotion {
+                chessmove.promot()
+            } else {
+                Piece::None
+            };
+
+            chessmove.flags() != MoveFlag::Castle
+                && chessmove.source() == source
+                && chessmove.target() == target
+                && promot == promotion
+        })?;
+
+        let is_capture = self.is_capture(chessmove);
+        let is_separator_valid = match separator {
+            Some(b'x') => is_capture,
+            Some(_) => !is_capture,
+            None => true,
+        };
+
+        if !self.piece_at(source).is(piece) || !is_separator_valid {
+            return None;
+        }
+
+        Some(chessmove)
+    }
+
+    /// to_lan converts the given legal move on the Board into its Long
+    /// Algebraic Notation, which is made up of the moving piece, its source
+    /// and target squares separated by `-`, or by `x` for captures, and the
+    /// promotion piece, if any, followed by the check or checkmate suffix.
+    pub fn to_lan(&mut self, chessmove: Move) -> String {
+        let source = chessmove.source();
+        let target = chessmove.target();
+
+        let mut lan = String::new();
+
+        if chessmove.flags() == MoveFlag::Castle {
+            lan += match castling::Side::from_sqs(source, target) {
+                castling::Side::H => "O-O",
+                castling::Side::A => "O-O-O",
+            };
+        } else {
+            let piece = self.piece_at(source).piece();
+            if piece != Piece::Pawn {
+                lan.push(SAN_PIECES[piece as usize] as char);
+            }
+
+            lan += &source.to_string();
+            lan.push(if self.is_capture(chessmove) { 'x' } else { '-' });
+            lan += &target.to_string();
+
+            if chessmove.flags() == MoveFlag::Promotion {
+                lan.push('=');
+                lan.push(SAN_PIECES[chessmove.promot() as usize] as char);
+            }
+        }
+
+        lan.extend(self.check_suffix(chessmove));
+        lan
+    }
+
+    /// parse_iccf finds the legal move on the Board which is described by the
+    /// given ICCF numeric notation string, like `5254` for e2-e4, or `57581`
+    /// for e7-e8=Q. It returns None if the string doesn't describe a legal
+    /// move. Castling moves are written as the king moving two squares.
+    pub fn parse_iccf(&mut self, iccf: &str) -> Option<Move> {
+        let digits: Vec<u8> = iccf.bytes().map(|digit| digit.wrapping_sub(b'0')).collect();
+
+        let (squares, promotion) = match digits.split_at_checked(4)? {
+            (squares, []) => (squares, Piece::None),
+            (squares, [promotion @ 1..=4]) => (squares, ICCF_PROMOTIONS[*promotion as usize]),
+            _ => return None,
+        };
+
+        if !squares.iter().all(|digit| (1..=8).contains(digit)) {
+            return None;
+        }
+
+        self.generate_legal_moves().into_iter().find(|&chessmove| {
+            let (source, target) = iccf_squares(chessmove);
+            let promot = if chessmove.flags() == MoveFlag::Promotion {
+                chessmove.promot()
+            } else {
+                Piece::None
+            };
+
+            iccf_digits(source) == [squares[0], squares[1]]
+                && iccf_digits(target) == [squares[2], squares[3]]
+                && promot == promotion
+        })
+    }
+
+    /// to_iccf converts the given legal move on the Board into its ICCF
+    /// numeric notation: the file and rank digits of its source and target
+    /// squares, followed by a digit for the promotion piece, if any.
+    pub fn to_iccf(&self, chessmove: Move) -> String {
+        let (source, target) = iccf_squares(chessmove);
+
+        let mut iccf: String = iccf_digits(source)
+            .into_iter()
+            .chain(iccf_digits(target))
+            .map(|digit| (b'0' + digit) as char)
+            .collect();
+
+        if chessmove.flags() == MoveFlag::Promotion {
+            let digit = ICCF_PROMOTIONS
+                .iter()
+                .position(|&piece| piece == chessmove.promot())
+                .unwrap();
+            iccf.push((b'0' + digit as u8) as char);
+        }
+
+        iccf
+    }
+
+    /// parse_uci finds the legal move on the Board which is described by the
+    /// given UCI long algebraic notation string, like `g1f3` or `e7e8q`. The
+    /// castling moves are the king capturing its own rook if chess960 is set,
+    /// and the king moving two squares otherwise.
+    pub fn parse_uci(&mut self, uci: &str, chess960: bool) -> Option<Move> {
+        self.generate_legal_moves()
+            .into_iter()
+            .find(|chessmove| chessmove.to_uci(chess960) == uci)
+    }
+
+    /// apply_uci_moves makes the given moves in UCI long algebraic notation
+    /// on the Board one after the other, like the `moves` of UCI's `position`
+    /// command. Each move is checked against the legal moves of the position
+    /// it is made in, and if any of them is illegal, none of them are made.
+    /// Castling moves are the king capturing its own rook if chess960 is set.
+    pub fn apply_uci_moves(
+        &mut self,
+        moves: &[&str],
+        chess960: bool,
+    ) -> Result<(), IllegalMoveError> {
+        for (index, &uci) in moves.iter().enumerate() {
+            let Some(chessmove) = self.parse_uci(uci, chess960) else {
+                for _ in 0..index {
+                    self.undo_move();
+                }
+
+                return Err(IllegalMoveError {
+                    index,
+                    chessmove: String::from(uci),
+                });
+            };
+
+            self.make_move(chessmove);
+        }
+
+        Ok(())
+    }
+
+    /// check_suffix returns the check or checkmate suffix of the given legal
+    /// move on the Board in the algebraic notations, if any.
+    fn check_suffix(&mut self, chessmove: Move) -> Option<char> {
+        self.make_move(chessmove);
+        let suffix = self.variant().check_suffix(self);
+        self.undo_move();
+
+        suffix
+    }
+}
+
+/// IllegalMoveError is the error returned when a move in a list of moves
+/// can't be made on the Board, because it isn't a legal move there.
+#[derive(Debug)]
+pub struct IllegalMoveError {
+    /// index is the index of the illegal move in the list.
+    pub index: usize,
+    /// chessmove is the illegal move, as it was written in the list.
+    pub chessmove: String,
+}
+
+impl fmt::Display for IllegalMoveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "illegal move {} at index {}", self.chessmove, self.index)
+    }
+}
+
+impl Error for IllegalMoveError {}
+
+/// SanStyle is the way pieces are written in Standard Algebraic Notation.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SanStyle {
+    /// Letters writes the pieces as their English letters, like `Nf3`.
+    #[default]
+    Letters,
+    /// Figurines writes the pieces as the Unicode chess symbols of the
+    /// side which owns them, like `♘f3` for White and `♞f6` for Black.
+    Figurines,
+}
+
+impl SanStyle {
+    /// symbol returns the character the given Piece of the given Color is
+    /// written as in this style. Pawns are never written in the notation.
+    fn symbol(self, color: Color, piece: Piece) -> char {
+        match self {
+            SanStyle::Letters => SAN_PIECES[piece as usize] as char,
+            SanStyle::Figurines => FIGURINES[color as usize][piece as usize],
+        }
+    }
+}
+
+/// FIGURINES contains the Unicode chess symbols of each Piece of each Color,
+/// indexed by the Color and the Piece.
+#[rustfmt::skip]
+const FIGURINES: [[char; Piece::N]; Color::N] = [
+    ['♙', '♘', '♗', '♖', '♕', '♔'],
+    ['♟', '♞', '♝', '♜', '♛', '♚'],
+];
+
+/// SAN_PIECES contains the Standard Algebraic Notation identifiers of each
+/// Piece, indexed by the Piece. Pawns don't have an identifier.
+const SAN_PIECES: [u8; Piece::N] = [b' ', b'N', b'B', b'R', b'Q', b'K'];
+
+/// san_piece converts a piece identifier used in Standard Algebraic Notation
+/// into a Piece. Pawns don't have an identifier in Standard Algebraic Notation.
+fn san_piece(ident: u8) -> Option<Piece> {
+    match ident {
+        b'N' => Some(Piece::Knight),
+        b'B' => Some(Piece::Bishop),
+        b'R' => Some(Piece::Rook),
+        b'Q' => Some(Piece::Queen),
+        b'K' => Some(Piece::King),
+        _ => None,
+    }
+}
+
+/// ICCF_PROMOTIONS contains the Piece promoted to, indexed by its digit in
+/// ICCF numeric notation. Zero isn't a promotion digit.
+const ICCF_PROMOTIONS: [Piece; 5] = [
+    Piece::None,
+    Piece::Queen,
+    Piece::Rook,
+    Piece::Bishop,
+    Piece::Knight,
+];
+
+/// iccf_squares returns the source and target squares of the given Move in
+/// ICCF numeric notation, where castling moves are the king moving two
+/// squares rather than the king capturing its own rook.
+fn iccf_squares(chessmove: Move) -> (Square, Square) {
+    let source = chessmove.source();
+    match chessmove.flags() {
+        MoveFlag::Castle => {
+            let side = castling::SideColor::from_sqs(source, chessmove.target());
+            (source, side.get_targets().0)
+        }
+
+        _ => (source, chessmove.target()),
+    }
+}
+
+/// iccf_digits returns the file and rank digits of the given Square in ICCF
+/// numeric notation, which both go from 1 to 8.
+fn iccf_digits(square: Square) -> [u8; 2] {
+    [
+        square.file() as u8 + 1,
+        Rank::First as u8 - square.rank() as u8 + 1,
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Positions with ambiguous moves, promotions, castling and checkmates.
+    const POSITIONS: [&str; 4] = [
+        "4k3/8/8/8/8/Q7/8/Q1Q1K3 w - - 0 1",
+        "r3k2r/1P6/8/8/8/8/8/R3K2R w KQkq - 0 1",
+        "4k3/8/8/R7/8/8/8/R1N1KN2 w - - 0 1",
+        "7k/8/6K1/8/8/8/8/5R2 w - - 0 1",
+    ];
+
+    #[test]
+    fn san_disambiguates_moves() {
+        let mut board = Board::from_str(POSITIONS[0]).unwrap();
+        for (uci, san) in [
+            ("a1b2", "Qa1b2"),
+            ("a3b2", "Q3b2"),
+            ("c1b2", "Qcb2"),
+            ("a3e3", "Qae3+"),
+        ] {
+            let chessmove = board.parse_uci(uci, false).unwrap();
+            assert_eq!(board.to_san(chessmove), san);
+        }
+
+        let mut board = Board::from_str(POSITIONS[2]).unwrap();
+        for (uci, san) in [("a5a3", "R5a3"), ("a1a3", "R1a3"), ("a1b1", "Rb1")] {
+            let chessmove = board.parse_uci(uci, false).unwrap();
+            assert_eq!(board.to_san(chessmove), san);
+        }
+    }
+
+    #[test]
+    fn san_promotions_castling_and_mates() {
+        let mut board = Board::from_str(POSITIONS[1]).unwrap();
+        for (uci, san) in [
+            ("b7b8q", "b8=Q+"),
+            ("b7a8n", "bxa8=N"),
+            ("e1h1", "O-O"),
+            ("e1a1", "O-O-O"),
+        ] {
+            let chessmove = board.parse_uci(uci, true).unwrap();
+            assert_eq!(board.to_san(chessmove), san);
+        }
+
+        let mut board = Board::from_str(POSITIONS[3]).unwrap();
+        let chessmove = board.parse_uci("f1f8", false).unwrap();
+        assert_eq!(board.to_san(chessmove), "Rf8#");
+        assert_eq!(board.parse_san("Rf8"), Some(chessmove));
+    }
+
+    #[test]
+    fn san_round_trips() {
+        for fen in POSITIONS {
+            let mut board = Board::from_str(fen).unwrap();
+            for chessmove in board.generate_legal_moves() {
+                let san = board.to_san(chessmove);
+                assert_eq!(board.parse_san(&san), Some(chessmove), "{fen} {san}");
+            }
+        }
+
+        // Moves which are ambiguous without their disambiguation.
+        let mut board = Board::from_str(POSITIONS[0]).unwrap();
+        assert_eq!(board.parse_san("Qb2"), None);
+        assert_eq!(board.parse_san("Qab2"), None);
+    }
+
+    #[test]
+    fn apply_uci_moves_is_all_or_nothing() {
+        let mut board = Board::startpos();
+        board
+            .apply_uci_moves(&["e2e4", "e7e5", "g1f3"], false)
+            .unwrap();
+        assert_eq!(board.plys(), 3);
+
+        let err = board
+            .apply_uci_moves(&["b8c6", "f1c4", "c6c4"], false)
+            .unwrap_err();
+        assert_eq!((err.index, err.chessmove.as_str()), (2, "c6c4"));
+        assert_eq!(board.plys(), 3);
+    }
+
+    #[test]
+    fn apply_uci_moves_castling_notation() {
+        let fen = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1";
+
+        let mut board = Board::from_str(fen).unwrap();
+        board.apply_uci_moves(&["e1g1", "e8c8"], false).unwrap();
+        assert!(board.apply_uci_moves(&["e1h1"], false).is_err());
+
+        let mut board = Board::from_str(fen).unwrap();
+        board.apply_uci_moves(&["e1h1", "e8a8"], true).unwrap();
+        assert!(board.apply_uci_moves(&["e1g1"], true).is_err());
+    }
+
+    #[test]
+    fn lan_round_trips() {
+        let mut board = Board::from_str(POSITIONS[1]).unwrap();
+        for (uci, lan) in [("a1a8", "Ra1xa8+"), ("b7a8n", "b7xa8=N"), ("e1h1", "O-O")] {
+            let chessmove = board.parse_uci(uci, true).unwrap();
+            assert_eq!(board.to_lan(chessmove), lan);
+        }
+
+        for fen in POSITIONS {
+            let mut board = Board::from_str(fen).unwrap();
+            for chessmove in board.generate_legal_moves() {
+                let lan = board.to_lan(chessmove);
+                assert_eq!(board.parse_lan(&lan), Some(chessmove), "{fen} {lan}");
+            }
+        }
+    }
+
+    #[test]
+    fn iccf_round_trips() {
+        let mut board = Board::from_str(POSITIONS[1]).unwrap();
+        for (uci, iccf) in [("b7b8q", "27281"), ("b7a8n", "27184"), ("e1h1", "5171")] {
+            let chessmove = board.parse_uci(uci, true).unwrap();
+            assert_eq!(board.to_iccf(chessmove), iccf);
+        }
+
+        for fen in POSITIONS {
+            let mut board = Board::from_str(fen).unwrap();
+            for chessmove in board.generate_legal_moves() {
+                let iccf = board.to_iccf(chessmove);
+                assert_eq!(board.parse_iccf(&iccf), Some(chessmove), "{fen} {iccf}");
+            }
+        }
+    }
+
+    #[test]
+    fn iccf_rejects_malformed_moves() {
+        let mut board = Board::startpos();
+        assert!(board.parse_iccf("5254").is_some());
+        for iccf in ["52540", "52545", "52549", "525", "525401", "0254", "5294"] {
+            assert_eq!(board.parse_iccf(iccf), None, "{iccf}");
+        }
+
+        // Promotions need a promotion digit from 1 to 4.
+        let mut board = Board::from_str(POSITIONS[1]).unwrap();
+        for iccf in ["2728", "27280", "27285"] {
+            assert_eq!(board.parse_iccf(iccf), None, "{iccf}");
+        }
+    }
+}
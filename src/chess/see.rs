@@ -0,0 +1,244 @@
+// Copyright © 2023 Rak Laptudirm <rak@laptudirm.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::{moves, BitBoard, Board, Color, Move, MoveFlag, Piece, Square};
+
+/// Static Exchange Evaluation of moves.
+impl Board {
+    /// see returns the Static Exchange Evaluation of the given move, which is
+    /// the material the moving side wins if both sides keep recapturing on
+    /// the move's target square with their least valuable pieces, and stop
+    /// when continuing would lose material. Castling, en passant and
+    /// promotion moves are evaluated as not exchanging anything.
+    pub fn see(&self, chessmove: Move) -> i32 {
+        if chessmove.flags() != MoveFlag::Normal {
+            return 0;
+        }
+
+        let target = chessmove.target();
+        let mut occupied = self.occupied() ^ BitBoard::from(chessmove.source());
+        let mut attackers = self.attackers_to(target, occupied);
+        let mut stm = self.side_to_move();
+
+        // gains contains the material won by the side which made each of the
+        // captures of the exchange, if the exchange ends after it.
+        let mut gains = vec![self.piece_at(target).piece().value()];
+        let mut on_target = self.piece_at(chessmove.source()).piece();
+
+        loop {
+            stm = !stm;
+            attackers &= occupied;
+
+            let Some((piece, square)) = least_valuable(self, attackers, stm) else {
+                break;
+            };
+
+            // Kings can only recapture if the square isn't defended anymore.
+            if piece == Piece::King && !(attackers & self.color_bb(!stm)).is_empty() {
+                break;
+            }
+
+            gains.push(on_target.value() - gains[gains.len() - 1]);
+            on_target = piece;
+
+            occupied ^= BitBoard::from(square);
+            attackers |= self.xray_attackers(target, occupied);
+        }
+
+        // Each side can stop the exchange instead of recapturing, which it
+        // does if recapturing would lose material.
+        while gains.len() > 1 {
+            let gain = gains.pop().unwrap();
+            let last = gains.len() - 1;
+            gains[last] = -(-gains[last]).max(gain);
+        }
+
+        gains[0]
+    }
+
+    /// see_ge checks if the Static Exchange Evaluation of the given move is
+    /// at least the given threshold. It is cheaper than computing the full
+    /// evaluation, since it stops as soon as the result is known.
+    pub fn see_ge(&self, chessmove: Move, threshold: i32) -> bool {
+        if chessmove.flags() != MoveFlag::Normal {
+            return 0 >= threshold;
+        }
+
+        let (source, target) = (chessmove.source(), chessmove.target());
+
+        // Even if the moving piece is recaptured for free, the capture on its
+        // own needs to be enough to reach the threshold.
+        let mut swap = self.piece_at(target).piece().value() - threshold;
+        if swap < 0 {
+            return false;
+        }
+
+        // If the moving piece being recaptured still keeps the threshold,
+        // the move is good enough, whatever else happens.
+        swap = self.piece_at(source).piece().value() - swap;
+        if swap <= 0 {
+            return true;
+        }
+
+        let mut occupied = self.occupied() ^ BitBoard::from(source) ^ BitBoard::from(target);
+        let mut attackers = self.attackers_to(target, occupied);
+        let mut stm = self.side_to_move();
+
+        // result is whether the side to move wins the exchange if it ends
+        // with the capture which was just made.
+        let mut result = true;
+
+        loop {
+            stm = !stm;
+            attackers &= occupied;
+
+            let Some((piece, square)) = least_valuable(self, attackers, stm) else {
+                break;
+            };
+
+            // Kings can only recapture if the square isn't defended anymore.
+            if piece == Piece::King {
+                return if (attackers & self.color_bb(!stm)).is_empty() {
+                    !result
+                } else {
+                    result
+                };
+            }
+
+            result = !result;
+
+            // The side which just recaptured wins if its recapturing piece
+            // being recaptured still keeps it at or above the threshold.
+            swap = piece.value() - swap;
+            if swap < result as i32 {
+                break;
+            }
+
+            occupied ^= BitBoard::from(square);
+            attackers |= self.xray_attackers(target, occupied);
+        }
+
+        result
+    }
+
+    /// least_valuable_attacker returns the least valuable piece of the given
+    /// side which attacks the given square, along with its square, if there
+    /// is any. Only the pieces in the given occupancy are considered, and
+    /// they block the attacks of sliding pieces.
+    pub fn least_valuable_attacker(
+        &self,
+        square: Square,
+        color: Color,
+        occupied: BitBoard,
+    ) -> Option<(Piece, Square)> {
+        least_valuable(self, self.attackers_to(square, occupied) & occupied, color)
+    }
+
+    /// attackers_to returns the pieces of both sides which attack the given
+    /// square with the given occupancy.
+    fn attackers_to(&self, square: Square, occupied: BitBoard) -> BitBoard {
+        let diagonal = self.piece_bb(Piece::Bishop) | self.piece_bb(Piece::Queen);
+        let lateral = self.piece_bb(Piece::Rook) | self.piece_bb(Piece::Queen);
+
+        (moves::pawn_attacks(square, Color::White) & self.piece_color_bb(Piece::Pawn, Color::Black))
+            | (moves::pawn_attacks(square, Color::Black)
+                & self.piece_color_bb(Piece::Pawn, Color::White))
+            | (moves::knight(square) & self.piece_bb(Piece::Knight))
+            | (moves::bishop(square, occupied) & diagonal)
+            | (moves::rook(square, occupied) & lateral)
+            | (moves::king(square) & self.piece_bb(Piece::King))
+    }
+
+    /// xray_attackers returns the sliding pieces which attack the given square
+    /// with the given occupancy, which includes the ones revealed by the
+    /// pieces which have left it.
+    fn xray_attackers(&self, square: Square, occupied: BitBoard) -> BitBoard {
+        let diagonal = self.piece_bb(Piece::Bishop) | self.piece_bb(Piece::Queen);
+        let lateral = self.piece_bb(Piece::Rook) | self.piece_bb(Piece::Queen);
+
+        (moves::bishop(square, occupied) & diagonal) | (moves::rook(square, occupied) & lateral)
+    }
+}
+
+/// least_valuable returns the least valuable of the given attackers on the
+/// Board which belongs to the given side, along with its square.
+fn least_valuable(board: &Board, attackers: BitBoard, color: Color) -> Option<(Piece, Square)> {
+    let attackers = attackers & board.color_bb(color);
+
+    [
+        Piece::Pawn,
+        Piece::Knight,
+        Piece::Bishop,
+        Piece::Rook,
+        Piece::Queen,
+        Piece::King,
+    ]
+    .into_iter()
+    .find_map(|piece| {
+        let square = (attackers & board.piece_bb(piece)).lsb();
+        (square != Square::None).then_some((piece, square))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    // Exchanges with their Static Exchange Evaluations.
+    #[rustfmt::skip]
+    const EXCHANGES: [(&str, &str, i32); 7] = [
+        // Undefended pawn.
+        ("1k1r4/1pp4p/p7/4p3/8/P5P1/1PP4P/2K1R3 w - - 0 1", "e1e5", 100),
+        // Both sides' pieces recapture, with queens behind rooks and bishops.
+        ("1k1r3q/1ppn3p/p4b2/4p3/8/P2N2P1/1PP1R1BP/2K1Q3 w - - 0 1", "d3e5", -200),
+        // Pawn defended by a pawn.
+        ("4k3/8/3p4/4p3/8/8/8/4R1K1 w - - 0 1", "e1e5", -400),
+        ("4k3/8/3p4/4n3/3P4/8/8/4K3 w - - 0 1", "d4e5", 200),
+        // Kings only recapture undefended pieces.
+        ("8/8/8/8/8/8/3pk3/3R2K1 w - - 0 1", "d1d2", -400),
+        ("8/8/8/8/8/8/3pk3/2BR2K1 w - - 0 1", "d1d2", 100),
+        // En passant doesn't exchange anything.
+        ("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1", "e5d6", 0),
+    ];
+
+    #[test]
+    fn see_of_known_exchanges() {
+        for (fen, uci, see) in EXCHANGES {
+            let mut board = Board::from_str(fen).unwrap();
+            let chessmove = board.parse_uci(uci, false).unwrap();
+            assert_eq!(board.see(chessmove), see, "{fen} {uci}");
+        }
+    }
+
+    #[test]
+    fn see_ge_agrees_with_see() {
+        for (fen, uci, see) in EXCHANGES {
+            let mut board = Board::from_str(fen).unwrap();
+            let chessmove = board.parse_uci(uci, false).unwrap();
+            for threshold in [see - 100, see - 1, see] {
+                assert!(
+                    board.see_ge(chessmove, threshold),
+                    "{fen} {uci} {threshold}"
+                );
+            }
+            for threshold in [see + 1, see + 100] {
+                assert!(
+                    !board.see_ge(chessmove, threshold),
+                    "{fen} {uci} {threshold}"
+                );
+            }
+        }
+    }
+}
@@ -11,7 +11,8 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::fmt::Display;
+use std::error::Error;
+use std::fmt::{self, Display};
 use std::str::FromStr;
 
 use crate::chess::{self, Color};
@@ -20,10 +21,10 @@ use crate::util::type_macros;
 use num_derive::FromPrimitive;
 use num_traits::FromPrimitive;
 
-use super::BitBoard;
+use super::{BitBoard, Direction};
 
 /// Enum Square represents all the different squares on a chessboard.
-#[derive(Copy, Clone, PartialEq, PartialOrd, Default, FromPrimitive)]
+#[derive(Copy, Clone, PartialEq, PartialOrd, Default, Debug, FromPrimitive)]
 #[rustfmt::skip]
 pub enum Square {
     A8, B8, C8, D8, E8, F8, G8, H8,
@@ -42,6 +43,25 @@ impl Square {
     /// N is the number of different squares.
     pub const N: usize = 64;
 
+    /// ALL contains every square on the board, in index order.
+    #[rustfmt::skip]
+    pub const ALL: [Square; Square::N] = [
+        Square::A8, Square::B8, Square::C8, Square::D8, Square::E8, Square::F8, Square::G8, Square::H8,
+        Square::A7, Square::B7, Square::C7, Square::D7, Square::E7, Square::F7, Square::G7, Square::H7,
+        Square::A6, Square::B6, Square::C6, Square::D6, Square::E6, Square::F6, Square::G6, Square::H6,
+        Square::A5, Square::B5, Square::C5, Square::D5, Square::E5, Square::F5, Square::G5, Square::H5,
+        Square::A4, Square::B4, Square::C4, Square::D4, Square::E4, Square::F4, Square::G4, Square::H4,
+        Square::A3, Square::B3, Square::C3, Square::D3, Square::E3, Square::F3, Square::G3, Square::H3,
+        Square::A2, Square::B2, Square::C2, Square::D2, Square::E2, Square::F2, Square::G2, Square::H2,
+        Square::A1, Square::B1, Square::C1, Square::D1, Square::E1, Square::F1, Square::G1, Square::H1,
+    ];
+
+    /// iter returns an iterator over every square on the board, in index
+    /// order, i.e. from a8 to h1 rank by rank.
+    pub fn iter() -> impl Iterator<Item = Square> {
+        Square::ALL.into_iter()
+    }
+
     pub fn new(file: File, rank: Rank) -> Square {
         Square::from(rank as usize * File::N + file as usize)
     }
@@ -96,36 +116,96 @@ impl Square {
 
     pub fn up(self, us: Color) -> Self {
         match us {
-            Color::White => self.north(),
-            Color::Black => self.south(),
             Color::None => self,
+            _ => Square::from(self as i8 + Direction::up(us).offset()),
         }
     }
 
     pub fn down(self, us: Color) -> Self {
         match us {
-            Color::White => self.south(),
-            Color::Black => self.north(),
             Color::None => self,
+            _ => Square::from(self as i8 + Direction::down(us).offset()),
         }
     }
 
+    /// north returns the square to the north of the current one. It does not
+    /// check for the edge of the board, use try_north for a checked version.
     pub fn north(self) -> Self {
         Square::from(self as usize - 8)
     }
 
+    /// south returns the square to the south of the current one. It does not
+    /// check for the edge of the board, use try_south for a checked version.
     pub fn south(self) -> Self {
         Square::from(self as usize + 8)
     }
 
+    /// east returns the square to the east of the current one. It does not
+    /// check for the edge of the board, use try_east for a checked version.
     pub fn east(self) -> Self {
         Square::from(self as usize + 1)
     }
 
+    /// west returns the square to the west of the current one. It does not
+    /// check for the edge of the board, use try_west for a checked version.
     pub fn west(self) -> Self {
         Square::from(self as usize - 1)
     }
 
+    /// try_up returns the square in front of the current one from the given
+    /// color's perspective, or None if it is off the board.
+    pub fn try_up(self, us: Color) -> Option<Self> {
+        self.shift(Direction::up(us))
+    }
+
+    /// try_down returns the square behind the current one from the given
+    /// color's perspective, or None if it is off the board.
+    pub fn try_down(self, us: Color) -> Option<Self> {
+        self.shift(Direction::down(us))
+    }
+
+    /// try_north returns the square to the north of the current one, or None
+    /// if the current square is on the eighth rank.
+    pub fn try_north(self) -> Option<Self> {
+        self.shift(Direction::North)
+    }
+
+    /// try_south returns the square to the south of the current one, or None
+    /// if the current square is on the first rank.
+    pub fn try_south(self) -> Option<Self> {
+        self.shift(Direction::South)
+    }
+
+    /// try_east returns the square to the east of the current one, or None
+    /// if the current square is on the h-file.
+    pub fn try_east(self) -> Option<Self> {
+        self.shift(Direction::East)
+    }
+
+    /// try_west returns the square to the west of the current one, or None
+    /// if the current square is on the a-file.
+    pub fn try_west(self) -> Option<Self> {
+        self.shift(Direction::West)
+    }
+
+    /// offset returns the square whose index is the given offset away from
+    /// the current square's, or None if that index is off the board. Since
+    /// only the index is checked, offsets may wrap around the board's edge
+    /// files; use shift to step in a Direction without wrapping.
+    pub fn offset(self, offset: i8) -> Option<Self> {
+        if self == Square::None {
+            return None;
+        }
+
+        // Offsets which overflow an i8 are off the board too.
+        let index = (self as i8).checked_add(offset)?;
+        if (0..Square::N as i8).contains(&index) {
+            Some(Square::from(index))
+        } else {
+            None
+        }
+    }
+
     pub fn distance(self, rhs: Square) -> usize {
         let rank_dist = (self.rank() as i32 - rhs.rank() as i32).unsigned_abs() as usize;
         let file_dist = (self.file() as i32 - rhs.file() as i32).unsigned_abs() as usize;
@@ -134,12 +214,27 @@ impl Square {
     }
 }
 
+#[derive(Debug)]
 pub enum SquareParseError {
     WrongStringSize,
     FileParseError(FileParseError),
     RankParseError(RankParseError),
 }
 
+impl fmt::Display for SquareParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SquareParseError::WrongStringSize => {
+                write!(f, "square should be a file and a rank, like e4, or -")
+            }
+            SquareParseError::FileParseError(err) => write!(f, "invalid square file: {err}"),
+            SquareParseError::RankParseError(err) => write!(f, "invalid square rank: {err}"),
+        }
+    }
+}
+
+impl Error for SquareParseError {}
+
 impl FromStr for Square {
     type Err = SquareParseError;
 
@@ -200,6 +295,17 @@ pub enum File {
 impl File {
     pub const N: usize = 8;
 
+    /// ALL contains every file on the board, from the a-file to the h-file.
+    #[rustfmt::skip]
+    pub const ALL: [File; File::N] = [
+        File::A, File::B, File::C, File::D, File::E, File::F, File::G, File::H,
+    ];
+
+    /// iter returns an iterator over every file, from the a-file to the h-file.
+    pub fn iter() -> impl Iterator<Item = File> {
+        File::ALL.into_iter()
+    }
+
     pub fn relative(self, color: chess::Color) -> File {
         match color {
             chess::Color::White => self,
@@ -209,11 +315,23 @@ impl File {
     }
 }
 
+#[derive(Debug)]
 pub enum FileParseError {
     WrongStringSize,
     InvalidFileString,
 }
 
+impl fmt::Display for FileParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FileParseError::WrongStringSize => write!(f, "file should be a single character"),
+            FileParseError::InvalidFileString => write!(f, "file should be one of a to h"),
+        }
+    }
+}
+
+impl Error for FileParseError {}
+
 impl FromStr for File {
     type Err = FileParseError;
 
@@ -267,6 +385,19 @@ pub enum Rank {
 impl Rank {
     pub const N: usize = 8;
 
+    /// ALL contains every rank on the board, from the eighth rank to the
+    /// first, which is the order in which they are indexed.
+    #[rustfmt::skip]
+    pub const ALL: [Rank; Rank::N] = [
+        Rank::Eighth, Rank::Seventh, Rank::Sixth, Rank::Fifth,
+        Rank::Fourth, Rank::Third, Rank::Second, Rank::First,
+    ];
+
+    /// iter returns an iterator over every rank, from the eighth to the first.
+    pub fn iter() -> impl Iterator<Item = Rank> {
+        Rank::ALL.into_iter()
+    }
+
     pub fn relative(self, color: chess::Color) -> Rank {
         match color {
             chess::Color::White => self,
@@ -276,11 +407,23 @@ impl Rank {
     }
 }
 
+#[derive(Debug)]
 pub enum RankParseError {
     WrongStringSize,
     InvalidRankString,
 }
 
+impl fmt::Display for RankParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RankParseError::WrongStringSize => write!(f, "rank should be a single character"),
+            RankParseError::InvalidRankString => write!(f, "rank should be one of 1 to 8"),
+        }
+    }
+}
+
+impl Error for RankParseError {}
+
 impl FromStr for Rank {
     type Err = RankParseError;
 
@@ -324,3 +467,23 @@ impl Display for Rank {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn offset_stays_on_the_board() {
+        assert_eq!(Square::A8.offset(1), Some(Square::B8));
+        assert_eq!(Square::H1.offset(-1), Some(Square::G1));
+        assert_eq!(Square::A8.offset(-1), None);
+        assert_eq!(Square::H1.offset(1), None);
+        assert_eq!(Square::None.offset(0), None);
+
+        // Offsets which would overflow the index are off the board too.
+        assert_eq!(Square::H1.offset(100), None);
+        assert_eq!(Square::H1.offset(i8::MAX), None);
+        assert_eq!(Square::A8.offset(i8::MIN), None);
+        assert_eq!(Square::H1.offset(i8::MIN), None);
+    }
+}
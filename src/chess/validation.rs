@@ -0,0 +1,326 @@
+// Copyright © 2023 Rak Laptudirm <rak@laptudirm.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{error::Error, fmt};
+
+use super::{
+    castling, moves, zobrist, BitBoard, Color, ColoredPiece, Piece, Position, Rank, Square,
+};
+
+/// PositionError is the reason a Position is illegal by the rules of its
+/// Variant, even though it may be well formed.
+#[derive(Debug)]
+pub enum PositionError {
+    MissingKing(Color),
+    TooManyKings(Color),
+    PawnOnBackRank(Square),
+    OpponentInCheck,
+    InvalidEnPassant(Square),
+    InvalidCastlingRights(castling::SideColor),
+}
+
+impl fmt::Display for PositionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PositionError::MissingKing(color) => write!(f, "{} has no king", color_name(*color)),
+            PositionError::TooManyKings(color) => {
+                write!(f, "{} has more than one king", color_name(*color))
+            }
+            PositionError::PawnOnBackRank(square) => write!(f, "pawn on back rank at {square}"),
+            PositionError::OpponentInCheck => write!(f, "the side not to move is in check"),
+            PositionError::InvalidEnPassant(square) => write!(
+                f,
+                "en passant square {square} isn't behind a pawn which just pushed twice"
+            ),
+            PositionError::InvalidCastlingRights(castling::SideColor(color, side)) => write!(
+                f,
+                "{} can't castle {} since its king or rook has moved",
+                color_name(*color),
+                match side {
+                    castling::Side::H => "kingside",
+                    castling::Side::A => "queenside",
+                }
+            ),
+        }
+    }
+}
+
+impl Error for PositionError {}
+
+/// IntegrityError is an inconsistency in the internal state of a Board,
+/// which is always caused by a bug, unlike a PositionError.
+#[derive(Debug)]
+pub enum IntegrityError {
+    OverlappingBitBoards,
+    MailboxMismatch(Square),
+    OccupancyMismatch,
+    KingCount(Color),
+    HashMismatch,
+    PawnHashMismatch,
+    MaterialHashMismatch,
+    PhaseMismatch,
+    ThreatsMismatch,
+}
+
+impl fmt::Display for IntegrityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IntegrityError::OverlappingBitBoards => {
+                write!(f, "piece or color bitboards overlap each other")
+            }
+            IntegrityError::MailboxMismatch(square) => {
+                write!(f, "mailbox and bitboards disagree at {square}")
+            }
+            IntegrityError::OccupancyMismatch => {
+                write!(
+                    f,
+                    "occupancy bitboards aren't the unions of the color bitboards"
+                )
+            }
+            IntegrityError::KingCount(color) => {
+                write!(f, "{} has the wrong number of kings", color_name(*color))
+            }
+            IntegrityError::HashMismatch => write!(f, "zobrist hash is out of date"),
+            IntegrityError::PawnHashMismatch => write!(f, "pawn hash is out of date"),
+            IntegrityError::MaterialHashMismatch => write!(f, "material hash is out of date"),
+            IntegrityError::PhaseMismatch => write!(f, "phase is out of date"),
+            IntegrityError::ThreatsMismatch => write!(f, "cached threats are out of date"),
+        }
+    }
+}
+
+impl Error for IntegrityError {}
+
+/// color_name returns the name of the given Color used in error messages.
+fn color_name(color: Color) -> &'static str {
+    match color {
+        Color::White => "white",
+        _ => "black",
+    }
+}
+
+impl Position {
+    /// validate checks if the Position is legal by the rules of its Variant.
+    /// Positions which aren't legal can't be reached by a game, and the move
+    /// generator may misbehave on them.
+    pub fn validate(&self) -> Result<(), PositionError> {
+        for color in [Color::White, Color::Black] {
+            self.validate_kings(color)?;
+            self.validate_pawns(color)?;
+        }
+
+        self.validate_check()?;
+        self.validate_en_passant()?;
+        self.validate_castling()
+    }
+
+    /// validate_kings checks that the given side has exactly one king if its
+    /// kings are royal, or none at all if the Variant allows that.
+    fn validate_kings(&self, color: Color) -> Result<(), PositionError> {
+        if !self.variant.has_royal_king() {
+            return Ok(());
+        }
+
+        match self.piece_color_bb(Piece::King, color).popcnt() {
+            0 if self.variant.needs_king(color) => Err(PositionError::MissingKing(color)),
+            0 | 1 => Ok(()),
+            _ => Err(PositionError::TooManyKings(color)),
+        }
+    }
+
+    /// validate_pawns checks that none of the given side's pawns are on its
+    /// last rank, where they should have promoted, or on its first rank,
+    /// unless pawns can push from there in the Variant.
+    fn validate_pawns(&self, color: Color) -> Result<(), PositionError> {
+        let mut back_ranks = BitBoard::rank(Rank::Eighth.relative(color));
+
+        let first = BitBoard::rank(Rank::First.relative(color));
+        if (first & self.variant.double_push_ranks(color)).is_empty() {
+            back_ranks |= first;
+        }
+
+        match (self.piece_color_bb(Piece::Pawn, color) & back_ranks).lsb() {
+            Square::None => Ok(()),
+            square => Err(PositionError::PawnOnBackRank(square)),
+        }
+    }
+
+    /// validate_check checks that the side which just moved didn't leave its
+    /// royal king in check.
+    fn validate_check(&self) -> Result<(), PositionError> {
+        let stm = self.side_to_mv;
+        let king = self.piece_color_bb(Piece::King, !stm).lsb();
+        if !self.variant.has_royal_king() || king == Square::None {
+            return Ok(());
+        }
+
+        let occupied = self.occupied();
+        let queens = self.piece_color_bb(Piece::Queen, stm);
+
+        let attackers = (self.piece_color_bb(Piece::Pawn, stm) & moves::pawn_attacks(king, !stm))
+            | (self.piece_color_bb(Piece::Knight, stm) & moves::knight(king))
+            | ((self.piece_color_bb(Piece::Bishop, stm) | queens) & moves::bishop(king, occupied))
+            | ((self.piece_color_bb(Piece::Rook, stm) | queens) & moves::rook(king, occupied))
+            | (self.piece_color_bb(Piece::King, stm) & moves::king(king));
+
+        if attackers.is_empty() {
+            Ok(())
+        } else {
+            Err(PositionError::OpponentInCheck)
+        }
+    }
+
+    /// validate_en_passant checks that the en passant target square, if any,
+    /// is behind an enemy pawn which could have just pushed twice.
+    fn validate_en_passant(&self) -> Result<(), PositionError> {
+        let stm = self.side_to_mv;
+        let target = self.enp_target;
+        if target == Square::None {
+            return Ok(());
+        }
+
+        let is_valid = target.rank() == Rank::Sixth.relative(stm)
+            && self.piece_at(target) == ColoredPiece::None
+            && self.piece_at(target.up(stm)) == ColoredPiece::None
+            && self.piece_at(target.down(stm)) == ColoredPiece::new(Piece::Pawn, !stm);
+
+        if is_valid {
+            Ok(())
+        } else {
+            Err(PositionError::InvalidEnPassant(target))
+        }
+    }
+
+    /// validate_castling checks that the king and the rook of every castling
+    /// right are still on their initial squares.
+    fn validate_castling(&self) -> Result<(), PositionError> {
+        let info = &self.castling_square_info;
+
+        for color in [Color::White, Color::Black] {
+            let king = info.king(color);
+
+            for side in [castling::Side::H, castling::Side::A] {
+                let side = castling::SideColor(color, side);
+                if !info.rights.has(side) {
+                    continue;
+                }
+
+                if self.piece_at(king) != ColoredPiece::new(Piece::King, color)
+                    || self.piece_at(info.rook(side)) != ColoredPiece::new(Piece::Rook, color)
+                {
+                    return Err(PositionError::InvalidCastlingRights(side));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// validate_integrity checks that the piece representations of the
+    /// Position agree with each other, that each side has as many kings as
+    /// its Variant allows, and that the incrementally updated metadata other
+    /// than the zobrist hash is what it would be if computed from scratch.
+    pub(crate) fn validate_integrity(&self) -> Result<(), IntegrityError> {
+        let pieces = self
+            .piece_bbs
+            .iter()
+            .fold(BitBoard::EMPTY, |all, &bb| all | bb);
+        let colors = self
+            .color_bbs
+            .iter()
+            .fold(BitBoard::EMPTY, |all, &bb| all | bb);
+
+        let piece_count: u32 = self.piece_bbs.iter().map(|bb| bb.popcnt()).sum();
+        let color_count: u32 = self.color_bbs.iter().map(|bb| bb.popcnt()).sum();
+
+        if pieces != colors || piece_count != pieces.popcnt() || color_count != colors.popcnt() {
+            return Err(IntegrityError::OverlappingBitBoards);
+        }
+
+        for square in 0..Square::N {
+            let square = Square::from(square);
+
+            let piece = (0..Piece::N).find(|&piece| self.piece_bbs[piece].contains(square));
+            let color = (0..Color::N).find(|&color| self.color_bbs[color].contains(square));
+
+            let expected = match (piece, color) {
+                (Some(piece), Some(color)) => {
+                    ColoredPiece::new(Piece::from(piece), Color::from(color))
+                }
+                _ => ColoredPiece::None,
+            };
+
+            if self.mailbox.0[square as usize] != expected {
+                return Err(IntegrityError::MailboxMismatch(square));
+            }
+        }
+
+        for color in [Color::White, Color::Black] {
+            self.validate_kings(color)
+                .map_err(|_| IntegrityError::KingCount(color))?;
+        }
+
+        let mut pawn_hash = zobrist::Hash::default();
+        for square in self.piece_bb(Piece::Pawn) {
+            pawn_hash ^= zobrist::piece_square_key(self.piece_at(square), square);
+        }
+
+        if pawn_hash != self.pawn_hash {
+            return Err(IntegrityError::PawnHashMismatch);
+        }
+
+        let mut material_hash = zobrist::Hash::default();
+        let mut phase = 0;
+        for piece in 0..ColoredPiece::N {
+            let piece = ColoredPiece::from(piece);
+            let count = self.colored_piece_bb(piece).popcnt();
+
+            for count in 0..count {
+                material_hash ^= zobrist::material_key(piece, count);
+            }
+
+            phase += count as u8 * piece.piece().phase();
+        }
+
+        if material_hash != self.material_hash {
+            return Err(IntegrityError::MaterialHashMismatch);
+        }
+
+        if phase != self.phase {
+            return Err(IntegrityError::PhaseMismatch);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::chess::{variant, Board, BoardParseError};
+
+    use super::*;
+
+    #[test]
+    fn horde_pawns_only_start_on_white_first_rank() {
+        let mut board = Board::from_fen("4k3/8/8/8/8/8/8/P3P3 w - - 0 1", &variant::Horde).unwrap();
+        assert_eq!(board.legal_moves_from(Square::A1).len(), 2);
+
+        let err = Board::from_fen("p3k3/8/8/8/8/8/8/P3P3 w - - 0 1", &variant::Horde);
+        assert!(matches!(
+            err,
+            Err(BoardParseError::IllegalPosition(
+                PositionError::PawnOnBackRank(Square::A8)
+            ))
+        ));
+    }
+}
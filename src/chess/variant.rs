@@ -0,0 +1,306 @@
+// Copyright © 2023 Rak Laptudirm <rak@laptudirm.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::{BitBoard, Board, Color, Move, MoveList, Outcome, Piece, Rank, Square, STARTPOS_FEN};
+
+/// Variant is the set of rules a game of chess is played by. The Board's
+/// move generation and terminal detection are the same for every Variant,
+/// and the rules which differ between them are encapsulated by the hooks
+/// of this trait, so that each Variant doesn't need a move generator of its
+/// own. The default implementations of the hooks are the standard rules.
+pub trait Variant: Sync {
+    /// name returns the name of the Variant, as used by UCI's UCI_Variant.
+    fn name(&self) -> &'static str;
+
+    /// start_fen returns the FEN of the starting position of the Variant.
+    fn start_fen(&self) -> &'static str {
+        STARTPOS_FEN
+    }
+
+    /// has_royal_king checks if the kings of the Variant are royal, which
+    /// means that they can be checked and that they can't be left in check.
+    fn has_royal_king(&self) -> bool {
+        true
+    }
+
+    /// needs_king checks if the given side must have a king in the Variant.
+    /// Sides whose kings aren't royal never need one.
+    fn needs_king(&self, _color: Color) -> bool {
+        self.has_royal_king()
+    }
+
+    /// can_castle checks if castling is allowed in the Variant.
+    fn can_castle(&self) -> bool {
+        true
+    }
+
+    /// double_push_ranks returns the ranks from which the pawns of the given
+    /// Color can move two squares forward.
+    fn double_push_ranks(&self, color: Color) -> BitBoard {
+        BitBoard::rank(Rank::Second.relative(color))
+    }
+
+    /// has_move_filter checks if the Variant has a move filter stage, which
+    /// needs all the legal moves of the position to be generated together.
+    fn has_move_filter(&self) -> bool {
+        false
+    }
+
+    /// filter_moves removes the moves which are illegal in the Variant from
+    /// the given legal moves of the position on the Board, which are legal
+    /// by the rules encapsulated by the other hooks. It is only called if
+    /// the Variant has a move filter stage.
+    fn filter_moves(&self, _board: &Board, _moves: &mut MoveList) {}
+
+    /// has_forced_captures checks if captures are forced in the Variant, so
+    /// that a side which can capture something isn't allowed to do anything
+    /// else. The Variant's move filter has to enforce this.
+    fn has_forced_captures(&self) -> bool {
+        false
+    }
+
+    /// outcome returns the Outcome of the game on the Board if it is over,
+    /// or None if it isn't.
+    fn outcome(&self, board: &mut Board) -> Option<Outcome> {
+        standard_outcome(board)
+    }
+
+    /// is_insufficient_material checks if neither side has enough material
+    /// on the Board to ever win the game, which by the standard rules is
+    /// when there is at most a single minor piece left besides the kings.
+    fn is_insufficient_material(&self, board: &Board) -> bool {
+        let majors_and_pawns = board.piece_bb(Piece::Pawn)
+            | board.piece_bb(Piece::Rook)
+            | board.piece_bb(Piece::Queen);
+        let minors = board.piece_bb(Piece::Knight) | board.piece_bb(Piece::Bishop);
+
+        majors_and_pawns.is_empty() && minors.popcnt() <= 1
+    }
+
+    /// check_suffix returns the suffix of the Standard Algebraic Notation
+    /// of the move which led to the position on the Board, if any.
+    fn check_suffix(&self, board: &mut Board) -> Option<char> {
+        if !board.is_check() {
+            return None;
+        }
+
+        Some(if !board.has_legal_moves() { '#' } else { '+' })
+    }
+}
+
+/// VARIANTS contains all of the supported Variants.
+pub const VARIANTS: [&dyn Variant; 4] = [&Standard, &Antichess, &KingOfTheHill, &Horde];
+
+/// from_name returns the Variant with the given name, ignoring case. Some
+/// Variants have alternative names too, like `giveaway` for Antichess.
+pub fn from_name(name: &str) -> Option<&'static dyn Variant> {
+    let name = name.to_ascii_lowercase();
+    let name = match name.as_str() {
+        "chess" => "standard",
+        "giveaway" => "antichess",
+        "koth" => "kingofthehill",
+        name => name,
+    };
+
+    VARIANTS.into_iter().find(|variant| variant.name() == name)
+}
+
+/// standard_outcome returns the Outcome of the game on the Board by the
+/// standard rules if it is over: a side without legal moves has lost if
+/// it is checkmated, and has drawn otherwise.
+pub fn standard_outcome(board: &mut Board) -> Option<Outcome> {
+    if !board.has_legal_moves() {
+        return Some(if board.is_check() {
+            Outcome::Win(!board.side_to_move())
+        } else {
+            Outcome::Draw
+        });
+    }
+
+    if board.is_draw() {
+        return Some(Outcome::Draw);
+    }
+
+    None
+}
+
+/// Standard is standard chess.
+pub struct Standard;
+
+impl Variant for Standard {
+    fn name(&self) -> &'static str {
+        "standard"
+    }
+}
+
+/// Antichess, also called Giveaway, is a variant where the goal is to lose
+/// all of one's pieces. Captures are forced, there is no check or checkmate,
+/// kings can be captured like any other piece, and castling isn't allowed. A
+/// side which has no legal moves, either because it has no pieces left or
+/// because it is stalemated, wins the game. Pawns can't promote to kings,
+/// since moves can't encode those promotions.
+pub struct Antichess;
+
+impl Variant for Antichess {
+    fn name(&self) -> &'static str {
+        "antichess"
+    }
+
+    fn start_fen(&self) -> &'static str {
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w - - 0 1"
+    }
+
+    fn has_royal_king(&self) -> bool {
+        false
+    }
+
+    fn can_castle(&self) -> bool {
+        false
+    }
+
+    fn has_move_filter(&self) -> bool {
+        true
+    }
+
+    /// filter_moves forces captures: if any capture is possible, only the
+    /// captures are legal.
+    fn filter_moves(&self, board: &Board, moves: &mut MoveList) {
+        let is_capture = |chessmove: &Move| board.is_capture(*chessmove);
+
+        if moves.iter().any(is_capture) {
+            moves.retain(is_capture);
+        }
+    }
+
+    fn has_forced_captures(&self) -> bool {
+        true
+    }
+
+    /// outcome is a win for the side to move if it has run out of moves.
+    fn outcome(&self, board: &mut Board) -> Option<Outcome> {
+        if !board.has_legal_moves() {
+            return Some(Outcome::Win(board.side_to_move()));
+        }
+
+        board.is_draw().then_some(Outcome::Draw)
+    }
+
+    /// is_insufficient_material is always false, since a side can win by
+    /// giving away any of its pieces.
+    fn is_insufficient_material(&self, _board: &Board) -> bool {
+        false
+    }
+
+    fn check_suffix(&self, _board: &mut Board) -> Option<char> {
+        None
+    }
+}
+
+/// KingOfTheHill is standard chess with an additional way of winning: a
+/// side whose king reaches one of the four central squares wins.
+pub struct KingOfTheHill;
+
+impl KingOfTheHill {
+    /// HILL contains the central squares a king has to reach to win.
+    pub const HILL: BitBoard = BitBoard(
+        1 << Square::D4 as u64
+            | 1 << Square::E4 as u64
+            | 1 << Square::D5 as u64
+            | 1 << Square::E5 as u64,
+    );
+
+    /// is_king_on_hill checks if the game on the Board has been won by the
+    /// last move, which brought the moving side's king to the hill.
+    fn is_king_on_hill(board: &Board) -> bool {
+        let king = board.piece_color_bb(Piece::King, !board.side_to_move());
+        !(king & KingOfTheHill::HILL).is_empty()
+    }
+}
+
+impl Variant for KingOfTheHill {
+    fn name(&self) -> &'static str {
+        "kingofthehill"
+    }
+
+    fn has_move_filter(&self) -> bool {
+        true
+    }
+
+    /// filter_moves removes all of the moves once a king is on the hill,
+    /// since the game is over.
+    fn filter_moves(&self, board: &Board, moves: &mut MoveList) {
+        if KingOfTheHill::is_king_on_hill(board) {
+            moves.clear();
+        }
+    }
+
+    fn outcome(&self, board: &mut Board) -> Option<Outcome> {
+        if KingOfTheHill::is_king_on_hill(board) {
+            return Some(Outcome::Win(!board.side_to_move()));
+        }
+
+        standard_outcome(board)
+    }
+
+    /// is_insufficient_material is always false, since a lone king can
+    /// still win by reaching the hill.
+    fn is_insufficient_material(&self, _board: &Board) -> bool {
+        false
+    }
+}
+
+/// Horde is a variant where white has 36 pawns and no king against the
+/// standard black army. White's pawns on the first rank can move two
+/// squares forward too. White wins by checkmating black, and black wins by
+/// capturing all of white's pieces.
+pub struct Horde;
+
+impl Variant for Horde {
+    fn name(&self) -> &'static str {
+        "horde"
+    }
+
+    fn start_fen(&self) -> &'static str {
+        "rnbqkbnr/pppppppp/8/1PP2PP1/PPPPPPPP/PPPPPPPP/PPPPPPPP/PPPPPPPP w kq - 0 1"
+    }
+
+    /// needs_king is false for white, since the horde has no king.
+    fn needs_king(&self, color: Color) -> bool {
+        color == Color::Black
+    }
+
+    /// double_push_ranks includes the first rank for white, whose horde
+    /// starts with pawns on it.
+    fn double_push_ranks(&self, color: Color) -> BitBoard {
+        let second = BitBoard::rank(Rank::Second.relative(color));
+        match color {
+            Color::White => BitBoard::rank(Rank::First) | second,
+            _ => second,
+        }
+    }
+
+    fn outcome(&self, board: &mut Board) -> Option<Outcome> {
+        let stm = board.side_to_move();
+        if board.color_bb(stm).is_empty() {
+            return Some(Outcome::Win(!stm));
+        }
+
+        standard_outcome(board)
+    }
+
+    /// is_insufficient_material is always false, since black wins by
+    /// capturing all of the horde, which even a lone king can do.
+    fn is_insufficient_material(&self, _board: &Board) -> bool {
+        false
+    }
+}
@@ -14,15 +14,31 @@
 use std::{fmt::Display, ops};
 
 use crate::{
-    chess::{ColoredPiece, File, Square},
+    chess::{Board, Color, ColoredPiece, File, Square},
     util::type_macros,
 };
 
 use super::castling;
 
-#[derive(Clone, Copy, Default, PartialEq, Eq)]
+/// Hash is a zobrist hash of a position, or of a part of it. Hashes of
+/// positions which differ by a piece, the side to move, or the castling or
+/// en passant metadata differ by the key of that difference, so hashes can
+/// be updated incrementally by xoring keys in and out of them.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash, Debug)]
 pub struct Hash(u64);
 
+impl From<Hash> for u64 {
+    fn from(hash: Hash) -> Self {
+        hash.0
+    }
+}
+
+impl From<u64> for Hash {
+    fn from(hash: u64) -> Self {
+        Hash(hash)
+    }
+}
+
 impl Display for Hash {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{:#X}", self.0)
@@ -63,6 +79,15 @@ pub const fn piece_square_key(piece: ColoredPiece, square: Square) -> Hash {
     Hash(PIECE_SQUARE_KEYS[piece as usize][square as usize])
 }
 
+/// material_key returns the key of having the given number of the given
+/// pieces, excluding the piece being counted, in a material hash. Material
+/// hashes are never mixed with position hashes, so the keys of the piece
+/// square table are reused for them, with the count as the square.
+#[inline(always)]
+pub const fn material_key(piece: ColoredPiece, count: u32) -> Hash {
+    Hash(PIECE_SQUARE_KEYS[piece as usize][count as usize])
+}
+
 #[inline(always)]
 pub fn en_passant_key(ep_square: Square) -> Hash {
     Hash(EN_PASSANT_KEYS[ep_square.file() as usize])
@@ -78,6 +103,28 @@ pub const fn side_to_move_key() -> Hash {
     STM_KEY
 }
 
+/// compute computes the zobrist hash of the position on the given Board
+/// from scratch, which is what its incrementally updated hash should be.
+pub fn compute(board: &Board) -> Hash {
+    let mut hash = castling_rights_key(board.castling_rights());
+
+    for (square, piece) in board.mailbox().0.into_iter().enumerate() {
+        if piece != ColoredPiece::None {
+            hash ^= piece_square_key(piece, Square::from(square));
+        }
+    }
+
+    if board.side_to_move() == Color::Black {
+        hash ^= side_to_move_key();
+    }
+
+    if board.en_passant_target() != Square::None {
+        hash ^= en_passant_key(board.en_passant_target());
+    }
+
+    hash
+}
+
 const CASTLE_WH: u64 = 0x4d28598573750b10;
 const CASTLE_WA: u64 = 0xdfe34de8892603ad;
 const CASTLE_BH: u64 = 0x177ab8314c2b200e;
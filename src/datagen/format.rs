@@ -0,0 +1,179 @@
+// Copyright © 2023 Rak Laptudirm <rak@laptudirm.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fmt;
+use std::io::{self, Write};
+use std::str::FromStr;
+
+use crate::chess::{Color, ColoredPiece, PackedBoard, Piece, Position, Square};
+use crate::pgn::GameResult;
+
+use super::Record;
+
+/// Format is a format training data can be written in.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Format {
+    /// Text is the text format of Record's Display implementation.
+    Text,
+
+    /// Marlin is the 32 byte binary format of marlinflow, where scores and
+    /// results are from white's point of view.
+    Marlin,
+
+    /// Bullet is the 32 byte binary format of bullet, where the position is
+    /// flipped so that the side to move is white, and scores and results are
+    /// from the side to move's point of view.
+    Bullet,
+}
+
+impl Format {
+    /// write writes the given Record in the Format to the given writer.
+    pub fn write<W: Write>(self, writer: &mut W, record: &Record) -> io::Result<()> {
+        match self {
+            Format::Text => writeln!(writer, "{record}"),
+            Format::Marlin => writer.write_all(&record.to_marlin()),
+            Format::Bullet => writer.write_all(&record.to_bullet()),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct FormatParseError;
+
+impl fmt::Display for FormatParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "formats should be one of text, marlin or bullet")
+    }
+}
+
+impl std::error::Error for FormatParseError {}
+
+impl FromStr for Format {
+    type Err = FormatParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(Format::Text),
+            "marlin" | "marlinformat" => Ok(Format::Marlin),
+            "bullet" | "bulletformat" => Ok(Format::Bullet),
+            _ => Err(FormatParseError),
+        }
+    }
+}
+
+impl Record {
+    /// to_marlin encodes the Record in the marlinformat encoding: the
+    /// position as a PackedBoard, followed by the score and the result,
+    /// whose multi-byte fields are little endian.
+    pub fn to_marlin(&self) -> [u8; 32] {
+        let wdl = match self.result {
+            GameResult::BlackWins => 0,
+            GameResult::WhiteWins => 2,
+            _ => 1,
+        };
+
+        let mut packed = [0; 32];
+        packed[..PackedBoard::SIZE].copy_from_slice(&PackedBoard::from(&self.position).to_bytes());
+        packed[28..30].copy_from_slice(&clamp_score(self.score).to_le_bytes());
+        packed[30] = wdl;
+        packed
+    }
+
+    /// to_bullet encodes the Record in the bulletformat encoding: the
+    /// occupancy, a nibble for each piece in the order of the occupancy, the
+    /// score, the result, and the squares of the kings of the side to move
+    /// and of its opponent. Positions with black to move are flipped so that
+    /// the side to move is white, and the opponent's king square is flipped
+    /// back. Squares are numbered from a1 to h8, and multi-byte fields are
+    /// little endian.
+    pub fn to_bullet(&self) -> [u8; 32] {
+        let position = &self.position;
+        let stm = position.side_to_move();
+        let flip = stm == Color::Black;
+
+        let (occupancy, pieces) = pack_pieces(position, flip, |_, piece| {
+            piece.piece() as u8 | ((piece.color() != stm) as u8) << 3
+        });
+
+        let (score, result) = match stm {
+            Color::White => (self.score, self.result),
+            _ => (-self.score, flip_result(self.result)),
+        };
+
+        let result = match result {
+            GameResult::BlackWins => 0,
+            GameResult::WhiteWins => 2,
+            _ => 1,
+        };
+
+        let king = lerf(position.piece_color_bb(Piece::King, stm).lsb()) ^ (flip as u8 * 56);
+        let opponent_king =
+            lerf(position.piece_color_bb(Piece::King, !stm).lsb()) ^ (!flip as u8 * 56);
+
+        let mut packed = [0; 32];
+        packed[0..8].copy_from_slice(&occupancy.to_le_bytes());
+        packed[8..24].copy_from_slice(&pieces.to_le_bytes());
+        packed[24..26].copy_from_slice(&clamp_score(score).to_le_bytes());
+        packed[26] = result;
+        packed[27] = king;
+        packed[28] = opponent_king;
+        packed
+    }
+}
+
+/// pack_pieces packs the pieces of the Position into its occupancy, with
+/// the squares numbered from a1 to h8 and flipped vertically if flip is set,
+/// and a nibble for each piece in the order of the occupancy, whose value
+/// is given by the encode function.
+fn pack_pieces(
+    position: &Position,
+    flip: bool,
+    mut encode: impl FnMut(Square, ColoredPiece) -> u8,
+) -> (u64, u128) {
+    let mut squares: Vec<(u8, Square)> = position
+        .occupied()
+        .into_iter()
+        .map(|square| (lerf(square) ^ (flip as u8 * 56), square))
+        .collect();
+    squares.sort_unstable_by_key(|&(index, _)| index);
+
+    let mut occupancy = 0;
+    let mut pieces = 0;
+    for (i, (index, square)) in squares.into_iter().enumerate() {
+        occupancy |= 1 << index;
+        pieces |= (encode(square, position.piece_at(square)) as u128) << (i * 4);
+    }
+
+    (occupancy, pieces)
+}
+
+/// lerf returns the index of the Square when the squares are numbered from
+/// a1 to h8, rather than from a8 to h1.
+fn lerf(square: Square) -> u8 {
+    square as u8 ^ 56
+}
+
+/// clamp_score clamps the given score to the range of the 16 bit scores of
+/// the binary formats.
+fn clamp_score(score: i32) -> i16 {
+    score.clamp(i16::MIN as i32, i16::MAX as i32) as i16
+}
+
+/// flip_result returns the GameResult with the colors of the sides swapped.
+fn flip_result(result: GameResult) -> GameResult {
+    match result {
+        GameResult::WhiteWins => GameResult::BlackWins,
+        GameResult::BlackWins => GameResult::WhiteWins,
+        result => result,
+    }
+}
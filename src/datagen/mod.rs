@@ -0,0 +1,9 @@
+// Non-namespaced modules.
+mod format;
+mod selfplay;
+
+// Make the contents of the non-namespaced
+// modules public, so they can be accessed
+// without their parent namespace.
+pub use self::format::*;
+pub use self::selfplay::*;
@@ -0,0 +1,250 @@
+// Copyright © 2023 Rak Laptudirm <rak@laptudirm.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Mutex};
+use std::thread;
+
+use crate::book::openings::OpeningSuite;
+use crate::chess::{Board, Color, Position, FEN};
+use crate::engine::{Engine, SearchInfo, SearchLimits, Signals};
+use crate::pgn::GameResult;
+use crate::r#match::{Adjudication, Adjudicator, DrawRule, ResignRule};
+use crate::search::{self, Score, SearchEngine};
+
+/// DatagenOptions are the options of a self-play data generation run.
+#[derive(Clone)]
+pub struct DatagenOptions {
+    /// threads is the number of games played at the same time.
+    pub threads: usize,
+
+    /// games is the total number of games to play.
+    pub games: usize,
+
+    /// limits are the limits of the search of each move.
+    pub limits: SearchLimits,
+
+    /// openings are the positions the games start from.
+    pub openings: OpeningSuite,
+
+    /// random_plys is the number of random moves played after the opening,
+    /// so that games from the same opening don't repeat.
+    pub random_plys: usize,
+
+    /// seed is the seed of the random moves.
+    pub seed: u64,
+
+    /// adjudication contains the rules by which games are ended early once
+    /// their results are clear, counting the moves after the random ones.
+    pub adjudication: Adjudication,
+}
+
+impl Default for DatagenOptions {
+    fn default() -> Self {
+        DatagenOptions {
+            threads: 1,
+            games: 1,
+            limits: SearchLimits {
+                nodes: Some(5000),
+                ..SearchLimits::default()
+            },
+            openings: OpeningSuite::default(),
+            random_plys: 8,
+            seed: 0,
+            adjudication: Adjudication {
+                resign: Some(ResignRule {
+                    score: 2000,
+                    moves: 2,
+                }),
+                draw: Some(DrawRule {
+                    score: 10,
+                    moves: 6,
+                    after: 30,
+                }),
+                tablebase: false,
+            },
+        }
+    }
+}
+
+/// Record is a position from a self-play game, along with the score of its
+/// search and the result of the game, both from white's point of view.
+#[derive(Clone)]
+pub struct Record {
+    pub position: Position,
+    pub score: Score,
+    pub result: GameResult,
+}
+
+impl fmt::Display for Record {
+    /// fmt formats the Record in the text format of training data, which is
+    /// the FEN, score and result separated by bars, like `<fen> | 35 | 0.5`,
+    /// where the result is 1 for a white win, 0.5 for a draw and 0 for a loss.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let result = match self.result {
+            GameResult::WhiteWins => "1.0",
+            GameResult::BlackWins => "0.0",
+            _ => "0.5",
+        };
+
+        write!(
+            f,
+            "{} | {} | {result}",
+            FEN::from(&self.position),
+            self.score
+        )
+    }
+}
+
+/// generate plays self-play games with the built-in engine on the given
+/// number of threads, calling the given function with the Records of each
+/// game as soon as it is over. It returns the number of Records generated.
+pub fn generate(options: &DatagenOptions, mut on_game: impl FnMut(&[Record])) -> usize {
+    let openings = Mutex::new(options.openings.clone());
+    let started = AtomicUsize::new(0);
+    let (sender, receiver) = mpsc::channel();
+
+    thread::scope(|scope| {
+        for thread in 0..options.threads.max(1) {
+            let sender = sender.clone();
+            let openings = &openings;
+            let started = &started;
+
+            scope.spawn(move || {
+                let mut generator = Generator::new(options, thread as u64);
+                while started.fetch_add(1, Ordering::Relaxed) < options.games {
+                    let opening = openings.lock().unwrap().next().unwrap();
+                    if sender.send(generator.play_game(opening)).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+
+        // The channel is closed once all of the threads are done.
+        drop(sender);
+
+        let mut records = 0;
+        for game in receiver {
+            records += game.len();
+            on_game(&game);
+        }
+
+        records
+    })
+}
+
+/// Generator plays self-play games on a single thread.
+struct Generator<'a> {
+    options: &'a DatagenOptions,
+    engine: SearchEngine,
+    signals: Signals,
+    rng: u64,
+}
+
+impl Generator<'_> {
+    /// new creates a Generator with the given options, whose random moves
+    /// are different from the ones of the Generators of the other threads.
+    fn new(options: &DatagenOptions, thread: u64) -> Generator<'_> {
+        Generator {
+            options,
+            engine: SearchEngine::new(),
+            signals: Signals::new(),
+
+            // Xorshift generators can't be seeded with zero.
+            rng: (options.seed ^ thread.wrapping_mul(0x9e3779b97f4a7c15)) | 1,
+        }
+    }
+
+    /// play_game plays a self-play game from the given opening and returns
+    /// its Records. Games which end during their random moves don't have
+    /// any Records.
+    fn play_game(&mut self, opening: Position) -> Vec<Record> {
+        let mut board = Board::from(opening);
+        for _ in 0..self.options.random_plys {
+            let moves = board.generate_legal_moves();
+            if moves.is_empty() {
+                return Vec::new();
+            }
+
+            let index = (self.random() % moves.len() as u64) as usize;
+            board.make_move(moves[index]);
+        }
+
+        self.engine.new_game();
+
+        let mut records = Vec::new();
+        let mut adjudicator = Adjudicator::new(self.options.adjudication);
+
+        let result = loop {
+            if let Some(outcome) = board.outcome() {
+                break GameResult::from(outcome);
+            }
+
+            if board.is_threefold_repetition() || board.is_insufficient_material() {
+                break GameResult::Draw;
+            }
+
+            let mut score = 0;
+            self.signals.reset(false);
+            let best_move = self
+                .engine
+                .search(
+                    board.clone(),
+                    self.options.limits,
+                    &self.signals,
+                    &mut |info: &SearchInfo| score = info.score,
+                )
+                .chessmove;
+
+            let side = board.side_to_move();
+            let white_score = if side == Color::White { score } else { -score };
+
+            // Adjudicate games whose result is already clear.
+            if let Some(result) = adjudicator.update(&board, Some(score)) {
+                break result;
+            }
+
+            // Only quiet positions are useful for training, since the scores
+            // of the other positions depend on the tactics in them.
+            if !board.is_check()
+                && !search::is_mate(score)
+                && !board.is_capture(best_move)
+                && !best_move.is_promotion()
+            {
+                records.push(Record {
+                    position: *board.position(),
+                    score: white_score,
+                    result: GameResult::Unknown,
+                });
+            }
+
+            board.make_move(best_move);
+        };
+
+        for record in &mut records {
+            record.result = result;
+        }
+
+        records
+    }
+
+    /// random returns the next number from the xorshift generator.
+    fn random(&mut self) -> u64 {
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 7;
+        self.rng ^= self.rng << 17;
+        self.rng
+    }
+}
@@ -0,0 +1,272 @@
+// Copyright © 2023 Rak Laptudirm <rak@laptudirm.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::chess::Color;
+
+use super::SearchLimits;
+
+/// TimeControl is the time control of a game, which decides how much time
+/// each side has for its moves.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum TimeControl {
+    /// SuddenDeath gives each side the base time for the whole game.
+    SuddenDeath { base: Duration },
+
+    /// Increment gives each side the base time for the whole game, and adds
+    /// the increment to a side's clock after each of its moves.
+    Increment { base: Duration, increment: Duration },
+
+    /// Moves gives each side the base time for every session of the given
+    /// number of moves, and adds the increment after each of its moves.
+    Moves {
+        moves: u32,
+        base: Duration,
+        increment: Duration,
+    },
+
+    /// MoveTime gives each side a fixed amount of time for each move, which
+    /// can't be saved up for later moves.
+    MoveTime(Duration),
+}
+
+impl TimeControl {
+    /// new creates the TimeControl with the given moves per session, base
+    /// time and increment, where zero moves per session means that the
+    /// base time is for the whole game.
+    pub fn new(moves: u32, base: Duration, increment: Duration) -> TimeControl {
+        match (moves, increment.is_zero()) {
+            (0, true) => TimeControl::SuddenDeath { base },
+            (0, false) => TimeControl::Increment { base, increment },
+            _ => TimeControl::Moves {
+                moves,
+                base,
+                increment,
+            },
+        }
+    }
+
+    /// base returns the time each side starts with, which is the time for
+    /// a single move with fixed move times.
+    pub fn base(&self) -> Duration {
+        match *self {
+            TimeControl::SuddenDeath { base }
+            | TimeControl::Increment { base, .. }
+            | TimeControl::Moves { base, .. } => base,
+            TimeControl::MoveTime(time) => time,
+        }
+    }
+
+    /// increment returns the time added to a side's clock after each of its
+    /// moves.
+    pub fn increment(&self) -> Duration {
+        match *self {
+            TimeControl::Increment { increment, .. } | TimeControl::Moves { increment, .. } => {
+                increment
+            }
+            _ => Duration::ZERO,
+        }
+    }
+
+    /// moves_per_session returns the number of moves after which the base
+    /// time is added to a side's clock again, if there is any.
+    pub fn moves_per_session(&self) -> Option<u32> {
+        match *self {
+            TimeControl::Moves { moves, .. } => Some(moves),
+            _ => None,
+        }
+    }
+
+    /// search_limits returns the SearchLimits of a search by a side which
+    /// has already made the given number of moves, with the given times
+    /// left on the clocks of each side, indexed by the Color.
+    pub fn search_limits(&self, time: [Duration; Color::N], moves_made: u32) -> SearchLimits {
+        if let TimeControl::MoveTime(time) = *self {
+            return SearchLimits {
+                movetime: Some(time),
+                ..SearchLimits::default()
+            };
+        }
+
+        SearchLimits {
+            time: time.map(Some),
+            increment: [self.increment(); Color::N],
+            moves_to_go: self
+                .moves_per_session()
+                .map(|moves| moves - moves_made % moves),
+            ..SearchLimits::default()
+        }
+    }
+}
+
+impl fmt::Display for TimeControl {
+    /// fmt formats the TimeControl as the value of a PGN TimeControl tag,
+    /// like 40/5400+30, 300+2 or 300, with the times in seconds. Fixed move
+    /// times aren't supported by the PGN standard and are formatted like
+    /// 5/move instead.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let TimeControl::MoveTime(time) = *self {
+            return write!(f, "{}/move", time.as_secs_f64());
+        }
+
+        if let Some(moves) = self.moves_per_session() {
+            write!(f, "{moves}/")?;
+        }
+
+        write!(f, "{}", self.base().as_secs_f64())?;
+
+        let increment = self.increment();
+        if !increment.is_zero() {
+            write!(f, "+{}", increment.as_secs_f64())?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct TimeControlParseError;
+
+impl fmt::Display for TimeControlParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "time controls should look like 40/5400+30, 300+2, 300 or 5/move"
+        )
+    }
+}
+
+impl Error for TimeControlParseError {}
+
+impl FromStr for TimeControl {
+    type Err = TimeControlParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(time) = s.strip_suffix("/move") {
+            return parse_seconds(time).map(TimeControl::MoveTime);
+        }
+
+        let (moves, time) = match s.split_once('/') {
+            Some((moves, time)) => (moves.parse().map_err(|_| TimeControlParseError)?, time),
+            None => (0, s),
+        };
+
+        let (base, increment) = time.split_once('+').unwrap_or((time, "0"));
+
+        Ok(TimeControl::new(
+            moves,
+            parse_seconds(base)?,
+            parse_seconds(increment)?,
+        ))
+    }
+}
+
+/// parse_seconds parses a possibly fractional number of seconds.
+fn parse_seconds(seconds: &str) -> Result<Duration, TimeControlParseError> {
+    seconds
+        .parse()
+        .ok()
+        .and_then(|seconds| Duration::try_from_secs_f64(seconds).ok())
+        .ok_or(TimeControlParseError)
+}
+
+/// Clock keeps track of the time left for each side in a game played with
+/// a TimeControl.
+#[derive(Copy, Clone, Debug)]
+pub struct Clock {
+    control: TimeControl,
+
+    // The time left and the number of moves made by each side, indexed by
+    // the Color.
+    remaining: [Duration; Color::N],
+    moves: [u32; Color::N],
+
+    // The side which ran out of time, if any.
+    flagged: Color,
+}
+
+impl Clock {
+    /// new creates a Clock for a game with the given TimeControl, which
+    /// hasn't started yet.
+    pub fn new(control: TimeControl) -> Clock {
+        Clock {
+            control,
+            remaining: [control.base(); Color::N],
+            moves: [0; Color::N],
+            flagged: Color::None,
+        }
+    }
+
+    /// control returns the TimeControl of the Clock.
+    pub fn control(&self) -> TimeControl {
+        self.control
+    }
+
+    /// remaining returns the time left on the clock of the given side.
+    pub fn remaining(&self, color: Color) -> Duration {
+        self.remaining[color as usize]
+    }
+
+    /// tick records a move by the given side which took the given time, and
+    /// returns false if the side ran out of time while making it. Clocks
+    /// don't tick anymore once a side has run out of time.
+    pub fn tick(&mut self, color: Color, elapsed: Duration) -> bool {
+        if self.is_flagged(color) {
+            return false;
+        }
+
+        let remaining = &mut self.remaining[color as usize];
+        if elapsed > *remaining {
+            *remaining = Duration::ZERO;
+            self.flagged = color;
+            return false;
+        }
+
+        self.moves[color as usize] += 1;
+        *remaining = match self.control {
+            TimeControl::MoveTime(time) => time,
+            _ => *remaining - elapsed + self.control.increment(),
+        };
+
+        // The base time is given again at the end of each session.
+        if let Some(moves) = self.control.moves_per_session() {
+            if self.moves[color as usize].is_multiple_of(moves) {
+                *remaining += self.control.base();
+            }
+        }
+
+        true
+    }
+
+    /// is_flagged checks if the given side has run out of time.
+    pub fn is_flagged(&self, color: Color) -> bool {
+        self.flagged == color
+    }
+
+    /// flagged returns the side which has run out of time, or Color::None
+    /// if neither of them have.
+    pub fn flagged(&self) -> Color {
+        self.flagged
+    }
+
+    /// search_limits returns the SearchLimits of a search by the given side
+    /// for its next move.
+    pub fn search_limits(&self, color: Color) -> SearchLimits {
+        self.control
+            .search_limits(self.remaining, self.moves[color as usize])
+    }
+}
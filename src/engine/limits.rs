@@ -0,0 +1,53 @@
+// Copyright © 2023 Rak Laptudirm <rak@laptudirm.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::time::Duration;
+
+use crate::chess::{Color, MoveList};
+
+/// SearchLimits are the limits a search needs to stay within. A search
+/// without any limits goes on until it is stopped.
+#[derive(Copy, Clone, Default)]
+pub struct SearchLimits {
+    /// time is the time left on each side's clock, indexed by the Color.
+    pub time: [Option<Duration>; Color::N],
+
+    /// increment is the time added to each side's clock after each of
+    /// their moves, indexed by the Color.
+    pub increment: [Duration; Color::N],
+
+    /// moves_to_go is the number of moves left until the next time control,
+    /// or None if the rest of the game needs to be played on the clock.
+    pub moves_to_go: Option<u32>,
+
+    /// depth is the maximum depth to search to, in plys.
+    pub depth: Option<u32>,
+
+    /// nodes is the maximum number of nodes to search.
+    pub nodes: Option<u64>,
+
+    /// movetime is the exact amount of time to search for.
+    pub movetime: Option<Duration>,
+
+    /// mate makes the search stop once it finds a mate in at most the given
+    /// number of moves.
+    pub mate: Option<u32>,
+
+    /// infinite makes the search go on until it is stopped, ignoring all
+    /// of the other limits.
+    pub infinite: bool,
+
+    /// search_moves restricts the search to the given moves at the root, if
+    /// there are any, like UCI's `searchmoves`.
+    pub search_moves: MoveList,
+}
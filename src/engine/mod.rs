@@ -0,0 +1,185 @@
+// Copyright © 2023 Rak Laptudirm <rak@laptudirm.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Non-namespaced modules.
+mod clock;
+mod limits;
+mod signals;
+
+// Make the contents of the non-namespaced
+// modules public, so they can be accessed
+// without their parent namespace.
+pub use self::clock::*;
+pub use self::limits::*;
+pub use self::signals::*;
+
+use std::sync::mpsc;
+use std::time::Duration;
+
+use crate::chess::{Board, Move};
+use crate::search::Score;
+use crate::uci::{OptionError, Options};
+
+/// Engine is the core of a chess engine, which the protocol frontends like
+/// UCI drive. Searches are run on a separate thread from the protocol loop,
+/// so that the loop can keep responding to commands while they are going on.
+pub trait Engine: Send + 'static {
+    /// name returns the name of the engine.
+    fn name(&self) -> String;
+
+    /// author returns the name of the engine's author.
+    fn author(&self) -> String;
+
+    /// options returns the registry of the engine's options, which the GUI
+    /// can change, or None if the engine doesn't have any options.
+    fn options(&mut self) -> Option<&mut Options> {
+        None
+    }
+
+    /// set_option sets one of the engine's options from the arguments of a
+    /// `setoption` command. Engines which can't use the new value right away,
+    /// like a file which fails to load, report that as an error too.
+    fn set_option(&mut self, args: &[&str]) -> Result<(), OptionError> {
+        match self.options() {
+            Some(options) => options.set_option(args),
+            // Every option is unknown to an engine without any options.
+            None => Options::new().set_option(args),
+        }
+    }
+
+    /// new_game resets any state the engine keeps between searches, since
+    /// the next search will be from a different game.
+    fn new_game(&mut self) {}
+
+    /// evaluate returns a report of the engine's static evaluation of the
+    /// position on the given Board, which is meant to be read by people,
+    /// or None if the engine doesn't have a static evaluation.
+    fn evaluate(&mut self, _board: &Board) -> Option<String> {
+        None
+    }
+
+    /// search searches the position on the given Board within the given
+    /// limits and returns the best move found in it. The search needs to
+    /// return as soon as possible once it is signaled to stop, and needs to
+    /// ignore its limits while it is pondering.
+    /// Information about the search, like the best line found so far, can
+    /// be reported to the given InfoListener while it is going on.
+    fn search(
+        &mut self,
+        board: Board,
+        limits: SearchLimits,
+        signals: &Signals,
+        listener: &mut dyn InfoListener,
+    ) -> BestMove;
+}
+
+/// BestMove is the result of a search.
+#[derive(Copy, Clone)]
+pub struct BestMove {
+    /// chessmove is the best move found by the search, or Move::NULL if
+    /// there are no legal moves in the searched position.
+    pub chessmove: Move,
+
+    /// ponder is the reply to the best move expected by the search, which
+    /// can be searched while waiting for the opponent to move, or Move::NULL
+    /// if the search doesn't expect any reply.
+    pub ponder: Move,
+}
+
+/// SearchInfo is the information about an ongoing search reported to the
+/// GUI, which is usually reported after each iteration of the search.
+#[derive(Clone)]
+pub struct SearchInfo {
+    /// depth is the depth the search has reached, in plys.
+    pub depth: u8,
+
+    /// seldepth is the selective depth the search has reached, which is the
+    /// highest ply from the root searched, including quiescence search.
+    pub seldepth: u8,
+
+    /// score is the score of the searched position, from the point of view
+    /// of the side to move in it.
+    pub score: Score,
+
+    /// nodes is the number of nodes searched so far.
+    pub nodes: u64,
+
+    /// time is the time spent searching so far.
+    pub time: Duration,
+
+    /// hashfull is the permillage of the transposition table which is used.
+    pub hashfull: usize,
+
+    /// tbhits is the number of positions whose scores were probed from
+    /// endgame tablebases or bitbases so far.
+    pub tbhits: u64,
+
+    /// pv is the principal variation, which is the best line found so far.
+    pub pv: Vec<Move>,
+}
+
+impl SearchInfo {
+    /// nps returns the number of nodes searched per second.
+    pub fn nps(&self) -> u64 {
+        (self.nodes as f64 / self.time.as_secs_f64().max(0.001)) as u64
+    }
+}
+
+/// InfoListener receives the information an Engine reports about an ongoing
+/// search, so that frontends can present it however they need to. Closures
+/// which take a SearchInfo are InfoListeners which ignore the current moves.
+pub trait InfoListener {
+    /// info is called with the SearchInfo of the search, which is usually
+    /// after each iteration of the search.
+    fn info(&mut self, info: &SearchInfo);
+
+    /// currmove is called when the search starts searching a move at the
+    /// root to the given depth, along with the move's number in the order
+    /// the moves are searched in, starting from one.
+    fn currmove(&mut self, _depth: u8, _chessmove: Move, _number: usize) {}
+}
+
+impl<F: FnMut(&SearchInfo)> InfoListener for F {
+    fn info(&mut self, info: &SearchInfo) {
+        self(info)
+    }
+}
+
+/// SearchEvent is an event reported by a search, which can be sent over a
+/// channel to be handled on another thread.
+#[derive(Clone)]
+pub enum SearchEvent {
+    /// Info is the SearchInfo of the search.
+    Info(SearchInfo),
+
+    /// CurrMove is a move the search has started searching at the root.
+    CurrMove {
+        depth: u8,
+        chessmove: Move,
+        number: usize,
+    },
+}
+
+impl InfoListener for mpsc::Sender<SearchEvent> {
+    fn info(&mut self, info: &SearchInfo) {
+        let _ = self.send(SearchEvent::Info(info.clone()));
+    }
+
+    fn currmove(&mut self, depth: u8, chessmove: Move, number: usize) {
+        let _ = self.send(SearchEvent::CurrMove {
+            depth,
+            chessmove,
+            number,
+        });
+    }
+}
@@ -0,0 +1,92 @@
+// Copyright © 2023 Rak Laptudirm <rak@laptudirm.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+/// Signals are the signals a protocol frontend sends to an ongoing search,
+/// which can change how the search behaves while it is going on.
+pub struct Signals {
+    stop: Arc<AtomicBool>,
+    pondering: AtomicBool,
+
+    // The time from which the search's time limits are counted, which is
+    // reset when a ponder search is converted into a normal search.
+    start: Mutex<Instant>,
+}
+
+impl Signals {
+    /// new creates a new set of Signals for a normal search.
+    pub fn new() -> Signals {
+        Signals {
+            stop: Arc::new(AtomicBool::new(false)),
+            pondering: AtomicBool::new(false),
+            start: Mutex::new(Instant::now()),
+        }
+    }
+
+    /// reset resets the Signals for a new search, which is a ponder search
+    /// if pondering is set, and starts the search's clock.
+    pub fn reset(&self, pondering: bool) {
+        self.stop.store(false, Ordering::Relaxed);
+        self.pondering.store(pondering, Ordering::Relaxed);
+        *self.start.lock().unwrap() = Instant::now();
+    }
+
+    /// stop signals the search to stop as soon as possible.
+    pub fn stop(&self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+
+    /// stop_handle returns a handle to the stop flag of the Signals, which
+    /// stops the search once it is set. It can be kept by embedders which
+    /// need to stop searches from another thread without the Signals.
+    pub fn stop_handle(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.stop)
+    }
+
+    /// is_stopped checks if the search has been signaled to stop.
+    pub fn is_stopped(&self) -> bool {
+        self.stop.load(Ordering::Relaxed)
+    }
+
+    /// ponderhit converts the ongoing ponder search into a normal search,
+    /// restarting the search's clock since its time limits apply from now.
+    pub fn ponderhit(&self) {
+        *self.start.lock().unwrap() = Instant::now();
+        self.pondering.store(false, Ordering::Relaxed);
+    }
+
+    /// is_pondering checks if the search is a ponder search. A ponder search
+    /// ignores all of its limits, and goes on until it is either stopped or
+    /// converted into a normal search.
+    pub fn is_pondering(&self) -> bool {
+        self.pondering.load(Ordering::Relaxed)
+    }
+
+    /// elapsed returns the time since the search's clock was started.
+    pub fn elapsed(&self) -> Duration {
+        self.start.lock().unwrap().elapsed()
+    }
+}
+
+impl Default for Signals {
+    fn default() -> Self {
+        Signals::new()
+    }
+}
@@ -0,0 +1,235 @@
+// Copyright © 2023 Rak Laptudirm <rak@laptudirm.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::thread;
+
+use crate::chess::{Board, Color, Move, Piece, Position};
+
+use super::nnue::{AccumulatorStack, Network};
+use super::{kpk, phase, Params, PawnTable, S};
+
+/// Evaluator statically evaluates positions, either with the classical
+/// evaluation or with an NNUE Network if it has one. It keeps the caches
+/// used by the evaluation, so a single Evaluator should be reused across
+/// positions.
+///
+/// The NNUE evaluation is updated incrementally: after the Evaluator is
+/// reset to a position, each move made on the Board must be made on the
+/// Evaluator before it is made on the Board, and taken back along with it.
+/// Positions are evaluated from scratch if the Evaluator hasn't been reset.
+pub struct Evaluator {
+    params: Params,
+    pawns: PawnTable,
+    network: Option<Network>,
+    accumulators: Option<AccumulatorStack>,
+}
+
+impl Evaluator {
+    /// new creates an Evaluator with empty caches.
+    pub fn new() -> Evaluator {
+        Evaluator::with_params(Params::DEFAULT)
+    }
+
+    /// with_params creates an Evaluator with empty caches whose classical
+    /// evaluation uses the given Params.
+    pub fn with_params(params: Params) -> Evaluator {
+        Evaluator {
+            params,
+            pawns: PawnTable::new(),
+            network: None,
+            accumulators: None,
+        }
+    }
+
+    /// set_network sets the NNUE Network used by the Evaluator. Positions
+    /// are evaluated classically if the Evaluator doesn't have a Network.
+    pub fn set_network(&mut self, network: Option<Network>) {
+        self.network = network;
+        self.accumulators = None;
+    }
+
+    /// network returns the NNUE Network used by the Evaluator, if any.
+    pub fn network(&self) -> Option<&Network> {
+        self.network.as_ref()
+    }
+
+    /// params returns the Params of the Evaluator's classical evaluation.
+    pub fn params(&self) -> &Params {
+        &self.params
+    }
+
+    /// set_params sets the Params of the Evaluator's classical evaluation.
+    pub fn set_params(&mut self, params: Params) {
+        self.params = params;
+        self.pawns.clear();
+    }
+
+    /// clear empties the caches of the Evaluator.
+    pub fn clear(&mut self) {
+        self.pawns.clear();
+    }
+
+    /// reset resets the incremental evaluation to the position on the Board.
+    pub fn reset(&mut self, board: &Board) {
+        let Some(network) = &self.network else {
+            return;
+        };
+
+        match &mut self.accumulators {
+            Some(accumulators) => accumulators.reset(network, board),
+            None => self.accumulators = Some(AccumulatorStack::new(network, board)),
+        }
+    }
+
+    /// make_move updates the incremental evaluation with the given move. It
+    /// must be called before the move is made on the Board.
+    pub fn make_move(&mut self, board: &Board, chessmove: Move) {
+        if let (Some(network), Some(accumulators)) = (&self.network, &mut self.accumulators) {
+            accumulators.make_move(network, board, chessmove);
+        }
+    }
+
+    /// undo_move takes back the last move from the incremental evaluation.
+    pub fn undo_move(&mut self) {
+        if let Some(accumulators) = &mut self.accumulators {
+            accumulators.undo_move();
+        }
+    }
+
+    /// evaluate statically evaluates the position on the Board from the
+    /// point of view of the side to move, in centipawns.
+    pub fn evaluate(&mut self, board: &Board) -> i32 {
+        // Endgames with known results are evaluated exactly.
+        if let Some(score) = kpk::evaluate(board) {
+            return score;
+        }
+
+        if let Some(network) = &self.network {
+            return match &mut self.accumulators {
+                Some(accumulators) => {
+                    network.output(accumulators.current(network, board), board.side_to_move())
+                }
+                None => network.evaluate(board),
+            };
+        }
+
+        let params = &self.params;
+        let score = params.pieces(board)
+            + self.pawns.score(board, params)
+            + params.mobility(board)
+            + params.king_safety(board);
+
+        centipawns(board, score)
+    }
+}
+
+impl Evaluator {
+    /// evaluate_many statically evaluates each of the given positions from
+    /// the point of view of its side to move, in centipawns, splitting them
+    /// between as many threads as the machine can run in parallel. Each of
+    /// the positions is evaluated from scratch, and the Evaluator's caches
+    /// aren't touched, so the scores are the same as the ones evaluate gives
+    /// to the positions on a Board which the Evaluator hasn't been reset to.
+    pub fn evaluate_many(&self, positions: &[Position]) -> Vec<i32> {
+        if positions.is_empty() {
+            return Vec::new();
+        }
+
+        let threads = thread::available_parallelism().map_or(1, |threads| threads.get());
+        let chunk_size = positions.len().div_ceil(threads);
+
+        thread::scope(|scope| {
+            let handles: Vec<_> = positions
+                .chunks(chunk_size)
+                .map(|chunk| scope.spawn(move || self.evaluate_chunk(chunk)))
+                .collect();
+
+            handles
+                .into_iter()
+                .flat_map(|handle| handle.join().expect("evaluate many: thread panicked"))
+                .collect()
+        })
+    }
+
+    /// evaluate_chunk statically evaluates each of the given positions from
+    /// scratch, with a pawn cache of its own for the classical evaluation.
+    fn evaluate_chunk(&self, positions: &[Position]) -> Vec<i32> {
+        let mut evaluator = Evaluator::with_params(self.params.clone());
+        positions
+            .iter()
+            .map(|&position| {
+                let board = Board::from(position);
+                match &self.network {
+                    Some(network) => {
+                        kpk::evaluate(&board).unwrap_or_else(|| network.evaluate(&board))
+                    }
+                    None => evaluator.evaluate(&board),
+                }
+            })
+            .collect()
+    }
+}
+
+/// evaluate_many statically evaluates each of the given positions with the
+/// default classical evaluation, in parallel. See Evaluator::evaluate_many.
+pub fn evaluate_many(positions: &[Position]) -> Vec<i32> {
+    Evaluator::new().evaluate_many(positions)
+}
+
+impl Default for Evaluator {
+    fn default() -> Self {
+        Evaluator::new()
+    }
+}
+
+impl Params {
+    /// evaluate classically evaluates the position on the Board from the
+    /// point of view of the side to move, in centipawns, without caching
+    /// any of its terms.
+    pub fn evaluate(&self, board: &Board) -> i32 {
+        let score =
+            self.pieces(board) + self.pawns(board) + self.mobility(board) + self.king_safety(board);
+        centipawns(board, score)
+    }
+
+    /// pieces evaluates the material and piece-square table values of the
+    /// pieces in the position on the Board from white's point of view.
+    pub fn pieces(&self, board: &Board) -> S {
+        self.pieces_color(board, Color::White) - self.pieces_color(board, Color::Black)
+    }
+
+    /// pieces_color returns the material and piece-square table values of
+    /// the pieces of the given Color on the Board.
+    fn pieces_color(&self, board: &Board, color: Color) -> S {
+        let mut score = S::ZERO;
+        for piece in 0..Piece::N {
+            let piece = Piece::from(piece);
+            for square in board.piece_color_bb(piece, color) {
+                let square = square.relative(color);
+                score += self.material[piece as usize] + self.psqt[piece as usize][square as usize];
+            }
+        }
+
+        score
+    }
+}
+
+/// centipawns tapers the given score of the position on the Board from
+/// white's point of view, and returns it from the side to move's.
+fn centipawns(board: &Board, score: S) -> i32 {
+    let score = score.taper(phase(board));
+    match board.side_to_move() {
+        Color::White => score,
+        _ => -score,
+    }
+}
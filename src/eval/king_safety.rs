@@ -0,0 +1,155 @@
+// Copyright © 2023 Rak Laptudirm <rak@laptudirm.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::chess::{moves, BitBoard, Board, Color, File, Piece, Square};
+
+use super::{Params, S};
+
+/// ATTACK_WEIGHTS contains the number of attack units each attack on the
+/// enemy king zone by a Piece is worth, indexed by the Piece.
+pub const ATTACK_WEIGHTS: [i32; Piece::N] = [0, 2, 2, 3, 5, 0];
+
+/// ATTACKER_SCALES contains the percentage of the king danger which is
+/// applied, indexed by the number of pieces attacking the king zone. A
+/// single piece can't do much against the king alone, however strong.
+pub const ATTACKER_SCALES: [i32; 8] = [0, 0, 50, 75, 88, 94, 97, 99];
+
+/// KING_DANGER contains the king danger penalty, indexed by the number of
+/// attack units against the king zone. It grows slowly at first, since a few
+/// attacks are easily parried, and then quickly before it levels off.
+#[rustfmt::skip]
+pub const KING_DANGER: [i32; 100] = [
+      0,   0,   1,   2,   3,   5,   7,   9,  12,  15,
+     18,  22,  26,  30,  35,  39,  44,  50,  56,  62,
+     68,  75,  82,  85,  89,  97, 105, 113, 122, 131,
+    140, 150, 169, 180, 191, 202, 213, 225, 237, 248,
+    260, 272, 283, 295, 307, 319, 330, 342, 354, 366,
+    377, 389, 401, 412, 424, 436, 448, 459, 471, 483,
+    494, 500, 500, 500, 500, 500, 500, 500, 500, 500,
+    500, 500, 500, 500, 500, 500, 500, 500, 500, 500,
+    500, 500, 500, 500, 500, 500, 500, 500, 500, 500,
+    500, 500, 500, 500, 500, 500, 500, 500, 500, 500,
+];
+
+/// PAWN_SHIELD contains the bonus for each friendly pawn shielding the king,
+/// indexed by its distance from the king's rank.
+pub const PAWN_SHIELD: [S; 2] = [S(12, 0), S(6, 0)];
+
+/// OPEN_FILE is the penalty for each file without any pawns next to or on
+/// the king's file, since enemy rooks and queens can use it to attack.
+pub const OPEN_FILE: S = S(-25, 0);
+
+/// SEMI_OPEN_FILE is the penalty for each file without any friendly pawns
+/// next to or on the king's file, which still has enemy pawns on it.
+pub const SEMI_OPEN_FILE: S = S(-12, 0);
+
+/// king_safety evaluates the safety of the kings in the position on the
+/// Board from white's point of view, with the default evaluation Params.
+pub fn king_safety(board: &Board) -> S {
+    Params::DEFAULT.king_safety(board)
+}
+
+impl Params {
+    /// king_safety evaluates the safety of the kings in the position on the
+    /// Board from white's point of view.
+    pub fn king_safety(&self, board: &Board) -> S {
+        self.king_safety_color(board, Color::White) - self.king_safety_color(board, Color::Black)
+    }
+
+    /// king_safety_color evaluates the safety of the given Color's king on
+    /// the Board, from its attackers, its pawn shield, and the open files
+    /// near it.
+    pub(crate) fn king_safety_color(&self, board: &Board, color: Color) -> S {
+        let king = board.piece_color_bb(Piece::King, color);
+        if king.is_empty() {
+            return S::ZERO;
+        }
+
+        let square = king.lsb();
+        self.king_attacks(board, color, square)
+            + self.pawn_shield(board, color, square)
+            + self.king_files(board, color, square)
+    }
+
+    /// king_attacks returns the king danger penalty of the given Color's
+    /// king on the given Square from the enemy pieces attacking the squares
+    /// around it.
+    fn king_attacks(&self, board: &Board, color: Color, square: Square) -> S {
+        let zone = moves::king(square) | BitBoard::from(square);
+        let blockers = board.occupied();
+
+        let mut attackers = 0;
+        let mut units = 0;
+        for piece in [Piece::Knight, Piece::Bishop, Piece::Rook, Piece::Queen] {
+            for attacker in board.piece_color_bb(piece, !color) {
+                let attacks = match piece {
+                    Piece::Knight => moves::knight(attacker),
+                    Piece::Bishop => moves::bishop(attacker, blockers),
+                    Piece::Rook => moves::rook(attacker, blockers),
+                    _ => moves::queen(attacker, blockers),
+                };
+
+                let zone_attacks = (attacks & zone).popcnt() as i32;
+                if zone_attacks > 0 {
+                    attackers += 1;
+                    units += self.attack_weights[piece as usize] * zone_attacks;
+                }
+            }
+        }
+
+        let attackers = attackers.min(self.attacker_scales.len() - 1);
+        let units = (units.max(0) as usize).min(self.king_danger.len() - 1);
+        let danger = self.king_danger[units] * self.attacker_scales[attackers] / 100;
+
+        S(-danger, 0)
+    }
+
+    /// pawn_shield returns the bonus of the given Color's king on the given
+    /// Square for the friendly pawns on the two ranks in front of it.
+    fn pawn_shield(&self, board: &Board, color: Color, square: Square) -> S {
+        let pawns = board.piece_color_bb(Piece::Pawn, color);
+
+        let king = BitBoard::from(square);
+        let mut shield = king | king.east() | king.west();
+
+        let mut score = S::ZERO;
+        for bonus in self.pawn_shield {
+            shield = shield.up(color);
+            score += bonus * (pawns & shield).popcnt() as i32;
+        }
+
+        score
+    }
+
+    /// king_files returns the penalty of the given Color's king on the given
+    /// Square for the open and semi-open files on or next to its file.
+    fn king_files(&self, board: &Board, color: Color, square: Square) -> S {
+        let ours = board.piece_color_bb(Piece::Pawn, color);
+        let theirs = board.piece_color_bb(Piece::Pawn, !color);
+
+        let king_file = square.file() as usize;
+        let mut score = S::ZERO;
+        for file in File::iter().filter(|&file| (file as usize).abs_diff(king_file) <= 1) {
+            let file = BitBoard::file(file);
+            if (ours & file).is_empty() {
+                score += if (theirs & file).is_empty() {
+                    self.open_file
+                } else {
+                    self.semi_open_file
+                };
+            }
+        }
+
+        score
+    }
+}
@@ -0,0 +1,284 @@
+// Copyright © 2023 Rak Laptudirm <rak@laptudirm.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::OnceLock;
+
+use crate::chess::variant::{Standard, Variant};
+use crate::chess::{moves, Board, Color, File, Piece, Rank, Square};
+
+/// KNOWN_WIN is the base score of a won KPvK position for the side with
+/// the pawn. It is higher than any score the other evaluations can give,
+/// but lower than the scores of forced mates.
+pub const KNOWN_WIN: i32 = 10000;
+
+/// N is the number of positions in the KPvK bitbase: the side to move, the
+/// squares of the two kings, and the 24 squares a queenside pawn can be on.
+const N: usize = Color::N * Square::N * Square::N * 24;
+
+// The results of the positions while the bitbase is being generated. They
+// are bit flags, so that the results of the successors of a position can be
+// or-ed together to check if any of them have a particular result.
+const INVALID: u8 = 0;
+const UNKNOWN: u8 = 1;
+const DRAW: u8 = 2;
+const WIN: u8 = 4;
+
+/// BITBASE is the KPvK bitbase, which has a bit set for each position which
+/// is won by white. It is generated the first time it is probed.
+static BITBASE: OnceLock<Vec<u64>> = OnceLock::new();
+
+/// probe checks if the KPvK position with white's king, black's king and
+/// white's pawn on the given squares and the given side to move is won by
+/// white. Positions where black has the pawn can be probed by flipping the
+/// ranks of the squares and the side to move.
+pub fn probe(white_king: Square, black_king: Square, pawn: Square, stm: Color) -> bool {
+    // Positions with kingside pawns are mirror images of ones with
+    // queenside pawns, so only the queenside ones are stored.
+    let (white_king, black_king, pawn) = if pawn.file() >= File::E {
+        (
+            white_king.flip_file(),
+            black_king.flip_file(),
+            pawn.flip_file(),
+        )
+    } else {
+        (white_king, black_king, pawn)
+    };
+
+    let index = index(stm, white_king, black_king, pawn);
+    let bitbase = BITBASE.get_or_init(generate);
+    bitbase[index / 64] >> (index % 64) & 1 != 0
+}
+
+/// evaluate returns the exact evaluation of the position on the Board from
+/// the point of view of the side to move, if it is a KPvK position. Won
+/// positions are scored higher the further the pawn is pushed, so that the
+/// winning side makes progress, and drawn positions are scored as draws.
+/// The bitbase only holds for standard chess, so positions of the other
+/// Variants are never KPvK positions.
+pub fn evaluate(board: &Board) -> Option<i32> {
+    if board.variant().name() != Standard.name() {
+        return None;
+    }
+
+    let pawns = board.piece_bb(Piece::Pawn);
+    let has_king = |color| board.piece_color_bb(Piece::King, color).popcnt() == 1;
+    if board.occupied().popcnt() != 3
+        || pawns.popcnt() != 1
+        || !has_king(Color::White)
+        || !has_king(Color::Black)
+    {
+        return None;
+    }
+
+    let pawn = pawns.lsb();
+    let strong = board.piece_at(pawn).color();
+    let strong_king = board.piece_color_bb(Piece::King, strong).lsb();
+    let weak_king = board.piece_color_bb(Piece::King, !strong).lsb();
+
+    // Flip the position so that the strong side is white.
+    let stm = match strong {
+        Color::White => board.side_to_move(),
+        _ => !board.side_to_move(),
+    };
+
+    let pawn = pawn.relative(strong);
+    if !probe(
+        strong_king.relative(strong),
+        weak_king.relative(strong),
+        pawn,
+        stm,
+    ) {
+        return Some(0);
+    }
+
+    let score = KNOWN_WIN + (Rank::First as i32 - pawn.rank() as i32) * 10;
+    Some(if stm == Color::White { score } else { -score })
+}
+
+/// index returns the index of the KPvK position with the given side to
+/// move, white king, black king, and white queenside pawn in the bitbase.
+fn index(stm: Color, white_king: Square, black_king: Square, pawn: Square) -> usize {
+    let pawn = pawn.file() as usize + 4 * (Rank::Second as usize - pawn.rank() as usize);
+    stm as usize | (white_king as usize) << 1 | (black_king as usize) << 7 | pawn << 13
+}
+
+/// generate generates the KPvK bitbase by retrograde analysis: positions
+/// whose results are known without searching are classified first, and the
+/// others are then classified from the results of their successors until
+/// no more positions can be classified. Positions which are still unknown
+/// after that are draws.
+fn generate() -> Vec<u64> {
+    let mut results: Vec<u8> = (0..N).map(|index| initial_result(decode(index))).collect();
+
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for index in 0..N {
+            if results[index] != UNKNOWN {
+                continue;
+            }
+
+            let result = successor_result(decode(index), &results);
+            if result != UNKNOWN {
+                results[index] = result;
+                changed = true;
+            }
+        }
+    }
+
+    let mut bitbase = vec![0; N / 64];
+    for (index, &result) in results.iter().enumerate() {
+        if result == WIN {
+            bitbase[index / 64] |= 1 << (index % 64);
+        }
+    }
+
+    bitbase
+}
+
+/// decode returns the side to move, white king, black king, and white pawn
+/// of the KPvK position with the given index in the bitbase.
+fn decode(index: usize) -> (Color, Square, Square, Square) {
+    let pawn = index >> 13;
+    let pawn_rank = Rank::Second as usize - pawn / 4;
+
+    (
+        Color::from(index & 1),
+        Square::from(index >> 1 & 63),
+        Square::from(index >> 7 & 63),
+        Square::from(pawn_rank * 8 + pawn % 4),
+    )
+}
+
+/// initial_result classifies the given KPvK position without looking at
+/// its successors, which is possible for illegal positions, immediate
+/// promotions, stalemates, and positions where the pawn can be captured.
+fn initial_result((stm, white_king, black_king, pawn): (Color, Square, Square, Square)) -> u8 {
+    let pawn_attacks = moves::pawn_attacks(pawn, Color::White);
+
+    if white_king.distance(black_king) <= 1
+        || white_king == pawn
+        || black_king == pawn
+        || (stm == Color::White && pawn_attacks.contains(black_king))
+    {
+        return INVALID;
+    }
+
+    // The pawn can safely promote if the black king can't capture the new
+    // queen, or if the white king defends it.
+    let push = pawn.up(Color::White);
+    if stm == Color::White
+        && pawn.rank() == Rank::Seventh
+        && white_king != push
+        && (black_king.distance(push) > 1 || white_king.distance(push) == 1)
+    {
+        return WIN;
+    }
+
+    if stm == Color::Black {
+        let black_moves = moves::king(black_king);
+        let white_attacks = moves::king(white_king) | pawn_attacks;
+
+        // Black is stalemated, or can capture an undefended pawn.
+        if (black_moves & !white_attacks).is_empty()
+            || (black_moves.contains(pawn) && !moves::king(white_king).contains(pawn))
+        {
+            return DRAW;
+        }
+    }
+
+    UNKNOWN
+}
+
+/// successor_result classifies the given KPvK position from the results of
+/// its successors: the side to move wins if any of them is a win for it,
+/// and loses if all of them are losses for it.
+fn successor_result(
+    (stm, white_king, black_king, pawn): (Color, Square, Square, Square),
+    results: &[u8],
+) -> u8 {
+    let (good, bad) = match stm {
+        Color::White => (WIN, DRAW),
+        _ => (DRAW, WIN),
+    };
+
+    // Illegal successors are INVALID, so they don't affect the result.
+    let mut result = INVALID;
+    if stm == Color::White {
+        for square in moves::king(white_king) {
+            result |= results[index(Color::Black, square, black_king, pawn)];
+        }
+
+        let push = pawn.up(Color::White);
+        if pawn.rank() != Rank::Seventh {
+            result |= results[index(Color::Black, white_king, black_king, push)];
+        }
+
+        if pawn.rank() == Rank::Second && push != white_king && push != black_king {
+            let double_push = push.up(Color::White);
+            result |= results[index(Color::Black, white_king, black_king, double_push)];
+        }
+    } else {
+        for square in moves::king(black_king) {
+            result |= results[index(Color::White, white_king, square, pawn)];
+        }
+    }
+
+    if result & good != 0 {
+        good
+    } else if result & UNKNOWN != 0 {
+        UNKNOWN
+    } else {
+        bad
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::chess::variant::{self, Variant};
+
+    use super::*;
+
+    fn evaluate_fen(fen: &str, variant: &'static dyn Variant) -> Option<i32> {
+        evaluate(&Board::from_fen(fen, variant).unwrap())
+    }
+
+    #[test]
+    fn standard_kpk_positions() {
+        // The defending king holds the draw in front of the rook pawn.
+        let fen = "k7/8/8/8/8/8/P7/K7 w - - 0 1";
+        assert_eq!(evaluate_fen(fen, &variant::Standard), Some(0));
+
+        // The pawn runs away from the defending king.
+        let fen = "7k/8/8/8/8/8/P7/K7 w - - 0 1";
+        assert!(evaluate_fen(fen, &variant::Standard).unwrap() >= KNOWN_WIN);
+        let fen = "7k/8/8/8/8/8/P7/K7 b - - 0 1";
+        assert!(evaluate_fen(fen, &variant::Standard).unwrap() <= -KNOWN_WIN);
+
+        // Positions with other pieces aren't KPvK positions.
+        let fen = "4k3/8/8/8/8/8/4P3/3NK3 w - - 0 1";
+        assert_eq!(evaluate_fen(fen, &variant::Standard), None);
+    }
+
+    #[test]
+    fn variant_positions_are_not_kpk_positions() {
+        let fen = "4k3/8/8/8/8/8/4P3/3N4 w - - 0 1";
+        assert_eq!(evaluate_fen(fen, &variant::Horde), None);
+
+        let fen = "4r3/8/8/8/8/8/4P3/3Q4 w - - 0 1";
+        assert_eq!(evaluate_fen(fen, &variant::Antichess), None);
+
+        let fen = "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1";
+        assert_eq!(evaluate_fen(fen, &variant::KingOfTheHill), None);
+    }
+}
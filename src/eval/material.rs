@@ -0,0 +1,34 @@
+// Copyright © 2023 Rak Laptudirm <rak@laptudirm.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::chess::{Board, Piece};
+
+use super::S;
+
+/// MATERIAL contains the material value of each Piece, indexed by the Piece.
+/// The king has no material value since it is never off the board.
+pub const MATERIAL: [S; Piece::N] = [
+    S(82, 94),
+    S(337, 281),
+    S(365, 297),
+    S(477, 512),
+    S(1025, 936),
+    S(0, 0),
+];
+
+/// phase returns the phase of the game in the position on the Board, which
+/// goes from MAX_PHASE with all the pieces on the board down to zero with
+/// only pawns and kings left.
+pub fn phase(board: &Board) -> i32 {
+    board.phase() as i32
+}
@@ -0,0 +1,95 @@
+// Copyright © 2023 Rak Laptudirm <rak@laptudirm.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::chess::{moves, BitBoard, Board, Color, Piece};
+
+use super::{attacked_by_pawns, Params, S};
+
+/// KNIGHT_MOBILITY contains the bonus for a knight, indexed by its mobility.
+#[rustfmt::skip]
+pub const KNIGHT_MOBILITY: [S; 9] = [
+    S(-31, -39), S(-26, -28), S(-6, -15), S(-1, -8), S(1, 3), S(6, 6), S(10, 8),
+    S(14, 10), S(18, 13),
+];
+
+/// BISHOP_MOBILITY contains the bonus for a bishop, indexed by its mobility.
+#[rustfmt::skip]
+pub const BISHOP_MOBILITY: [S; 14] = [
+    S(-23, -29), S(-10, -12), S(7, -4), S(14, 6), S(19, 10), S(26, 20), S(26, 28),
+    S(30, 29), S(31, 32), S(34, 36), S(39, 39), S(41, 43), S(45, 44), S(48, 49),
+];
+
+/// ROOK_MOBILITY contains the bonus for a rook, indexed by its mobility.
+#[rustfmt::skip]
+pub const ROOK_MOBILITY: [S; 15] = [
+    S(-30, -41), S(-12, -7), S(0, 8), S(1, 21), S(2, 36), S(7, 50), S(10, 51),
+    S(15, 61), S(20, 66), S(20, 69), S(20, 76), S(22, 80), S(28, 82), S(29, 85),
+    S(33, 87),
+];
+
+/// QUEEN_MOBILITY contains the bonus for a queen, indexed by its mobility.
+#[rustfmt::skip]
+pub const QUEEN_MOBILITY: [S; 28] = [
+    S(-14, -24), S(-8, -14), S(-4, -4), S(-4, 8), S(9, 19), S(12, 27), S(11, 29),
+    S(18, 36), S(20, 38), S(27, 47), S(32, 47), S(34, 50), S(34, 62), S(35, 64),
+    S(35, 66), S(35, 66), S(35, 68), S(36, 70), S(37, 73), S(38, 74), S(45, 76),
+    S(52, 84), S(52, 85), S(53, 85), S(56, 89), S(57, 92), S(57, 93), S(59, 110),
+];
+
+/// mobility evaluates the mobility of the pieces in the position on the
+/// Board from white's point of view, with the default evaluation Params.
+pub fn mobility(board: &Board) -> S {
+    Params::DEFAULT.mobility(board)
+}
+
+impl Params {
+    /// mobility evaluates the mobility of the pieces in the position on the
+    /// Board from white's point of view. The mobility of a piece is the number
+    /// of squares it attacks which are neither occupied by friendly pieces nor
+    /// attacked by enemy pawns, since it can't safely go to any of the others.
+    pub fn mobility(&self, board: &Board) -> S {
+        self.mobility_color(board, Color::White) - self.mobility_color(board, Color::Black)
+    }
+
+    /// mobility_color evaluates the mobility of the given Color's pieces on
+    /// the Board. Pawns and kings don't have any mobility scores.
+    pub(crate) fn mobility_color(&self, board: &Board, color: Color) -> S {
+        let area = !(board.color_bb(color) | attacked_by_pawns(board, !color));
+        let blockers = board.occupied();
+
+        let mut score = S::ZERO;
+        for square in board.piece_color_bb(Piece::Knight, color) {
+            score += self.knight_mobility[count(moves::knight(square), area)];
+        }
+
+        for square in board.piece_color_bb(Piece::Bishop, color) {
+            score += self.bishop_mobility[count(moves::bishop(square, blockers), area)];
+        }
+
+        for square in board.piece_color_bb(Piece::Rook, color) {
+            score += self.rook_mobility[count(moves::rook(square, blockers), area)];
+        }
+
+        for square in board.piece_color_bb(Piece::Queen, color) {
+            score += self.queen_mobility[count(moves::queen(square, blockers), area)];
+        }
+
+        score
+    }
+}
+
+/// count returns the number of the given attacks which are in the given
+/// mobility area.
+fn count(attacks: BitBoard, area: BitBoard) -> usize {
+    (attacks & area).popcnt() as usize
+}
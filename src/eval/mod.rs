@@ -0,0 +1,28 @@
+// Namespaced modules.
+pub mod kpk;
+pub mod nnue;
+pub mod tune;
+
+// Non-namespaced modules.
+mod evaluate;
+mod king_safety;
+mod material;
+mod mobility;
+mod params;
+mod pawns;
+mod psqt;
+mod score;
+mod trace;
+
+// Make the contents of the non-namespaced
+// modules public, so they can be accessed
+// without their parent namespace.
+pub use self::evaluate::*;
+pub use self::king_safety::*;
+pub use self::material::*;
+pub use self::mobility::*;
+pub use self::params::*;
+pub use self::pawns::*;
+pub use self::psqt::*;
+pub use self::score::*;
+pub use self::trace::*;
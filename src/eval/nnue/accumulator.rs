@@ -0,0 +1,366 @@
+// Copyright © 2023 Rak Laptudirm <rak@laptudirm.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::chess::{castling, Board, Color, ColoredPiece, Move, MoveFlag, Piece, Square};
+
+use crate::search::MAX_PLY;
+
+use super::{feature, is_mirrored, Network, HIDDEN};
+
+/// Accumulator contains the hidden layer of a Network from the perspective
+/// of each side. The hidden layer is the sum of the feature weights of the
+/// active input features, so it can be updated as pieces move around. The
+/// hidden layers are stored inline, so creating one doesn't allocate.
+#[derive(Clone)]
+pub struct Accumulator {
+    values: [[i16; HIDDEN]; Color::N],
+}
+
+impl Accumulator {
+    /// new creates an Accumulator without any active input features.
+    pub fn new(network: &Network) -> Accumulator {
+        let mut values = [[0; HIDDEN]; Color::N];
+        for perspective in &mut values {
+            perspective.copy_from_slice(network.feature_bias());
+        }
+
+        Accumulator { values }
+    }
+
+    /// get returns the hidden layer from the perspective of the given side.
+    pub fn get(&self, perspective: Color) -> &[i16] {
+        &self.values[perspective as usize]
+    }
+
+    /// refresh recomputes the Accumulator from scratch for the position on
+    /// the Board.
+    pub fn refresh(&mut self, network: &Network, board: &Board) {
+        self.refresh_perspective(network, board, Color::White);
+        self.refresh_perspective(network, board, Color::Black);
+    }
+
+    /// refresh_perspective recomputes the hidden layer from the perspective
+    /// of the given side from scratch for the position on the Board.
+    pub fn refresh_perspective(&mut self, network: &Network, board: &Board, perspective: Color) {
+        let king = king_square(board, perspective);
+        let values = &mut self.values[perspective as usize];
+        values.copy_from_slice(network.feature_bias());
+
+        for color in [Color::White, Color::Black] {
+            for piece in 0..Piece::N {
+                let piece = Piece::from(piece);
+                for square in board.piece_color_bb(piece, color) {
+                    let feature = feature(perspective, king, piece, color, square);
+                    add(values, network.feature_weights(feature));
+                }
+            }
+        }
+    }
+
+    /// add activates the input feature of the given piece on the given Square
+    /// in the hidden layer from the perspective of the given side, whose king
+    /// is on the given king Square.
+    pub fn add(
+        &mut self,
+        network: &Network,
+        perspective: Color,
+        king: Square,
+        piece: ColoredPiece,
+        square: Square,
+    ) {
+        let feature = feature(perspective, king, piece.piece(), piece.color(), square);
+        add(
+            &mut self.values[perspective as usize],
+            network.feature_weights(feature),
+        );
+    }
+
+    /// remove deactivates the input feature of the given piece on the given
+    /// Square in the hidden layer from the perspective of the given side,
+    /// whose king is on the given king Square.
+    pub fn remove(
+        &mut self,
+        network: &Network,
+        perspective: Color,
+        king: Square,
+        piece: ColoredPiece,
+        square: Square,
+    ) {
+        let feature = feature(perspective, king, piece.piece(), piece.color(), square);
+        sub(
+            &mut self.values[perspective as usize],
+            network.feature_weights(feature),
+        );
+    }
+}
+
+/// AccumulatorStack keeps the Accumulators of the positions along the line
+/// being searched, so that the Accumulator of each position is computed by
+/// updating the one before it with only the pieces changed by the move, and
+/// taking back a move is as cheap as popping its Accumulator. Space for the
+/// Accumulators of the deepest line the search can reach is allocated when
+/// the stack is created, so that the search doesn't allocate any more.
+pub struct AccumulatorStack {
+    // Accumulators are reused between moves to avoid reallocating them, so
+    // only the first len of them belong to the current line.
+    accumulators: Vec<Accumulator>,
+    len: usize,
+
+    // The perspectives whose hidden layers in the top Accumulator need to
+    // be refreshed, since the king of that perspective was mirrored.
+    stale: [bool; Color::N],
+    stale_stack: Vec<[bool; Color::N]>,
+}
+
+impl AccumulatorStack {
+    /// CAPACITY is the number of Accumulators an AccumulatorStack has space
+    /// for before it needs to grow, which is more than the number of plys
+    /// the search can go down from the root.
+    pub const CAPACITY: usize = MAX_PLY + 1;
+
+    /// new creates an AccumulatorStack for the position on the given Board.
+    pub fn new(network: &Network, board: &Board) -> AccumulatorStack {
+        let mut accumulator = Accumulator::new(network);
+        accumulator.refresh(network, board);
+
+        let mut accumulators = Vec::with_capacity(AccumulatorStack::CAPACITY);
+        accumulators.push(accumulator);
+
+        AccumulatorStack {
+            accumulators,
+            len: 1,
+            stale: [false; Color::N],
+            stale_stack: Vec::with_capacity(AccumulatorStack::CAPACITY),
+        }
+    }
+
+    /// reset makes the position on the given Board the bottom of the stack,
+    /// refreshing its Accumulator from scratch.
+    pub fn reset(&mut self, network: &Network, board: &Board) {
+        self.accumulators[0].refresh(network, board);
+        self.len = 1;
+        self.stale = [false; Color::N];
+        self.stale_stack.clear();
+    }
+
+    /// make_move pushes the Accumulator of the position after the given move
+    /// is made on the Board. It must be called before the move is made.
+    pub fn make_move(&mut self, network: &Network, board: &Board, chessmove: Move) {
+        if self.len == self.accumulators.len() {
+            self.accumulators
+                .push(self.accumulators[self.len - 1].clone());
+        } else {
+            let (previous, next) = self.accumulators.split_at_mut(self.len);
+            next[0].clone_from(&previous[self.len - 1]);
+        }
+
+        self.stale_stack.push(self.stale);
+        self.len += 1;
+
+        let us = board.side_to_move();
+        let (removed, added) = changes(board, chessmove);
+
+        // A king move which mirrors its perspective changes all of the
+        // perspective's features, so it is refreshed from scratch instead.
+        let king = [
+            king_square(board, Color::White),
+            king_square(board, Color::Black),
+        ];
+        let moved = board.piece_at(chessmove.source());
+        if moved.is(Piece::King) && is_mirrored(added[0].1) != is_mirrored(chessmove.source()) {
+            self.stale[us as usize] = true;
+        }
+
+        let accumulator = &mut self.accumulators[self.len - 1];
+        for perspective in [Color::White, Color::Black] {
+            if self.stale[perspective as usize] {
+                continue;
+            }
+
+            let king = king[perspective as usize];
+            for (piece, square) in removed
+                .into_iter()
+                .filter(|&(piece, _)| piece != ColoredPiece::None)
+            {
+                accumulator.remove(network, perspective, king, piece, square);
+            }
+
+            for (piece, square) in added
+                .into_iter()
+                .filter(|&(piece, _)| piece != ColoredPiece::None)
+            {
+                accumulator.add(network, perspective, king, piece, square);
+            }
+        }
+    }
+
+    /// undo_move pops the Accumulator of the position after the last move.
+    pub fn undo_move(&mut self) {
+        self.len -= 1;
+        self.stale = self.stale_stack.pop().expect("undo move: no moves to undo");
+    }
+
+    /// current returns the Accumulator of the position on the given Board,
+    /// which must be the position at the top of the stack.
+    pub fn current(&mut self, network: &Network, board: &Board) -> &Accumulator {
+        let accumulator = &mut self.accumulators[self.len - 1];
+        for perspective in [Color::White, Color::Black] {
+            if self.stale[perspective as usize] {
+                accumulator.refresh_perspective(network, board, perspective);
+                self.stale[perspective as usize] = false;
+            }
+        }
+
+        accumulator
+    }
+}
+
+/// Changes contains the pieces changed on the Board by a move, as pieces and
+/// the squares they are on, with the moving piece first. Unused slots
+/// contain ColoredPiece::None.
+type Changes = [(ColoredPiece, Square); 2];
+
+/// changes returns the pieces removed from and added to the Board by the
+/// given move.
+fn changes(board: &Board, chessmove: Move) -> (Changes, Changes) {
+    let source = chessmove.source();
+    let target = chessmove.target();
+
+    let moved = board.piece_at(source);
+    let captured = board.piece_at(target);
+    let none = (ColoredPiece::None, Square::None);
+
+    match chessmove.flags() {
+        MoveFlag::Castle => {
+            let (king_target, rook_target) =
+                castling::SideColor::from_sqs(source, target).get_targets();
+            (
+                [(moved, source), (captured, target)],
+                [(moved, king_target), (captured, rook_target)],
+            )
+        }
+
+        MoveFlag::EnPassant => {
+            let captured_square = target.down(board.side_to_move());
+            (
+                [
+                    (moved, source),
+                    (board.piece_at(captured_square), captured_square),
+                ],
+                [(moved, target), none],
+            )
+        }
+
+        MoveFlag::Promotion => {
+            let promotion = ColoredPiece::new(chessmove.promot(), board.side_to_move());
+            (
+                [(moved, source), (captured, target)],
+                [(promotion, target), none],
+            )
+        }
+
+        MoveFlag::Normal => (
+            [(moved, source), (captured, target)],
+            [(moved, target), none],
+        ),
+    }
+}
+
+/// king_square returns the square of the given side's king on the Board.
+fn king_square(board: &Board, color: Color) -> Square {
+    board.piece_color_bb(Piece::King, color).lsb()
+}
+
+/// add adds the given weights to the given hidden layer.
+fn add(values: &mut [i16], weights: &[i16]) {
+    debug_assert_eq!(values.len(), HIDDEN);
+    for (value, &weight) in values.iter_mut().zip(weights) {
+        *value += weight;
+    }
+}
+
+/// sub subtracts the given weights from the given hidden layer.
+fn sub(values: &mut [i16], weights: &[i16]) {
+    debug_assert_eq!(values.len(), HIDDEN);
+    for (value, &weight) in values.iter_mut().zip(weights) {
+        *value -= weight;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use crate::chess::FEN;
+
+    use super::*;
+
+    /// network returns a Network with pseudo-random parameters, which are
+    /// small enough for the hidden layers to never overflow.
+    fn network() -> Network {
+        let mut state = 0x2545_f491_4f6c_dd1d_u64;
+        let bytes: Vec<u8> = (0..Network::SIZE / 2)
+            .flat_map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                ((state % 128) as i16 - 64).to_le_bytes()
+            })
+            .collect();
+
+        Network::from_bytes(&bytes).unwrap()
+    }
+
+    /// assert_refreshed checks that the top Accumulator of the stack is the
+    /// same as one computed from scratch for the position on the Board.
+    fn assert_refreshed(stack: &mut AccumulatorStack, network: &Network, board: &Board) {
+        let mut expected = Accumulator::new(network);
+        expected.refresh(network, board);
+
+        let accumulator = stack.current(network, board);
+        for perspective in [Color::White, Color::Black] {
+            assert_eq!(
+                accumulator.get(perspective),
+                expected.get(perspective),
+                "{} {perspective}",
+                FEN::from(board)
+            );
+        }
+    }
+
+    #[test]
+    fn incremental_updates_match_refreshes() {
+        let network = network();
+        let mut board = Board::from_str("r3k2r/1P6/8/3pP3/8/8/6PP/R3K2R w KQkq d6 0 1").unwrap();
+        let mut stack = AccumulatorStack::new(&network, &board);
+
+        // En passant, castling on both sides, a capturing promotion, and
+        // kings crossing between the mirrored and unmirrored halves.
+        let line =
+            "e5d6 e8g8 b7a8q f8a8 e1c1 g8f7 c1d2 f7e6 d2e3 e6d7 e3d3 a8a2 d3e4 a2g2 h2h4 d7e6";
+
+        assert_refreshed(&mut stack, &network, &board);
+        for uci in line.split_whitespace() {
+            let chessmove = board.parse_uci(uci, false).unwrap();
+            stack.make_move(&network, &board, chessmove);
+            board.make_move(chessmove);
+            assert_refreshed(&mut stack, &network, &board);
+        }
+
+        for _ in line.split_whitespace() {
+            stack.undo_move();
+            board.undo_move();
+            assert_refreshed(&mut stack, &network, &board);
+        }
+    }
+}
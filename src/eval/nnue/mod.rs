@@ -0,0 +1,9 @@
+// Non-namespaced modules.
+mod accumulator;
+mod network;
+
+// Make the contents of the non-namespaced
+// modules public, so they can be accessed
+// without their parent namespace.
+pub use self::accumulator::*;
+pub use self::network::*;
@@ -0,0 +1,162 @@
+// Copyright © 2023 Rak Laptudirm <rak@laptudirm.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{fs, io, path::Path};
+
+use crate::chess::{Board, Color, ColoredPiece, File, Piece, Square};
+
+use super::Accumulator;
+
+/// INPUTS is the number of input features of a Network, which are the
+/// squares each of the pieces of either color can be on.
+pub const INPUTS: usize = ColoredPiece::N * Square::N;
+
+/// HIDDEN is the number of neurons in the hidden layer of a Network, which
+/// is the size of the accumulator of each perspective.
+pub const HIDDEN: usize = 256;
+
+/// QA is the quantization factor of the feature transformer's parameters.
+pub const QA: i32 = 255;
+
+/// QB is the quantization factor of the output layer's weights.
+pub const QB: i32 = 64;
+
+/// SCALE converts the output of a Network into centipawns.
+pub const SCALE: i32 = 400;
+
+/// Network is an efficiently updatable neural network which evaluates
+/// positions. It has a feature transformer, which turns the pieces on the
+/// board into a hidden layer for each side's perspective, and an output
+/// layer, which turns the squared clipped ReLU of the hidden layers of the
+/// side to move and its opponent into an evaluation.
+pub struct Network {
+    feature_weights: Vec<i16>,
+    feature_bias: Vec<i16>,
+    output_weights: Vec<i16>,
+    output_bias: i16,
+}
+
+impl Network {
+    /// SIZE is the size of a network file in bytes.
+    pub const SIZE: usize = (INPUTS * HIDDEN + HIDDEN + 2 * HIDDEN + 1) * 2;
+
+    /// load reads the Network from the network file at the given path.
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Network> {
+        Network::from_bytes(&fs::read(path)?)
+    }
+
+    /// from_bytes reads the Network from the given bytes of a network file,
+    /// which contains the feature weights, feature biases, output weights,
+    /// and output bias in that order, as little-endian 16-bit integers. The
+    /// feature weights of each input feature are stored contiguously.
+    pub fn from_bytes(bytes: &[u8]) -> io::Result<Network> {
+        if bytes.len() != Network::SIZE {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "network file is {} bytes instead of {}",
+                    bytes.len(),
+                    Network::SIZE
+                ),
+            ));
+        }
+
+        let mut params = bytes
+            .chunks_exact(2)
+            .map(|param| i16::from_le_bytes([param[0], param[1]]));
+
+        Ok(Network {
+            feature_weights: params.by_ref().take(INPUTS * HIDDEN).collect(),
+            feature_bias: params.by_ref().take(HIDDEN).collect(),
+            output_weights: params.by_ref().take(2 * HIDDEN).collect(),
+            output_bias: params.next().unwrap_or_default(),
+        })
+    }
+
+    /// evaluate evaluates the position on the Board from the point of view
+    /// of the side to move, in centipawns, by refreshing an Accumulator.
+    pub fn evaluate(&self, board: &Board) -> i32 {
+        let mut accumulator = Accumulator::new(self);
+        accumulator.refresh(self, board);
+        self.output(&accumulator, board.side_to_move())
+    }
+
+    /// output runs the output layer of the Network on the given Accumulator
+    /// from the point of view of the given side to move, in centipawns.
+    pub fn output(&self, accumulator: &Accumulator, side: Color) -> i32 {
+        let (us, them) = self.output_weights.split_at(HIDDEN);
+
+        let sum = screlu_dot(accumulator.get(side), us) + screlu_dot(accumulator.get(!side), them);
+        let output = sum / QA as i64 + self.output_bias as i64;
+        (output * SCALE as i64 / (QA * QB) as i64) as i32
+    }
+
+    /// feature_bias returns the biases of the feature transformer.
+    pub(crate) fn feature_bias(&self) -> &[i16] {
+        &self.feature_bias
+    }
+
+    /// feature_weights returns the feature transformer's weights of the given
+    /// input feature.
+    pub(crate) fn feature_weights(&self, feature: usize) -> &[i16] {
+        &self.feature_weights[feature * HIDDEN..][..HIDDEN]
+    }
+}
+
+/// feature returns the input feature of the given Piece of the given Color
+/// on the given Square from the given perspective, whose king is on the
+/// given king Square. Features are relative to the perspective, with its
+/// own pieces first and squares indexed from a1. The board is mirrored
+/// horizontally when the king is on the e to h files, so that the king is
+/// always on the a to d files from the perspective of the Network.
+pub fn feature(
+    perspective: Color,
+    king: Square,
+    piece: Piece,
+    color: Color,
+    square: Square,
+) -> usize {
+    let (side, square) = match perspective {
+        Color::White => (color as usize, square.flip_rank()),
+        _ => (!color as usize, square),
+    };
+
+    let square = if is_mirrored(king) {
+        square.flip_file()
+    } else {
+        square
+    };
+
+    (side * Piece::N + piece as usize) * Square::N + square as usize
+}
+
+/// is_mirrored checks if the features of a perspective whose king is on the
+/// given Square are mirrored horizontally. A king moving across the middle of
+/// the board changes all the features of its perspective.
+pub fn is_mirrored(king: Square) -> bool {
+    king != Square::None && king.file() >= File::E
+}
+
+/// screlu_dot returns the dot product of the squared clipped ReLU of the
+/// given hidden layer and the given weights. The products are too large to
+/// be summed up as 32-bit integers without the risk of overflowing.
+fn screlu_dot(hidden: &[i16], weights: &[i16]) -> i64 {
+    hidden
+        .iter()
+        .zip(weights)
+        .map(|(&value, &weight)| {
+            let value = (value as i32).clamp(0, QA);
+            (value * value) as i64 * weight as i64
+        })
+        .sum()
+}
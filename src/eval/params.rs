@@ -0,0 +1,189 @@
+// Copyright © 2023 Rak Laptudirm <rak@laptudirm.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fmt;
+
+use crate::chess::{Piece, Rank, Square};
+
+use super::{
+    ATTACKER_SCALES, ATTACK_WEIGHTS, BACKWARD, BISHOP_MOBILITY, DOUBLED, ISOLATED, KING_DANGER,
+    KNIGHT_MOBILITY, MATERIAL, OPEN_FILE, PASSED, PAWN_SHIELD, PSQT, QUEEN_MOBILITY, ROOK_MOBILITY,
+    S, SEMI_OPEN_FILE,
+};
+
+/// Params contains the tunable parameters of the classical evaluation. The
+/// evaluation uses Params::DEFAULT, which is made up of the constants of
+/// each of its terms, unless it is given other Params, like tuned ones.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Params {
+    pub material: [S; Piece::N],
+    pub psqt: [[S; Square::N]; Piece::N],
+
+    pub doubled: S,
+    pub isolated: S,
+    pub backward: S,
+    pub passed: [S; Rank::N],
+
+    pub knight_mobility: [S; 9],
+    pub bishop_mobility: [S; 14],
+    pub rook_mobility: [S; 15],
+    pub queen_mobility: [S; 28],
+
+    pub attack_weights: [i32; Piece::N],
+    pub attacker_scales: [i32; 8],
+    pub king_danger: [i32; 100],
+    pub pawn_shield: [S; 2],
+    pub open_file: S,
+    pub semi_open_file: S,
+}
+
+impl Params {
+    /// DEFAULT contains the default values of the evaluation parameters.
+    pub const DEFAULT: Params = Params {
+        material: MATERIAL,
+        psqt: PSQT,
+
+        doubled: DOUBLED,
+        isolated: ISOLATED,
+        backward: BACKWARD,
+        passed: PASSED,
+
+        knight_mobility: KNIGHT_MOBILITY,
+        bishop_mobility: BISHOP_MOBILITY,
+        rook_mobility: ROOK_MOBILITY,
+        queen_mobility: QUEEN_MOBILITY,
+
+        attack_weights: ATTACK_WEIGHTS,
+        attacker_scales: ATTACKER_SCALES,
+        king_danger: KING_DANGER,
+        pawn_shield: PAWN_SHIELD,
+        open_file: OPEN_FILE,
+        semi_open_file: SEMI_OPEN_FILE,
+    };
+
+    /// to_vec flattens the Params into a vector of all of their values, with
+    /// the middlegame value of each tapered score before its endgame value.
+    pub fn to_vec(&self) -> Vec<i32> {
+        self.clone()
+            .values_mut()
+            .into_iter()
+            .map(|value| *value)
+            .collect()
+    }
+
+    /// set_from_slice sets the Params to the given flattened values, which
+    /// are in the same order as the ones returned by to_vec.
+    pub fn set_from_slice(&mut self, values: &[i32]) {
+        for (value, &new) in self.values_mut().into_iter().zip(values) {
+            *value = new;
+        }
+    }
+
+    /// values_mut returns mutable references to all of the values of the
+    /// Params, in the order of the fields.
+    fn values_mut(&mut self) -> Vec<&mut i32> {
+        let scores = self
+            .material
+            .iter_mut()
+            .chain(self.psqt.as_flattened_mut())
+            .chain([&mut self.doubled, &mut self.isolated, &mut self.backward])
+            .chain(&mut self.passed)
+            .chain(&mut self.knight_mobility)
+            .chain(&mut self.bishop_mobility)
+            .chain(&mut self.rook_mobility)
+            .chain(&mut self.queen_mobility)
+            .chain(&mut self.pawn_shield)
+            .chain([&mut self.open_file, &mut self.semi_open_file])
+            .flat_map(|score| [&mut score.0, &mut score.1]);
+
+        let values = self
+            .attack_weights
+            .iter_mut()
+            .chain(&mut self.attacker_scales)
+            .chain(&mut self.king_danger);
+
+        scores.chain(values).collect()
+    }
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        Params::DEFAULT
+    }
+}
+
+/// Params are displayed as the Rust constants of each of the evaluation
+/// terms, so that tuned Params can be copied back into the source code.
+impl fmt::Display for Params {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_scores(f, "MATERIAL", "Piece::N", &self.material)?;
+
+        writeln!(f, "const PSQT: [[S; Square::N]; Piece::N] = [")?;
+        for table in &self.psqt {
+            writeln!(f, "    [")?;
+            for rank in table.chunks(8) {
+                writeln!(f, "        {},", join(rank))?;
+            }
+            writeln!(f, "    ],")?;
+        }
+        writeln!(f, "];\n")?;
+
+        writeln!(f, "const DOUBLED: S = {};", self.doubled)?;
+        writeln!(f, "const ISOLATED: S = {};", self.isolated)?;
+        writeln!(f, "const BACKWARD: S = {};\n", self.backward)?;
+        write_scores(f, "PASSED", "Rank::N", &self.passed)?;
+
+        write_scores(f, "KNIGHT_MOBILITY", "9", &self.knight_mobility)?;
+        write_scores(f, "BISHOP_MOBILITY", "14", &self.bishop_mobility)?;
+        write_scores(f, "ROOK_MOBILITY", "15", &self.rook_mobility)?;
+        write_scores(f, "QUEEN_MOBILITY", "28", &self.queen_mobility)?;
+
+        writeln!(
+            f,
+            "const ATTACK_WEIGHTS: [i32; Piece::N] = [{}];",
+            join(&self.attack_weights)
+        )?;
+        writeln!(
+            f,
+            "const ATTACKER_SCALES: [i32; 8] = [{}];",
+            join(&self.attacker_scales)
+        )?;
+        writeln!(f, "const KING_DANGER: [i32; 100] = [")?;
+        for row in self.king_danger.chunks(10) {
+            writeln!(f, "    {},", join(row))?;
+        }
+        writeln!(f, "];\n")?;
+        write_scores(f, "PAWN_SHIELD", "2", &self.pawn_shield)?;
+        writeln!(f, "const OPEN_FILE: S = {};", self.open_file)?;
+        write!(f, "const SEMI_OPEN_FILE: S = {};", self.semi_open_file)
+    }
+}
+
+/// write_scores writes a constant array of tapered scores with the given
+/// name and length, eight scores to a line.
+fn write_scores(f: &mut fmt::Formatter<'_>, name: &str, len: &str, scores: &[S]) -> fmt::Result {
+    writeln!(f, "const {name}: [S; {len}] = [")?;
+    for line in scores.chunks(8) {
+        writeln!(f, "    {},", join(line))?;
+    }
+    writeln!(f, "];\n")
+}
+
+/// join joins the given values with commas.
+fn join<T: fmt::Display>(values: &[T]) -> String {
+    values
+        .iter()
+        .map(|value| value.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
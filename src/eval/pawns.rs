@@ -0,0 +1,163 @@
+// Copyright © 2023 Rak Laptudirm <rak@laptudirm.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::chess::{zobrist, BitBoard, Board, Color, Piece, Rank, Square};
+
+use super::{Params, S};
+
+/// DOUBLED is the penalty for a pawn with another friendly pawn in front
+/// of it on the same file.
+pub const DOUBLED: S = S(-10, -25);
+
+/// ISOLATED is the penalty for a pawn without any friendly pawns on the
+/// files adjacent to it.
+pub const ISOLATED: S = S(-12, -15);
+
+/// BACKWARD is the penalty for a pawn which is behind all the friendly
+/// pawns on the files adjacent to it, and can't advance safely since its
+/// stop square is attacked by an enemy pawn.
+pub const BACKWARD: S = S(-8, -10);
+
+/// PASSED contains the bonus for a pawn without any enemy pawns in front of
+/// it on its own or the adjacent files, indexed by its relative rank.
+#[rustfmt::skip]
+pub const PASSED: [S; Rank::N] = [
+    S(0, 0), S(5, 10), S(5, 15), S(10, 25), S(20, 45), S(35, 75), S(60, 120), S(0, 0),
+];
+
+/// PawnTable is a hash table which caches the pawn structure evaluation of
+/// positions by their pawn hash. Pawn structures change much less often
+/// than positions do, so most of the lookups in a search are hits.
+pub struct PawnTable {
+    entries: Vec<PawnEntry>,
+}
+
+#[derive(Copy, Clone, Default)]
+struct PawnEntry {
+    key: zobrist::Hash,
+    score: S,
+}
+
+impl PawnTable {
+    /// N is the number of entries in a PawnTable.
+    pub const N: usize = 1 << 14;
+
+    /// new creates an empty PawnTable.
+    pub fn new() -> PawnTable {
+        PawnTable {
+            entries: vec![PawnEntry::default(); PawnTable::N],
+        }
+    }
+
+    /// clear removes all of the entries from the PawnTable.
+    pub fn clear(&mut self) {
+        self.entries.fill(PawnEntry::default());
+    }
+
+    /// score returns the pawn structure evaluation of the position on the
+    /// Board from white's point of view with the given Params, evaluating
+    /// and caching it if it isn't already in the PawnTable. The PawnTable
+    /// must be cleared when the Params change.
+    pub fn score(&mut self, board: &Board, params: &Params) -> S {
+        let key = board.pawn_hash();
+        let entry = &mut self.entries[(u64::from(key) % PawnTable::N as u64) as usize];
+
+        // Positions without any pawns have an empty pawn hash, which is also
+        // the key of an empty entry, but they have no pawn structure anyway.
+        if entry.key != key {
+            *entry = PawnEntry {
+                key,
+                score: params.pawns(board),
+            };
+        }
+
+        entry.score
+    }
+}
+
+impl Default for PawnTable {
+    fn default() -> Self {
+        PawnTable::new()
+    }
+}
+
+/// evaluate_pawns evaluates the pawn structure of the position on the Board
+/// from white's point of view, with the default evaluation Params.
+pub fn evaluate_pawns(board: &Board) -> S {
+    Params::DEFAULT.pawns(board)
+}
+
+impl Params {
+    /// pawns evaluates the pawn structure of the position on the Board from
+    /// white's point of view.
+    pub fn pawns(&self, board: &Board) -> S {
+        self.pawns_color(board, Color::White) - self.pawns_color(board, Color::Black)
+    }
+
+    /// pawns_color evaluates the pawn structure of the given Color's pawns
+    /// on the Board.
+    pub(crate) fn pawns_color(&self, board: &Board, color: Color) -> S {
+        let ours = board.piece_color_bb(Piece::Pawn, color);
+        let theirs = board.piece_color_bb(Piece::Pawn, !color);
+
+        let their_attacks = attacked_by_pawns(board, !color);
+
+        let mut score = S::ZERO;
+        for square in ours {
+            let file = BitBoard::file(square.file());
+            let adjacent = file.east() | file.west();
+            let ahead = ranks_ahead(square, color);
+
+            let is_doubled = !(ours & file & ahead).is_empty();
+            let is_isolated = (ours & adjacent).is_empty();
+
+            if is_doubled {
+                score += self.doubled;
+            }
+
+            if is_isolated {
+                score += self.isolated;
+            } else if (ours & adjacent & !ahead).is_empty()
+                && their_attacks.contains(square.up(color))
+            {
+                score += self.backward;
+            }
+
+            // Only the frontmost of doubled pawns can be passed.
+            if !is_doubled && (theirs & (file | adjacent) & ahead).is_empty() {
+                let rank = Rank::First as usize - square.rank().relative(color) as usize;
+                score += self.passed[rank];
+            }
+        }
+
+        score
+    }
+}
+
+/// attacked_by_pawns returns the squares attacked by the given Color's
+/// pawns on the Board.
+pub fn attacked_by_pawns(board: &Board, color: Color) -> BitBoard {
+    let pushed = board.piece_color_bb(Piece::Pawn, color).up(color);
+    pushed.east() | pushed.west()
+}
+
+/// ranks_ahead returns the squares on the ranks in front of the given
+/// Square from the point of view of the given Color.
+fn ranks_ahead(square: Square, color: Color) -> BitBoard {
+    let rank = square.rank().relative(color) as usize;
+    Rank::iter()
+        .filter(|&ahead| (ahead.relative(color) as usize) < rank)
+        .fold(BitBoard::EMPTY, |ranks, ahead| {
+            ranks | BitBoard::rank(ahead)
+        })
+}
@@ -0,0 +1,98 @@
+// Copyright © 2023 Rak Laptudirm <rak@laptudirm.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{fmt, ops};
+
+use crate::chess::Board;
+
+/// S is a tapered evaluation score, which has separate values for the
+/// middlegame and the endgame. The two are interpolated between by the
+/// phase of the game to get the final score of a position.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Default)]
+pub struct S(pub i32, pub i32);
+
+impl S {
+    /// ZERO is the tapered score with a zero middlegame and endgame value.
+    pub const ZERO: S = S(0, 0);
+
+    /// mg returns the middlegame value of the tapered score.
+    pub fn mg(self) -> i32 {
+        self.0
+    }
+
+    /// eg returns the endgame value of the tapered score.
+    pub fn eg(self) -> i32 {
+        self.1
+    }
+
+    /// taper interpolates between the middlegame and endgame values of the
+    /// tapered score by the given phase, which goes from MAX_PHASE at the
+    /// start of the game down to zero in a pawn endgame.
+    pub fn taper(self, phase: i32) -> i32 {
+        let phase = phase.min(MAX_PHASE);
+        (self.mg() * phase + self.eg() * (MAX_PHASE - phase)) / MAX_PHASE
+    }
+}
+
+/// MAX_PHASE is the phase of a position with all of its pieces on the board.
+pub const MAX_PHASE: i32 = Board::MAX_PHASE as i32;
+
+impl fmt::Display for S {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "S({}, {})", self.0, self.1)
+    }
+}
+
+impl ops::Add for S {
+    type Output = S;
+
+    fn add(self, rhs: S) -> Self::Output {
+        S(self.0 + rhs.0, self.1 + rhs.1)
+    }
+}
+
+impl ops::AddAssign for S {
+    fn add_assign(&mut self, rhs: S) {
+        *self = *self + rhs;
+    }
+}
+
+impl ops::Sub for S {
+    type Output = S;
+
+    fn sub(self, rhs: S) -> Self::Output {
+        S(self.0 - rhs.0, self.1 - rhs.1)
+    }
+}
+
+impl ops::SubAssign for S {
+    fn sub_assign(&mut self, rhs: S) {
+        *self = *self - rhs;
+    }
+}
+
+impl ops::Neg for S {
+    type Output = S;
+
+    fn neg(self) -> Self::Output {
+        S(-self.0, -self.1)
+    }
+}
+
+impl ops::Mul<i32> for S {
+    type Output = S;
+
+    fn mul(self, rhs: i32) -> Self::Output {
+        S(self.0 * rhs, self.1 * rhs)
+    }
+}
@@ -0,0 +1,190 @@
+// Copyright © 2023 Rak Laptudirm <rak@laptudirm.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fmt;
+
+use crate::chess::{Board, Color, Piece};
+
+use super::{phase, Params, MAX_PHASE, S};
+
+/// Term is one of the terms of the classical evaluation.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Term {
+    Material,
+    Psqt,
+    Pawns,
+    Mobility,
+    KingSafety,
+}
+
+impl Term {
+    /// N is the number of terms of the classical evaluation.
+    pub const N: usize = 5;
+
+    /// ALL contains all of the terms, in the order they are evaluated in.
+    pub const ALL: [Term; Term::N] = [
+        Term::Material,
+        Term::Psqt,
+        Term::Pawns,
+        Term::Mobility,
+        Term::KingSafety,
+    ];
+}
+
+impl fmt::Display for Term {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(match self {
+            Term::Material => "Material",
+            Term::Psqt => "PSQT",
+            Term::Pawns => "Pawns",
+            Term::Mobility => "Mobility",
+            Term::KingSafety => "King Safety",
+        })
+    }
+}
+
+/// Trace is a breakdown of the classical evaluation of a position into
+/// the score of each of its terms for each color. The scores of each color
+/// are from its own point of view, while the totals are from white's.
+#[derive(Clone, Debug)]
+pub struct Trace {
+    scores: [[S; Color::N]; Term::N],
+    phase: i32,
+    side_to_move: Color,
+}
+
+impl Trace {
+    /// get returns the score of the given Term for the given Color.
+    pub fn get(&self, term: Term, color: Color) -> S {
+        self.scores[term as usize][color as usize]
+    }
+
+    /// total returns the score of the given Term from white's point of view.
+    pub fn total(&self, term: Term) -> S {
+        self.get(term, Color::White) - self.get(term, Color::Black)
+    }
+
+    /// score returns the sum of the scores of all the terms from white's
+    /// point of view, before it is tapered.
+    pub fn score(&self) -> S {
+        Term::ALL
+            .iter()
+            .fold(S::ZERO, |score, &term| score + self.total(term))
+    }
+
+    /// phase returns the phase of the game the scores are tapered by.
+    pub fn phase(&self) -> i32 {
+        self.phase
+    }
+
+    /// evaluation returns the tapered score of the position from the point
+    /// of view of the side to move, in centipawns, which is the same as its
+    /// classical evaluation.
+    pub fn evaluation(&self) -> i32 {
+        let score = self.score().taper(self.phase);
+        match self.side_to_move {
+            Color::White => score,
+            _ => -score,
+        }
+    }
+}
+
+impl fmt::Display for Trace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:>12} | {:^13} | {:^13} | {:^13}",
+            "Term", "White", "Black", "Total"
+        )?;
+        writeln!(
+            f,
+            "{:>12} | {:>6} {:>6} | {:>6} {:>6} | {:>6} {:>6}",
+            "", "MG", "EG", "MG", "EG", "MG", "EG"
+        )?;
+        writeln!(f, "{:-<12}-+-{:-<13}-+-{:-<13}-+-{:-<13}", "", "", "", "")?;
+
+        for term in Term::ALL {
+            let white = self.get(term, Color::White);
+            let black = self.get(term, Color::Black);
+            let total = self.total(term);
+            writeln!(
+                f,
+                "{:>12} | {:>6} {:>6} | {:>6} {:>6} | {:>6} {:>6}",
+                term,
+                white.mg(),
+                white.eg(),
+                black.mg(),
+                black.eg(),
+                total.mg(),
+                total.eg()
+            )?;
+        }
+
+        let score = self.score();
+        writeln!(f, "{:-<12}-+-{:-<13}-+-{:-<13}-+-{:-<13}", "", "", "", "")?;
+        writeln!(
+            f,
+            "{:>12} | {:>13} | {:>13} | {:>6} {:>6}",
+            "Total",
+            "",
+            "",
+            score.mg(),
+            score.eg()
+        )?;
+        writeln!(f)?;
+        writeln!(f, "Phase: {}/{}", self.phase, MAX_PHASE)?;
+        write!(
+            f,
+            "Classical evaluation: {} (white side), {} (side to move)",
+            score.taper(self.phase),
+            self.evaluation()
+        )
+    }
+}
+
+/// trace breaks down the classical evaluation of the position on the Board
+/// into the scores of each of its terms, with the default evaluation Params.
+pub fn trace(board: &Board) -> Trace {
+    Params::DEFAULT.trace(board)
+}
+
+impl Params {
+    /// trace breaks down the classical evaluation of the position on the
+    /// Board into the scores of each of its terms for each color.
+    pub fn trace(&self, board: &Board) -> Trace {
+        let mut scores = [[S::ZERO; Color::N]; Term::N];
+        for color in [Color::White, Color::Black] {
+            for piece in 0..Piece::N {
+                let piece = Piece::from(piece);
+                for square in board.piece_color_bb(piece, color) {
+                    let square = square.relative(color);
+                    scores[Term::Material as usize][color as usize] +=
+                        self.material[piece as usize];
+                    scores[Term::Psqt as usize][color as usize] +=
+                        self.psqt[piece as usize][square as usize];
+                }
+            }
+
+            scores[Term::Pawns as usize][color as usize] = self.pawns_color(board, color);
+            scores[Term::Mobility as usize][color as usize] = self.mobility_color(board, color);
+            scores[Term::KingSafety as usize][color as usize] =
+                self.king_safety_color(board, color);
+        }
+
+        Trace {
+            scores,
+            phase: phase(board),
+            side_to_move: board.side_to_move(),
+        }
+    }
+}
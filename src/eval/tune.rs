@@ -0,0 +1,218 @@
+// Copyright © 2023 Rak Laptudirm <rak@laptudirm.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{fs, io, path::Path, str::FromStr, thread};
+
+use crate::chess::{Board, Color};
+
+use super::Params;
+
+/// Entry is a position from a tuning dataset, with the result of the game
+/// it was taken from for white: 1 for a win, 0.5 for a draw and 0 for a loss.
+pub struct Entry {
+    pub board: Board,
+    pub result: f64,
+}
+
+/// load_dataset loads the tuning dataset at the given path, which has one
+/// position per line, as described by parse_entry. Empty lines are skipped.
+pub fn load_dataset<P: AsRef<Path>>(path: P) -> io::Result<Vec<Entry>> {
+    let dataset = fs::read_to_string(path)?;
+
+    let mut entries = Vec::new();
+    for (number, line) in dataset.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        match parse_entry(line) {
+            Some(entry) => entries.push(entry),
+            None => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("invalid dataset entry on line {}", number + 1),
+                ))
+            }
+        }
+    }
+
+    Ok(entries)
+}
+
+/// parse_entry parses a dataset entry, which is a FEN or an EPD followed by
+/// the result of the game, either as a number in brackets, like `[0.5]`, or
+/// as a game result, like `"1/2-1/2";`.
+pub fn parse_entry(line: &str) -> Option<Entry> {
+    let (position, result) = match line.find('[') {
+        Some(start) => {
+            let end = start + line[start..].find(']')?;
+            (&line[..start], line[start + 1..end].trim().parse().ok()?)
+        }
+
+        None => [("1/2-1/2", 0.5), ("1-0", 1.0), ("0-1", 0.0)]
+            .into_iter()
+            .find_map(|(result, score)| Some((&line[..line.find(result)?], score)))?,
+    };
+
+    // EPDs don't have the move counters of FENs, and may have operations.
+    let fields: Vec<&str> = position.split_whitespace().take(6).collect();
+    let has_counters =
+        fields.len() == 6 && fields[4..].iter().all(|field| field.parse::<u16>().is_ok());
+    let fen = if has_counters {
+        fields.join(" ")
+    } else {
+        format!("{} 0 1", fields.get(..4)?.join(" "))
+    };
+
+    Some(Entry {
+        board: Board::from_str(&fen).ok()?,
+        result,
+    })
+}
+
+/// Tuner tunes the Params of the classical evaluation on a dataset with
+/// Texel's method: it minimizes the mean squared error between the results
+/// of the games and the win probabilities predicted from the evaluations of
+/// their positions, with a local search over the values of the Params.
+pub struct Tuner {
+    entries: Vec<Entry>,
+    threads: usize,
+}
+
+impl Tuner {
+    /// new creates a Tuner for the given dataset, which computes its errors
+    /// with as many threads as there are available cores.
+    pub fn new(entries: Vec<Entry>) -> Tuner {
+        let threads = thread::available_parallelism().map_or(1, |threads| threads.get());
+        Tuner { entries, threads }
+    }
+
+    /// len returns the number of entries in the Tuner's dataset.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// is_empty checks if the Tuner's dataset has no entries.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// error returns the mean squared error of the given Params on the
+    /// dataset, with the given scaling constant for the win probabilities.
+    pub fn error(&self, params: &Params, k: f64) -> f64 {
+        if self.entries.is_empty() {
+            return 0.0;
+        }
+
+        let chunk_size = self.entries.len().div_ceil(self.threads);
+        let total: f64 = thread::scope(|scope| {
+            let handles: Vec<_> = self
+                .entries
+                .chunks(chunk_size)
+                .map(|chunk| scope.spawn(move || chunk_error(chunk, params, k)))
+                .collect();
+
+            handles
+                .into_iter()
+                .map(|handle| handle.join().expect("tuner: error thread panicked"))
+                .sum()
+        });
+
+        total / self.entries.len() as f64
+    }
+
+    /// optimal_k returns the scaling constant for the win probabilities
+    /// which minimizes the error of the given Params on the dataset.
+    pub fn optimal_k(&self, params: &Params) -> f64 {
+        // The error is unimodal in the scaling constant, so narrow down its
+        // minimum with a ternary search.
+        let (mut lo, mut hi) = (0.0, 10.0);
+        for _ in 0..50 {
+            let third = (hi - lo) / 3.0;
+            if self.error(params, lo + third) < self.error(params, hi - third) {
+                hi -= third;
+            } else {
+                lo += third;
+            }
+        }
+
+        (lo + hi) / 2.0
+    }
+
+    /// tune tunes the given Params for at most the given number of passes
+    /// over all their values, with the given scaling constant. Each value is
+    /// moved by one in whichever direction lowers the error, and the tuning
+    /// stops early once a pass doesn't change any of them. The given report
+    /// function is called with the pass number, the error, and the Params
+    /// after each pass. The final error is returned.
+    pub fn tune<F: FnMut(usize, f64, &Params)>(
+        &self,
+        params: &mut Params,
+        k: f64,
+        passes: usize,
+        mut report: F,
+    ) -> f64 {
+        let mut values = params.to_vec();
+        let mut best_error = self.error(params, k);
+
+        for pass in 1..=passes {
+            let mut improved = false;
+            for index in 0..values.len() {
+                for step in [1, -1] {
+                    values[index] += step;
+                    params.set_from_slice(&values);
+
+                    let error = self.error(params, k);
+                    if error < best_error {
+                        best_error = error;
+                        improved = true;
+                        break;
+                    }
+
+                    values[index] -= step;
+                }
+            }
+
+            params.set_from_slice(&values);
+            report(pass, best_error, params);
+
+            if !improved {
+                break;
+            }
+        }
+
+        best_error
+    }
+}
+
+/// chunk_error returns the sum of the squared errors of the given Params on
+/// the given dataset entries.
+fn chunk_error(entries: &[Entry], params: &Params, k: f64) -> f64 {
+    entries
+        .iter()
+        .map(|entry| {
+            let eval = match entry.board.side_to_move() {
+                Color::White => params.evaluate(&entry.board),
+                _ => -params.evaluate(&entry.board),
+            };
+
+            (entry.result - sigmoid(eval as f64, k)).powi(2)
+        })
+        .sum()
+}
+
+/// sigmoid returns the win probability predicted from the given evaluation
+/// in centipawns, with the given scaling constant.
+fn sigmoid(eval: f64, k: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-k * eval / 400.0))
+}
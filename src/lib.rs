@@ -1,2 +1,18 @@
+pub mod annotate;
+pub mod book;
+pub mod cecp;
 pub mod chess;
+pub mod datagen;
+pub mod engine;
+pub mod eval;
+pub mod r#match;
+pub mod perft;
+pub mod pgn;
+pub mod puzzle;
+pub mod render;
+pub mod search;
+pub mod uci;
 pub mod util;
+
+#[cfg(feature = "wasm")]
+pub mod wasm;
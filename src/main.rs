@@ -1,62 +1,1056 @@
-use std::{str::FromStr, time::Instant};
+use std::{
+    env, fs,
+    io::{self, BufRead, Write},
+    process,
+    str::FromStr,
+    sync::Arc,
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
 
-use mess::chess::{Board, Move, MoveFlag, Square};
+use mess::annotate::{Annotator, AnnotatorOptions};
+use mess::book::builder::Builder;
+use mess::book::openings::{OpeningSuite, Order};
+use mess::chess::{Board, Color, Move};
+use mess::datagen::{self, DatagenOptions, Format};
+use mess::engine::{Engine, SearchInfo, SearchLimits, Signals};
+use mess::eval::{tune::Tuner, Params};
+use mess::puzzle::{PuzzleFinder, PuzzleOptions};
+use mess::r#match::{
+    Crosstable, DrawRule, EloEstimate, EngineFactory, ExternalEngine, Match, MatchOptions, Ratings,
+    ResignRule, Tournament, TournamentOptions,
+};
+use mess::search::{self, SearchEngine};
+use mess::{cecp, perft, pgn, uci};
+
+/// USAGE lists the subcommands of the binary, which runs the UCI protocol
+/// if it isn't given one.
+const USAGE: &str = "usage: mess [command] [args...]
+
+commands:
+    uci                                   run the UCI protocol (default)
+    xboard                                run the XBoard protocol
+    perft <fen|startpos> <depth>          count the nodes of a perft tree
+    perftsuite <epd>                      run a perft suite
+    bench [depth]                         search a fixed set of positions
+    analyze <fen|startpos>                analyze a position until enter is pressed
+    eval <fen|startpos>                   print the static evaluation of a position
+    play                                  play a game against the engine
+    book <pgn> <output>                   build a polyglot book from a pgn file
+    tune <dataset> <output>               tune the classical evaluation
+    match <engine> <engine>               play a match between two engines
+    tournament <engine> <engine>...       play a tournament between engines
+    ratings <pgn>                         estimate ratings from the results of games
+    annotate <pgn>                        find the mistakes in the games of a pgn file
+    datagen <output>                      generate training data by self-play
+    puzzles <pgn> <output>                extract puzzles from the games of a pgn file";
 
 fn main() {
-    let mut board =
-        Board::from_str("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap();
+    let args: Vec<String> = env::args().skip(1).collect();
+    let (command, args) = match args.split_first() {
+        Some((command, args)) => (command.as_str(), args),
+        None => ("uci", &[][..]),
+    };
+
+    let result = match command {
+        "uci" => {
+            uci::Client::new(SearchEngine::new()).run();
+            Ok(())
+        }
+
+        "xboard" => {
+            cecp::Client::new(SearchEngine::new()).run();
+            Ok(())
+        }
+
+        "perft" => run_perft(args),
+        "perftsuite" => perft_suite(args),
+        "bench" => bench(args),
+        "analyze" => analyze(args),
+        "eval" => eval(args),
+        "play" => play(args),
+        "book" => build_book(args),
+        "tune" => tune(args),
+        "match" => run_match(args),
+        "tournament" => tournament(args),
+        "ratings" => ratings(args),
+        "annotate" => annotate(args),
+        "datagen" => datagen(args),
+        "puzzles" => puzzles(args),
+
+        "help" | "-h" | "--help" => {
+            println!("{USAGE}");
+            Ok(())
+        }
+
+        _ => Err(format!("unknown command\n{USAGE}")),
+    };
+
+    if let Err(err) = result {
+        eprintln!("{command}: {err}");
+        process::exit(1);
+    }
+}
+
+/// split_options splits the given arguments into the positional arguments
+/// before the first `--` option, and the options after them.
+fn split_options(args: &[String]) -> (&[String], &[String]) {
+    let index = args
+        .iter()
+        .position(|arg| arg.starts_with("--"))
+        .unwrap_or(args.len());
+
+    args.split_at(index)
+}
+
+/// parse_board parses a position given as the words of a FEN, or as the
+/// word `startpos` for the standard starting position.
+fn parse_board(words: &[String]) -> Result<Board, String> {
+    match words {
+        [] => Err(String::from("missing position")),
+        fen => Board::from_str(&fen.join(" ")).map_err(|err| err.to_string()),
+    }
+}
+
+/// run_perft counts the nodes of the perft tree of a position, printing the
+/// node count of each root move unless the count is multithreaded or hashed.
+/// Usage: perft <fen|startpos> <depth> [--threads N] [--hash MB]
+fn run_perft(args: &[String]) -> Result<(), String> {
+    let usage = "usage: perft <fen|startpos> <depth> [--threads N] [--hash MB]";
+
+    let (positional, options) = split_options(args);
+    let [position @ .., depth] = positional else {
+        return Err(usage.to_string());
+    };
+
+    let mut board = parse_board(position)?;
+    let depth: u8 = depth.parse().map_err(|_| usage.to_string())?;
+
+    let mut threads = 1;
+    let mut hash_mb = None;
+    let mut options = options.iter();
+    while let Some(option) = options.next() {
+        let value = options.next().ok_or_else(|| usage.to_string())?;
+        match option.as_str() {
+            "--threads" => threads = value.parse().map_err(|_| usage.to_string())?,
+            "--hash" => hash_mb = Some(value.parse().map_err(|_| usage.to_string())?),
+            _ => return Err(usage.to_string()),
+        }
+    }
+
+    let start = Instant::now();
+    let nodes = if let Some(hash_mb) = hash_mb {
+        perft::hash_perft(&mut board, depth, &mut perft::PerftTable::new(hash_mb))
+    } else if threads > 1 {
+        perft::parallel_perft(&board, depth, threads)
+    } else {
+        let split = perft::divide(&mut board, depth);
+        for (chessmove, nodes) in &split {
+            println!("{}: {nodes}", uci::format_move(*chessmove));
+        }
+
+        println!();
+        split.iter().map(|&(_, nodes)| nodes).sum()
+    };
+
+    let duration = start.elapsed().as_secs_f64();
+    println!(
+        "nodes {nodes} time {:.3}s nps {}",
+        duration,
+        (nodes as f64 / duration.max(0.001)) as u64
+    );
+
+    Ok(())
+}
+
+/// BENCH_POSITIONS are the positions searched by the bench command, which
+/// cover the different phases of the game.
+#[rustfmt::skip]
+const BENCH_POSITIONS: [&str; 8] = [
+    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    "r1bq1rk1/pp2bppp/2n2n2/3p4/3P4/2NB1N2/PP3PPP/R1BQ1RK1 w - - 4 10",
+    "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+    "2r3k1/pp3ppp/4p3/3n4/3P4/P4N2/1P3PPP/2R3K1 b - - 1 24",
+    "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+    "8/8/4k3/3p4/3P4/4K3/8/8 w - - 0 1",
+    "6k1/5ppp/8/8/8/8/5PPP/3R2K1 w - - 0 1",
+];
+
+/// BENCH_DEPTH is the default depth the bench command searches to.
+const BENCH_DEPTH: u32 = 6;
+
+/// bench searches each of the bench positions to a fixed depth from a new
+/// game, and prints the total number of nodes searched, which is used to
+/// check that changes which shouldn't change the search don't.
+/// Usage: bench [depth]
+fn bench(args: &[String]) -> Result<(), String> {
+    let depth = match args {
+        [] => BENCH_DEPTH,
+        [depth] => depth.parse().map_err(|_| "usage: bench [depth]")?,
+        _ => return Err(String::from("usage: bench [depth]")),
+    };
+
+    let mut engine = SearchEngine::new();
+    let signals = Signals::new();
+    let limits = SearchLimits {
+        depth: Some(depth),
+        ..SearchLimits::default()
+    };
+
+    let mut nodes = 0;
+    let start = Instant::now();
+    for fen in BENCH_POSITIONS {
+        let board = Board::from_str(fen).unwrap();
+        let mut position_nodes = 0;
+
+        engine.new_game();
+        signals.reset(false);
+        engine.search(board, limits, &signals, &mut |info: &SearchInfo| {
+            position_nodes = info.nodes
+        });
+
+        println!("{fen}: {position_nodes}");
+        nodes += position_nodes;
+    }
+
+    let duration = start.elapsed().as_secs_f64();
+    println!(
+        "\n{nodes} nodes {} nps",
+        (nodes as f64 / duration.max(0.001)) as u64
+    );
+
+    Ok(())
+}
+
+/// eval prints the static evaluation of a position along with the score
+/// of each of its terms. Usage: eval <fen|startpos>
+fn eval(args: &[String]) -> Result<(), String> {
+    let board = parse_board(args)?;
+    let report = SearchEngine::new()
+        .evaluate(&board)
+        .ok_or("engine has no static evaluation")?;
+
+    println!("{report}");
+    Ok(())
+}
+
+/// analyze searches a position until enter is pressed or its limits are
+/// reached, printing the best line found after each iteration.
+/// Usage: analyze <fen|startpos> [--depth N] [--movetime MS]
+fn analyze(args: &[String]) -> Result<(), String> {
+    let usage = "usage: analyze <fen|startpos> [--depth N] [--movetime MS]";
+
+    let (position, options) = split_options(args);
+    let board = parse_board(position)?;
+
+    let mut limits = SearchLimits {
+        infinite: true,
+        ..SearchLimits::default()
+    };
+
+    let mut options = options.iter();
+    while let Some(option) = options.next() {
+        let value = options.next().ok_or_else(|| usage.to_string())?;
+        match option.as_str() {
+            "--depth" => limits.depth = Some(value.parse().map_err(|_| usage.to_string())?),
+            "--movetime" => {
+                let millis = value.parse().map_err(|_| usage.to_string())?;
+                limits.movetime = Some(Duration::from_millis(millis));
+            }
+
+            _ => return Err(usage.to_string()),
+        }
+    }
+
+    limits.infinite = limits.depth.is_none() && limits.movetime.is_none();
 
-    board.make_move(Move::new(Square::F2, Square::F3, MoveFlag::Normal));
-    board.make_move(Move::new(Square::E7, Square::E5, MoveFlag::Normal));
-    board.make_move(Move::new(Square::G2, Square::G4, MoveFlag::Normal));
-    board.make_move(Move::new(Square::D8, Square::H4, MoveFlag::Normal));
+    // Stop the search once enter is pressed. Searches with limits aren't
+    // stopped if the standard input is closed, so that they can be run
+    // without a terminal.
+    let signals = Arc::new(Signals::new());
+    {
+        let signals = Arc::clone(&signals);
+        let infinite = limits.infinite;
+        thread::spawn(move || {
+            let mut line = String::new();
+            let read = io::stdin().lock().read_line(&mut line);
+            if infinite || matches!(read, Ok(length) if length > 0) {
+                signals.stop();
+            }
+        });
+    }
+
+    println!("analyzing, press enter to stop\n");
+
+    let mut engine = SearchEngine::new();
+    let mut report = |info: &SearchInfo| println!("{}", format_analysis(&board, info));
+    let best_move = engine.search(board.clone(), limits, &signals, &mut report);
+
+    let mut board = board;
+    if best_move.chessmove != Move::NULL {
+        println!("\nbest move {}", board.to_san(best_move.chessmove));
+    }
+
+    Ok(())
+}
 
-    println!("\n{board}");
+/// format_analysis formats the given SearchInfo of a search of the position
+/// on the Board as a line of analysis, with the score in pawns for white
+/// and the principal variation in SAN.
+fn format_analysis(board: &Board, info: &SearchInfo) -> String {
+    let score = if board.side_to_move() == Color::White {
+        info.score
+    } else {
+        -info.score
+    };
 
-    // let start = Instant::now();
-    // let nodes = perft::<true, true>(&mut board, 6);
-    // let duration = start.elapsed().as_secs_f64();
-    // println!(
-    //     "\nnodes {} nps {} mnps",
-    //     nodes,
-    //     (nodes as f64 / duration) as u64 / 1_000_000
-    // );
+    let score = if search::is_mate(score) {
+        format!("#{}", search::mate_moves(score))
+    } else {
+        format!("{:+.2}", score as f64 / 100.0)
+    };
+
+    let mut board = board.clone();
+    let pv: Vec<String> = info
+        .pv
+        .iter()
+        .map(|&chessmove| {
+            let san = board.to_san(chessmove);
+            board.make_move(chessmove);
+            san
+        })
+        .collect();
+
+    format!(
+        "depth {:>2} score {score:>7} nodes {:>10} nps {:>8} {}",
+        info.depth,
+        info.nodes,
+        info.nps(),
+        pv.join(" ")
+    )
 }
 
-fn perft<const BULK_COUNT: bool, const SPLIT_MOVES: bool>(board: &mut Board, depth: i32) -> usize {
-    // Return 1 for current node at depth 0.
-    if depth <= 0 {
-        return 1;
+/// play plays a game against the engine on the terminal, where the user
+/// enters their moves in SAN or in UCI notation.
+/// Usage: play [--color white|black] [--movetime MS] [--fen FEN]
+fn play(args: &[String]) -> Result<(), String> {
+    let usage = "usage: play [--color white|black] [--movetime MS] [--fen FEN]";
+
+    let mut user_color = Color::White;
+    let mut movetime = Duration::from_secs(1);
+    let mut board = Board::startpos();
+
+    let mut options = args.iter();
+    while let Some(option) = options.next() {
+        let value = options.next().ok_or_else(|| usage.to_string())?;
+        match option.as_str() {
+            "--color" => {
+                user_color = match value.as_str() {
+                    "white" => Color::White,
+                    "black" => Color::Black,
+                    _ => return Err(usage.to_string()),
+                }
+            }
+
+            "--movetime" => {
+                let millis = value.parse().map_err(|_| usage.to_string())?;
+                movetime = Duration::from_millis(millis);
+            }
+
+            "--fen" => board = Board::from_str(value).map_err(|err| err.to_string())?,
+            _ => return Err(usage.to_string()),
+        }
     }
 
-    // When bulk counting is enabled, return the length of
-    // the legal move-list when depth is one. This saves a
-    // lot of time cause it saves make moves and recursion.
-    if BULK_COUNT && depth == 1 {
-        return board.generate_legal_moves().len();
+    let mut engine = SearchEngine::new();
+    let signals = Signals::new();
+    let limits = SearchLimits {
+        movetime: Some(movetime),
+        ..SearchLimits::default()
+    };
+
+    println!("enter moves in SAN or UCI notation, `undo` to take back a move, or `quit`");
+
+    let mut lines = io::stdin().lock().lines();
+    loop {
+        println!("\n{board}");
+
+        if let Some(result) = game_result(&mut board) {
+            println!("\n{result}");
+            return Ok(());
+        }
+
+        if board.side_to_move() != user_color {
+            signals.reset(false);
+            let best_move = engine
+                .search(board.clone(), limits, &signals, &mut |_: &SearchInfo| {})
+                .chessmove;
+
+            println!("\nengine plays {}", board.to_san(best_move));
+            board.make_move(best_move);
+            continue;
+        }
+
+        print!("\nyour move: ");
+        io::stdout().flush().map_err(|err| err.to_string())?;
+
+        let Some(Ok(line)) = lines.next() else {
+            return Ok(());
+        };
+
+        match line.trim() {
+            "quit" => return Ok(()),
+
+            // Take back both the engine's last move and the user's.
+            "undo" => {
+                for _ in 0..2 {
+                    if board.last_move().is_some() {
+                        board.undo_move();
+                    }
+                }
+            }
+
+            user_move => match parse_user_move(&mut board, user_move) {
+                Some(chessmove) => board.make_move(chessmove),
+                None => println!("illegal move: {user_move}"),
+            },
+        }
     }
+}
+
+/// parse_user_move finds the legal move on the Board described by the
+/// given move in either SAN or UCI notation.
+fn parse_user_move(board: &mut Board, user_move: &str) -> Option<Move> {
+    board.parse_san(user_move).or_else(|| {
+        board
+            .generate_legal_moves()
+            .into_iter()
+            .find(|&chessmove| uci::format_move(chessmove) == user_move)
+    })
+}
+
+/// game_result describes the result of the game on the Board if it is over,
+/// or returns None if it isn't.
+fn game_result(board: &mut Board) -> Option<&'static str> {
+    if board.has_legal_moves() {
+        return board.is_draw().then_some("1/2-1/2, draw");
+    }
+
+    Some(match (board.is_check(), board.side_to_move()) {
+        (true, Color::White) => "0-1, black mates",
+        (true, _) => "1-0, white mates",
+        (false, _) => "1/2-1/2, stalemate",
+    })
+}
+
+/// perft_suite runs a perft suite and reports the results of each of its
+/// positions, failing if any of them failed. Usage:
+/// perftsuite <epd> [--depth N] [--threads N]
+fn perft_suite(args: &[String]) -> Result<(), String> {
+    let usage = "usage: perftsuite <epd> [--depth N] [--threads N]";
+
+    let [suite_path, options @ ..] = args else {
+        return Err(usage.to_string());
+    };
+
+    let mut max_depth = u8::MAX;
+    let mut threads = thread::available_parallelism().map_or(1, |threads| threads.get());
+    let mut options = options.iter();
+    while let Some(option) = options.next() {
+        let value = options.next().ok_or_else(|| usage.to_string())?;
+        match option.as_str() {
+            "--depth" => max_depth = value.parse().map_err(|_| usage.to_string())?,
+            "--threads" => threads = value.parse().map_err(|_| usage.to_string())?,
+            _ => return Err(usage.to_string()),
+        }
+    }
+
+    let entries = perft::load_suite(suite_path).map_err(|err| err.to_string())?;
 
-    // Generate legal move-list.
-    let moves = board.generate_legal_moves();
+    let start = Instant::now();
+    let failures = perft::run_suite(&entries, max_depth, threads, |entry, results| match results
+        .iter()
+        .find(|result| !result.passed())
+    {
+        Some(result) => println!(
+            "FAIL {} depth {}: expected {}, got {}",
+            entry.fen, result.depth, result.expected, result.nodes
+        ),
+        None => println!("ok   {} ({} depths)", entry.fen, results.len()),
+    });
 
-    // Variable to cumulate node count in.
-    let mut nodes: usize = 0;
+    println!(
+        "\n{} passed, {failures} failed in {:.2}s",
+        entries.len() - failures,
+        start.elapsed().as_secs_f64()
+    );
+
+    match failures {
+        0 => Ok(()),
+        _ => Err(format!("{failures} positions failed")),
+    }
+}
+
+/// build_book builds a Polyglot book from a PGN file. Usage:
+/// book <pgn> <output> [--min-games N] [--min-rating R] [--max-plys P]
+///      [--weights WIN DRAW LOSS]
+fn build_book(args: &[String]) -> Result<(), String> {
+    let usage = "usage: book <pgn> <output> [--min-games N] [--min-rating R] \
+                 [--max-plys P] [--weights WIN DRAW LOSS]";
+
+    let [pgn_path, book_path, options @ ..] = args else {
+        return Err(usage.to_string());
+    };
+
+    fn parse<T: FromStr>(value: Option<&String>, usage: &str) -> Result<T, String> {
+        value
+            .and_then(|value| value.parse().ok())
+            .ok_or_else(|| usage.to_string())
+    }
+
+    let mut builder = Builder::new();
+    let mut options = options.iter();
+    while let Some(option) = options.next() {
+        builder = match option.as_str() {
+            "--min-games" => builder.min_games(parse(options.next(), usage)?),
+            "--min-rating" => builder.min_rating(parse(options.next(), usage)?),
+            "--max-plys" => builder.max_plys(parse(options.next(), usage)?),
+            "--weights" => builder.result_weights(
+                parse(options.next(), usage)?,
+                parse(options.next(), usage)?,
+                parse(options.next(), usage)?,
+            ),
+            _ => return Err(usage.to_string()),
+        };
+    }
+
+    let pgn = fs::read_to_string(pgn_path).map_err(|err| err.to_string())?;
+    let games = builder.add_pgn(&pgn);
+
+    let file = fs::File::create(book_path).map_err(|err| err.to_string())?;
+    let mut writer = io::BufWriter::new(file);
+    let entries = builder.write(&mut writer).map_err(|err| err.to_string())?;
+    writer.flush().map_err(|err| err.to_string())?;
+
+    println!("added {games} games, wrote {entries} entries to {book_path}");
+    Ok(())
+}
 
-    // Recursively call perft for child nodes.
-    for chessmove in moves {
-        board.make_move(chessmove);
-        let new_nodes = perft::<BULK_COUNT, false>(board, depth - 1);
-        board.undo_move();
+/// tune tunes the classical evaluation on a dataset and writes the tuned
+/// parameters to a file. Usage:
+/// tune <dataset> <output> [--passes N] [--k K]
+fn tune(args: &[String]) -> Result<(), String> {
+    let usage = "usage: tune <dataset> <output> [--passes N] [--k K]";
 
-        nodes += new_nodes;
+    let [dataset_path, output_path, options @ ..] = args else {
+        return Err(usage.to_string());
+    };
 
-        // If split moves is enabled, display each child move's
-        // contribution to the node count separately.
-        if SPLIT_MOVES {
-            println!("{chessmove}: {new_nodes}");
+    let mut passes = 100;
+    let mut k = None;
+    let mut options = options.iter();
+    while let Some(option) = options.next() {
+        let value = options.next().ok_or_else(|| usage.to_string())?;
+        match option.as_str() {
+            "--passes" => passes = value.parse().map_err(|_| usage.to_string())?,
+            "--k" => k = Some(value.parse().map_err(|_| usage.to_string())?),
+            _ => return Err(usage.to_string()),
         }
     }
 
-    // Return cumulative node count.
-    nodes
+    let entries = mess::eval::tune::load_dataset(dataset_path).map_err(|err| err.to_string())?;
+    let tuner = Tuner::new(entries);
+    println!("loaded {} positions from {dataset_path}", tuner.len());
+
+    let mut params = Params::DEFAULT;
+    let k = k.unwrap_or_else(|| tuner.optimal_k(&params));
+    println!("k {k:.4} error {:.8}", tuner.error(&params, k));
+
+    tuner.tune(&mut params, k, passes, |pass, error, params| {
+        println!("pass {pass} error {error:.8}");
+
+        // Save the parameters after every pass, since tuning takes a while.
+        if let Err(err) = fs::write(output_path, format!("{params}\n")) {
+            eprintln!("tune: {err}");
+        }
+    });
+
+    Ok(())
+}
+
+/// run_match plays a match between two engines, which are either commands
+/// which run UCI engines or `builtin` for the built-in engine, printing the
+/// result of each game and writing the games to a PGN file if given one.
+/// Usage: match <engine> <engine> [match options]
+fn run_match(args: &[String]) -> Result<(), String> {
+    let usage = format!("usage: match <engine> <engine> {MATCH_OPTIONS}");
+
+    let [first, second, options @ ..] = args else {
+        return Err(usage);
+    };
+
+    let (match_options, mut pgn) = parse_match_options(options, &usage)?;
+
+    let mut engine_match = Match::new(load_engine(first)?, load_engine(second)?, match_options);
+    let mut write_error = None;
+    let score = engine_match.run(|game, score| {
+        println!(
+            "game {}: {} vs {}: {} ({}), score {score}",
+            score.games(),
+            game.tag("White").unwrap_or_default(),
+            game.tag("Black").unwrap_or_default(),
+            game.result,
+            game.tag("Termination").unwrap_or_default(),
+        );
+
+        if let Some(file) = &mut pgn {
+            if let Err(err) = writeln!(file, "{game}") {
+                write_error.get_or_insert(err);
+            }
+        }
+    });
+
+    println!("\nfinal score {score}");
+    if let Some(estimate) = EloEstimate::new(&score) {
+        println!("{estimate}");
+    }
+
+    match write_error {
+        Some(err) => Err(err.to_string()),
+        None => Ok(()),
+    }
+}
+
+/// tournament plays a round-robin or gauntlet tournament between engines,
+/// which are given like the engines of a match, printing the result of each
+/// game and the final crosstable, and writing the games to a PGN file if
+/// given one. Usage:
+/// tournament <engine> <engine> [engine...] [--format round-robin|gauntlet]
+///            [--concurrency N] [match options]
+fn tournament(args: &[String]) -> Result<(), String> {
+    let usage = format!(
+        "usage: tournament <engine> <engine> [engine...] [--format round-robin|gauntlet] \
+         [--concurrency N] {MATCH_OPTIONS}"
+    );
+
+    let engine_count = args.iter().take_while(|arg| !arg.starts_with("--")).count();
+    let (commands, options) = args.split_at(engine_count);
+    if commands.len() < 2 {
+        return Err(usage);
+    }
+
+    // Split the tournament's own options from the options of its matches.
+    let mut tournament_options = TournamentOptions::default();
+    let mut match_options = Vec::new();
+    let mut options = options.iter();
+    while let Some(option) = options.next() {
+        match option.as_str() {
+            "--format" => {
+                let value = options.next().ok_or_else(|| usage.clone())?;
+                tournament_options.format = value.parse().map_err(|err| format!("{err}"))?;
+            }
+            "--concurrency" => {
+                let value = options.next().ok_or_else(|| usage.clone())?;
+                tournament_options.concurrency = value.parse().map_err(|_| usage.clone())?;
+            }
+            _ => match_options.push(option.clone()),
+        }
+    }
+
+    let (match_options, mut pgn) = parse_match_options(&match_options, &usage)?;
+    tournament_options.match_options = match_options;
+
+    let engines = commands
+        .iter()
+        .map(|command| engine_factory(command.clone()))
+        .collect();
+
+    let start = Instant::now();
+    let mut games = 0;
+    let mut write_error = None;
+    let mut tournament = Tournament::new(engines, tournament_options);
+    let crosstable = tournament
+        .run(|game, _| {
+            games += 1;
+            println!(
+                "game {games}: {} vs {}: {} ({})",
+                game.tag("White").unwrap_or_default(),
+                game.tag("Black").unwrap_or_default(),
+                game.result,
+                game.tag("Termination").unwrap_or_default(),
+            );
+
+            if let Some(file) = &mut pgn {
+                if let Err(err) = writeln!(file, "{game}") {
+                    write_error.get_or_insert(err);
+                }
+            }
+        })
+        .map_err(|err| err.to_string())?;
+
+    println!(
+        "\n{games} games in {:.0}s\n\n{crosstable}\n",
+        start.elapsed().as_secs_f64()
+    );
+
+    let names = crosstable.names();
+    for (first, second) in tournament.pairings() {
+        println!(
+            "{} vs {}: {}",
+            names[first],
+            names[second],
+            crosstable.score(first, second)
+        );
+    }
+
+    println!("\n{}", Ratings::new(&crosstable));
+
+    match write_error {
+        Some(err) => Err(err.to_string()),
+        None => Ok(()),
+    }
+}
+
+/// ratings estimates the ratings of the players of the games in a PGN file,
+/// like the ones written by the match and tournament commands, from their
+/// results. Usage: ratings <pgn>
+fn ratings(args: &[String]) -> Result<(), String> {
+    let [pgn_path] = args else {
+        return Err(String::from("usage: ratings <pgn>"));
+    };
+
+    let pgn = fs::read_to_string(pgn_path).map_err(|err| err.to_string())?;
+    let games: Vec<_> = pgn::Reader::new(&pgn).flatten().collect();
+    let crosstable = Crosstable::from_games(&games);
+
+    println!(
+        "{} games\n\n{crosstable}\n\n{}",
+        games.len(),
+        Ratings::new(&crosstable)
+    );
+    Ok(())
+}
+
+/// annotate analyzes the moves of the games in a PGN file, and prints the
+/// inaccuracies, mistakes and blunders of each game along with the
+/// statistics of both players. The annotated games are written to a PGN
+/// file if given one. Usage:
+/// annotate <pgn> [--depth N] [--nodes N] [--output FILE]
+fn annotate(args: &[String]) -> Result<(), String> {
+    let usage = "usage: annotate <pgn> [--depth N] [--nodes N] [--output FILE]";
+
+    let [pgn_path, options @ ..] = args else {
+        return Err(usage.to_string());
+    };
+
+    let mut annotator_options = AnnotatorOptions::default();
+    let mut output_path = None;
+    let mut options = options.iter();
+    while let Some(option) = options.next() {
+        let value = options.next().ok_or_else(|| usage.to_string())?;
+        match option.as_str() {
+            "--depth" => {
+                annotator_options.limits.depth = Some(value.parse().map_err(|_| usage.to_string())?)
+            }
+            "--nodes" => {
+                annotator_options.limits.nodes = Some(value.parse().map_err(|_| usage.to_string())?)
+            }
+            "--output" => output_path = Some(value),
+            _ => return Err(usage.to_string()),
+        }
+    }
+
+    let pgn = fs::read_to_string(pgn_path).map_err(|err| err.to_string())?;
+    let mut output = match output_path {
+        Some(path) => Some(fs::File::create(path).map_err(|err| err.to_string())?),
+        None => None,
+    };
+
+    let mut annotator = Annotator::new(annotator_options);
+    for (number, game) in pgn::Reader::new(&pgn).flatten().enumerate() {
+        println!(
+            "game {}: {} vs {}",
+            number + 1,
+            game.tag("White").unwrap_or("?"),
+            game.tag("Black").unwrap_or("?")
+        );
+
+        let analysis = annotator.analyze(&game);
+        let mut board = game.start_board();
+        for analysis in &analysis.moves {
+            if let Some(judgement) = analysis.judgement {
+                let dots = if analysis.side == Color::White {
+                    "."
+                } else {
+                    "..."
+                };
+                println!(
+                    "    {}{dots} {} is a {judgement} ({} -> {}), best is {}",
+                    board.plys() / 2 + 1,
+                    board.clone().to_san(analysis.chessmove),
+                    analysis.best_score,
+                    analysis.score,
+                    board.clone().to_san(analysis.best_move())
+                );
+            }
+
+            board.make_move(analysis.chessmove);
+        }
+
+        println!("    white: {}", analysis.stats(Color::White));
+        println!("    black: {}", analysis.stats(Color::Black));
+
+        if let Some(file) = &mut output {
+            writeln!(file, "{}", analysis.annotate(&game)).map_err(|err| err.to_string())?;
+        }
+    }
+
+    Ok(())
+}
+
+/// MATCH_OPTIONS lists the options of the matches played by the match and
+/// tournament commands.
+const MATCH_OPTIONS: &str = "[--tc TC] [--rounds N] [--pgn FILE] [--event NAME] \
+                             [--openings FILE] [--plys N] [--random] [--resign SCORE MOVES] \
+                             [--draw SCORE MOVES AFTER] [--tb-adjudication]";
+
+/// parse_match_options parses the options of the matches played by the
+/// match and tournament commands, and creates the PGN file the games are
+/// written to if it is given one.
+fn parse_match_options(
+    options: &[String],
+    usage: &str,
+) -> Result<(MatchOptions, Option<fs::File>), String> {
+    fn parse<T: FromStr>(value: Option<&String>, usage: &str) -> Result<T, String> {
+        value
+            .and_then(|value| value.parse().ok())
+            .ok_or_else(|| usage.to_string())
+    }
+
+    let mut match_options = MatchOptions::default();
+    let mut pgn_path = None;
+    let mut openings_path = None;
+    let mut max_plys = None;
+    let mut order = Order::Sequential;
+    let mut options = options.iter();
+    while let Some(option) = options.next() {
+        match option.as_str() {
+            // Options without values.
+            "--random" => {
+                order = Order::Random(random_seed());
+                continue;
+            }
+            "--tb-adjudication" => {
+                match_options.adjudication.tablebase = true;
+                continue;
+            }
+
+            // Options with more than one value.
+            "--resign" => {
+                match_options.adjudication.resign = Some(ResignRule {
+                    score: parse(options.next(), usage)?,
+                    moves: parse(options.next(), usage)?,
+                });
+                continue;
+            }
+            "--draw" => {
+                match_options.adjudication.draw = Some(DrawRule {
+                    score: parse(options.next(), usage)?,
+                    moves: parse(options.next(), usage)?,
+                    after: parse(options.next(), usage)?,
+                });
+                continue;
+            }
+            _ => {}
+        }
+
+        let value = options.next().ok_or_else(|| usage.to_string())?;
+        match option.as_str() {
+            "--tc" => match_options.time_control = value.parse().map_err(|err| format!("{err}"))?,
+            "--rounds" => match_options.rounds = value.parse().map_err(|_| usage.to_string())?,
+            "--pgn" => pgn_path = Some(value),
+            "--event" => match_options.event = value.clone(),
+            "--openings" => openings_path = Some(value),
+            "--plys" => max_plys = Some(value.parse().map_err(|_| usage.to_string())?),
+            _ => return Err(usage.to_string()),
+        }
+    }
+
+    if let Some(path) = openings_path {
+        match_options.openings =
+            OpeningSuite::load(path, max_plys, order).map_err(|err| format!("{path}: {err}"))?;
+    }
+
+    let pgn = match pgn_path {
+        Some(path) => Some(fs::File::create(path).map_err(|err| err.to_string())?),
+        None => None,
+    };
+
+    Ok((match_options, pgn))
+}
+
+/// engine_factory returns an EngineFactory which creates instances of the
+/// engine run by the given command, or of the built-in engine if the
+/// command is `builtin`.
+fn engine_factory(command: String) -> EngineFactory {
+    Box::new(move || -> io::Result<Box<dyn Engine>> {
+        match command.as_str() {
+            "builtin" => Ok(Box::new(SearchEngine::new())),
+            command => match ExternalEngine::new(command) {
+                Ok(engine) => Ok(Box::new(engine)),
+                Err(err) => Err(io::Error::new(err.kind(), format!("{command}: {err}"))),
+            },
+        }
+    })
+}
+
+/// datagen generates training data by playing the built-in engine against
+/// itself, and writes a position per line to the output file. Usage:
+/// datagen <output> [--games N] [--threads N] [--nodes N] [--openings FILE]
+///         [--random-plys N] [--format text|marlin|bullet]
+fn datagen(args: &[String]) -> Result<(), String> {
+    let usage = "usage: datagen <output> [--games N] [--threads N] [--nodes N] \
+                 [--openings FILE] [--random-plys N] [--format text|marlin|bullet]";
+
+    let [output_path, options @ ..] = args else {
+        return Err(usage.to_string());
+    };
+
+    let mut datagen_options = DatagenOptions {
+        seed: random_seed(),
+        ..DatagenOptions::default()
+    };
+
+    let mut format = Format::Text;
+    let mut options = options.iter();
+    while let Some(option) = options.next() {
+        let value = options.next().ok_or_else(|| usage.to_string())?;
+        match option.as_str() {
+            "--format" => format = value.parse().map_err(|err| format!("{err}"))?,
+            "--games" => datagen_options.games = value.parse().map_err(|_| usage.to_string())?,
+            "--threads" => {
+                datagen_options.threads = value.parse().map_err(|_| usage.to_string())?
+            }
+            "--nodes" => {
+                datagen_options.limits.nodes = Some(value.parse().map_err(|_| usage.to_string())?)
+            }
+            "--openings" => {
+                datagen_options.openings =
+                    OpeningSuite::load(value, None, Order::Random(datagen_options.seed))
+                        .map_err(|err| format!("{value}: {err}"))?
+            }
+            "--random-plys" => {
+                datagen_options.random_plys = value.parse().map_err(|_| usage.to_string())?
+            }
+            _ => return Err(usage.to_string()),
+        }
+    }
+
+    let file = fs::File::create(output_path).map_err(|err| err.to_string())?;
+    let mut writer = io::BufWriter::new(file);
+
+    let start = Instant::now();
+    let mut games = 0;
+    let mut write_error = None;
+    let records = datagen::generate(&datagen_options, |records| {
+        for record in records {
+            if let Err(err) = format.write(&mut writer, record) {
+                write_error.get_or_insert(err);
+            }
+        }
+
+        games += 1;
+        if games % 100 == 0 {
+            println!("{games} games in {:.0}s", start.elapsed().as_secs_f64());
+        }
+    });
+
+    if let Some(err) = write_error {
+        return Err(err.to_string());
+    }
+
+    writer.flush().map_err(|err| err.to_string())?;
+    println!(
+        "wrote {records} positions from {games} games to {output_path} in {:.2}s",
+        start.elapsed().as_secs_f64()
+    );
+
+    Ok(())
+}
+
+/// puzzles extracts the puzzles from the games of a pgn file, and writes a
+/// puzzle per line to the output file. Usage:
+/// puzzles <pgn> <output> [--depth N] [--nodes N] [--gap CP] [--max-alternative CP]
+fn puzzles(args: &[String]) -> Result<(), String> {
+    let usage = "usage: puzzles <pgn> <output> [--depth N] [--nodes N] [--gap CP] \
+                 [--max-alternative CP]";
+
+    let [pgn_path, output_path, options @ ..] = args else {
+        return Err(usage.to_string());
+    };
+
+    let mut puzzle_options = PuzzleOptions::default();
+    let mut options = options.iter();
+    while let Some(option) = options.next() {
+        let value = options.next().ok_or_else(|| usage.to_string())?;
+        match option.as_str() {
+            "--depth" => {
+                puzzle_options.limits.depth = Some(value.parse().map_err(|_| usage.to_string())?)
+            }
+            "--nodes" => {
+                puzzle_options.limits.nodes = Some(value.parse().map_err(|_| usage.to_string())?)
+            }
+            "--gap" => puzzle_options.min_gap = value.parse().map_err(|_| usage.to_string())?,
+            "--max-alternative" => {
+                puzzle_options.max_alternative = value.parse().map_err(|_| usage.to_string())?
+            }
+            _ => return Err(usage.to_string()),
+        }
+    }
+
+    let pgn = fs::read_to_string(pgn_path).map_err(|err| err.to_string())?;
+
+    let file = fs::File::create(output_path).map_err(|err| err.to_string())?;
+    let mut writer = io::BufWriter::new(file);
+
+    let start = Instant::now();
+    let mut finder = PuzzleFinder::new(puzzle_options);
+    let (mut games, mut puzzles) = (0, 0);
+    for game in pgn::Reader::new(&pgn).flatten() {
+        for puzzle in finder.find(&game) {
+            writeln!(writer, "{puzzle}").map_err(|err| err.to_string())?;
+            puzzles += 1;
+        }
+
+        games += 1;
+    }
+
+    writer.flush().map_err(|err| err.to_string())?;
+    println!(
+        "wrote {puzzles} puzzles from {games} games to {output_path} in {:.2}s",
+        start.elapsed().as_secs_f64()
+    );
+
+    Ok(())
+}
+
+/// random_seed returns a seed for random choices which is different for
+/// each run of the binary.
+fn random_seed() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_nanos() as u64)
+}
+
+/// load_engine loads the engine run by the given command, or the built-in
+/// engine if the command is `builtin`.
+fn load_engine(command: &str) -> Result<Box<dyn Engine>, String> {
+    match command {
+        "builtin" => Ok(Box::new(SearchEngine::new())),
+        command => match ExternalEngine::new(command) {
+            Ok(engine) => Ok(Box::new(engine)),
+            Err(err) => Err(format!("{command}: {err}")),
+        },
+    }
 }
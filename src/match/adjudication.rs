@@ -0,0 +1,139 @@
+// Copyright © 2023 Rak Laptudirm <rak@laptudirm.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::chess::{Board, Color, Outcome};
+use crate::eval::kpk;
+use crate::pgn::GameResult;
+use crate::search::Score;
+
+/// Adjudication contains the rules by which games between engines are ended
+/// early once their results are clear. Games aren't adjudicated by any of
+/// the rules which are None or false.
+#[derive(Copy, Clone, Default)]
+pub struct Adjudication {
+    /// resign adjudicates a game as lost by a side once both engines agree
+    /// that it is losing by a large margin.
+    pub resign: Option<ResignRule>,
+
+    /// draw adjudicates a game as drawn once both engines agree that the
+    /// position is balanced for a long time.
+    pub draw: Option<DrawRule>,
+
+    /// tablebase adjudicates games which reach positions with results known
+    /// from endgame tablebases, which is currently the KPK bitbase.
+    pub tablebase: bool,
+}
+
+/// ResignRule adjudicates a game as lost by a side once the scores of both
+/// engines have been at least score against it for moves moves in a row.
+#[derive(Copy, Clone)]
+pub struct ResignRule {
+    pub score: Score,
+    pub moves: usize,
+}
+
+/// DrawRule adjudicates a game as drawn once the scores of both engines
+/// have been at most score either way for moves moves in a row, after the
+/// engines have played at least after moves.
+#[derive(Copy, Clone)]
+pub struct DrawRule {
+    pub score: Score,
+    pub moves: usize,
+    pub after: usize,
+}
+
+/// Adjudicator keeps track of the scores of the engines over a game, and
+/// adjudicates it by the rules of an Adjudication.
+pub struct Adjudicator {
+    rules: Adjudication,
+
+    // The number of plys in a row which the scores have been losing for
+    // each side by the resign rule, and balanced by the draw rule.
+    resign_plys: [usize; Color::N],
+    draw_plys: usize,
+
+    plys: usize,
+}
+
+impl Adjudicator {
+    /// new creates an Adjudicator for a new game with the given rules.
+    pub fn new(rules: Adjudication) -> Adjudicator {
+        Adjudicator {
+            rules,
+            resign_plys: [0; Color::N],
+            draw_plys: 0,
+            plys: 0,
+        }
+    }
+
+    /// update adds the score an engine reported for the position on the
+    /// Board, from the point of view of the side to move, before its move
+    /// is made. It returns the adjudicated result of the game, if any. An
+    /// engine which didn't report a score breaks the streaks of all rules.
+    pub fn update(&mut self, board: &Board, score: Option<Score>) -> Option<GameResult> {
+        if self.rules.tablebase {
+            if let Some(score) = kpk::evaluate(board) {
+                let side = board.side_to_move();
+                return Some(match score {
+                    0 => GameResult::Draw,
+                    _ if score > 0 => GameResult::from(Outcome::Win(side)),
+                    _ => GameResult::from(Outcome::Win(!side)),
+                });
+            }
+        }
+
+        let plys = self.plys;
+        self.plys += 1;
+
+        let Some(score) = score else {
+            self.resign_plys = [0; Color::N];
+            self.draw_plys = 0;
+            return None;
+        };
+
+        let white_score = match board.side_to_move() {
+            Color::White => score,
+            _ => -score,
+        };
+
+        if let Some(rule) = self.rules.resign {
+            for (side, side_score) in [(Color::White, white_score), (Color::Black, -white_score)] {
+                let streak = &mut self.resign_plys[side as usize];
+                *streak = if side_score <= -rule.score {
+                    *streak + 1
+                } else {
+                    0
+                };
+
+                // Both engines need to agree, which takes two plys a move.
+                if *streak >= rule.moves * 2 {
+                    return Some(GameResult::from(Outcome::Win(!side)));
+                }
+            }
+        }
+
+        if let Some(rule) = self.rules.draw {
+            self.draw_plys = if score.abs() <= rule.score {
+                self.draw_plys + 1
+            } else {
+                0
+            };
+
+            if plys >= rule.after * 2 && self.draw_plys >= rule.moves * 2 {
+                return Some(GameResult::Draw);
+            }
+        }
+
+        None
+    }
+}
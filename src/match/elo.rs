@@ -0,0 +1,329 @@
+// Copyright © 2023 Rak Laptudirm <rak@laptudirm.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::f64::consts::LN_10;
+use std::fmt;
+
+use super::{Crosstable, MatchScore};
+
+/// Z_95 is the z-score of the 95% confidence interval of a normal
+/// distribution, which the error bars of the estimates cover.
+const Z_95: f64 = 1.959964;
+
+/// MAX_ITERATIONS is the maximum number of iterations of the solver of the
+/// ratings of a pool, which usually converges in far fewer of them.
+const MAX_ITERATIONS: usize = 10000;
+
+/// EloEstimate is an estimate of the Elo difference between two engines
+/// from the results of the games between them.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct EloEstimate {
+    /// elo is the estimated Elo difference.
+    pub elo: f64,
+
+    /// error is the half width of the 95% confidence interval of the Elo
+    /// difference, so that it is within elo ± error.
+    pub error: f64,
+
+    /// los is the likelihood of superiority, which is the probability of
+    /// the Elo difference being positive.
+    pub los: f64,
+}
+
+impl EloEstimate {
+    /// new estimates the Elo difference of a MatchScore by the logistic
+    /// model, from the point of view of the MatchScore. There is no
+    /// estimate without games, or if all of the games were won or lost.
+    pub fn new(score: &MatchScore) -> Option<EloEstimate> {
+        let games = score.games() as f64;
+        let mean = score.points() / games;
+        if score.games() == 0 || mean <= 0.0 || mean >= 1.0 {
+            return None;
+        }
+
+        // The variance of the result of a single game.
+        let variance = (score.wins as f64 * (1.0 - mean).powi(2)
+            + score.draws as f64 * (0.5 - mean).powi(2)
+            + score.losses as f64 * mean.powi(2))
+            / games;
+        let deviation = (variance / games).sqrt();
+
+        let low = elo_difference((mean - Z_95 * deviation).max(f64::EPSILON));
+        let high = elo_difference((mean + Z_95 * deviation).min(1.0 - f64::EPSILON));
+
+        let decisive = (score.wins + score.losses) as f64;
+        let los = match decisive {
+            0.0 => 0.5,
+            _ => {
+                0.5 * (1.0
+                    + erf((score.wins as f64 - score.losses as f64) / (2.0 * decisive).sqrt()))
+            }
+        };
+
+        Some(EloEstimate {
+            elo: elo_difference(mean),
+            error: (high - low) / 2.0,
+            los,
+        })
+    }
+}
+
+impl fmt::Display for EloEstimate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "elo {:.1} +/- {:.1}, los {:.1}%",
+            self.elo,
+            self.error,
+            self.los * 100.0
+        )
+    }
+}
+
+/// Rating is the estimated rating of an engine in a pool of engines.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Rating {
+    /// elo is the estimated rating, relative to the average rating of the
+    /// pool, which is zero.
+    pub elo: f64,
+
+    /// error is the half width of the 95% confidence interval of the
+    /// rating, so that it is within elo ± error.
+    pub error: f64,
+
+    /// score is the engine's score against the rest of the pool.
+    pub score: MatchScore,
+}
+
+/// Ratings are the estimated ratings of a pool of engines which played games
+/// against each other, like in a Tournament.
+#[derive(Clone, Debug)]
+pub struct Ratings {
+    names: Vec<String>,
+    ratings: Vec<Rating>,
+}
+
+impl Ratings {
+    /// new estimates the ratings of the engines of the given Crosstable like
+    /// Ordo does, by iteratively finding the ratings under which the expected
+    /// score of each engine by the logistic model is its actual score. Perfect
+    /// scores are counted as half a point away from perfect, so that their
+    /// ratings are finite.
+    pub fn new(crosstable: &Crosstable) -> Ratings {
+        let n = crosstable.names().len();
+        let games = |engine: usize, opponent: usize| crosstable.score(engine, opponent).games();
+
+        let points: Vec<f64> = (0..n)
+            .map(|engine| {
+                let total = crosstable.total(engine);
+                let games = total.games() as f64;
+                total.points().clamp(0.5, (games - 0.5).max(0.5))
+            })
+            .collect();
+
+        let mut elos = vec![0.0; n];
+        for _ in 0..MAX_ITERATIONS {
+            let mut change: f64 = 0.0;
+            for engine in 0..n {
+                let (mut expected, mut information) = (0.0, 0.0);
+                for opponent in (0..n).filter(|&opponent| games(engine, opponent) > 0) {
+                    let p = expected_score(elos[engine] - elos[opponent]);
+                    let games = games(engine, opponent) as f64;
+                    expected += games * p;
+                    information += games * p * (1.0 - p);
+                }
+
+                if information > 0.0 {
+                    // Newton's method on the difference between the expected
+                    // and the actual score, which is monotonic in the rating.
+                    let step = (points[engine] - expected) / information * 400.0 / LN_10;
+                    elos[engine] += step.clamp(-400.0, 400.0);
+                    change = change.max(step.abs());
+                }
+            }
+
+            if change < 1e-6 {
+                break;
+            }
+        }
+
+        // Ratings are only meaningful relative to each other, so they are
+        // centered around zero.
+        let average = elos.iter().sum::<f64>() / n.max(1) as f64;
+
+        let ratings = (0..n)
+            .map(|engine| {
+                let information: f64 = (0..n)
+                    .filter(|&opponent| games(engine, opponent) > 0)
+                    .map(|opponent| {
+                        let p = expected_score(elos[engine] - elos[opponent]);
+                        games(engine, opponent) as f64 * p * (1.0 - p)
+                    })
+                    .sum();
+
+                Rating {
+                    elo: elos[engine] - average,
+                    error: match information {
+                        0.0 => f64::INFINITY,
+                        _ => Z_95 * 400.0 / LN_10 / information.sqrt(),
+                    },
+                    score: crosstable.total(engine),
+                }
+            })
+            .collect();
+
+        Ratings {
+            names: crosstable.names().to_vec(),
+            ratings,
+        }
+    }
+
+    /// names returns the names of the engines, in the order of their indices.
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    /// rating returns the Rating of the engine with the given index.
+    pub fn rating(&self, engine: usize) -> Rating {
+        self.ratings[engine]
+    }
+}
+
+impl fmt::Display for Ratings {
+    /// fmt formats the Ratings as a table with a row for each engine, from
+    /// the highest rated to the lowest rated.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut order: Vec<usize> = (0..self.names.len()).collect();
+        order.sort_by(|&a, &b| self.ratings[b].elo.total_cmp(&self.ratings[a].elo));
+
+        let width = self.names.iter().map(String::len).max().unwrap_or(0).max(6);
+        write!(
+            f,
+            "{:>3} {:<width$} {:>8} {:>7} {:>6} {:>7}",
+            "#", "engine", "elo", "+/-", "games", "score"
+        )?;
+
+        for (rank, &engine) in order.iter().enumerate() {
+            let rating = &self.ratings[engine];
+            let games = rating.score.games();
+            write!(
+                f,
+                "\n{:>3} {:<width$} {:>8.1} {:>7.1} {:>6} {:>6.1}%",
+                rank + 1,
+                self.names[engine],
+                rating.elo,
+                rating.error,
+                games,
+                rating.score.points() * 100.0 / games.max(1) as f64
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// expected_score returns the expected score of an engine against another
+/// engine rated the given amount lower than it by the logistic model.
+fn expected_score(difference: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-difference / 400.0))
+}
+
+/// elo_difference returns the Elo difference under which the given score is
+/// expected by the logistic model. It is the inverse of expected_score.
+fn elo_difference(score: f64) -> f64 {
+    -400.0 * (1.0 / score - 1.0).log10()
+}
+
+/// erf approximates the error function, with a maximum error of 1.5e-7, by
+/// formula 7.1.26 of Abramowitz and Stegun.
+fn erf(x: f64) -> f64 {
+    let t = 1.0 / (1.0 + 0.3275911 * x.abs());
+    let polynomial = t
+        * (0.254829592
+            + t * (-0.284496736 + t * (1.421413741 + t * (-1.453152027 + t * 1.061405429))));
+    let y = 1.0 - polynomial * (-x * x).exp();
+
+    if x < 0.0 {
+        -y
+    } else {
+        y
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::chess::Color;
+    use crate::pgn::GameResult;
+
+    use super::*;
+
+    fn score(wins: u32, draws: u32, losses: u32) -> MatchScore {
+        MatchScore {
+            wins,
+            draws,
+            losses,
+        }
+    }
+
+    fn assert_close(actual: f64, expected: f64, tolerance: f64) {
+        assert!(
+            (actual - expected).abs() <= tolerance,
+            "{actual} isn't {expected}"
+        );
+    }
+
+    #[test]
+    fn estimate_matches_reference_values() {
+        let estimate = EloEstimate::new(&score(60, 20, 20)).unwrap();
+        assert_close(estimate.elo, 147.19, 0.01);
+        assert_close(estimate.error, 66.01, 0.01);
+        assert_close(estimate.los, 0.999996, 1e-6);
+
+        let estimate = EloEstimate::new(&score(20, 20, 60)).unwrap();
+        assert_close(estimate.elo, -147.19, 0.01);
+        assert_close(estimate.los, 0.000004, 1e-6);
+
+        let estimate = EloEstimate::new(&score(0, 10, 0)).unwrap();
+        assert_close(estimate.elo, 0.0, 1e-9);
+        assert_close(estimate.los, 0.5, 1e-9);
+    }
+
+    #[test]
+    fn estimate_needs_finite_scores() {
+        assert_eq!(EloEstimate::new(&score(0, 0, 0)), None);
+        assert_eq!(EloEstimate::new(&score(10, 0, 0)), None);
+        assert_eq!(EloEstimate::new(&score(0, 0, 10)), None);
+    }
+
+    #[test]
+    fn ratings_of_a_pool() {
+        // A beats B and B beats C by 30 to 10, while A and C never meet.
+        let names = ["A", "B", "C"].map(String::from).to_vec();
+        let mut crosstable = Crosstable::new(names);
+        for (first, second) in [(0, 1), (1, 2)] {
+            for game in 0..40 {
+                let result = match game < 30 {
+                    true => GameResult::WhiteWins,
+                    false => GameResult::BlackWins,
+                };
+                crosstable.add(first, second, result, Color::White);
+            }
+        }
+
+        let ratings = Ratings::new(&crosstable);
+        assert_close(ratings.rating(0).elo, 190.85, 0.01);
+        assert_close(ratings.rating(1).elo, 0.0, 0.01);
+        assert_close(ratings.rating(2).elo, -190.85, 0.01);
+        assert_eq!(ratings.rating(1).score.games(), 80);
+    }
+}
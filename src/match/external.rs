@@ -0,0 +1,299 @@
+// Copyright © 2023 Rak Laptudirm <rak@laptudirm.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::io::{self, BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+use std::time::Duration;
+
+use crate::chess::{Board, Move, FEN};
+use crate::engine::{BestMove, Engine, InfoListener, SearchInfo, SearchLimits, Signals};
+use crate::search::{self, Score};
+use crate::uci;
+
+/// ExternalEngine is an Engine which runs in a separate process and is
+/// driven over the Universal Chess Interface, so that other engines can be
+/// played against in matches. Its searches can't be stopped early through
+/// the Signals, so they always run until the engine's own limits end them.
+pub struct ExternalEngine {
+    name: String,
+    author: String,
+
+    process: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+}
+
+impl ExternalEngine {
+    /// new starts the engine run by the given command, whose arguments are
+    /// separated by whitespace, and waits for it to be ready.
+    pub fn new(command: &str) -> io::Result<ExternalEngine> {
+        let mut words = command.split_whitespace();
+        let program = words
+            .next()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "empty engine command"))?;
+
+        let mut process = Command::new(program)
+            .args(words)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()?;
+
+        let stdin = process.stdin.take().unwrap();
+        let stdout = BufReader::new(process.stdout.take().unwrap());
+
+        let mut engine = ExternalEngine {
+            name: program.to_string(),
+            author: String::new(),
+            process,
+            stdin,
+            stdout,
+        };
+
+        engine.send("uci")?;
+        loop {
+            let line = engine.read_line()?;
+            if let Some(name) = line.strip_prefix("id name ") {
+                engine.name = name.trim().to_string();
+            } else if let Some(author) = line.strip_prefix("id author ") {
+                engine.author = author.trim().to_string();
+            } else if line.trim() == "uciok" {
+                break;
+            }
+        }
+
+        engine.sync()?;
+        Ok(engine)
+    }
+
+    /// set_option sets the option of the engine with the given name to the
+    /// given value.
+    pub fn set_option(&mut self, name: &str, value: &str) -> io::Result<()> {
+        self.send(&format!("setoption name {name} value {value}"))?;
+        self.sync()
+    }
+
+    /// send sends the given command to the engine.
+    fn send(&mut self, command: &str) -> io::Result<()> {
+        writeln!(self.stdin, "{command}")?;
+        self.stdin.flush()
+    }
+
+    /// read_line reads the next line of output from the engine, failing if
+    /// the engine has exited.
+    fn read_line(&mut self) -> io::Result<String> {
+        let mut line = String::new();
+        if self.stdout.read_line(&mut line)? == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                format!("{} exited", self.name),
+            ));
+        }
+
+        Ok(line)
+    }
+
+    /// sync waits for the engine to finish processing the commands it has
+    /// been sent so far.
+    fn sync(&mut self) -> io::Result<()> {
+        self.send("isready")?;
+        while self.read_line()?.trim() != "readyok" {}
+        Ok(())
+    }
+
+    /// go searches the position on the given Board within the given limits,
+    /// reporting the engine's search information to the given InfoListener
+    /// as it is received.
+    fn go(
+        &mut self,
+        board: &Board,
+        limits: &SearchLimits,
+        listener: &mut dyn InfoListener,
+    ) -> io::Result<BestMove> {
+        // Send the game's moves from its starting position, so that the
+        // engine knows about the positions which have been repeated.
+        let moves: Vec<Move> = board.move_history().collect();
+        let mut start = board.clone();
+        for _ in &moves {
+            start.undo_move();
+        }
+
+        let mut position = format!("position fen {}", FEN::from(&start));
+        if !moves.is_empty() {
+            position += " moves";
+            for chessmove in moves {
+                position += " ";
+                position += &chessmove.to_uci(board.is_fischer_random());
+            }
+        }
+
+        self.send(&position)?;
+        self.send(&uci::format_go(limits))?;
+
+        loop {
+            let line = self.read_line()?;
+            let tokens: Vec<&str> = line.split_whitespace().collect();
+            match tokens.as_slice() {
+                ["info", "depth", depth, "currmove", chessmove, "currmovenumber", number] => {
+                    let depth = depth.parse().ok();
+                    let number = number.parse().ok();
+                    if let (Some(depth), Some(chessmove), Some(number)) =
+                        (depth, parse_move(board, chessmove), number)
+                    {
+                        listener.currmove(depth, chessmove, number);
+                    }
+                }
+
+                ["info", info @ ..] => {
+                    if let Some(info) = parse_info(board, info) {
+                        listener.info(&info);
+                    }
+                }
+
+                ["bestmove", best_move, rest @ ..] => {
+                    let chessmove = parse_move(board, best_move).unwrap_or(Move::NULL);
+                    let ponder = match rest {
+                        ["ponder", ponder, ..] => {
+                            let mut board = board.clone();
+                            board.make_move(chessmove);
+                            parse_move(&board, ponder).unwrap_or(Move::NULL)
+                        }
+
+                        _ => Move::NULL,
+                    };
+
+                    return Ok(BestMove { chessmove, ponder });
+                }
+
+                _ => {}
+            }
+        }
+    }
+}
+
+impl Engine for ExternalEngine {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn author(&self) -> String {
+        self.author.clone()
+    }
+
+    fn new_game(&mut self) {
+        let _ = self.send("ucinewgame").and_then(|_| self.sync());
+    }
+
+    /// search searches the position on the Board with the external engine.
+    /// Engines which exit or misbehave during the search return Move::NULL
+    /// as their best move.
+    fn search(
+        &mut self,
+        board: Board,
+        limits: SearchLimits,
+        _signals: &Signals,
+        listener: &mut dyn InfoListener,
+    ) -> BestMove {
+        self.go(&board, &limits, listener).unwrap_or(BestMove {
+            chessmove: Move::NULL,
+            ponder: Move::NULL,
+        })
+    }
+}
+
+impl Drop for ExternalEngine {
+    fn drop(&mut self) {
+        // Give the engine a chance to exit by itself before killing it.
+        if self.send("quit").is_ok() {
+            for _ in 0..100 {
+                if let Ok(Some(_)) = self.process.try_wait() {
+                    return;
+                }
+
+                std::thread::sleep(Duration::from_millis(10));
+            }
+        }
+
+        let _ = self.process.kill();
+        let _ = self.process.wait();
+    }
+}
+
+/// parse_move finds the legal move on the Board described by the given move
+/// in the UCI long algebraic notation.
+fn parse_move(board: &Board, uci_move: &str) -> Option<Move> {
+    let chess960 = board.is_fischer_random();
+    board
+        .clone()
+        .generate_legal_moves()
+        .into_iter()
+        .find(|chessmove| chessmove.to_uci(chess960) == uci_move)
+}
+
+/// parse_info parses the parameters of an `info` command about a search of
+/// the position on the Board, if it reports the search's score.
+fn parse_info(board: &Board, params: &[&str]) -> Option<SearchInfo> {
+    let mut info = SearchInfo {
+        depth: 0,
+        seldepth: 0,
+        score: 0,
+        nodes: 0,
+        time: Duration::ZERO,
+        hashfull: 0,
+        tbhits: 0,
+        pv: Vec::new(),
+    };
+
+    let mut score = None;
+    let mut params = params.iter();
+    while let Some(&param) = params.next() {
+        match param {
+            "depth" => info.depth = params.next()?.parse().ok()?,
+            "seldepth" => info.seldepth = params.next()?.parse().ok()?,
+            "nodes" => info.nodes = params.next()?.parse().ok()?,
+            "time" => info.time = Duration::from_millis(params.next()?.parse().ok()?),
+            "hashfull" => info.hashfull = params.next()?.parse().ok()?,
+            "tbhits" => info.tbhits = params.next()?.parse().ok()?,
+            "score" => score = parse_score(params.next()?, params.next()?),
+
+            // The principal variation is always the last parameter.
+            "pv" => {
+                let mut board = board.clone();
+                for uci_move in params.by_ref() {
+                    let Some(chessmove) = parse_move(&board, uci_move) else {
+                        break;
+                    };
+
+                    board.make_move(chessmove);
+                    info.pv.push(chessmove);
+                }
+            }
+
+            _ => {}
+        }
+    }
+
+    info.score = score?;
+    Some(info)
+}
+
+/// parse_score parses a score given in centipawns or in moves to mate.
+fn parse_score(kind: &str, value: &str) -> Option<Score> {
+    let value: i32 = value.parse().ok()?;
+    match kind {
+        "cp" => Some(value),
+        "mate" if value > 0 => Some(search::mate_in(value as usize * 2 - 1)),
+        "mate" => Some(search::mated_in(-value as usize * 2)),
+        _ => None,
+    }
+}
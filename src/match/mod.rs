@@ -0,0 +1,15 @@
+// Non-namespaced modules.
+mod adjudication;
+mod elo;
+mod external;
+mod runner;
+mod tournament;
+
+// Make the contents of the non-namespaced
+// modules public, so they can be accessed
+// without their parent namespace.
+pub use self::adjudication::*;
+pub use self::elo::*;
+pub use self::external::*;
+pub use self::runner::*;
+pub use self::tournament::*;
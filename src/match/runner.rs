@@ -0,0 +1,270 @@
+// Copyright © 2023 Rak Laptudirm <rak@laptudirm.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use crate::book::openings::OpeningSuite;
+use crate::chess::{Board, Color, Position};
+use crate::engine::{Clock, Engine, SearchInfo, Signals, TimeControl};
+use crate::pgn::{Game, GameResult};
+
+use super::{Adjudication, Adjudicator};
+
+/// MatchOptions are the options of a Match between two engines.
+#[derive(Clone)]
+pub struct MatchOptions {
+    /// time_control is the time control each game is played with.
+    pub time_control: TimeControl,
+
+    /// time_margin is how far over its time an engine can go before it
+    /// loses on time, which makes up for the communication delays.
+    pub time_margin: Duration,
+
+    /// rounds is the number of rounds to play. Each round is a pair of games
+    /// from the same opening, with the engines playing both colors.
+    pub rounds: usize,
+
+    /// openings are the positions the games start from. Each round takes
+    /// the next position from them.
+    pub openings: OpeningSuite,
+
+    /// event is the name of the match, used in the Event tag of its games.
+    pub event: String,
+
+    /// adjudication contains the rules by which games are ended early once
+    /// their results are clear. No games are adjudicated by default.
+    pub adjudication: Adjudication,
+}
+
+impl Default for MatchOptions {
+    fn default() -> Self {
+        MatchOptions {
+            time_control: TimeControl::Increment {
+                base: Duration::from_secs(10),
+                increment: Duration::from_millis(100),
+            },
+            time_margin: Duration::from_millis(50),
+            rounds: 1,
+            openings: OpeningSuite::default(),
+            event: String::from("?"),
+            adjudication: Adjudication::default(),
+        }
+    }
+}
+
+/// Match plays games between two engines, which alternate colors between
+/// the games, and keeps track of the score between them.
+pub struct Match {
+    engines: [Box<dyn Engine>; 2],
+    options: MatchOptions,
+}
+
+impl Match {
+    /// new creates a Match between the given engines with the given options.
+    pub fn new(first: Box<dyn Engine>, second: Box<dyn Engine>, options: MatchOptions) -> Match {
+        Match {
+            engines: [first, second],
+            options,
+        }
+    }
+
+    /// run plays all of the games of the Match, calling the given function
+    /// after each of them with the game and the score so far, and returns
+    /// the final score of the match from the first engine's point of view.
+    pub fn run(&mut self, mut on_game: impl FnMut(&Game, &MatchScore)) -> MatchScore {
+        let mut score = MatchScore::default();
+
+        for round in 0..self.options.rounds {
+            let opening = self.options.openings.next().unwrap();
+
+            for first_color in [Color::White, Color::Black] {
+                let game = self.play_game(&opening, first_color, round + 1);
+                score.add(game.result, first_color);
+                on_game(&game, &score);
+            }
+        }
+
+        score
+    }
+
+    /// play_round plays a round of the Match with the given number, which is
+    /// a pair of games from the given opening where the first engine plays
+    /// white in the first game and black in the second one.
+    pub fn play_round(&mut self, opening: &Position, round: usize) -> [Game; 2] {
+        [Color::White, Color::Black].map(|first_color| self.play_game(opening, first_color, round))
+    }
+
+    /// play_game plays a game from the given opening, where the first engine
+    /// plays the given color, and returns it.
+    fn play_game(&mut self, opening: &Position, first_color: Color, round: usize) -> Game {
+        let mut board = Board::from(*opening);
+        let mut game = Game::new(*opening);
+        let mut clock = Clock::new(self.options.time_control);
+
+        let names = self.engines.each_ref().map(|engine| engine.name());
+        let (white, black) = match first_color {
+            Color::White => (&names[0], &names[1]),
+            _ => (&names[1], &names[0]),
+        };
+
+        for (name, value) in [
+            ("Event", self.options.event.clone()),
+            ("Round", round.to_string()),
+            ("White", white.clone()),
+            ("Black", black.clone()),
+            ("TimeControl", self.options.time_control.to_string()),
+        ] {
+            game.tags.push((name.to_string(), value));
+        }
+
+        for engine in &mut self.engines {
+            engine.new_game();
+        }
+
+        let signals = Signals::new();
+        let mut adjudicator = Adjudicator::new(self.options.adjudication);
+        let (result, termination) = loop {
+            if let Some(outcome) = board.outcome() {
+                break (GameResult::from(outcome), Termination::Normal);
+            }
+
+            if board.is_threefold_repetition() || board.is_insufficient_material() {
+                break (GameResult::Draw, Termination::Normal);
+            }
+
+            let side = board.side_to_move();
+            let engine = &mut self.engines[(side != first_color) as usize];
+
+            signals.reset(false);
+            let start = Instant::now();
+            let mut score = None;
+            let best_move = engine
+                .search(
+                    board.clone(),
+                    clock.search_limits(side),
+                    &signals,
+                    &mut |info: &SearchInfo| score = Some(info.score),
+                )
+                .chessmove;
+
+            let elapsed = start.elapsed().saturating_sub(self.options.time_margin);
+            if !clock.tick(side, elapsed) {
+                break (loss(side), Termination::TimeForfeit);
+            }
+
+            if !board.generate_legal_moves().contains(&best_move) {
+                break (loss(side), Termination::IllegalMove);
+            }
+
+            if let Some(result) = adjudicator.update(&board, score) {
+                break (result, Termination::Adjudication);
+            }
+
+            board.make_move(best_move);
+            game.push(best_move);
+        };
+
+        game.result = result;
+        game.tags
+            .push((String::from("Termination"), termination.to_string()));
+
+        game
+    }
+}
+
+/// MatchScore is the score of a Match from the first engine's point of view.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub struct MatchScore {
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+}
+
+impl MatchScore {
+    /// games returns the number of games played.
+    pub fn games(&self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+
+    /// points returns the number of points scored, where wins are worth a
+    /// point and draws are worth half a point.
+    pub fn points(&self) -> f64 {
+        self.wins as f64 + self.draws as f64 / 2.0
+    }
+
+    /// add adds the given result of a game where the first engine played
+    /// the given color to the score. Games without a result aren't counted.
+    pub fn add(&mut self, result: GameResult, first_color: Color) {
+        match result.winner() {
+            Color::None if result == GameResult::Draw => self.draws += 1,
+            Color::None => {}
+            winner if winner == first_color => self.wins += 1,
+            _ => self.losses += 1,
+        }
+    }
+}
+
+impl fmt::Display for MatchScore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "+{} ={} -{} ({}/{})",
+            self.wins,
+            self.draws,
+            self.losses,
+            self.points(),
+            self.games()
+        )
+    }
+}
+
+/// Termination is the reason a game of a Match ended for.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Termination {
+    /// Normal is a game which ended by the rules of chess.
+    Normal,
+
+    /// TimeForfeit is a game lost by a side which ran out of time.
+    TimeForfeit,
+
+    /// IllegalMove is a game lost by a side which didn't play a legal move.
+    IllegalMove,
+
+    /// Adjudication is a game ended early by the Match's adjudication rules.
+    Adjudication,
+}
+
+impl fmt::Display for Termination {
+    /// fmt formats the Termination as the value of a PGN Termination tag.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Termination::Normal => "normal",
+                Termination::TimeForfeit => "time forfeit",
+                Termination::IllegalMove => "rules infraction",
+                Termination::Adjudication => "adjudication",
+            }
+        )
+    }
+}
+
+/// loss returns the GameResult of a game lost by the given side.
+fn loss(side: Color) -> GameResult {
+    match side {
+        Color::White => GameResult::BlackWins,
+        _ => GameResult::WhiteWins,
+    }
+}
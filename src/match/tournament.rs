@@ -0,0 +1,327 @@
+// Copyright © 2023 Rak Laptudirm <rak@laptudirm.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::thread;
+
+use crate::chess::{Color, Position};
+use crate::engine::Engine;
+use crate::pgn::{Game, GameResult};
+
+use super::{Match, MatchOptions, MatchScore};
+
+/// EngineFactory creates new instances of an engine which plays in a
+/// Tournament, since each of the games played at the same time needs its
+/// own instance of the engine.
+pub type EngineFactory = Box<dyn Fn() -> io::Result<Box<dyn Engine>> + Send + Sync>;
+
+/// TournamentFormat is the format of a Tournament, which decides the pairs
+/// of engines which play matches against each other.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub enum TournamentFormat {
+    /// RoundRobin pairs each engine with every other engine.
+    #[default]
+    RoundRobin,
+
+    /// Gauntlet pairs the first engine with every other engine.
+    Gauntlet,
+}
+
+/// TournamentFormatParseError is the error returned when parsing an unknown
+/// TournamentFormat.
+#[derive(Debug)]
+pub struct TournamentFormatParseError;
+
+impl fmt::Display for TournamentFormatParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "tournament format should be round-robin or gauntlet")
+    }
+}
+
+impl Error for TournamentFormatParseError {}
+
+impl FromStr for TournamentFormat {
+    type Err = TournamentFormatParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "round-robin" => Ok(TournamentFormat::RoundRobin),
+            "gauntlet" => Ok(TournamentFormat::Gauntlet),
+            _ => Err(TournamentFormatParseError),
+        }
+    }
+}
+
+/// TournamentOptions are the options of a Tournament.
+#[derive(Clone, Default)]
+pub struct TournamentOptions {
+    /// format is the format of the Tournament.
+    pub format: TournamentFormat,
+
+    /// concurrency is the number of games played at the same time.
+    pub concurrency: usize,
+
+    /// match_options are the options of the match between each pair of
+    /// engines. Every pair plays its rounds from the same openings.
+    pub match_options: MatchOptions,
+}
+
+/// Tournament plays matches between the pairs of a set of engines decided
+/// by its format, and keeps track of their results in a Crosstable.
+pub struct Tournament {
+    engines: Vec<EngineFactory>,
+    options: TournamentOptions,
+}
+
+impl Tournament {
+    /// new creates a Tournament between the engines created by the given
+    /// EngineFactories with the given options.
+    pub fn new(engines: Vec<EngineFactory>, options: TournamentOptions) -> Tournament {
+        Tournament { engines, options }
+    }
+
+    /// pairings returns the pairs of the indices of the engines which play
+    /// matches against each other in the Tournament.
+    pub fn pairings(&self) -> Vec<(usize, usize)> {
+        let n = self.engines.len();
+        match self.options.format {
+            TournamentFormat::RoundRobin => (0..n)
+                .flat_map(|first| (first + 1..n).map(move |second| (first, second)))
+                .collect(),
+            TournamentFormat::Gauntlet => (1..n).map(|second| (0, second)).collect(),
+        }
+    }
+
+    /// run plays all of the games of the Tournament, calling the given
+    /// function after each of them with the game and the Crosstable so far,
+    /// and returns the final Crosstable. The games are played on as many
+    /// threads as the concurrency, which is at least one, and the first
+    /// rounds of all of the pairings are played before the later ones. An
+    /// error is returned if an engine can't be created.
+    pub fn run(&mut self, mut on_game: impl FnMut(&Game, &Crosstable)) -> io::Result<Crosstable> {
+        let names = self
+            .engines
+            .iter()
+            .map(|engine| engine().map(|engine| engine.name()))
+            .collect::<io::Result<Vec<_>>>()?;
+        let mut crosstable = Crosstable::new(names);
+
+        let pairings = self.pairings();
+        let rounds = self.options.match_options.rounds;
+        let openings: Vec<Position> = self
+            .options
+            .match_options
+            .openings
+            .by_ref()
+            .take(rounds)
+            .collect();
+
+        // Each job is a round of a pairing, which is a pair of games.
+        let jobs: Vec<(usize, usize)> = (0..rounds)
+            .flat_map(|round| (0..pairings.len()).map(move |pairing| (pairing, round)))
+            .collect();
+
+        let next_job = AtomicUsize::new(0);
+        let (sender, receiver) = mpsc::channel();
+        let (engines, options) = (&self.engines, &self.options.match_options);
+        let (pairings, jobs, openings, next_job) = (&pairings, &jobs, &openings, &next_job);
+
+        let mut error = None;
+        thread::scope(|scope| {
+            for _ in 0..self.options.concurrency.clamp(1, jobs.len().max(1)) {
+                let sender = sender.clone();
+                scope.spawn(move || loop {
+                    let job = next_job.fetch_add(1, Ordering::Relaxed);
+                    let Some(&(pairing, round)) = jobs.get(job) else {
+                        break;
+                    };
+
+                    let (first, second) = pairings[pairing];
+                    let games = (|| {
+                        let mut round_match =
+                            Match::new(engines[first]()?, engines[second]()?, options.clone());
+                        Ok(round_match.play_round(&openings[round], round + 1))
+                    })();
+
+                    if sender.send((pairing, games)).is_err() {
+                        break;
+                    }
+                });
+            }
+
+            // Only the workers hold senders now, so the channel is closed
+            // once all of them are done.
+            drop(sender);
+
+            for (pairing, games) in receiver {
+                let games: [Game; 2] = match games {
+                    Ok(games) => games,
+                    Err(err) => {
+                        // Stop the other workers from starting new jobs.
+                        next_job.store(jobs.len(), Ordering::Relaxed);
+                        error.get_or_insert(err);
+                        continue;
+                    }
+                };
+
+                let (first, second) = pairings[pairing];
+                for (game, first_color) in games.iter().zip([Color::White, Color::Black]) {
+                    crosstable.add(first, second, game.result, first_color);
+                    on_game(game, &crosstable);
+                }
+            }
+        });
+
+        match error {
+            Some(err) => Err(err),
+            None => Ok(crosstable),
+        }
+    }
+}
+
+/// Crosstable contains the results of the games of a Tournament between
+/// each pair of its engines.
+#[derive(Clone, Debug)]
+pub struct Crosstable {
+    names: Vec<String>,
+
+    // The score of each engine against each of the other engines, from its
+    // own point of view, indexed by the indices of the two engines.
+    scores: Vec<Vec<MatchScore>>,
+}
+
+impl Crosstable {
+    /// new creates an empty Crosstable between engines with the given names.
+    pub fn new(names: Vec<String>) -> Crosstable {
+        let scores = vec![vec![MatchScore::default(); names.len()]; names.len()];
+        Crosstable { names, scores }
+    }
+
+    /// from_games creates a Crosstable from the results of the given games,
+    /// whose players are named by their White and Black tags. The engines
+    /// are indexed in the order they first appear in.
+    pub fn from_games<'a>(games: impl IntoIterator<Item = &'a Game>) -> Crosstable {
+        let mut crosstable = Crosstable::new(Vec::new());
+        for game in games {
+            let white = crosstable.index(game.tag("White").unwrap_or("?"));
+            let black = crosstable.index(game.tag("Black").unwrap_or("?"));
+            crosstable.add(white, black, game.result, Color::White);
+        }
+
+        crosstable
+    }
+
+    /// names returns the names of the engines, in the order of their indices.
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    /// score returns the score of the engine with the given index against
+    /// the engine with the other given index, from its point of view.
+    pub fn score(&self, engine: usize, opponent: usize) -> MatchScore {
+        self.scores[engine][opponent]
+    }
+
+    /// total returns the score of the engine with the given index against
+    /// all of the other engines, from its point of view.
+    pub fn total(&self, engine: usize) -> MatchScore {
+        self.scores[engine]
+            .iter()
+            .fold(MatchScore::default(), |total, score| MatchScore {
+                wins: total.wins + score.wins,
+                draws: total.draws + score.draws,
+                losses: total.losses + score.losses,
+            })
+    }
+
+    /// standings returns the indices of the engines ordered by their points,
+    /// from the most to the least.
+    pub fn standings(&self) -> Vec<usize> {
+        let mut standings: Vec<usize> = (0..self.names.len()).collect();
+        standings.sort_by(|&a, &b| self.total(b).points().total_cmp(&self.total(a).points()));
+        standings
+    }
+
+    /// index returns the index of the engine with the given name, adding it
+    /// to the Crosstable if it isn't in it.
+    fn index(&mut self, name: &str) -> usize {
+        if let Some(index) = self.names.iter().position(|other| other == name) {
+            return index;
+        }
+
+        self.names.push(name.to_string());
+        for scores in &mut self.scores {
+            scores.push(MatchScore::default());
+        }
+
+        self.scores
+            .push(vec![MatchScore::default(); self.names.len()]);
+        self.names.len() - 1
+    }
+
+    /// add adds the given result of a game between the engines with the
+    /// given indices, where the first of them played the given color.
+    pub fn add(&mut self, first: usize, second: usize, result: GameResult, first_color: Color) {
+        self.scores[first][second].add(result, first_color);
+        self.scores[second][first].add(result, !first_color);
+    }
+}
+
+impl fmt::Display for Crosstable {
+    /// fmt formats the Crosstable as a table with a row for each engine in
+    /// the order of the standings, with its total score followed by its
+    /// points against each of the engines, in the order of their ranks.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let standings = self.standings();
+        let width = self.names.iter().map(String::len).max().unwrap_or(0).max(6);
+        let totals: Vec<String> = (0..self.names.len())
+            .map(|engine| self.total(engine).to_string())
+            .collect();
+        let score_width = totals.iter().map(String::len).max().unwrap_or(0).max(5);
+
+        write!(
+            f,
+            "{:>3} {:<width$} {:>score_width$}",
+            "#", "engine", "score"
+        )?;
+        for rank in 1..=standings.len() {
+            write!(f, " {rank:>5}")?;
+        }
+
+        for (rank, &engine) in standings.iter().enumerate() {
+            write!(
+                f,
+                "\n{:>3} {:<width$} {:>score_width$}",
+                rank + 1,
+                self.names[engine],
+                totals[engine]
+            )?;
+
+            for &opponent in &standings {
+                let score = self.score(engine, opponent);
+                if opponent == engine || score.games() == 0 {
+                    write!(f, " {:>5}", "-")?;
+                } else {
+                    write!(f, " {:>5}", score.points())?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
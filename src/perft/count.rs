@@ -0,0 +1,295 @@
+// Copyright © 2023 Rak Laptudirm <rak@laptudirm.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+    ops::ControlFlow,
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+};
+
+use crate::chess::{Board, Move};
+
+use super::PerftTable;
+
+/// perft counts the number of leaf nodes in the move generation tree of
+/// the given depth rooted at the Board's position, making every move of
+/// the tree. It is mainly useful for validating the move generator, along
+/// with the make and undo move functions of the Board.
+pub fn perft(board: &mut Board, depth: u8) -> u64 {
+    count::<false>(board, depth)
+}
+
+/// bulk_perft is the same as perft, except that it counts the nodes at the
+/// last ply of the tree from the length of the legal move-list instead of
+/// making each of those moves, which makes it a lot faster.
+pub fn bulk_perft(board: &mut Board, depth: u8) -> u64 {
+    count::<true>(board, depth)
+}
+
+/// divide returns the node count of the subtree of each root move of the
+/// perft tree of the given depth, in move generation order, which is useful
+/// for finding the moves which a buggy move generator miscounts. The counts
+/// are bulk counted, and add up to the node count of the whole tree.
+pub fn divide(board: &mut Board, depth: u8) -> Vec<(Move, u64)> {
+    if depth == 0 {
+        return Vec::new();
+    }
+
+    board
+        .generate_legal_moves()
+        .into_iter()
+        .map(|chessmove| {
+            board.make_move(chessmove);
+            let nodes = bulk_perft(board, depth - 1);
+            board.undo_move();
+
+            (chessmove, nodes)
+        })
+        .collect()
+}
+
+/// hash_perft is the same as bulk_perft, except that it stores the node
+/// counts of the subtrees it counts in the given PerftTable, and looks them
+/// up instead of counting them again when they are reached by transposition.
+/// The table may be reused across calls to speed up later counts.
+pub fn hash_perft(board: &mut Board, depth: u8, table: &mut PerftTable) -> u64 {
+    if depth <= 1 {
+        return bulk_perft(board, depth);
+    }
+
+    let hash = board.hash();
+    if let Some(nodes) = table.probe(hash, depth) {
+        return nodes;
+    }
+
+    let mut nodes = 0;
+    for chessmove in board.generate_legal_moves() {
+        board.make_move(chessmove);
+        nodes += hash_perft(board, depth - 1, table);
+        board.undo_move();
+    }
+
+    table.store(hash, depth, nodes);
+    nodes
+}
+
+/// parallel_perft is the same as bulk_perft, except that it counts the
+/// subtrees of the root moves on the given number of threads. The threads
+/// take the next uncounted root move whenever they are done with one, so
+/// that the work is spread evenly even if the subtrees differ in size.
+pub fn parallel_perft(board: &Board, depth: u8, threads: usize) -> u64 {
+    let mut board = board.clone();
+    if depth <= 1 {
+        return bulk_perft(&mut board, depth);
+    }
+
+    let moves = board.generate_legal_moves();
+    let next_move = AtomicUsize::new(0);
+
+    thread::scope(|scope| {
+        let handles: Vec<_> = (0..threads.max(1))
+            .map(|_| {
+                let mut board = board.clone();
+                let moves = &moves;
+                let next_move = &next_move;
+                scope.spawn(move || {
+                    let mut nodes = 0;
+                    while let Some(&chessmove) =
+                        moves.get(next_move.fetch_add(1, Ordering::Relaxed))
+                    {
+                        board.make_move(chessmove);
+                        nodes += bulk_perft(&mut board, depth - 1);
+                        board.undo_move();
+                    }
+
+                    nodes
+                })
+            })
+            .collect();
+
+        handles
+            .into_iter()
+            .map(|handle| handle.join().expect("perft: counting thread panicked"))
+            .sum()
+    })
+}
+
+/// count is the recursive implementation of perft, which bulk counts the
+/// nodes at the last ply of the tree if BULK_COUNT is enabled.
+fn count<const BULK_COUNT: bool>(board: &mut Board, depth: u8) -> u64 {
+    // Return 1 for current node at depth 0.
+    if depth == 0 {
+        return 1;
+    }
+
+    // When bulk counting is enabled, return the number of
+    // legal moves when depth is one. This saves a lot of
+    // time cause it saves make moves and recursion.
+    if BULK_COUNT && depth == 1 {
+        let mut nodes = 0;
+        board.for_each_legal_move(|_| {
+            nodes += 1;
+            ControlFlow::Continue(())
+        });
+
+        return nodes;
+    }
+
+    // Generate legal move-list.
+    let moves = board.generate_legal_moves();
+
+    // Recursively call perft for child nodes.
+    let mut nodes = 0;
+    for chessmove in moves {
+        board.make_move(chessmove);
+        nodes += count::<BULK_COUNT>(board, depth - 1);
+        board.undo_move();
+    }
+
+    nodes
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use crate::chess::variant::{self, Variant};
+
+    use super::*;
+
+    // Standard perft positions with their node counts from depth one onward.
+    #[rustfmt::skip]
+    const POSITIONS: [(&str, &[u64]); 6] = [
+        ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", &[20, 400, 8902, 197281]),
+        ("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", &[48, 2039, 97862]),
+        ("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", &[14, 191, 2812, 43238, 674624]),
+        ("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1", &[6, 264, 9467]),
+        ("rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8", &[44, 1486, 62379]),
+        ("r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10", &[46, 2079, 89890]),
+    ];
+
+    #[test]
+    fn bulk_perft_matches_standard_counts() {
+        for (fen, counts) in POSITIONS {
+            let mut board = Board::from_str(fen).unwrap();
+            for (depth, &expected) in counts.iter().enumerate() {
+                let nodes = bulk_perft(&mut board, depth as u8 + 1);
+                assert_eq!(nodes, expected, "{fen} depth {}", depth + 1);
+            }
+        }
+    }
+
+    #[test]
+    fn hash_perft_matches_standard_counts() {
+        let mut table = PerftTable::new(1);
+        for (fen, counts) in POSITIONS {
+            let mut board = Board::from_str(fen).unwrap();
+            for (depth, &expected) in counts.iter().enumerate() {
+                let nodes = hash_perft(&mut board, depth as u8 + 1, &mut table);
+                assert_eq!(nodes, expected, "{fen} depth {}", depth + 1);
+            }
+        }
+    }
+
+    #[test]
+    fn parallel_perft_matches_standard_counts() {
+        for (fen, counts) in POSITIONS {
+            let board = Board::from_str(fen).unwrap();
+            for (depth, &expected) in counts.iter().enumerate() {
+                let nodes = parallel_perft(&board, depth as u8 + 1, 4);
+                assert_eq!(nodes, expected, "{fen} depth {}", depth + 1);
+            }
+        }
+    }
+
+    #[test]
+    fn divide_adds_up_to_bulk_perft() {
+        for (fen, _) in POSITIONS {
+            let mut board = Board::from_str(fen).unwrap();
+            let split = divide(&mut board, 3);
+            let nodes: u64 = split.iter().map(|&(_, nodes)| nodes).sum();
+
+            assert_eq!(split.len(), board.generate_legal_moves().len(), "{fen}");
+            assert_eq!(nodes, bulk_perft(&mut board, 3), "{fen}");
+        }
+    }
+
+    #[test]
+    fn antichess_perft_matches_standard_counts() {
+        let mut board =
+            Board::from_fen(variant::Antichess.start_fen(), &variant::Antichess).unwrap();
+
+        for (depth, expected) in [20, 400, 8067, 153299].into_iter().enumerate() {
+            assert_eq!(
+                bulk_perft(&mut board, depth as u8 + 1),
+                expected,
+                "depth {}",
+                depth + 1
+            );
+        }
+    }
+
+    #[test]
+    fn horde_perft_matches_standard_counts() {
+        let mut board = Board::from_fen(variant::Horde.start_fen(), &variant::Horde).unwrap();
+
+        for (depth, expected) in [8, 128, 1274, 23310].into_iter().enumerate() {
+            assert_eq!(
+                bulk_perft(&mut board, depth as u8 + 1),
+                expected,
+                "depth {}",
+                depth + 1
+            );
+        }
+    }
+
+    #[test]
+    fn chess960_perft_matches_standard_counts() {
+        let fen = "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9";
+        let mut board = Board::from_str(fen).unwrap();
+
+        for (depth, expected) in [21, 528, 12189, 326672].into_iter().enumerate() {
+            assert_eq!(
+                bulk_perft(&mut board, depth as u8 + 1),
+                expected,
+                "depth {}",
+                depth + 1
+            );
+        }
+    }
+
+    #[test]
+    fn perft_matches_bulk_perft() {
+        for (fen, _) in POSITIONS {
+            let mut board = Board::from_str(fen).unwrap();
+            assert_eq!(perft(&mut board, 3), bulk_perft(&mut board, 3), "{fen}");
+        }
+    }
+
+    #[test]
+    fn perft_restores_position() {
+        for (fen, _) in POSITIONS {
+            let mut board = Board::from_str(fen).unwrap();
+            let hash = board.hash();
+            perft(&mut board, 2);
+            assert_eq!(board.hash(), hash, "{fen}");
+        }
+    }
+
+    #[test]
+    fn perft_at_depth_zero_counts_root() {
+        let mut board = Board::from_str(POSITIONS[0].0).unwrap();
+        assert_eq!(perft(&mut board, 0), 1);
+        assert_eq!(bulk_perft(&mut board, 0), 1);
+    }
+}
@@ -0,0 +1,11 @@
+// Non-namespaced modules.
+mod count;
+mod suite;
+mod table;
+
+// Make the contents of the non-namespaced
+// modules public, so they can be accessed
+// without their parent namespace.
+pub use self::count::*;
+pub use self::suite::*;
+pub use self::table::*;
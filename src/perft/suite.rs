@@ -0,0 +1,185 @@
+// Copyright © 2023 Rak Laptudirm <rak@laptudirm.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{fs, io, path::Path, str::FromStr};
+
+use crate::chess::Board;
+
+use super::parallel_perft;
+
+/// SuiteEntry is a position from a perft suite, with the node counts of
+/// its perft trees of different depths.
+pub struct SuiteEntry {
+    pub fen: String,
+    pub board: Board,
+    pub counts: Vec<(u8, u64)>,
+}
+
+/// DepthResult is the result of counting the perft tree of a SuiteEntry's
+/// position to one of its depths.
+#[derive(Copy, Clone)]
+pub struct DepthResult {
+    pub depth: u8,
+    pub expected: u64,
+    pub nodes: u64,
+}
+
+impl DepthResult {
+    /// passed checks if the node count matches the expected one.
+    pub fn passed(&self) -> bool {
+        self.nodes == self.expected
+    }
+}
+
+/// load_suite loads the perft suite at the given path, which has one
+/// position per line, as described by parse_suite_entry. Empty lines and
+/// lines starting with a `#` are skipped.
+pub fn load_suite<P: AsRef<Path>>(path: P) -> io::Result<Vec<SuiteEntry>> {
+    let suite = fs::read_to_string(path)?;
+
+    let mut entries = Vec::new();
+    for (number, line) in suite.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        match parse_suite_entry(line) {
+            Some(entry) => entries.push(entry),
+            None => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("invalid suite entry on line {}", number + 1),
+                ))
+            }
+        }
+    }
+
+    Ok(entries)
+}
+
+/// parse_suite_entry parses a perft suite entry, which is a FEN or an EPD
+/// followed by the node counts of its perft trees as semicolon separated
+/// operations, like `<fen> ;D1 20 ;D2 400 ;D3 8902`.
+pub fn parse_suite_entry(line: &str) -> Option<SuiteEntry> {
+    let mut fields = line.split(';');
+
+    // EPDs don't have the move counters of FENs.
+    let position: Vec<&str> = fields.next()?.split_whitespace().collect();
+    let fen = match position.len() {
+        4 => format!("{} 0 1", position.join(" ")),
+        6 => position.join(" "),
+        _ => return None,
+    };
+
+    let mut counts = Vec::new();
+    for operation in fields {
+        let (depth, nodes) = operation.trim().split_once(char::is_whitespace)?;
+        let depth = depth.strip_prefix(['D', 'd'])?.parse().ok()?;
+        counts.push((depth, nodes.trim().parse().ok()?));
+    }
+
+    Some(SuiteEntry {
+        board: Board::from_str(&fen).ok()?,
+        fen,
+        counts,
+    })
+}
+
+impl SuiteEntry {
+    /// run counts the perft trees of the SuiteEntry's position to each of
+    /// its depths which are at most the given maximum depth, in order, with
+    /// the given number of threads. Since a miscount at a depth leads to
+    /// miscounts at all of the deeper ones, it stops at the first miscount.
+    pub fn run(&self, max_depth: u8, threads: usize) -> Vec<DepthResult> {
+        let mut results = Vec::new();
+        for &(depth, expected) in &self.counts {
+            if depth > max_depth {
+                continue;
+            }
+
+            let nodes = parallel_perft(&self.board, depth, threads);
+            results.push(DepthResult {
+                depth,
+                expected,
+                nodes,
+            });
+
+            if nodes != expected {
+                break;
+            }
+        }
+
+        results
+    }
+}
+
+/// run_suite runs each of the given SuiteEntries as described by run, and
+/// reports the results of each one to the given function once it is done.
+/// It returns the number of entries which failed.
+pub fn run_suite<F>(entries: &[SuiteEntry], max_depth: u8, threads: usize, mut report: F) -> usize
+where
+    F: FnMut(&SuiteEntry, &[DepthResult]),
+{
+    let mut failures = 0;
+    for entry in entries {
+        let results = entry.run(max_depth, threads);
+        if !results.iter().all(DepthResult::passed) {
+            failures += 1;
+        }
+
+        report(entry, &results);
+    }
+
+    failures
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_suite_entry_reads_epds_and_fens() {
+        let entry = parse_suite_entry("8/8/8/8/8/8/8/K6k w - - ;D1 3 ;D2 9").unwrap();
+        assert_eq!(entry.fen, "8/8/8/8/8/8/8/K6k w - - 0 1");
+        assert_eq!(entry.counts, [(1, 3), (2, 9)]);
+
+        let entry = parse_suite_entry("8/8/8/8/8/8/8/K6k w - - 0 1;D1 3").unwrap();
+        assert_eq!(entry.counts, [(1, 3)]);
+
+        assert!(parse_suite_entry("8/8/8/8/8/8/8/K6k w ;D1 3").is_none());
+        assert!(parse_suite_entry("8/8/8/8/8/8/8/K6k w - - ;D1").is_none());
+    }
+
+    #[test]
+    fn run_suite_reports_failures() {
+        let entries = [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1 ;D1 20 ;D2 400 ;D3 8902",
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1 ;D1 20 ;D2 401 ;D3 8902",
+        ]
+        .map(|line| parse_suite_entry(line).unwrap());
+
+        let mut reported = Vec::new();
+        let failures = run_suite(&entries, 3, 2, |_, results| {
+            reported.push(
+                results
+                    .iter()
+                    .map(|result| result.passed())
+                    .collect::<Vec<_>>(),
+            );
+        });
+
+        assert_eq!(failures, 1);
+        assert_eq!(reported, [vec![true, true, true], vec![true, false]]);
+    }
+}
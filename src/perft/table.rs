@@ -0,0 +1,95 @@
+// Copyright © 2023 Rak Laptudirm <rak@laptudirm.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::mem;
+
+use crate::chess::zobrist;
+
+/// PerftTable is a hash table of the node counts of perft subtrees, keyed
+/// by the zobrist hashes and the depths of their roots, so that subtrees
+/// reached through different move orders don't need to be counted again.
+pub struct PerftTable {
+    entries: Vec<Entry>,
+    size_mb: usize,
+}
+
+/// Entry is an entry of a PerftTable.
+#[derive(Copy, Clone, Default)]
+struct Entry {
+    // The full hash of the subtree's root, since a wrong node count can't
+    // be told apart from a right one like a wrong best move can.
+    hash: u64,
+
+    // The node count in the upper 56 bits and the depth in the lower 8 bits.
+    nodes_depth: u64,
+}
+
+impl PerftTable {
+    /// new creates a PerftTable of the given size in megabytes.
+    pub fn new(size_mb: usize) -> PerftTable {
+        let mut table = PerftTable {
+            entries: Vec::new(),
+            size_mb: 0,
+        };
+
+        table.resize(size_mb);
+        table
+    }
+
+    /// size_mb returns the size of the PerftTable in megabytes.
+    pub fn size_mb(&self) -> usize {
+        self.size_mb
+    }
+
+    /// resize resizes the PerftTable to the given size in megabytes,
+    /// clearing all of its entries.
+    pub fn resize(&mut self, size_mb: usize) {
+        let size_mb = size_mb.max(1);
+        let len = size_mb * 1024 * 1024 / mem::size_of::<Entry>();
+
+        self.entries = vec![Entry::default(); len];
+        self.size_mb = size_mb;
+    }
+
+    /// clear clears all of the entries of the PerftTable.
+    pub fn clear(&mut self) {
+        self.entries.fill(Entry::default());
+    }
+
+    /// probe returns the node count of the subtree of the given depth rooted
+    /// at the position with the given hash, if the PerftTable has it.
+    pub fn probe(&self, hash: zobrist::Hash, depth: u8) -> Option<u64> {
+        let entry = self.entries[self.index(hash)];
+        if entry.hash == u64::from(hash) && entry.nodes_depth as u8 == depth && depth > 0 {
+            Some(entry.nodes_depth >> 8)
+        } else {
+            None
+        }
+    }
+
+    /// store stores the node count of the subtree of the given depth rooted
+    /// at the position with the given hash into the PerftTable, replacing
+    /// the old entry with the same index.
+    pub fn store(&mut self, hash: zobrist::Hash, depth: u8, nodes: u64) {
+        let index = self.index(hash);
+        self.entries[index] = Entry {
+            hash: u64::from(hash),
+            nodes_depth: nodes << 8 | depth as u64,
+        };
+    }
+
+    /// index returns the index of the Entry of the given hash.
+    fn index(&self, hash: zobrist::Hash) -> usize {
+        ((u64::from(hash) as u128 * self.entries.len() as u128) >> 64) as usize
+    }
+}
@@ -0,0 +1,380 @@
+// Copyright © 2023 Rak Laptudirm <rak@laptudirm.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{error::Error, fmt, str::FromStr};
+
+use crate::chess::{Board, Color, Move, Outcome, Position};
+
+use super::MoveNode;
+
+/// Game is a chess game, made up of its tag pairs, the position it started
+/// from, and the tree of moves played from it. A Game also keeps a Board
+/// with the position at its current ply, which can be moved along the
+/// game's mainline to navigate through it.
+#[derive(Clone)]
+pub struct Game {
+    /// tags are the game's tag pairs, in the order they appeared in.
+    pub tags: Vec<(String, String)>,
+
+    /// start is the position the game started from.
+    pub start: Position,
+
+    // The nodes of the first move of the game, which are private so that
+    // changes to the move tree can drop the cached mainline.
+    moves: Vec<MoveNode>,
+
+    /// result is the result of the game.
+    pub result: GameResult,
+
+    // Navigation cursor along the mainline, and the moves of the mainline,
+    // which are collected once the game is navigated through, so that each
+    // step doesn't have to walk the move tree from its root.
+    board: Board,
+    ply: usize,
+    line: Option<Vec<Move>>,
+}
+
+impl Game {
+    /// new creates a Game without any moves from the given position.
+    pub fn new(start: Position) -> Game {
+        Game {
+            tags: Vec::new(),
+            board: Board::from(start),
+            start,
+            moves: Vec::new(),
+            result: GameResult::Unknown,
+            ply: 0,
+            line: None,
+        }
+    }
+
+    /// moves returns the nodes of the first move of the game. The first of
+    /// them is the mainline, while the rest of them are the variations to it.
+    pub fn moves(&self) -> &[MoveNode] {
+        &self.moves
+    }
+
+    /// moves_mut returns the nodes of the first move of the game, so that
+    /// its move tree can be changed.
+    pub fn moves_mut(&mut self) -> &mut Vec<MoveNode> {
+        self.line = None;
+        &mut self.moves
+    }
+
+    /// tag returns the value of the tag pair with the given name, if any.
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(tag, _)| tag == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// start_board returns a Board set up with the game's starting position.
+    pub fn start_board(&self) -> Board {
+        Board::from(self.start)
+    }
+
+    /// mainline returns an iterator over the nodes of the game's mainline.
+    pub fn mainline(&self) -> impl Iterator<Item = &MoveNode> {
+        self.moves.first().into_iter().flat_map(MoveNode::mainline)
+    }
+
+    /// mainline_moves returns the moves of the game's mainline.
+    pub fn mainline_moves(&self) -> Vec<Move> {
+        self.mainline().map(|node| node.chessmove).collect()
+    }
+
+    /// mainline_san returns the moves of the game's mainline in Standard
+    /// Algebraic Notation.
+    pub fn mainline_san(&self) -> Vec<String> {
+        let mut board = self.start_board();
+        self.mainline()
+            .map(|node| {
+                let san = board.to_san(node.chessmove);
+                board.make_move(node.chessmove);
+                san
+            })
+            .collect()
+    }
+
+    /// node returns the node at the end of the given path through the move
+    /// tree, where each element of the path is the index of the child to
+    /// go to, with zero being the mainline of the current node.
+    pub fn node(&self, path: &[usize]) -> Option<&MoveNode> {
+        let (&first, rest) = path.split_first()?;
+
+        let mut node = self.moves.get(first)?;
+        for &index in rest {
+            node = node.children.get(index)?;
+        }
+
+        Some(node)
+    }
+
+    /// final_board returns a Board with all of the moves of the game's
+    /// mainline played on it from the game's starting position.
+    pub fn final_board(&self) -> Board {
+        let mut board = self.start_board();
+        for node in self.mainline() {
+            board.make_move(node.chessmove);
+        }
+
+        board
+    }
+
+    /// push adds the given move to the end of the game's mainline. The move
+    /// needs to be legal in the game's final position.
+    pub fn push(&mut self, chessmove: Move) {
+        let mut nodes = &mut self.moves;
+        while !nodes.is_empty() {
+            nodes = &mut nodes[0].children;
+        }
+
+        nodes.push(MoveNode::new(chessmove));
+        if let Some(line) = &mut self.line {
+            line.push(chessmove);
+        }
+    }
+
+    /// len returns the number of moves in the game's mainline.
+    pub fn len(&self) -> usize {
+        self.mainline().count()
+    }
+
+    /// is_empty checks if the game doesn't have any moves.
+    pub fn is_empty(&self) -> bool {
+        self.moves.is_empty()
+    }
+}
+
+// Navigation along the game's mainline.
+impl Game {
+    /// board returns the Board with the position at the current ply.
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    /// ply returns the number of mainline moves played to reach the current
+    /// position from the game's starting position.
+    pub fn ply(&self) -> usize {
+        self.ply
+    }
+
+    /// goto_ply moves to the position after the given number of mainline
+    /// moves. It returns false and leaves the position unchanged if the
+    /// mainline is shorter than the given number of moves.
+    pub fn goto_ply(&mut self, ply: usize) -> bool {
+        if ply > self.line().len() {
+            return false;
+        }
+
+        while self.ply > ply {
+            self.back();
+        }
+
+        while self.ply < ply {
+            self.forward();
+        }
+
+        true
+    }
+
+    /// forward plays the next mainline move and returns it, or returns None
+    /// if the current position is at the end of the mainline.
+    pub fn forward(&mut self) -> Option<Move> {
+        let ply = self.ply;
+        let chessmove = *self.line().get(ply)?;
+
+        self.board.make_move(chessmove);
+        self.ply += 1;
+
+        Some(chessmove)
+    }
+
+    /// back takes back the last mainline move and returns it, or returns None
+    /// if the current position is the game's starting position.
+    pub fn back(&mut self) -> Option<Move> {
+        let chessmove = self.board.last_move()?;
+
+        self.board.undo_move();
+        self.ply -= 1;
+
+        Some(chessmove)
+    }
+
+    /// line returns the moves of the mainline, collecting them if the move
+    /// tree was changed since they were last collected.
+    fn line(&mut self) -> &[Move] {
+        let moves = &self.moves;
+        self.line.get_or_insert_with(|| {
+            moves
+                .first()
+                .into_iter()
+                .flat_map(MoveNode::mainline)
+                .map(|node| node.chessmove)
+                .collect()
+        })
+    }
+}
+
+impl From<&Board> for Game {
+    /// from creates a Game out of the moves made on the Board since it was
+    /// created. The result is only known if the game is over on the Board.
+    fn from(board: &Board) -> Self {
+        let mut start = board.clone();
+        let moves: Vec<_> = board.move_history().collect();
+        for _ in &moves {
+            start.undo_move();
+        }
+
+        let result = board
+            .clone()
+            .outcome()
+            .map_or(GameResult::Unknown, GameResult::from);
+
+        // Build the mainline from its last move to its first one.
+        let mut nodes = Vec::new();
+        for chessmove in moves.into_iter().rev() {
+            let mut node = MoveNode::new(chessmove);
+            node.children = nodes;
+            nodes = vec![node];
+        }
+
+        let mut game = Game::new(Position::from(&start));
+        game.moves = nodes;
+        game.result = result;
+        game
+    }
+}
+
+/// GameResult represents the result of a chess game.
+#[derive(Copy, Clone, PartialEq, Eq, Default)]
+#[rustfmt::skip]
+pub enum GameResult {
+    WhiteWins, BlackWins, Draw, #[default] Unknown,
+}
+
+impl GameResult {
+    /// winner returns the Color which won the game, or Color::None if the
+    /// game was drawn or its result is unknown.
+    pub fn winner(self) -> Color {
+        match self {
+            GameResult::WhiteWins => Color::White,
+            GameResult::BlackWins => Color::Black,
+            GameResult::Draw | GameResult::Unknown => Color::None,
+        }
+    }
+}
+
+impl From<Outcome> for GameResult {
+    fn from(outcome: Outcome) -> Self {
+        match outcome.winner() {
+            Color::White => GameResult::WhiteWins,
+            Color::Black => GameResult::BlackWins,
+            Color::None => GameResult::Draw,
+        }
+    }
+}
+
+impl fmt::Display for GameResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match *self {
+                GameResult::WhiteWins => "1-0",
+                GameResult::BlackWins => "0-1",
+                GameResult::Draw => "1/2-1/2",
+                GameResult::Unknown => "*",
+            }
+        )
+    }
+}
+
+#[derive(Debug)]
+pub struct GameResultParseError;
+
+impl fmt::Display for GameResultParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "results should be one of 1-0, 0-1, 1/2-1/2 or *")
+    }
+}
+
+impl Error for GameResultParseError {}
+
+impl FromStr for GameResult {
+    type Err = GameResultParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "1-0" => Ok(GameResult::WhiteWins),
+            "0-1" => Ok(GameResult::BlackWins),
+            "1/2-1/2" => Ok(GameResult::Draw),
+            "*" => Ok(GameResult::Unknown),
+            _ => Err(GameResultParseError),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::chess::variant::{self, Variant};
+
+    use super::*;
+
+    fn play(mut board: Board, moves: &[&str]) -> Board {
+        board.apply_uci_moves(moves, false).unwrap();
+        board
+    }
+
+    #[test]
+    fn result_is_the_outcome_of_the_board() {
+        let board = play(Board::startpos(), &["f2f3", "e7e5", "g2g4", "d8h4"]);
+        assert!(Game::from(&board).result == GameResult::BlackWins);
+
+        let koth =
+            Board::from_fen(variant::KingOfTheHill.start_fen(), &variant::KingOfTheHill).unwrap();
+        let board = play(
+            koth,
+            &["e2e4", "a7a6", "e1e2", "a6a5", "e2d3", "a5a4", "d3d4"],
+        );
+        assert!(Game::from(&board).result == GameResult::WhiteWins);
+
+        let board = play(Board::startpos(), &["e2e4"]);
+        assert!(Game::from(&board).result == GameResult::Unknown);
+    }
+
+    #[test]
+    fn navigation_along_the_mainline() {
+        let board = play(Board::startpos(), &["e2e4", "e7e5", "g1f3"]);
+        let mut game = Game::from(&board);
+
+        assert!(game.goto_ply(3));
+        assert!(game.board().position().hash() == board.position().hash());
+        assert!(game.forward().is_none());
+        assert!(!game.goto_ply(4));
+
+        assert_eq!(game.back(), board.last_move());
+        assert!(game.goto_ply(0));
+        assert!(game.back().is_none());
+
+        // Changes to the move tree are seen by the navigation.
+        game.moves_mut()[0].children.clear();
+        assert!(!game.goto_ply(2));
+
+        let chessmove = game.final_board().parse_uci("e7e6", false).unwrap();
+        game.push(chessmove);
+        assert!(game.goto_ply(2));
+        assert_eq!(game.board().last_move(), Some(chessmove));
+    }
+}
@@ -0,0 +1,13 @@
+// Non-namespaced modules.
+mod game;
+mod node;
+mod reader;
+mod scoresheet;
+mod writer;
+
+// Make the contents of the non-namespaced
+// modules public, so they can be accessed
+// without their parent namespace.
+pub use self::game::*;
+pub use self::node::*;
+pub use self::reader::*;
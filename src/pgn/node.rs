@@ -0,0 +1,236 @@
+// Copyright © 2023 Rak Laptudirm <rak@laptudirm.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{error::Error, fmt, str::FromStr, time::Duration};
+
+use crate::chess::Move;
+
+/// MoveNode is a move in a game's move tree, along with its annotations.
+/// The first of a node's children continues the line the node is in, while
+/// the rest of them are the recursive variations which replace that move.
+#[derive(Clone)]
+pub struct MoveNode {
+    pub chessmove: Move,
+
+    /// nags are the move's Numeric Annotation Glyphs. Move suffix annotations
+    /// like `!` and `?!` are stored as their equivalent glyphs.
+    pub nags: Vec<u8>,
+
+    /// comments_before are the comments which come before the move, which
+    /// is only possible at the start of a game or a variation.
+    pub comments_before: Vec<String>,
+
+    /// comments are the comments which come after the move, with any clock
+    /// and evaluation annotations taken out of them.
+    pub comments: Vec<String>,
+
+    /// clock is the `[%clk]` annotation of the move, which is the time left
+    /// on the clock of the player who made the move.
+    pub clock: Option<Duration>,
+
+    /// eval is the `[%eval]` annotation of the move, which is the evaluation
+    /// of the position after the move from White's point of view.
+    pub eval: Option<Eval>,
+
+    pub children: Vec<MoveNode>,
+}
+
+impl MoveNode {
+    /// new creates a MoveNode for the given move without any annotations.
+    pub fn new(chessmove: Move) -> MoveNode {
+        MoveNode {
+            chessmove,
+            nags: Vec::new(),
+            comments_before: Vec::new(),
+            comments: Vec::new(),
+            clock: None,
+            eval: None,
+            children: Vec::new(),
+        }
+    }
+
+    /// next returns the node which continues the line the node is in.
+    pub fn next(&self) -> Option<&MoveNode> {
+        self.children.first()
+    }
+
+    /// variations returns the nodes of the variations which replace the
+    /// move continuing the line the node is in.
+    pub fn variations(&self) -> &[MoveNode] {
+        self.children.get(1..).unwrap_or_default()
+    }
+
+    /// mainline returns an iterator over the node and the nodes which
+    /// continue the line it is in.
+    pub fn mainline(&self) -> impl Iterator<Item = &MoveNode> {
+        std::iter::successors(Some(self), |node| node.next())
+    }
+
+    /// add_comment adds the given comment after the move, taking any clock
+    /// and evaluation annotations out of it.
+    pub fn add_comment(&mut self, comment: &str) {
+        let mut rest = String::new();
+        let mut comment = comment;
+
+        while let Some(start) = comment.find("[%") {
+            let Some(length) = comment[start..].find(']') else {
+                break;
+            };
+
+            let command = &comment[start + 2..start + length];
+            let (name, value) = command.split_once(' ').unwrap_or((command, ""));
+
+            let parsed = match name {
+                "clk" => match parse_clock(value.trim()) {
+                    Some(clock) => {
+                        self.clock = Some(clock);
+                        true
+                    }
+                    None => false,
+                },
+                "eval" => match Eval::from_str(value.trim()) {
+                    Ok(eval) => {
+                        self.eval = Some(eval);
+                        true
+                    }
+                    Err(_) => false,
+                },
+                _ => false,
+            };
+
+            // Keep unknown or malformed commands as a part of the comment.
+            rest += &comment[..start];
+            if !parsed {
+                rest += &comment[start..start + length + 1];
+            }
+
+            comment = &comment[start + length + 1..];
+        }
+
+        rest += comment;
+
+        let rest = rest.split_whitespace().collect::<Vec<_>>().join(" ");
+        if !rest.is_empty() {
+            self.comments.push(rest);
+        }
+    }
+
+    /// annotations returns the clock and evaluation annotations of the move
+    /// as PGN comment commands, if it has any.
+    pub fn annotations(&self) -> Option<String> {
+        let mut commands = Vec::new();
+
+        if let Some(clock) = self.clock {
+            commands.push(format!("[%clk {}]", format_clock(clock)));
+        }
+
+        if let Some(eval) = self.eval {
+            commands.push(format!("[%eval {eval}]"));
+        }
+
+        if commands.is_empty() {
+            None
+        } else {
+            Some(commands.join(" "))
+        }
+    }
+}
+
+/// Eval is a position evaluation from a `[%eval]` annotation.
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub enum Eval {
+    /// Centipawns is an evaluation in hundredths of a pawn.
+    Centipawns(i32),
+
+    /// Mate is a mate in the given number of moves, which is negative if
+    /// Black is the side delivering mate.
+    Mate(i32),
+}
+
+impl fmt::Display for Eval {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Eval::Centipawns(score) => write!(f, "{:.2}", score as f64 / 100.0),
+            Eval::Mate(moves) => write!(f, "#{moves}"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct EvalParseError;
+
+impl fmt::Display for EvalParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "evaluations should be in pawns, like 0.25, or mates, like #3"
+        )
+    }
+}
+
+impl Error for EvalParseError {}
+
+impl FromStr for Eval {
+    type Err = EvalParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(moves) = s.strip_prefix('#') {
+            return moves.parse().map(Eval::Mate).map_err(|_| EvalParseError);
+        }
+
+        let pawns: f64 = s.parse().map_err(|_| EvalParseError)?;
+        if !pawns.is_finite() {
+            return Err(EvalParseError);
+        }
+
+        Ok(Eval::Centipawns((pawns * 100.0).round() as i32))
+    }
+}
+
+/// NAG_SUFFIXES contains the move suffix annotations, indexed by the value
+/// of their equivalent Numeric Annotation Glyph minus one.
+pub(crate) const NAG_SUFFIXES: [&str; 6] = ["!", "?", "!!", "??", "!?", "?!"];
+
+/// parse_clock parses a clock time of the form `h:mm:ss` or `h:mm:ss.s`.
+fn parse_clock(clock: &str) -> Option<Duration> {
+    let mut parts = clock.splitn(3, ':');
+
+    let hours: u64 = parts.next()?.parse().ok()?;
+    let minutes: u64 = parts.next()?.parse().ok()?;
+    let seconds: f64 = parts.next()?.parse().ok()?;
+
+    if !seconds.is_finite() || seconds < 0.0 {
+        return None;
+    }
+
+    Some(Duration::from_secs(hours * 3600 + minutes * 60) + Duration::from_secs_f64(seconds))
+}
+
+/// format_clock formats a clock time as `h:mm:ss`, adding tenths of a
+/// second only if the time isn't a whole number of seconds.
+fn format_clock(clock: Duration) -> String {
+    let seconds = clock.as_secs();
+    let tenths = clock.subsec_millis() / 100;
+
+    let time = format!(
+        "{}:{:02}:{:02}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    );
+    if tenths == 0 {
+        time
+    } else {
+        format!("{time}.{tenths}")
+    }
+}
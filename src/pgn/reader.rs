@@ -0,0 +1,411 @@
+// Copyright © 2023 Rak Laptudirm <rak@laptudirm.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{error::Error, fmt, str::FromStr};
+
+use crate::chess::{variant, Board, BoardParseError, STARTPOS_FEN};
+
+use super::{Game, GameResult, MoveNode, NAG_SUFFIXES};
+
+/// Reader is an iterator over the games in a PGN string. Games which fail to
+/// parse are reported as errors, after which the Reader skips to the next
+/// game in the string.
+pub struct Reader<'a> {
+    pgn: &'a str,
+}
+
+#[derive(Debug)]
+pub enum PGNParseError {
+    InvalidTag,
+    InvalidFEN(BoardParseError),
+    IllegalMove(String),
+    InvalidNAG,
+    UnexpectedCharacter(char),
+    UnterminatedComment,
+    UnterminatedVariation,
+}
+
+impl fmt::Display for PGNParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PGNParseError::InvalidTag => write!(f, "tags should look like [Name \"value\"]"),
+            PGNParseError::InvalidFEN(err) => write!(f, "invalid FEN tag: {err}"),
+            PGNParseError::IllegalMove(san) => write!(f, "illegal move {san}"),
+            PGNParseError::InvalidNAG => write!(f, "invalid numeric annotation glyph"),
+            PGNParseError::UnexpectedCharacter(ident) => {
+                write!(f, "unexpected character {ident:?}")
+            }
+            PGNParseError::UnterminatedComment => write!(f, "comment is missing its closing }}"),
+            PGNParseError::UnterminatedVariation => {
+                write!(f, "variation is missing its closing )")
+            }
+        }
+    }
+}
+
+impl Error for PGNParseError {}
+
+impl<'a> Reader<'a> {
+    /// new creates a Reader over the games in the given PGN string.
+    pub fn new(pgn: &'a str) -> Reader<'a> {
+        Reader { pgn }
+    }
+
+    /// read_game reads the next game from the PGN string.
+    fn read_game(&mut self) -> Result<Game, PGNParseError> {
+        let mut tags = Vec::new();
+        while self.pgn.starts_with('[') {
+            tags.push(self.read_tag()?);
+            self.skip_whitespace();
+        }
+
+        let fen = tags
+            .iter()
+            .find(|(name, _)| name == "FEN")
+            .map_or(STARTPOS_FEN, |(_, fen)| fen.as_str());
+
+        // Games of other variants are validated by their own rules.
+        let variant = tags
+            .iter()
+            .find(|(name, _)| name == "Variant")
+            .and_then(|(_, name)| variant::from_name(name))
+            .unwrap_or(&variant::Standard);
+
+        let mut board = Board::from_fen(fen, variant).map_err(PGNParseError::InvalidFEN)?;
+        let start = *board.position();
+
+        let mut result = None;
+        let moves = self.read_moves(&mut board, &mut result, false)?;
+
+        // Fall back to the result tag if the game termination marker is missing.
+        let result = result.unwrap_or_else(|| {
+            tags.iter()
+                .find(|(name, _)| name == "Result")
+                .and_then(|(_, result)| GameResult::from_str(result).ok())
+                .unwrap_or_default()
+        });
+
+        let mut game = Game::new(start);
+        game.tags = tags;
+        *game.moves_mut() = moves;
+        game.result = result;
+
+        Ok(game)
+    }
+
+    /// read_moves reads a line of moves played from the given Board, along
+    /// with their annotations and variations, and returns the nodes of its
+    /// first move. The line ends at a game termination marker, at the tag
+    /// pairs of the next game, or at the closing parenthesis of a variation.
+    /// The Board is left with the position it started with.
+    fn read_moves(
+        &mut self,
+        board: &mut Board,
+        result: &mut Option<GameResult>,
+        is_variation: bool,
+    ) -> Result<Vec<MoveNode>, PGNParseError> {
+        // The nodes of the line along with the variations to each of them.
+        let mut line: Vec<(MoveNode, Vec<MoveNode>)> = Vec::new();
+        let mut comments_before = Vec::new();
+
+        loop {
+            self.skip_whitespace();
+
+            let Some(ident) = self.pgn.chars().next() else {
+                if is_variation {
+                    return Err(PGNParseError::UnterminatedVariation);
+                }
+
+                break;
+            };
+
+            match ident {
+                // A tag pair starts the next game if the
+                // game termination marker is missing.
+                '[' if !is_variation => break,
+
+                '{' | ';' => {
+                    let comment = self.read_comment()?;
+                    match line.last_mut() {
+                        Some((node, _)) => node.add_comment(comment),
+                        None => comments_before.push(comment.trim().to_string()),
+                    }
+                }
+
+                '(' => {
+                    let Some((node, variations)) = line.last_mut() else {
+                        return Err(PGNParseError::UnexpectedCharacter(ident));
+                    };
+
+                    // Variations replace the last move of the line.
+                    self.pgn = &self.pgn[1..];
+                    board.undo_move();
+                    variations.extend(self.read_moves(board, result, true)?);
+                    board.make_move(node.chessmove);
+                }
+
+                ')' if is_variation => {
+                    self.pgn = &self.pgn[1..];
+                    break;
+                }
+
+                _ => {
+                    let end = self
+                        .pgn
+                        .find(|c: char| c.is_whitespace() || "[]{}();".contains(c))
+                        .unwrap_or(self.pgn.len());
+
+                    if end == 0 {
+                        return Err(PGNParseError::UnexpectedCharacter(ident));
+                    }
+
+                    let token = &self.pgn[..end];
+                    self.pgn = &self.pgn[end..];
+
+                    // Strip move numbers like "12." and "12...".
+                    let token = match token.rfind('.') {
+                        Some(index) => &token[index + 1..],
+                        None => token,
+                    };
+
+                    if token.is_empty() {
+                        continue;
+                    }
+
+                    if let Ok(termination) = GameResult::from_str(token) {
+                        // Variations can't end the game, but some
+                        // writers put a result token at their end.
+                        if !is_variation {
+                            *result = Some(termination);
+                            break;
+                        }
+
+                        continue;
+                    }
+
+                    // Split off move suffix annotations, which are stored
+                    // as their equivalent numeric annotation glyphs.
+                    let san = token.trim_end_matches(['!', '?']);
+                    let suffix = &token[san.len()..];
+
+                    let nag = if let Some(nag) = san.strip_prefix('$') {
+                        Some(nag.parse().map_err(|_| PGNParseError::InvalidNAG)?)
+                    } else if suffix.is_empty() {
+                        None
+                    } else {
+                        let index = NAG_SUFFIXES.iter().position(|&nag| nag == suffix);
+                        Some(index.ok_or(PGNParseError::InvalidNAG)? as u8 + 1)
+                    };
+
+                    if san.is_empty() || san.starts_with('$') {
+                        match line.last_mut() {
+                            Some((node, _)) => node.nags.extend(nag),
+                            None => return Err(PGNParseError::InvalidNAG),
+                        }
+
+                        continue;
+                    }
+
+                    let chessmove = board
+                        .parse_san(san)
+                        .ok_or_else(|| PGNParseError::IllegalMove(san.to_string()))?;
+
+                    let mut node = MoveNode::new(chessmove);
+                    node.nags.extend(nag);
+                    node.comments_before = std::mem::take(&mut comments_before);
+
+                    board.make_move(chessmove);
+                    line.push((node, Vec::new()));
+                }
+            }
+        }
+
+        // Restore the Board's position and build the move
+        // tree of the line from its last move to its first one.
+        let mut nodes = Vec::new();
+        for (mut node, variations) in line.into_iter().rev() {
+            board.undo_move();
+
+            node.children = nodes;
+            nodes = std::iter::once(node).chain(variations).collect();
+        }
+
+        Ok(nodes)
+    }
+
+    /// read_comment reads a brace comment or a rest of line comment, and
+    /// returns its text.
+    fn read_comment(&mut self) -> Result<&'a str, PGNParseError> {
+        let (comment, rest) = if self.pgn.starts_with('{') {
+            let end = self
+                .pgn
+                .find('}')
+                .ok_or(PGNParseError::UnterminatedComment)?;
+            (&self.pgn[1..end], &self.pgn[end + 1..])
+        } else {
+            let end = self.pgn.find('\n').unwrap_or(self.pgn.len());
+            (&self.pgn[1..end], &self.pgn[end..])
+        };
+
+        self.pgn = rest;
+        Ok(comment)
+    }
+
+    /// read_tag reads a tag pair of the form `[Name "Value"]`.
+    fn read_tag(&mut self) -> Result<(String, String), PGNParseError> {
+        let tag = self.pgn[1..].trim_start();
+
+        let name_end = tag
+            .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+            .ok_or(PGNParseError::InvalidTag)?;
+        let (name, tag) = tag.split_at(name_end);
+
+        let Some(tag) = tag.trim_start().strip_prefix('"') else {
+            return Err(PGNParseError::InvalidTag);
+        };
+
+        // Tag values may contain escaped quotes and backslashes.
+        let mut value = String::new();
+        let mut chars = tag.char_indices();
+        let value_end = loop {
+            match chars.next() {
+                Some((_, '\\')) => match chars.next() {
+                    Some((_, escaped)) => value.push(escaped),
+                    None => return Err(PGNParseError::InvalidTag),
+                },
+                Some((index, '"')) => break index,
+                Some((_, c)) => value.push(c),
+                None => return Err(PGNParseError::InvalidTag),
+            }
+        };
+
+        let Some(rest) = tag[value_end + 1..].trim_start().strip_prefix(']') else {
+            return Err(PGNParseError::InvalidTag);
+        };
+
+        if name.is_empty() {
+            return Err(PGNParseError::InvalidTag);
+        }
+
+        self.pgn = rest;
+        Ok((name.to_string(), value))
+    }
+
+    /// skip_whitespace skips whitespace and escaped lines starting with `%`.
+    fn skip_whitespace(&mut self) {
+        loop {
+            self.pgn = self.pgn.trim_start();
+
+            if !self.pgn.starts_with('%') {
+                break;
+            }
+
+            let end = self.pgn.find('\n').unwrap_or(self.pgn.len());
+            self.pgn = &self.pgn[end..];
+        }
+    }
+
+    /// skip_game skips to the start of the next game after a parse error.
+    fn skip_game(&mut self) {
+        self.pgn = match self.pgn.find("\n[") {
+            Some(index) => &self.pgn[index + 1..],
+            None => "",
+        };
+    }
+}
+
+impl Iterator for Reader<'_> {
+    type Item = Result<Game, PGNParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.skip_whitespace();
+
+        if self.pgn.is_empty() {
+            return None;
+        }
+
+        let game = self.read_game();
+        if game.is_err() {
+            self.skip_game();
+        }
+
+        Some(game)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Games in the export format, which the writer should reproduce exactly.
+    const GAMES: &str = r#"[Event "Test"]
+[Site "?"]
+[Date "????.??.??"]
+[Round "?"]
+[White "A"]
+[Black "B"]
+[Result "1-0"]
+
+{Start} 1. e4 $1 {Good move} (1. d4 d5 $2 (1... Nf6 2. c4) 2. c4) 1... e5 2. Nf3
+Nc6 $5 3. Bb5 {[%eval 0.30] Ruy} 3... a6 1-0
+
+[Event "Setup"]
+[Site "?"]
+[Date "????.??.??"]
+[Round "?"]
+[White "?"]
+[Black "?"]
+[Result "*"]
+[SetUp "1"]
+[FEN "4k3/8/8/8/8/8/4P3/4K3 w - - 0 40"]
+
+40. e4 Kd7 (40... Ke7 41. e5) 41. e5 *
+"#;
+
+    #[test]
+    fn games_round_trip_through_the_writer() {
+        let games: Vec<Game> = Reader::new(GAMES).map(Result::unwrap).collect();
+        assert_eq!(games.len(), 2);
+
+        let written: Vec<String> = games.iter().map(Game::to_string).collect();
+        assert_eq!(written.join("\n"), GAMES);
+
+        let first = games[0].moves().first().unwrap();
+        assert_eq!(first.nags, [1]);
+        assert_eq!(first.comments_before, ["Start"]);
+        assert_eq!(first.comments, ["Good move"]);
+        assert_eq!(games[0].moves().len(), 2);
+        assert!(matches!(games[0].result, GameResult::WhiteWins));
+
+        assert_eq!(games[1].start.plys(), 78);
+    }
+
+    #[test]
+    fn illegal_fen_tags_are_errors() {
+        let pgn = "[SetUp \"1\"]\n[FEN \"4k3/8/8/8/8/8/8/4K2K w - - 0 1\"]\n\n*\n";
+        assert!(matches!(
+            Reader::new(pgn).next(),
+            Some(Err(PGNParseError::InvalidFEN(
+                BoardParseError::IllegalPosition(_)
+            )))
+        ));
+
+        let pgn = "[SetUp \"1\"]\n[FEN \"4k3/8/8/8/8/8/8/4K3 x - - 0 1\"]\n\n*\n";
+        assert!(matches!(
+            Reader::new(pgn).next(),
+            Some(Err(PGNParseError::InvalidFEN(BoardParseError::InvalidFen(
+                _
+            ))))
+        ));
+    }
+}
@@ -0,0 +1,79 @@
+// Copyright © 2023 Rak Laptudirm <rak@laptudirm.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fmt::Write;
+
+use crate::chess::Color;
+
+use super::Game;
+
+/// SAN_WIDTH is the width of each move column of a scoresheet.
+const SAN_WIDTH: usize = 10;
+
+impl Game {
+    /// scoresheet formats the Game's mainline as a two column scoresheet,
+    /// with a row for each move number containing white's and black's moves
+    /// in Standard Algebraic Notation, headed by the players' names and
+    /// followed by the result.
+    pub fn scoresheet(&self) -> String {
+        let mut sheet = String::new();
+
+        // Writing to a String never fails, so the results are ignored.
+        let white = self.tag("White").unwrap_or("?");
+        let black = self.tag("Black").unwrap_or("?");
+        let _ = writeln!(sheet, "White: {white}");
+        let _ = writeln!(sheet, "Black: {black}");
+        sheet.push('\n');
+
+        let mut number = self.start.plys() as usize / 2 + 1;
+        let mut moves = self.mainline_san().into_iter();
+
+        // Games starting with black to move have no white move in the first
+        // row, which is marked with an ellipsis instead.
+        let mut row = Vec::new();
+        if self.start.side_to_move() == Color::Black {
+            row.push(String::from("..."));
+        }
+
+        let last_number = number + (row.len() + moves.len()).saturating_sub(1) / 2;
+        let number_width = last_number.to_string().len();
+
+        loop {
+            while row.len() < 2 {
+                match moves.next() {
+                    Some(san) => row.push(san),
+                    None => break,
+                }
+            }
+
+            if row.is_empty() {
+                break;
+            }
+
+            let _ = write!(sheet, "{number:>number_width$}. {:<SAN_WIDTH$}", row[0]);
+            if let Some(san) = row.get(1) {
+                let _ = write!(sheet, " {san}");
+            }
+
+            // Trim the padding of rows without a black move.
+            sheet.truncate(sheet.trim_end().len());
+            sheet.push('\n');
+
+            row.clear();
+            number += 1;
+        }
+
+        let _ = writeln!(sheet, "\nResult: {}", self.result);
+        sheet
+    }
+}
@@ -0,0 +1,201 @@
+// Copyright © 2023 Rak Laptudirm <rak@laptudirm.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fmt;
+
+use crate::chess::{Board, Color, SanStyle, FEN, STARTPOS_FEN};
+
+use super::{Game, MoveNode};
+
+/// SEVEN_TAG_ROSTER contains the tags every PGN game needs to have, in the
+/// order they need to be written in, along with their default values.
+const SEVEN_TAG_ROSTER: [(&str, &str); 7] = [
+    ("Event", "?"),
+    ("Site", "?"),
+    ("Date", "????.??.??"),
+    ("Round", "?"),
+    ("White", "?"),
+    ("Black", "?"),
+    ("Result", "*"),
+];
+
+/// LINE_WIDTH is the maximum width of a movetext line.
+const LINE_WIDTH: usize = 80;
+
+impl fmt::Display for Game {
+    /// fmt writes the Game in the PGN export format: the Seven Tag Roster
+    /// followed by the other tags, and the movetext in SAN wrapped to 80
+    /// columns and ended by the result token. The alternate flag, `{:#}`,
+    /// writes the movetext in Figurine Algebraic Notation instead.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let result = self.result.to_string();
+
+        for (name, default) in SEVEN_TAG_ROSTER {
+            let value = match name {
+                "Result" => &result,
+                _ => self.tag(name).unwrap_or(default),
+            };
+
+            write_tag(f, name, value)?;
+        }
+
+        // Games which don't start from the standard starting
+        // position need to record their starting position.
+        let fen = FEN::from(&self.start).to_string();
+        let is_setup = fen != STARTPOS_FEN;
+
+        for (name, value) in &self.tags {
+            let is_roster = SEVEN_TAG_ROSTER.iter().any(|(tag, _)| tag == name);
+            if is_roster || (is_setup && (name == "SetUp" || name == "FEN")) {
+                continue;
+            }
+
+            write_tag(f, name, value)?;
+        }
+
+        if is_setup {
+            write_tag(f, "SetUp", "1")?;
+            write_tag(f, "FEN", &fen)?;
+        }
+
+        writeln!(f)?;
+
+        let style = match f.alternate() {
+            true => SanStyle::Figurines,
+            false => SanStyle::Letters,
+        };
+
+        let mut tokens = Vec::new();
+        write_moves(&mut tokens, &mut self.start_board(), self.moves(), style);
+        tokens.push(result);
+
+        // Wrap the movetext so that no line is wider than the line width.
+        let mut width = 0;
+        let mut tokens = tokens.into_iter().peekable();
+        while let Some(mut token) = tokens.next() {
+            // Keep variation parentheses next to the moves they surround.
+            while token.ends_with('(') {
+                token += &tokens.next().unwrap_or_default();
+            }
+
+            while let Some(closing) = tokens.next_if(|token| token == ")") {
+                token += &closing;
+            }
+
+            // Figurines take up more than a byte, so the width is in chars.
+            let length = token.chars().count();
+            if width > 0 && width + 1 + length > LINE_WIDTH {
+                writeln!(f)?;
+                width = 0;
+            }
+
+            if width > 0 {
+                write!(f, " ")?;
+                width += 1;
+            }
+
+            write!(f, "{token}")?;
+            width += length;
+        }
+
+        writeln!(f)
+    }
+}
+
+/// write_tag writes a tag pair, escaping any quotes and backslashes in its value.
+fn write_tag(f: &mut fmt::Formatter<'_>, name: &str, value: &str) -> fmt::Result {
+    let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+    writeln!(f, "[{name} \"{value}\"]")
+}
+
+/// write_moves adds the movetext tokens of the given nodes, played from the
+/// given Board, to the tokens, writing the moves in the given style. The first
+/// node is written as the mainline and the rest of them as variations to it.
+/// The Board is left unchanged.
+fn write_moves(
+    tokens: &mut Vec<String>,
+    board: &mut Board,
+    mut nodes: &[MoveNode],
+    style: SanStyle,
+) {
+    // A move number is needed before Black's moves too if they follow
+    // a comment or a variation, or if they start a line.
+    let mut needs_number = true;
+    let mut played = 0;
+
+    while let Some(node) = nodes.first() {
+        for comment in &node.comments_before {
+            push_comment(tokens, comment);
+            needs_number = true;
+        }
+
+        let number = board.plys() / 2 + 1;
+        if board.side_to_move() == Color::White {
+            tokens.push(format!("{number}."));
+        } else if needs_number {
+            tokens.push(format!("{number}..."));
+        }
+
+        tokens.push(board.to_styled_san(node.chessmove, style));
+        needs_number = false;
+
+        for nag in &node.nags {
+            tokens.push(format!("${nag}"));
+        }
+
+        // Clock and evaluation annotations go at the start of the first comment.
+        let mut comments = node.comments.clone();
+        if let Some(annotations) = node.annotations() {
+            match comments.first_mut() {
+                Some(comment) => *comment = format!("{annotations} {comment}"),
+                None => comments.push(annotations),
+            }
+        }
+
+        for comment in &comments {
+            push_comment(tokens, comment);
+            needs_number = true;
+        }
+
+        for variation in &nodes[1..] {
+            tokens.push(String::from("("));
+            write_moves(tokens, board, std::slice::from_ref(variation), style);
+            tokens.push(String::from(")"));
+            needs_number = true;
+        }
+
+        board.make_move(node.chessmove);
+        played += 1;
+
+        nodes = &node.children;
+    }
+
+    for _ in 0..played {
+        board.undo_move();
+    }
+}
+
+/// push_comment splits a comment into words so that it can be wrapped, and
+/// adds them to the movetext tokens surrounded by braces.
+fn push_comment(tokens: &mut Vec<String>, comment: &str) {
+    let start = tokens.len();
+    tokens.extend(comment.split_whitespace().map(String::from));
+
+    if tokens.len() == start {
+        tokens.push(String::from("{}"));
+        return;
+    }
+
+    tokens[start].insert(0, '{');
+    tokens.last_mut().unwrap().push('}');
+}
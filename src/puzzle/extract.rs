@@ -0,0 +1,199 @@
+// Copyright © 2023 Rak Laptudirm <rak@laptudirm.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fmt;
+
+use crate::chess::{Board, Move, Position, FEN};
+use crate::engine::{Engine, SearchInfo, SearchLimits, Signals};
+use crate::pgn::Game;
+use crate::search::{Score, SearchEngine};
+
+/// PuzzleOptions are the options of a puzzle extraction run.
+#[derive(Clone)]
+pub struct PuzzleOptions {
+    /// limits are the limits of each search of a position.
+    pub limits: SearchLimits,
+
+    /// min_gap is the least amount by which the best move has to be better
+    /// than the second best move for a position to be a puzzle.
+    pub min_gap: Score,
+
+    /// max_alternative is the highest score the second best move can have,
+    /// so that positions which are won after most moves aren't puzzles.
+    pub max_alternative: Score,
+
+    /// max_solution_plys is the most plys of a puzzle's solution kept from
+    /// the principal variation of its best move.
+    pub max_solution_plys: usize,
+}
+
+impl Default for PuzzleOptions {
+    fn default() -> Self {
+        PuzzleOptions {
+            limits: SearchLimits {
+                depth: Some(10),
+                ..SearchLimits::default()
+            },
+            min_gap: 200,
+            max_alternative: 100,
+            max_solution_plys: 8,
+        }
+    }
+}
+
+/// Puzzle is a position where the best move is much better than all of the
+/// other moves, along with the line which solves it.
+#[derive(Clone)]
+pub struct Puzzle {
+    /// position is the position of the puzzle.
+    pub position: Position,
+
+    /// ply is the number of plys played in the game the puzzle was found in
+    /// before its position.
+    pub ply: usize,
+
+    /// solution is the line which solves the puzzle, starting with its best
+    /// move.
+    pub solution: Vec<Move>,
+
+    /// score and alternative are the scores of the best and the second best
+    /// moves, from the point of view of the side to move in the position.
+    pub score: Score,
+    pub alternative: Score,
+}
+
+impl Puzzle {
+    /// gap returns the amount by which the best move is better than the
+    /// second best move.
+    pub fn gap(&self) -> Score {
+        self.score - self.alternative
+    }
+
+    /// to_game returns a Game which starts from the Puzzle's position and
+    /// whose mainline is the Puzzle's solution.
+    pub fn to_game(&self) -> Game {
+        let mut game = Game::new(self.position);
+        for &chessmove in &self.solution {
+            game.push(chessmove);
+        }
+
+        game
+    }
+}
+
+impl fmt::Display for Puzzle {
+    /// fmt formats the Puzzle as its FEN, solution, and the scores of the
+    /// best and second best moves separated by bars, like
+    /// `<fen> | e2e4 e7e5 | 350 | 20`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let solution: Vec<String> = self.solution.iter().map(Move::to_string).collect();
+
+        write!(
+            f,
+            "{} | {} | {} | {}",
+            FEN::from(&self.position),
+            solution.join(" "),
+            self.score,
+            self.alternative
+        )
+    }
+}
+
+/// PuzzleFinder finds Puzzles in games by searching each of their positions
+/// twice, once for the best move and once for the best of the rest of the
+/// moves, and comparing the scores of the two.
+pub struct PuzzleFinder {
+    options: PuzzleOptions,
+    engine: SearchEngine,
+    signals: Signals,
+}
+
+impl PuzzleFinder {
+    /// new creates a PuzzleFinder with the given options.
+    pub fn new(options: PuzzleOptions) -> PuzzleFinder {
+        PuzzleFinder {
+            options,
+            engine: SearchEngine::new(),
+            signals: Signals::new(),
+        }
+    }
+
+    /// find returns the Puzzles in the positions of the given Game's
+    /// mainline, in the order they were played in.
+    pub fn find(&mut self, game: &Game) -> Vec<Puzzle> {
+        self.engine.new_game();
+
+        let mut puzzles = Vec::new();
+        let mut board = game.start_board();
+        for node in game.mainline() {
+            puzzles.extend(self.analyze(&board));
+            board.make_move(node.chessmove);
+        }
+
+        puzzles.extend(self.analyze(&board));
+        puzzles
+    }
+
+    /// analyze returns the Puzzle in the given position, if it is one.
+    /// Positions with less than two legal moves are never Puzzles.
+    pub fn analyze(&mut self, board: &Board) -> Option<Puzzle> {
+        let moves = board.clone().generate_legal_moves();
+        if moves.len() < 2 {
+            return None;
+        }
+
+        let best = self.search(board, self.options.limits)?;
+        let best_move = *best.pv.first()?;
+
+        // Search the position again without the best move for the score of
+        // the second best move.
+        let mut limits = self.options.limits;
+        limits.search_moves = moves;
+        limits
+            .search_moves
+            .retain(|&chessmove| chessmove != best_move);
+        let alternative = self.search(board, limits)?;
+
+        if alternative.score > self.options.max_alternative
+            || best.score - alternative.score < self.options.min_gap
+        {
+            return None;
+        }
+
+        let mut solution = best.pv;
+        solution.truncate(self.options.max_solution_plys.max(1));
+
+        Some(Puzzle {
+            position: *board.position(),
+            ply: board.plys() as usize,
+            solution,
+            score: best.score,
+            alternative: alternative.score,
+        })
+    }
+
+    /// search searches the given position within the given limits, and
+    /// returns the information of its last completed iteration.
+    fn search(&mut self, board: &Board, limits: SearchLimits) -> Option<SearchInfo> {
+        let mut last = None;
+        self.signals.reset(false);
+        self.engine.search(
+            board.clone(),
+            limits,
+            &self.signals,
+            &mut |info: &SearchInfo| last = Some(info.clone()),
+        );
+
+        last
+    }
+}
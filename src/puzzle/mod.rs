@@ -0,0 +1,7 @@
+// Non-namespaced modules.
+mod extract;
+
+// Make the contents of the non-namespaced
+// modules public, so they can be accessed
+// without their parent namespace.
+pub use self::extract::*;
@@ -0,0 +1,76 @@
+// Copyright © 2023 Rak Laptudirm <rak@laptudirm.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::chess::{castling, Board, ColoredPiece, MoveFlag, Piece, Square};
+
+// Board diagram formats.
+pub mod svg;
+
+#[cfg(feature = "png")]
+pub mod png;
+
+/// RenderOptions configures how a board diagram is rendered.
+#[derive(Copy, Clone)]
+pub struct RenderOptions {
+    /// square_size is the size of each square in pixels.
+    pub square_size: u32,
+
+    /// flipped renders the board from black's perspective.
+    pub flipped: bool,
+
+    /// highlight_last_move highlights the squares of the last move.
+    pub highlight_last_move: bool,
+
+    /// highlight_check highlights the king of the side to move if it is
+    /// in check.
+    pub highlight_check: bool,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        RenderOptions {
+            square_size: 45,
+            flipped: false,
+            highlight_last_move: true,
+            highlight_check: true,
+        }
+    }
+}
+
+/// last_move_squares returns the squares highlighted for the last move made
+/// on the Board, if any. Castling moves highlight the king's source and
+/// target squares instead of the rook's square.
+fn last_move_squares(board: &Board) -> Option<(Square, Square)> {
+    let chessmove = board.last_move()?;
+
+    let target = match chessmove.flags() {
+        MoveFlag::Castle => {
+            let side = castling::SideColor::from_sqs(chessmove.source(), chessmove.target());
+            side.get_targets().0
+        }
+        _ => chessmove.target(),
+    };
+
+    Some((chessmove.source(), target))
+}
+
+/// checked_king returns the square of the side to move's king if it is in
+/// check on the Board.
+fn checked_king(board: &Board) -> Option<Square> {
+    if !board.is_check() {
+        return None;
+    }
+
+    let king = ColoredPiece::new(Piece::King, board.side_to_move());
+    Some(board.position().colored_piece_bb(king).lsb())
+}
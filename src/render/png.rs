@@ -0,0 +1,39 @@
+// Copyright © 2023 Rak Laptudirm <rak@laptudirm.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::io;
+
+use resvg::{tiny_skia, usvg};
+
+use crate::chess::Board;
+
+use super::{svg, RenderOptions};
+
+/// render renders the position on the Board as a PNG image, by rasterizing
+/// its SVG diagram.
+pub fn render(board: &Board, options: &RenderOptions) -> io::Result<Vec<u8>> {
+    let invalid_data = |err: String| io::Error::new(io::ErrorKind::InvalidData, err);
+
+    let svg = svg::render(board, options);
+    let tree = usvg::Tree::from_str(&svg, &usvg::Options::default())
+        .map_err(|err| invalid_data(err.to_string()))?;
+
+    let size = options.square_size * 8;
+    let mut pixmap = tiny_skia::Pixmap::new(size, size)
+        .ok_or_else(|| invalid_data(String::from("invalid image size")))?;
+
+    resvg::render(&tree, tiny_skia::Transform::default(), &mut pixmap.as_mut());
+    pixmap
+        .encode_png()
+        .map_err(|err| invalid_data(err.to_string()))
+}
@@ -0,0 +1,139 @@
+// Copyright © 2023 Rak Laptudirm <rak@laptudirm.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fmt::Write;
+
+use crate::chess::{Board, Color, ColoredPiece, Piece, Square};
+
+use super::{checked_king, last_move_squares, RenderOptions};
+
+// Colors of the diagram's squares and highlights.
+const LIGHT_SQUARE: &str = "#f0d9b5";
+const DARK_SQUARE: &str = "#b58863";
+const LAST_MOVE: &str = "#cdd26a";
+const CHECK: &str = "#e04040";
+
+/// PIECE_SIZE is the size of the box the piece shapes are drawn in, which
+/// is scaled to the size of a square.
+const PIECE_SIZE: f64 = 45.0;
+
+/// PIECE_SHAPES contains the SVG path data of each Piece, drawn inside a
+/// PIECE_SIZE sized box, indexed by the Piece. All pieces share a base.
+#[rustfmt::skip]
+const PIECE_SHAPES: [&str; Piece::N] = [
+    // Pawn.
+    "M22.5 8a5 5 0 1 1 0 10a5 5 0 1 1 0-10z\
+     M22.5 18C18 22 16 28 14 32H31C29 28 27 22 22.5 18z",
+    // Knight.
+    "M14 32C14 26 18 23 21 21C17 22 13 24 11 22C9 19 13 14 17 12L18 8L21 11\
+     C27 10 33 15 33 24C33 28 31 30 31 32z",
+    // Bishop.
+    "M22.5 4a2.5 2.5 0 1 1 0 5a2.5 2.5 0 1 1 0-5z\
+     M22.5 9C15 14 14 22 16 27L14 32H31L29 27C31 22 30 14 22.5 9z",
+    // Rook.
+    "M12 32V29H15V17H12V10H16V13H20V10H25V13H29V10H33V17H30V29H33V32z",
+    // Queen.
+    "M10 32L8 14L14 25L15 10L20 24L22.5 8L25 24L30 10L31 25L37 14L35 32z",
+    // King.
+    "M20.5 4H24.5V7H27.5V11H24.5V15H20.5V11H17.5V7H20.5z\
+     M11 32C6 24 10 16 22.5 19C35 16 39 24 34 32z",
+];
+
+/// PIECE_BASE is the SVG path data of the base shared by all pieces.
+const PIECE_BASE: &str = "M9.5 32H35.5V37H9.5z";
+
+/// render renders the position on the Board as an SVG diagram.
+pub fn render(board: &Board, options: &RenderOptions) -> String {
+    let size = options.square_size * 8;
+    let mut svg = String::new();
+
+    // Writing to a String never fails, so the results are ignored.
+    let _ = write!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{size}" height="{size}" viewBox="0 0 {size} {size}">"#
+    );
+
+    let last_move = last_move_squares(board).filter(|_| options.highlight_last_move);
+    let checked_king = checked_king(board).filter(|_| options.highlight_check);
+
+    for index in 0..Square::N {
+        let square = Square::from(index);
+        let (x, y) = square_origin(square, options);
+
+        let color = if (square.file() as usize + square.rank() as usize).is_multiple_of(2) {
+            LIGHT_SQUARE
+        } else {
+            DARK_SQUARE
+        };
+
+        let side = options.square_size;
+        let _ = write!(
+            svg,
+            r#"<rect x="{x}" y="{y}" width="{side}" height="{side}" fill="{color}"/>"#
+        );
+
+        if let Some((source, target)) = last_move {
+            if square == source || square == target {
+                let _ = write!(
+                    svg,
+                    r#"<rect x="{x}" y="{y}" width="{side}" height="{side}" fill="{LAST_MOVE}" fill-opacity="0.8"/>"#
+                );
+            }
+        }
+
+        if checked_king == Some(square) {
+            let radius = side as f64 / 2.0;
+            let _ = write!(
+                svg,
+                r#"<circle cx="{}" cy="{}" r="{radius}" fill="{CHECK}" fill-opacity="0.7"/>"#,
+                x as f64 + radius,
+                y as f64 + radius
+            );
+        }
+
+        let piece = board.piece_at(square);
+        if piece != ColoredPiece::None {
+            write_piece(&mut svg, piece, x, y, options);
+        }
+    }
+
+    svg.push_str("</svg>");
+    svg
+}
+
+/// write_piece writes the given piece at the given origin to the diagram.
+fn write_piece(svg: &mut String, piece: ColoredPiece, x: u32, y: u32, options: &RenderOptions) {
+    let (fill, stroke) = match piece.color() {
+        Color::White => ("#ffffff", "#000000"),
+        _ => ("#000000", "#ffffff"),
+    };
+
+    let scale = options.square_size as f64 / PIECE_SIZE;
+    let _ = write!(
+        svg,
+        r#"<g transform="translate({x} {y}) scale({scale})" fill="{fill}" stroke="{stroke}" stroke-width="1.5" stroke-linejoin="round"><path d="{}"/><path d="{PIECE_BASE}"/></g>"#,
+        PIECE_SHAPES[piece.piece() as usize]
+    );
+}
+
+/// square_origin returns the coordinates of the top left corner of the given
+/// square in the diagram.
+fn square_origin(square: Square, options: &RenderOptions) -> (u32, u32) {
+    let (mut column, mut row) = (square.file() as u32, square.rank() as u32);
+    if options.flipped {
+        column = 7 - column;
+        row = 7 - row;
+    }
+
+    (column * options.square_size, row * options.square_size)
+}
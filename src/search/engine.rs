@@ -0,0 +1,204 @@
+// Copyright © 2023 Rak Laptudirm <rak@laptudirm.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+
+use crate::chess::{Board, Move};
+use crate::engine::{BestMove, Engine, InfoListener, SearchLimits, Signals};
+use crate::eval::nnue::Network;
+use crate::eval::{kpk, Evaluator};
+
+use crate::uci::{OptionError, Options};
+
+use super::{History, Score, SearchParams, Searcher, TranspositionTable};
+
+/// SearchEngine is an Engine which picks its moves with an iterative
+/// deepening alpha-beta Searcher.
+pub struct SearchEngine {
+    options: Options,
+
+    // The path of the network file used by the evaluator, which is empty if
+    // it uses the classical evaluation.
+    eval_file: String,
+
+    tt: TranspositionTable,
+    evaluator: Evaluator,
+    history: History,
+
+    // The state of the xorshift generator of the seeds of the root noise.
+    rng: u64,
+}
+
+/// DEFAULT_HASH is the default size of the transposition table in megabytes.
+const DEFAULT_HASH: usize = 16;
+
+impl SearchEngine {
+    /// new creates a new SearchEngine.
+    pub fn new() -> SearchEngine {
+        let mut options = Options::new();
+        options.add_spin("Hash", DEFAULT_HASH as i64, 1, 65536);
+        options.add_string("EvalFile", "");
+        options.add_spin("RootNoise", 0, 0, 200);
+        SearchParams::add_options(&mut options);
+
+        SearchEngine {
+            options,
+            eval_file: String::new(),
+            tt: TranspositionTable::new(DEFAULT_HASH),
+            evaluator: Evaluator::new(),
+            history: History::new(),
+
+            // The standard library's hashers are randomly keyed, which makes
+            // for a seed that differs between runs. Xorshift generators can't
+            // be seeded with zero.
+            rng: RandomState::new().build_hasher().finish() | 1,
+        }
+    }
+
+    /// random returns the next number from the xorshift generator.
+    fn random(&mut self) -> u64 {
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 7;
+        self.rng ^= self.rng << 17;
+        self.rng
+    }
+
+    /// update_network loads the NNUE network if its file option was changed,
+    /// falling back to the classical evaluation if it is empty or can't be
+    /// loaded, in which case the error is returned.
+    fn update_network(&mut self) -> Result<(), OptionError> {
+        let eval_file = self
+            .options
+            .string("EvalFile")
+            .unwrap_or_default()
+            .to_string();
+        if eval_file == self.eval_file {
+            return Ok(());
+        }
+
+        let (network, result) = match eval_file.as_str() {
+            "" | "<empty>" => (None, Ok(())),
+            path => match Network::load(path) {
+                Ok(network) => (Some(network), Ok(())),
+                Err(err) => (
+                    None,
+                    Err(OptionError::Unusable(path.to_string(), err.to_string())),
+                ),
+            },
+        };
+
+        self.evaluator.set_network(network);
+        self.eval_file = eval_file;
+
+        result
+    }
+}
+
+impl Default for SearchEngine {
+    fn default() -> Self {
+        SearchEngine::new()
+    }
+}
+
+impl Engine for SearchEngine {
+    fn name(&self) -> String {
+        String::from("mess")
+    }
+
+    fn author(&self) -> String {
+        String::from("Rak Laptudirm")
+    }
+
+    fn options(&mut self) -> Option<&mut Options> {
+        Some(&mut self.options)
+    }
+
+    /// set_option loads the NNUE network as soon as its file is set, so
+    /// that a file which can't be loaded is reported to the GUI.
+    fn set_option(&mut self, args: &[&str]) -> Result<(), OptionError> {
+        self.options.set_option(args)?;
+        self.update_network()
+    }
+
+    fn new_game(&mut self) {
+        self.tt.clear();
+        self.history.clear();
+    }
+
+    fn evaluate(&mut self, board: &Board) -> Option<String> {
+        // A network which fails to load was already reported by set_option.
+        let _ = self.update_network();
+
+        // Only the classical evaluation can be broken down into its terms,
+        // so it is shown even when the network is used instead.
+        let mut report = self.evaluator.params().trace(board).to_string();
+
+        let source = if kpk::evaluate(board).is_some() {
+            "KPK bitbase"
+        } else if self.evaluator.network().is_some() {
+            "NNUE"
+        } else {
+            "classical"
+        };
+
+        self.evaluator.reset(board);
+        let score = self.evaluator.evaluate(board);
+        report += &format!("\nFinal evaluation: {score} ({source}, side to move)");
+
+        Some(report)
+    }
+
+    fn search(
+        &mut self,
+        board: Board,
+        limits: SearchLimits,
+        signals: &Signals,
+        listener: &mut dyn InfoListener,
+    ) -> BestMove {
+        // Resize the transposition table if its size option was changed.
+        let hash = self.options.spin("Hash").unwrap_or_default() as usize;
+        if hash != self.tt.size_mb() {
+            self.tt.resize(hash);
+        }
+
+        // A network which fails to load was already reported by set_option.
+        let _ = self.update_network();
+
+        self.tt.new_search();
+        self.history.age();
+
+        // Noise in the scores of the root moves varies the moves picked
+        // among ones with similar scores, so that games don't repeat.
+        let noise = self.options.spin("RootNoise").unwrap_or_default() as Score;
+        let seed = self.random();
+
+        let mut searcher = Searcher::new(
+            board,
+            limits,
+            SearchParams::from_options(&self.options),
+            signals,
+            &mut self.tt,
+            &mut self.evaluator,
+            &mut self.history,
+        );
+        searcher.set_root_noise(noise, seed);
+
+        let result = searcher.iterative_deepening(listener);
+
+        BestMove {
+            chessmove: result.best_move,
+            ponder: result.pv.get(1).copied().unwrap_or(Move::NULL),
+        }
+    }
+}
@@ -0,0 +1,117 @@
+// Copyright © 2023 Rak Laptudirm <rak@laptudirm.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::chess::{Color, Move, Square};
+
+use super::MAX_PLY;
+
+/// Killers stores the killer moves of each ply, which are the quiet moves
+/// which last caused a beta cutoff at that ply. Since positions at the same
+/// ply are often similar, these moves are likely to cause cutoffs again.
+pub struct Killers {
+    moves: [[Move; Killers::N]; MAX_PLY],
+}
+
+impl Killers {
+    /// N is the number of killer moves stored for each ply.
+    pub const N: usize = 2;
+
+    /// new creates an empty Killers table.
+    pub fn new() -> Killers {
+        Killers {
+            moves: [[Move::NULL; Killers::N]; MAX_PLY],
+        }
+    }
+
+    /// clear removes all of the killer moves.
+    pub fn clear(&mut self) {
+        *self = Killers::new();
+    }
+
+    /// get returns the killer moves of the given ply, from the most recent
+    /// to the least recent. Empty slots contain Move::NULL.
+    pub fn get(&self, ply: usize) -> [Move; Killers::N] {
+        self.moves[ply]
+    }
+
+    /// store adds the given move as the most recent killer move of the given
+    /// ply, replacing the least recent one.
+    pub fn store(&mut self, ply: usize, chessmove: Move) {
+        let killers = &mut self.moves[ply];
+        if killers[0] != chessmove {
+            killers.rotate_right(1);
+            killers[0] = chessmove;
+        }
+    }
+}
+
+impl Default for Killers {
+    fn default() -> Self {
+        Killers::new()
+    }
+}
+
+/// History is a butterfly history table, which scores quiet moves by their
+/// source and target squares according to how often they caused beta
+/// cutoffs in the past, for each side to move.
+pub struct History {
+    table: Box<[[[i32; Square::N]; Square::N]; Color::N]>,
+}
+
+impl History {
+    /// MAX is the maximum absolute value of a history score.
+    pub const MAX: i32 = 16384;
+
+    /// new creates an empty History table.
+    pub fn new() -> History {
+        History {
+            table: Box::new([[[0; Square::N]; Square::N]; Color::N]),
+        }
+    }
+
+    /// clear resets all of the history scores to zero.
+    pub fn clear(&mut self) {
+        *self.table = [[[0; Square::N]; Square::N]; Color::N];
+    }
+
+    /// age halves all of the history scores, so that the scores from older
+    /// searches matter less than the scores from newer ones.
+    pub fn age(&mut self) {
+        for score in self.table.iter_mut().flatten().flatten() {
+            *score /= 2;
+        }
+    }
+
+    /// get returns the history score of the given move by the given side.
+    pub fn get(&self, side: Color, chessmove: Move) -> i32 {
+        self.table[side as usize][chessmove.source() as usize][chessmove.target() as usize]
+    }
+
+    /// update rewards the given move by the given side if it caused a beta
+    /// cutoff at the given depth, and penalizes it otherwise. Scores close
+    /// to the maximum change more slowly, so that they stay within bounds.
+    pub fn update(&mut self, side: Color, chessmove: Move, depth: u8, is_cutoff: bool) {
+        let bonus = (depth as i32 * depth as i32).min(History::MAX);
+        let bonus = if is_cutoff { bonus } else { -bonus };
+
+        let score = &mut self.table[side as usize][chessmove.source() as usize]
+            [chessmove.target() as usize];
+        *score += bonus - *score * bonus.abs() / History::MAX;
+    }
+}
+
+impl Default for History {
+    fn default() -> Self {
+        History::new()
+    }
+}
@@ -0,0 +1,22 @@
+// Namespaced modules.
+pub mod timeman;
+
+// Non-namespaced modules.
+mod engine;
+mod heuristics;
+mod ordering;
+mod params;
+mod score;
+mod searcher;
+mod tt;
+
+// Make the contents of the non-namespaced
+// modules public, so they can be accessed
+// without their parent namespace.
+pub use self::engine::*;
+pub use self::heuristics::*;
+pub use self::ordering::*;
+pub use self::params::*;
+pub use self::score::*;
+pub use self::searcher::*;
+pub use self::tt::*;
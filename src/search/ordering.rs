@@ -0,0 +1,85 @@
+// Copyright © 2023 Rak Laptudirm <rak@laptudirm.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::chess::{Board, Move, MoveFlag, Piece, MAX_MOVES};
+
+/// is_noisy checks if the given move on the Board is a noisy move, which is
+/// either a capture or a queen promotion.
+pub fn is_noisy(board: &Board, chessmove: Move) -> bool {
+    board.is_capture(chessmove) || (chessmove.is_promotion() && chessmove.promot() == Piece::Queen)
+}
+
+/// mvv_lva scores the given noisy move on the Board by the Most Valuable
+/// Victim - Least Valuable Attacker heuristic: captures of more valuable
+/// pieces are scored higher, and among captures of the same piece, the ones
+/// by less valuable pieces are scored higher. Promotions count as captures
+/// of the piece being promoted to.
+pub fn mvv_lva(board: &Board, chessmove: Move) -> i32 {
+    let victim = match chessmove.flags() {
+        MoveFlag::EnPassant => Piece::Pawn,
+        MoveFlag::Promotion => chessmove.promot(),
+        _ => board.piece_at(chessmove.target()).piece(),
+    };
+
+    let attacker = board.piece_at(chessmove.source()).piece();
+
+    // Promotions which are also captures gain the captured piece too.
+    let captured = match chessmove.flags() {
+        MoveFlag::Promotion => board.piece_at(chessmove.target()).piece(),
+        _ => Piece::None,
+    };
+
+    victim_score(victim) + victim_score(captured) - attacker as i32
+}
+
+/// order_captures sorts the given noisy moves on the Board by their
+/// MVV-LVA scores, from the highest scored to the lowest scored.
+pub fn order_captures(board: &Board, moves: &mut [Move]) {
+    sort_moves(moves, |chessmove| mvv_lva(board, chessmove));
+}
+
+/// sort_moves sorts the given moves by the scores given to them by the given
+/// function, from the highest scored to the lowest scored, keeping the order
+/// of moves with the same score. Each move is scored once, and the sort is
+/// done in place so that ordering moves doesn't allocate. There can't be more
+/// than MAX_MOVES moves.
+pub fn sort_moves(moves: &mut [Move], mut score: impl FnMut(Move) -> i32) {
+    let mut scores = [0; MAX_MOVES];
+    for (index, &chessmove) in moves.iter().enumerate() {
+        scores[index] = score(chessmove);
+    }
+
+    // Insertion sort is fast for lists as short as move lists.
+    for index in 1..moves.len() {
+        let (chessmove, move_score) = (moves[index], scores[index]);
+
+        let mut hole = index;
+        while hole > 0 && scores[hole - 1] < move_score {
+            moves[hole] = moves[hole - 1];
+            scores[hole] = scores[hole - 1];
+            hole -= 1;
+        }
+
+        moves[hole] = chessmove;
+        scores[hole] = move_score;
+    }
+}
+
+/// victim_score returns the part of the MVV-LVA score of capturing the
+/// given piece, which is zero if no piece is captured.
+fn victim_score(victim: Piece) -> i32 {
+    match victim {
+        Piece::None => 0,
+        _ => (victim as i32 + 1) * Piece::N as i32,
+    }
+}
@@ -0,0 +1,66 @@
+// Copyright © 2023 Rak Laptudirm <rak@laptudirm.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::uci::Options;
+
+use super::Score;
+
+/// SearchParams contains the tunable parameters of the search. The search
+/// uses SearchParams::DEFAULT unless it is given other SearchParams, like
+/// the ones set through the engine's options while tuning.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct SearchParams {
+    /// qs_delta_margin is the margin of delta pruning in the quiescence
+    /// search. Captures which can't raise alpha even after winning the
+    /// captured piece for free and gaining this much more are skipped.
+    pub qs_delta_margin: Score,
+
+    /// qs_see_margin is the margin of SEE pruning in the quiescence search.
+    /// Captures which lose more than this much material in the exchange
+    /// are skipped.
+    pub qs_see_margin: Score,
+}
+
+impl SearchParams {
+    /// DEFAULT contains the default values of the search parameters.
+    pub const DEFAULT: SearchParams = SearchParams {
+        qs_delta_margin: 200,
+        qs_see_margin: 0,
+    };
+
+    /// add_options adds a spin option for each of the search parameters to
+    /// the given Options, with the parameter's default value.
+    pub fn add_options(options: &mut Options) {
+        let params = SearchParams::DEFAULT;
+        options.add_spin("QSDeltaMargin", params.qs_delta_margin as i64, 0, 2000);
+        options.add_spin("QSSeeMargin", params.qs_see_margin as i64, -1000, 1000);
+    }
+
+    /// from_options returns the SearchParams set in the given Options, with
+    /// the default values of the parameters which aren't in them.
+    pub fn from_options(options: &Options) -> SearchParams {
+        let params = SearchParams::DEFAULT;
+        let get = |name, default: Score| options.spin(name).map_or(default, |value| value as Score);
+
+        SearchParams {
+            qs_delta_margin: get("QSDeltaMargin", params.qs_delta_margin),
+            qs_see_margin: get("QSSeeMargin", params.qs_see_margin),
+        }
+    }
+}
+
+impl Default for SearchParams {
+    fn default() -> Self {
+        SearchParams::DEFAULT
+    }
+}
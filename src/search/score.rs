@@ -0,0 +1,59 @@
+// Copyright © 2023 Rak Laptudirm <rak@laptudirm.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Score is the score of a position in centipawns, from the point of view
+/// of the side to move in it. Scores close to MATE represent forced mates.
+pub type Score = i32;
+
+/// INFINITY is a score greater than any score a position can have.
+pub const INFINITY: Score = 32000;
+
+/// MATE is the score of delivering checkmate on the board. Mates which are
+/// further away have scores closer to zero, so that shorter mates are
+/// preferred over longer ones.
+pub const MATE: Score = 31000;
+
+/// MAX_PLY is the maximum number of plys a search can go from its root.
+pub const MAX_PLY: usize = 128;
+
+/// DRAW is the score of a drawn position.
+pub const DRAW: Score = 0;
+
+/// mated_in returns the score of being checkmated after the given number of
+/// plys from the root of the search.
+pub fn mated_in(ply: usize) -> Score {
+    -MATE + ply as Score
+}
+
+/// mate_in returns the score of delivering checkmate after the given number
+/// of plys from the root of the search.
+pub fn mate_in(ply: usize) -> Score {
+    MATE - ply as Score
+}
+
+/// is_mate checks if the given score represents a forced mate, either for
+/// or against the side to move.
+pub fn is_mate(score: Score) -> bool {
+    score.abs() >= MATE - MAX_PLY as Score
+}
+
+/// mate_moves returns the number of moves until the mate represented by the
+/// given score is delivered, which is negative if the side to move is the
+/// one getting mated.
+pub fn mate_moves(score: Score) -> i32 {
+    if score > 0 {
+        (MATE - score + 1) / 2
+    } else {
+        -(MATE + score) / 2
+    }
+}
@@ -0,0 +1,777 @@
+// Copyright © 2023 Rak Laptudirm <rak@laptudirm.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::time::Duration;
+
+use crate::chess::{Board, Move, MoveFlag, MoveList, Outcome, Piece};
+use crate::engine::{InfoListener, SearchInfo, SearchLimits, Signals};
+use crate::eval::{kpk, Evaluator};
+use crate::util::array_vec::ArrayVec;
+
+use super::timeman::TimeManager;
+use super::{
+    is_mate, is_noisy, mate_in, mate_moves, mated_in, mvv_lva, order_captures, score_from_tt,
+    score_to_tt, sort_moves, Bound, History, Killers, Score, SearchParams, TranspositionTable,
+    DRAW, INFINITY, MATE, MAX_PLY,
+};
+
+/// Line is a line of moves found by the search, like a principal variation.
+/// Lines are never longer than the maximum depth of the search, so they are
+/// stored inline, and the search doesn't allocate at any of its nodes.
+pub type Line = ArrayVec<Move, MAX_PLY>;
+
+/// Searcher is a negamax alpha-beta searcher of the position on a Board,
+/// which stays within the given SearchLimits and listens to the Signals
+/// sent to it while it is searching.
+pub struct Searcher<'a> {
+    board: Board,
+
+    limits: SearchLimits,
+    params: SearchParams,
+    signals: &'a Signals,
+    tt: &'a mut TranspositionTable,
+    evaluator: &'a mut Evaluator,
+
+    // Move ordering heuristics. The history is kept between searches.
+    killers: Killers,
+    history: &'a mut History,
+
+    timeman: TimeManager,
+
+    // The best move found at the root by the last search.
+    root_best: Move,
+
+    // The amplitude of the random noise added to the scores of the root
+    // moves, and the seed it is generated from.
+    root_noise: Score,
+    noise_seed: u64,
+
+    nodes: u64,
+    seldepth: usize,
+    tbhits: u64,
+    stopped: bool,
+}
+
+/// SearchResult is the result of searching a position to a fixed depth.
+#[derive(Clone)]
+pub struct SearchResult {
+    /// best_move is the best move found in the position, or Move::NULL if
+    /// there are no legal moves in it.
+    pub best_move: Move,
+
+    /// score is the score of the position with the best move played.
+    pub score: Score,
+
+    /// pv is the principal variation, which is the line of best play for
+    /// both sides found by the search, starting with the best move.
+    pub pv: Line,
+}
+
+/// CHECK_INTERVAL is the number of nodes searched between checks of whether
+/// the search needs to stop, since the checks are relatively expensive.
+const CHECK_INTERVAL: u64 = 2048;
+
+/// CURRMOVE_DELAY is the time after which the moves being searched at the
+/// root are reported, since they are too many to be useful before that.
+const CURRMOVE_DELAY: Duration = Duration::from_secs(1);
+
+// Move ordering scores of the different kinds of moves.
+const TT_MOVE_SCORE: i32 = 2_000_000;
+const NOISY_SCORE: i32 = 1_000_000;
+const KILLER_SCORE: i32 = 900_000;
+
+impl<'a> Searcher<'a> {
+    /// new creates a Searcher for the position on the given Board with the
+    /// given SearchParams, which uses the given TranspositionTable to store
+    /// its search results, the given Evaluator to evaluate positions, and the
+    /// given History to order its quiet moves.
+    pub fn new(
+        board: Board,
+        limits: SearchLimits,
+        params: SearchParams,
+        signals: &'a Signals,
+        tt: &'a mut TranspositionTable,
+        evaluator: &'a mut Evaluator,
+        history: &'a mut History,
+    ) -> Searcher<'a> {
+        let timeman = TimeManager::new(&limits, board.side_to_move());
+        evaluator.reset(&board);
+
+        Searcher {
+            board,
+            limits,
+            params,
+            signals,
+            tt,
+            evaluator,
+            killers: Killers::new(),
+            history,
+            timeman,
+            root_best: Move::NULL,
+            root_noise: 0,
+            noise_seed: 0,
+            nodes: 0,
+            seldepth: 0,
+            tbhits: 0,
+            stopped: false,
+        }
+    }
+
+    /// set_root_noise makes the Searcher add random noise of at most the
+    /// given amplitude to the scores of the root moves when picking the best
+    /// one, so that it doesn't always pick the same move among ones with
+    /// similar scores. The noise of each move is derived from the given seed
+    /// and stays the same across iterations. Mate scores are never noised.
+    pub fn set_root_noise(&mut self, amplitude: Score, seed: u64) {
+        self.root_noise = amplitude.max(0);
+        self.noise_seed = seed;
+    }
+
+    /// nodes returns the number of nodes searched by the Searcher so far.
+    pub fn nodes(&self) -> u64 {
+        self.nodes
+    }
+
+    /// is_stopped checks if the Searcher has stopped searching, either
+    /// because it was signaled to stop or because it ran out of its limits.
+    /// Searches which are stopped return the best result found until then.
+    pub fn is_stopped(&self) -> bool {
+        self.stopped
+    }
+
+    /// iterative_deepening searches the position to increasing depths until
+    /// it reaches the depth limit or is stopped, and returns the result of
+    /// the last completed search. The SearchInfo of each completed search is
+    /// reported to the given InfoListener.
+    pub fn iterative_deepening(&mut self, listener: &mut dyn InfoListener) -> SearchResult {
+        let max_depth = self.limits.depth.map_or(MAX_PLY, |depth| depth as usize);
+        let max_depth = max_depth.clamp(1, MAX_PLY - 1) as u8;
+
+        let mut result: Option<SearchResult> = None;
+        for depth in 1..=max_depth {
+            self.seldepth = 0;
+            let current = self.search(depth, listener);
+
+            // An incomplete search hasn't searched all of the moves, so its
+            // result can't be trusted over the last complete one, if any.
+            if self.stopped && result.is_some() {
+                break;
+            }
+
+            listener.info(&SearchInfo {
+                depth,
+                seldepth: self.seldepth as u8,
+                score: current.score,
+                nodes: self.nodes,
+                time: self.signals.elapsed(),
+                hashfull: self.tt.hashfull(),
+                tbhits: self.tbhits,
+                pv: current.pv.to_vec(),
+            });
+
+            self.timeman.update(current.best_move);
+
+            // Starting another iteration is pointless if it is unlikely to
+            // finish before the search runs out of time.
+            let is_out_of_time = !self.signals.is_pondering()
+                && self
+                    .timeman
+                    .soft_deadline()
+                    .is_some_and(|deadline| self.signals.elapsed() >= deadline);
+
+            // Searches for mates are over once they have found one which is
+            // short enough.
+            let is_mate_found = self.limits.mate.is_some_and(|moves| {
+                is_mate(current.score) && (1..=moves as i32).contains(&mate_moves(current.score))
+            });
+
+            let is_over =
+                self.stopped || is_out_of_time || is_mate_found || current.best_move == Move::NULL;
+            result = Some(current);
+
+            if is_over {
+                break;
+            }
+        }
+
+        result.expect("iterative deepening: no searches completed")
+    }
+
+    /// search searches the position to the given depth and returns the best
+    /// move found in it along with its score. If the search is stopped, the
+    /// best move out of the moves which were fully searched is returned.
+    /// Only the root moves in the limits' search_moves are searched, if it
+    /// has any. The best move of the previous search is searched first, if
+    /// any. The moves are reported to the given InfoListener as they are
+    /// searched once the search has been going on for a while.
+    pub fn search(&mut self, depth: u8, listener: &mut dyn InfoListener) -> SearchResult {
+        let mut moves = self.board.generate_legal_moves();
+        if !self.limits.search_moves.is_empty() {
+            moves.retain(|chessmove| self.limits.search_moves.contains(chessmove));
+        }
+
+        let mut result = SearchResult {
+            best_move: Move::NULL,
+            score: -INFINITY,
+            pv: Line::new(),
+        };
+
+        if moves.is_empty() {
+            result.score = self.terminal_score(0).unwrap_or(DRAW);
+            return result;
+        }
+
+        self.order_moves(&mut moves, self.root_best, 0);
+
+        let mut alpha = -INFINITY;
+        let beta = INFINITY;
+
+        // The best score including the noise, which the moves are compared by.
+        let mut best_noisy = -INFINITY;
+
+        let mut child_pv = Line::new();
+        for (index, chessmove) in moves.into_iter().enumerate() {
+            if self.signals.elapsed() >= CURRMOVE_DELAY {
+                listener.currmove(depth, chessmove, index + 1);
+            }
+
+            // The window is widened by the amplitude of the noise, so that
+            // the scores of moves which can still be picked after their
+            // noise is added are exact.
+            self.make_move(chessmove);
+            let lower = alpha - self.root_noise;
+            let score = -self.negamax(depth.saturating_sub(1), 1, -beta, -lower, &mut child_pv);
+            self.undo_move();
+
+            // The score of a move whose search was stopped is not reliable,
+            // unless no other moves have been searched to fall back upon.
+            if self.stopped && result.best_move != Move::NULL {
+                break;
+            }
+
+            let noisy = score + self.noise(chessmove, score);
+            if noisy > best_noisy {
+                best_noisy = noisy;
+                result.best_move = chessmove;
+                result.score = score;
+
+                result.pv.clear();
+                result.pv.push(chessmove);
+                result.pv.extend_from_slice(&child_pv);
+
+                alpha = alpha.max(noisy);
+            }
+
+            if self.stopped {
+                break;
+            }
+        }
+
+        // The principal variation is cut short wherever the search returned
+        // early, like at transposition table cutoffs, so fill in the rest of
+        // it from the best moves stored in the transposition table.
+        if result.pv.len() < depth as usize {
+            for &chessmove in &result.pv {
+                self.board.make_move(chessmove);
+            }
+
+            let rest = self
+                .tt
+                .pv(&mut self.board, depth as usize - result.pv.len());
+
+            for _ in 0..result.pv.len() {
+                self.board.undo_move();
+            }
+
+            result.pv.extend(rest);
+        }
+
+        self.root_best = result.best_move;
+        result
+    }
+
+    /// negamax searches the position on the Board to the given depth, and
+    /// returns its score if it is within the alpha-beta window, or a bound
+    /// on its score if it is outside of the window. The principal variation
+    /// of the position is written into pv if its score is within the window.
+    fn negamax(
+        &mut self,
+        depth: u8,
+        ply: usize,
+        mut alpha: Score,
+        mut beta: Score,
+        pv: &mut Line,
+    ) -> Score {
+        pv.clear();
+
+        if depth == 0 {
+            return self.quiescence(ply, alpha, beta);
+        }
+
+        self.nodes += 1;
+        self.seldepth = self.seldepth.max(ply);
+        if self.should_stop() {
+            return DRAW;
+        }
+
+        if self.is_draw() {
+            return DRAW;
+        }
+
+        // Mate distance pruning: no line from here can be better than giving
+        // checkmate on the next ply, or worse than being checkmated now, so
+        // the window can be narrowed to those scores. Shorter mates found
+        // elsewhere in the tree cut the search off here.
+        alpha = alpha.max(mated_in(ply));
+        beta = beta.min(mate_in(ply + 1));
+        if alpha >= beta {
+            return alpha;
+        }
+
+        // A side which can force a repetition can get at least a draw.
+        if alpha < DRAW && self.board.upcoming_repetition(ply) {
+            alpha = DRAW;
+            if alpha >= beta {
+                return alpha;
+            }
+        }
+
+        if ply >= MAX_PLY {
+            return self.evaluate();
+        }
+
+        let hash = self.board.hash();
+        let entry = self.tt.probe(hash);
+
+        // Use the stored result of a search of the position which was at
+        // least as deep as this one, if its bound allows it.
+        if let Some(entry) = entry {
+            let score = score_from_tt(entry.score(), ply);
+            let is_usable = match entry.bound() {
+                Bound::Exact => true,
+                Bound::Lower => score >= beta,
+                Bound::Upper => score <= alpha,
+                Bound::None => false,
+            };
+
+            if entry.depth() >= depth && is_usable {
+                return score;
+            }
+        }
+
+        let mut moves = self.board.generate_legal_moves();
+        if moves.is_empty() {
+            return self.terminal_score(ply).unwrap_or(DRAW);
+        }
+
+        let tt_move = entry.map_or(Move::NULL, |entry| entry.chessmove());
+        self.order_moves(&mut moves, tt_move, ply);
+
+        let original_alpha = alpha;
+
+        let mut child_pv = Line::new();
+        let mut best_score = -INFINITY;
+        let mut best_move = Move::NULL;
+        let mut quiets_searched = MoveList::new();
+        for chessmove in moves {
+            let is_quiet = !is_noisy(&self.board, chessmove);
+
+            self.make_move(chessmove);
+            let score = -self.negamax(depth - 1, ply + 1, -beta, -alpha, &mut child_pv);
+            self.undo_move();
+
+            if self.stopped {
+                return DRAW;
+            }
+
+            if score > best_score {
+                best_score = score;
+                best_move = chessmove;
+            }
+
+            if score > alpha {
+                alpha = score;
+
+                pv.clear();
+                pv.push(chessmove);
+                pv.extend_from_slice(&child_pv);
+            }
+
+            // The opponent won't allow this position since they have a
+            // better alternative earlier on, so stop searching it.
+            if alpha >= beta {
+                // Quiet moves which cause cutoffs are likely to cause them
+                // again, unlike the quiet moves searched before them.
+                if is_quiet {
+                    let side = self.board.side_to_move();
+
+                    self.killers.store(ply, chessmove);
+                    self.history.update(side, chessmove, depth, true);
+                    for &quiet in &quiets_searched {
+                        self.history.update(side, quiet, depth, false);
+                    }
+                }
+
+                break;
+            }
+
+            if is_quiet {
+                quiets_searched.push(chessmove);
+            }
+        }
+
+        let bound = if best_score >= beta {
+            Bound::Lower
+        } else if best_score > original_alpha {
+            Bound::Exact
+        } else {
+            Bound::Upper
+        };
+
+        // Fail-low nodes don't have a best move, since all of the moves
+        // were worse than an alternative earlier on.
+        if bound == Bound::Upper {
+            best_move = Move::NULL;
+        }
+
+        // The static evaluation is only needed for the entry, so it is only
+        // computed if the entry is going to be stored.
+        if self.tt.replaces(hash, depth, bound) {
+            let eval = entry.map_or_else(|| self.evaluate(), |entry| entry.eval());
+            let score = score_to_tt(best_score, ply);
+            self.tt.store(hash, depth, bound, score, eval, best_move);
+        }
+
+        best_score
+    }
+
+    /// quiescence searches only the noisy moves in the position on the Board
+    /// until it is quiet, so that positions in the middle of exchanges are
+    /// not statically evaluated. Positions in check are searched fully.
+    fn quiescence(&mut self, ply: usize, mut alpha: Score, beta: Score) -> Score {
+        self.nodes += 1;
+        self.seldepth = self.seldepth.max(ply);
+        if self.should_stop() {
+            return DRAW;
+        }
+
+        if self.is_draw() {
+            return DRAW;
+        }
+
+        if ply >= MAX_PLY {
+            return self.evaluate();
+        }
+
+        // The game can be over in positions with legal moves too, like when
+        // a king has reached the hill in King of the Hill.
+        if let Some(score) = self.terminal_score(ply) {
+            return score;
+        }
+
+        let in_check = self.board.is_check();
+
+        // The side to move can't choose not to capture anything if it is in
+        // check or if its Variant forces it to capture something, and all of
+        // its legal moves need to be searched then.
+        let forced_moves = if in_check {
+            Some(self.board.generate_legal_moves())
+        } else if self.board.variant().has_forced_captures() {
+            let moves = self.board.generate_legal_moves();
+            let board = &self.board;
+            moves
+                .iter()
+                .any(|&chessmove| board.is_capture(chessmove))
+                .then_some(moves)
+        } else {
+            None
+        };
+
+        let is_forced = forced_moves.is_some();
+        let mut best_score = -INFINITY;
+        let mut moves = match forced_moves {
+            Some(moves) => moves,
+            None => {
+                // The side to move can choose not to capture anything, so the
+                // static evaluation is a lower bound on the position's score.
+                best_score = self.evaluate();
+                if best_score >= beta {
+                    return best_score;
+                }
+
+                alpha = alpha.max(best_score);
+                self.board.generate_noisy_moves()
+            }
+        };
+
+        if !in_check {
+            order_captures(&self.board, &mut moves);
+        }
+
+        let stand_pat = best_score;
+        for chessmove in moves {
+            if !is_forced {
+                // Delta pruning: captures which can't raise alpha even if
+                // the captured piece is won for free aren't searched.
+                // Promotions can gain much more than the captured piece.
+                let captured = match chessmove.flags() {
+                    MoveFlag::EnPassant => Piece::Pawn,
+                    _ => self.board.piece_at(chessmove.target()).piece(),
+                };
+                if !chessmove.is_promotion()
+                    && stand_pat + captured.value() + self.params.qs_delta_margin <= alpha
+                {
+                    continue;
+                }
+
+                // SEE pruning: captures which lose material in the exchange
+                // are unlikely to raise alpha either.
+                if !self.board.see_ge(chessmove, -self.params.qs_see_margin) {
+                    continue;
+                }
+            }
+
+            self.make_move(chessmove);
+            let score = -self.quiescence(ply + 1, -beta, -alpha);
+            self.undo_move();
+
+            if self.stopped {
+                return DRAW;
+            }
+
+            best_score = best_score.max(score);
+            alpha = alpha.max(score);
+
+            if alpha >= beta {
+                break;
+            }
+        }
+
+        best_score
+    }
+
+    /// order_moves orders the given moves at the given ply so that the moves
+    /// most likely to be the best are searched first: the best move found by
+    /// a previous search, followed by the noisy moves ordered by MVV-LVA,
+    /// followed by the killer moves, followed by the other quiet moves
+    /// ordered by their history scores.
+    fn order_moves(&self, moves: &mut [Move], tt_move: Move, ply: usize) {
+        let side = self.board.side_to_move();
+        let killers = self.killers.get(ply);
+
+        sort_moves(moves, |chessmove| {
+            if chessmove == tt_move {
+                TT_MOVE_SCORE
+            } else if is_noisy(&self.board, chessmove) {
+                NOISY_SCORE + mvv_lva(&self.board, chessmove)
+            } else if let Some(slot) = killers.iter().position(|&killer| killer == chessmove) {
+                KILLER_SCORE - slot as i32
+            } else {
+                self.history.get(side, chessmove)
+            }
+        });
+    }
+
+    /// make_move makes the given move on the Board, updating the Evaluator.
+    fn make_move(&mut self, chessmove: Move) {
+        self.evaluator.make_move(&self.board, chessmove);
+        self.board.make_move(chessmove);
+    }
+
+    /// undo_move takes back the last move made on the Board, updating the
+    /// Evaluator.
+    fn undo_move(&mut self) {
+        self.evaluator.undo_move();
+        self.board.undo_move();
+    }
+
+    /// evaluate statically evaluates the position on the Board from the
+    /// point of view of the side to move.
+    fn evaluate(&mut self) -> Score {
+        // Keep the evaluations out of the range of the mate scores, so that
+        // they are never mistaken for forced mates.
+        let bound = MATE - MAX_PLY as Score - 1;
+
+        // Probes of the KPK bitbase are reported as tablebase hits.
+        let score = match kpk::evaluate(&self.board) {
+            Some(score) => {
+                self.tbhits += 1;
+                score
+            }
+            None => self.evaluator.evaluate(&self.board),
+        };
+
+        score.clamp(-bound, bound)
+    }
+
+    /// noise returns the random noise added to the given score of the given
+    /// root move, which is zero if there is no root noise or if the score
+    /// is a mate score.
+    fn noise(&self, chessmove: Move, score: Score) -> Score {
+        if self.root_noise == 0 || is_mate(score) {
+            return 0;
+        }
+
+        let promot = if chessmove.is_promotion() {
+            chessmove.promot() as u64 + 1
+        } else {
+            0
+        };
+        let key = chessmove.source() as u64 | (chessmove.target() as u64) << 6 | promot << 12;
+
+        // Mix the seed and the move with the finalizer of SplitMix64.
+        let mut x = self.noise_seed ^ key.wrapping_mul(0x9e3779b97f4a7c15);
+        x = (x ^ (x >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        x = (x ^ (x >> 27)).wrapping_mul(0x94d049bb133111eb);
+        x ^= x >> 31;
+
+        (x % (2 * self.root_noise as u64 + 1)) as Score - self.root_noise
+    }
+
+    /// terminal_score returns the score of the position on the Board from
+    /// the point of view of the side to move if the game is over, or None if
+    /// it isn't. Its Outcome is decided by the rules of the Board's Variant,
+    /// so running out of moves may lose, draw or even win the game.
+    fn terminal_score(&mut self, ply: usize) -> Option<Score> {
+        Some(match self.board.outcome()? {
+            Outcome::Win(winner) if winner == self.board.side_to_move() => mate_in(ply),
+            Outcome::Win(_) => mated_in(ply),
+            Outcome::Draw => DRAW,
+        })
+    }
+
+    /// is_draw checks if the position on the Board is drawn by either the
+    /// fifty move rule or by repetition. Positions are considered drawn on
+    /// their first repetition, since the side which could repeat them once
+    /// can also repeat them again. A checkmate on the last move before the
+    /// fifty move rule applies still wins.
+    fn is_draw(&mut self) -> bool {
+        if self.board.is_50_move_draw() {
+            return true;
+        }
+
+        // Only positions since the last irreversible move can be repeated,
+        // and only the ones with the same side to move.
+        let hash = self.board.hash();
+        self.board
+            .hash_history()
+            .rev()
+            .take(self.board.draw_clock() as usize)
+            .skip(1)
+            .step_by(2)
+            .any(|previous| previous == hash)
+    }
+
+    /// should_stop checks if the search needs to stop, and marks it stopped
+    /// if it does. Limits are ignored while the search is pondering.
+    fn should_stop(&mut self) -> bool {
+        if self.stopped || !self.nodes.is_multiple_of(CHECK_INTERVAL) {
+            return self.stopped;
+        }
+
+        self.stopped = self.signals.is_stopped() || self.is_out_of_limits();
+
+        self.stopped
+    }
+
+    /// is_out_of_limits checks if the search has run out of its node or
+    /// time limits, which don't apply while the search is pondering.
+    fn is_out_of_limits(&self) -> bool {
+        if self.signals.is_pondering() {
+            return false;
+        }
+
+        let out_of_nodes = self.limits.nodes.is_some_and(|nodes| self.nodes >= nodes);
+        let out_of_time = self
+            .timeman
+            .hard_deadline()
+            .is_some_and(|deadline| self.signals.elapsed() >= deadline);
+
+        out_of_nodes || out_of_time
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::chess::variant::{self, Variant};
+
+    use super::*;
+
+    struct Silent;
+
+    impl InfoListener for Silent {
+        fn info(&mut self, _info: &SearchInfo) {}
+    }
+
+    fn search(fen: &str, variant: &'static dyn Variant, depth: u8) -> Score {
+        let board = Board::from_fen(fen, variant).unwrap();
+        let signals = Signals::new();
+        let mut tt = TranspositionTable::new(1);
+        let mut evaluator = Evaluator::new();
+        let mut history = History::new();
+
+        let mut searcher = Searcher::new(
+            board,
+            SearchLimits::default(),
+            SearchParams::DEFAULT,
+            &signals,
+            &mut tt,
+            &mut evaluator,
+            &mut history,
+        );
+
+        searcher.search(depth, &mut Silent).score
+    }
+
+    #[test]
+    fn standard_terminal_scores() {
+        let fen = "7k/5Q2/6K1/8/8/8/8/8 w - - 0 80";
+        assert_eq!(search(fen, &variant::Standard, 2), mate_in(1));
+
+        // Checkmate on the last move before the fifty move rule applies.
+        let fen = "7k/5Q2/6K1/8/8/8/8/8 w - - 99 80";
+        assert_eq!(search(fen, &variant::Standard, 2), mate_in(1));
+
+        let fen = "7k/5Q2/6K1/8/8/8/8/8 b - - 0 80";
+        assert_eq!(search(fen, &variant::Standard, 1), DRAW);
+    }
+
+    #[test]
+    fn variant_terminal_scores() {
+        // Antichess is won by running out of pieces.
+        let fen = "8/8/8/8/8/8/8/k7 w - - 0 1";
+        assert_eq!(search(fen, &variant::Antichess, 1), mate_in(0));
+
+        let fen = "r7/8/8/8/8/8/8/R7 w - - 0 1";
+        assert_eq!(search(fen, &variant::Antichess, 2), mated_in(1));
+
+        // King of the Hill is won by reaching the center.
+        let fen = "7k/8/8/8/8/2K5/8/8 w - - 0 1";
+        assert_eq!(search(fen, &variant::KingOfTheHill, 2), mate_in(1));
+    }
+
+    #[test]
+    fn variant_terminal_scores_in_quiescence() {
+        // The king reaching the hill is only seen by the quiescence search.
+        let fen = "7k/8/8/8/8/2K5/8/8 w - - 0 1";
+        assert_eq!(search(fen, &variant::KingOfTheHill, 1), mate_in(1));
+
+        // Running out of pieces is seen there too.
+        let fen = "r7/8/8/8/8/8/8/R7 w - - 0 1";
+        assert_eq!(search(fen, &variant::Antichess, 1), mated_in(1));
+
+        // A side which has to capture can't stand pat instead.
+        let fen = "r7/1p6/8/8/8/8/8/7R w - - 0 1";
+        assert_eq!(search(fen, &variant::Antichess, 1), mate_in(2));
+    }
+}
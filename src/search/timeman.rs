@@ -0,0 +1,122 @@
+// Copyright © 2023 Rak Laptudirm <rak@laptudirm.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::time::Duration;
+
+use crate::chess::{Color, Move};
+use crate::engine::SearchLimits;
+
+/// TimeManager decides how long a search should go on for. It has a soft
+/// deadline, after which no new iterations of the search are started, and
+/// a hard deadline, after which the search is aborted. The soft deadline is
+/// extended when the best move is unstable, and shortened when it isn't.
+pub struct TimeManager {
+    soft: Option<Duration>,
+    hard: Option<Duration>,
+
+    // The best move of the last iteration, and the number of iterations
+    // in a row it has stayed the best move for.
+    best_move: Move,
+    stability: u32,
+}
+
+/// MOVES_TO_GO is the number of moves left in the game assumed when
+/// allocating time for a search without a known moves to go.
+const MOVES_TO_GO: u32 = 30;
+
+/// MOVE_OVERHEAD is the time kept aside for the communication with the GUI,
+/// so that the engine doesn't lose on time because of it.
+const MOVE_OVERHEAD: Duration = Duration::from_millis(30);
+
+/// STABILITY_SCALES contains the factors the soft deadline is scaled by,
+/// indexed by the number of iterations the best move has stayed the same.
+const STABILITY_SCALES: [f64; 5] = [2.0, 1.4, 1.1, 0.9, 0.8];
+
+impl TimeManager {
+    /// new creates a TimeManager for a search by the given side within the
+    /// given limits. Searches without time limits have no deadlines.
+    pub fn new(limits: &SearchLimits, side: Color) -> TimeManager {
+        let (soft, hard) = deadlines(limits, side);
+
+        TimeManager {
+            soft,
+            hard,
+            best_move: Move::NULL,
+            stability: 0,
+        }
+    }
+
+    /// update updates the TimeManager with the best move found by the last
+    /// iteration of the search.
+    pub fn update(&mut self, best_move: Move) {
+        if best_move == self.best_move {
+            self.stability += 1;
+        } else {
+            self.best_move = best_move;
+            self.stability = 0;
+        }
+    }
+
+    /// soft_deadline returns the time after which no new iterations of the
+    /// search should be started, scaled by the stability of the best move.
+    pub fn soft_deadline(&self) -> Option<Duration> {
+        let index = (self.stability as usize).min(STABILITY_SCALES.len() - 1);
+        let soft = self.soft?.mul_f64(STABILITY_SCALES[index]);
+
+        Some(soft.min(self.hard?))
+    }
+
+    /// hard_deadline returns the time after which the search is aborted.
+    pub fn hard_deadline(&self) -> Option<Duration> {
+        self.hard
+    }
+}
+
+/// deadlines returns the base soft deadline and the hard deadline of a
+/// search by the given side within the given limits, if it has any.
+fn deadlines(limits: &SearchLimits, side: Color) -> (Option<Duration>, Option<Duration>) {
+    if limits.infinite {
+        return (None, None);
+    }
+
+    // Fixed time searches use all of their time.
+    if let Some(movetime) = limits.movetime {
+        return (None, Some(movetime));
+    }
+
+    let Some(time) = limits.time[side as usize] else {
+        return (None, None);
+    };
+
+    let increment = limits.increment[side as usize];
+    let moves_to_go = limits.moves_to_go.unwrap_or(MOVES_TO_GO).max(1);
+
+    // Never use more time than what is left on the clock.
+    let available = time.saturating_sub(MOVE_OVERHEAD);
+
+    // Spread the time left evenly over the moves left, and use most of the
+    // increment. The search may go on for a few times longer than that when
+    // the best move is unstable, but never for more than half of the time
+    // left unless it is the last move before the time control.
+    let optimum = time / moves_to_go + increment * 3 / 4;
+    let maximum = if moves_to_go == 1 {
+        available
+    } else {
+        available / 2
+    };
+
+    let soft = (optimum / 2).min(available);
+    let hard = (optimum * 3).min(maximum).max(soft);
+
+    (Some(soft), Some(hard))
+}
@@ -0,0 +1,283 @@
+// Copyright © 2023 Rak Laptudirm <rak@laptudirm.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::mem;
+
+use num_derive::FromPrimitive;
+use num_traits::FromPrimitive;
+
+use crate::chess::{zobrist, Board, Move};
+use crate::util::array_vec::ArrayVec;
+
+use super::{is_mate, Line, Score, MAX_PLY};
+
+/// TranspositionTable is a hash table of the results of searching positions,
+/// keyed by the zobrist hashes of the positions, so that positions reached
+/// through different move orders don't need to be searched again.
+pub struct TranspositionTable {
+    entries: Vec<Entry>,
+    size_mb: usize,
+
+    // The age of the current search, which is used to replace the entries
+    // of previous searches before the entries of the current one.
+    age: u8,
+}
+
+/// Entry is a packed entry of a TranspositionTable.
+#[derive(Copy, Clone, Default)]
+pub struct Entry {
+    // The lower bits of the position's hash, used to verify that the entry
+    // is of the probed position and not of another one with the same index.
+    key: u16,
+
+    chessmove: Move,
+    score: i16,
+    eval: i16,
+    depth: u8,
+
+    // The bound in the lower 2 bits and the age in the upper 6 bits.
+    bound_age: u8,
+}
+
+/// Bound is the relation between the actual score of a position and the
+/// score stored in its Entry.
+#[derive(Copy, Clone, PartialEq, Eq, Default, FromPrimitive)]
+#[rustfmt::skip]
+pub enum Bound {
+    #[default] None, Lower, Upper, Exact,
+}
+
+/// AGE_MASK is the mask of the bits of the age of the current search which
+/// are stored in an Entry.
+const AGE_MASK: u8 = 0b111111;
+
+impl TranspositionTable {
+    /// new creates a TranspositionTable of the given size in megabytes.
+    pub fn new(size_mb: usize) -> TranspositionTable {
+        let mut tt = TranspositionTable {
+            entries: Vec::new(),
+            size_mb: 0,
+            age: 0,
+        };
+
+        tt.resize(size_mb);
+        tt
+    }
+
+    /// size_mb returns the size of the TranspositionTable in megabytes.
+    pub fn size_mb(&self) -> usize {
+        self.size_mb
+    }
+
+    /// resize resizes the TranspositionTable to the given size in megabytes,
+    /// clearing all of its entries.
+    pub fn resize(&mut self, size_mb: usize) {
+        let size_mb = size_mb.max(1);
+        let len = size_mb * 1024 * 1024 / mem::size_of::<Entry>();
+
+        self.entries = vec![Entry::default(); len];
+        self.size_mb = size_mb;
+        self.age = 0;
+    }
+
+    /// clear clears all of the entries of the TranspositionTable.
+    pub fn clear(&mut self) {
+        self.entries.fill(Entry::default());
+        self.age = 0;
+    }
+
+    /// new_search ages the entries of the TranspositionTable, so that the
+    /// entries of the previous searches are replaced before the new ones.
+    pub fn new_search(&mut self) {
+        self.age = (self.age + 1) & AGE_MASK;
+    }
+
+    /// probe returns the Entry of the position with the given hash, if the
+    /// TranspositionTable has one.
+    pub fn probe(&self, hash: zobrist::Hash) -> Option<Entry> {
+        let entry = self.entries[self.index(hash)];
+        if entry.bound() != Bound::None && entry.key == hash_key(hash) {
+            Some(entry)
+        } else {
+            None
+        }
+    }
+
+    /// replaces checks if storing a result with the given depth and bound for
+    /// the position with the given hash would replace the entry in its slot.
+    /// Entries from the current search which are of deeper searches are only
+    /// replaced by exact scores.
+    pub fn replaces(&self, hash: zobrist::Hash, depth: u8, bound: Bound) -> bool {
+        let old = self.entries[self.index(hash)];
+        old.key != hash_key(hash)
+            || old.age() != self.age
+            || bound == Bound::Exact
+            || depth >= old.depth
+    }
+
+    /// store stores the result of searching the position with the given hash
+    /// into the TranspositionTable, if it replaces the entry in its slot.
+    pub fn store(
+        &mut self,
+        hash: zobrist::Hash,
+        depth: u8,
+        bound: Bound,
+        score: Score,
+        eval: Score,
+        chessmove: Move,
+    ) {
+        if !self.replaces(hash, depth, bound) {
+            return;
+        }
+
+        let index = self.index(hash);
+        let old = self.entries[index];
+        let key = hash_key(hash);
+
+        // Keep the old best move if the new search didn't find any.
+        let chessmove = if chessmove == Move::NULL && old.key == key {
+            old.chessmove
+        } else {
+            chessmove
+        };
+
+        self.entries[index] = Entry {
+            key,
+            chessmove,
+            score: score as i16,
+            eval: eval as i16,
+            depth,
+            bound_age: self.age << 2 | bound as u8,
+        };
+    }
+
+    /// pv follows the best moves stored in the TranspositionTable from the
+    /// position on the given Board, and returns the line of at most max_len
+    /// moves it finds, which can't be more than MAX_PLY. The line ends at the first move which isn't legal,
+    /// since entries may have been overwritten by other positions, and at
+    /// the first position which repeats, so that it doesn't cycle forever.
+    pub fn pv(&self, board: &mut Board, max_len: usize) -> Line {
+        let mut pv = Line::new();
+        let mut seen = ArrayVec::<zobrist::Hash, { MAX_PLY + 1 }>::new();
+        seen.push(board.hash());
+
+        while pv.len() < max_len.min(MAX_PLY) {
+            let Some(entry) = self.probe(board.hash()) else {
+                break;
+            };
+
+            let chessmove = entry.chessmove();
+            if chessmove == Move::NULL || !board.generate_legal_moves().contains(&chessmove) {
+                break;
+            }
+
+            board.make_move(chessmove);
+            pv.push(chessmove);
+
+            if seen.contains(&board.hash()) {
+                break;
+            }
+
+            seen.push(board.hash());
+        }
+
+        for _ in 0..pv.len() {
+            board.undo_move();
+        }
+
+        pv
+    }
+
+    /// hashfull returns the permillage of the TranspositionTable's entries
+    /// which are used by the current search, sampled over its first entries.
+    pub fn hashfull(&self) -> usize {
+        let sample = &self.entries[..self.entries.len().min(1000)];
+        let used = sample
+            .iter()
+            .filter(|entry| entry.bound() != Bound::None && entry.age() == self.age)
+            .count();
+
+        used * 1000 / sample.len()
+    }
+
+    /// index returns the index of the Entry of the given hash.
+    fn index(&self, hash: zobrist::Hash) -> usize {
+        // Map the hash onto the table with a multiplication, which is
+        // faster than a modulo and works with any table length.
+        ((u64::from(hash) as u128 * self.entries.len() as u128) >> 64) as usize
+    }
+}
+
+impl Entry {
+    /// chessmove returns the best move found in the position, or Move::NULL
+    /// if no move was better than the other ones.
+    pub fn chessmove(&self) -> Move {
+        self.chessmove
+    }
+
+    /// score returns the score of the position, which is relative to the
+    /// position for mate scores. See score_from_tt.
+    pub fn score(&self) -> Score {
+        self.score as Score
+    }
+
+    /// eval returns the static evaluation of the position.
+    pub fn eval(&self) -> Score {
+        self.eval as Score
+    }
+
+    /// depth returns the depth the position was searched to.
+    pub fn depth(&self) -> u8 {
+        self.depth
+    }
+
+    /// bound returns the relation between the position's actual score and
+    /// the score stored in the Entry.
+    pub fn bound(&self) -> Bound {
+        Bound::from_u8(self.bound_age & 0b11).unwrap()
+    }
+
+    fn age(&self) -> u8 {
+        self.bound_age >> 2
+    }
+}
+
+/// hash_key returns the part of the given hash stored in its Entry.
+fn hash_key(hash: zobrist::Hash) -> u16 {
+    u64::from(hash) as u16
+}
+
+/// score_to_tt converts a score relative to the root of the search into a
+/// score relative to the position at the given ply, so that mate scores
+/// stay correct when the position is reached at a different ply.
+pub fn score_to_tt(score: Score, ply: usize) -> Score {
+    if !is_mate(score) {
+        score
+    } else if score > 0 {
+        score + ply as Score
+    } else {
+        score - ply as Score
+    }
+}
+
+/// score_from_tt converts a score relative to the position at the given ply
+/// into a score relative to the root of the search.
+pub fn score_from_tt(score: Score, ply: usize) -> Score {
+    if !is_mate(score) {
+        score
+    } else if score > 0 {
+        score - ply as Score
+    } else {
+        score + ply as Score
+    }
+}
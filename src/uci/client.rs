@@ -0,0 +1,416 @@
+// Copyright © 2023 Rak Laptudirm <rak@laptudirm.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+    io::{self, BufRead},
+    str::FromStr,
+    sync::Arc,
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use crate::chess::{Board, Color, Move};
+use crate::engine::{Engine, InfoListener, SearchInfo, SearchLimits, Signals};
+use crate::search;
+
+/// Client runs the Universal Chess Interface protocol for an Engine over
+/// the standard input and output, so that it can be used by chess GUIs.
+pub struct Client<E: Engine> {
+    // The engine is moved into the search thread while a search is going
+    // on, and is given back by the thread once the search is over.
+    engine: Option<E>,
+    search: Option<JoinHandle<E>>,
+    signals: Arc<Signals>,
+
+    board: Board,
+
+    // Whether castling moves are written as the king capturing its own
+    // rook, as set by the UCI_Chess960 option.
+    chess960: bool,
+}
+
+impl<E: Engine> Client<E> {
+    /// new creates a Client for the given Engine.
+    pub fn new(engine: E) -> Client<E> {
+        Client {
+            engine: Some(engine),
+            search: None,
+            signals: Arc::new(Signals::new()),
+
+            board: Board::startpos(),
+            chess960: false,
+        }
+    }
+
+    /// run runs the protocol loop until the `quit` command is received or
+    /// the standard input is closed.
+    pub fn run(mut self) {
+        for line in io::stdin().lock().lines() {
+            let Ok(line) = line else {
+                break;
+            };
+
+            let tokens: Vec<&str> = line.split_whitespace().collect();
+            let Some((&command, args)) = tokens.split_first() else {
+                continue;
+            };
+
+            match command {
+                "uci" => {
+                    let engine = self.engine();
+                    println!("id name {}", engine.name());
+                    println!("id author {}", engine.author());
+                    if let Some(options) = engine.options() {
+                        print!("{options}");
+                    }
+                    println!("option name UCI_Chess960 type check default false");
+                    println!("uciok");
+                }
+
+                "isready" => println!("readyok"),
+
+                "setoption" => {
+                    // UCI_Chess960 changes how moves are written, which is
+                    // up to the Client rather than the Engine. Positions
+                    // with Chess960 castling rights can be set up whether
+                    // or not it is set, since their FENs say so.
+                    if let ["name", name, "value", value] = args {
+                        if name.eq_ignore_ascii_case("UCI_Chess960") {
+                            self.chess960 = *value == "true";
+                            continue;
+                        }
+                    }
+
+                    // Errors are reported to the GUI, but are otherwise ignored.
+                    if let Err(err) = self.engine().set_option(args) {
+                        println!("info string {err}");
+                    }
+                }
+
+                "ucinewgame" => {
+                    self.engine().new_game();
+                    self.board = Board::startpos();
+                }
+
+                "position" => {
+                    self.stop_search();
+                    // A position which fails to parse would leave the Board
+                    // out of sync with the GUI, so it is reset instead.
+                    self.board = parse_position(args, self.chess960).unwrap_or_else(|err| {
+                        println!("info string {err}");
+                        Board::startpos()
+                    });
+                }
+
+                "go" => {
+                    let ponder = args.contains(&"ponder");
+                    let limits = parse_go(args, &mut self.board, self.chess960);
+                    self.start_search(limits, ponder);
+                }
+
+                // Non-standard command which prints the static evaluation
+                // of the current position, for debugging the engine.
+                "eval" => {
+                    let board = self.board.clone();
+                    match self.engine().evaluate(&board) {
+                        Some(report) => println!("{report}"),
+                        None => println!("info string engine has no static evaluation"),
+                    }
+                }
+
+                "ponderhit" => self.signals.ponderhit(),
+                "stop" => self.stop_search(),
+
+                "quit" => break,
+
+                // Unknown commands are ignored, as required by the protocol.
+                _ => {}
+            }
+        }
+
+        self.stop_search();
+    }
+
+    /// engine returns the Client's Engine, stopping any ongoing search
+    /// to get it back from the search thread.
+    fn engine(&mut self) -> &mut E {
+        self.stop_search();
+        self.engine.as_mut().unwrap()
+    }
+
+    /// start_search starts searching the current position on a separate
+    /// thread, which reports the best move once the search is over. Ponder
+    /// searches only report their best move once they are stopped or are
+    /// converted into normal searches by a `ponderhit`.
+    fn start_search(&mut self, limits: SearchLimits, ponder: bool) {
+        self.stop_search();
+        let mut engine = self.engine.take().unwrap();
+
+        let board = self.board.clone();
+        let signals = Arc::clone(&self.signals);
+        signals.reset(ponder);
+
+        let chess960 = self.chess960;
+        self.search = Some(thread::spawn(move || {
+            let mut reporter = Reporter { chess960 };
+            let best_move = engine.search(board, limits, &signals, &mut reporter);
+
+            // The best move of a ponder search can't be reported while the
+            // search is still pondering, even if it has ended by itself.
+            while signals.is_pondering() && !signals.is_stopped() {
+                thread::sleep(Duration::from_millis(1));
+            }
+
+            match best_move.ponder {
+                Move::NULL => println!("bestmove {}", best_move.chessmove.to_uci(chess960)),
+                ponder => println!(
+                    "bestmove {} ponder {}",
+                    best_move.chessmove.to_uci(chess960),
+                    ponder.to_uci(chess960)
+                ),
+            }
+
+            engine
+        }));
+    }
+
+    /// stop_search stops the ongoing search, if any, and waits for it to
+    /// report its best move.
+    fn stop_search(&mut self) {
+        if self.search.is_some() {
+            self.signals.stop();
+            let engine = self.join_search();
+            self.engine = Some(engine);
+        }
+    }
+
+    /// join_search waits for the ongoing search to end and takes back the
+    /// Engine from the search thread.
+    fn join_search(&mut self) -> E {
+        let search = self.search.take().expect("join search: no ongoing search");
+        search.join().expect("join search: search thread panicked")
+    }
+}
+
+/// parse_position parses the arguments of a `position` command, which are
+/// either `startpos` or `fen <fen>`, followed by an optional `moves <moves>`.
+/// Castling moves are the king capturing its own rook if chess960 is set.
+/// The error describes why the position couldn't be set up.
+fn parse_position(args: &[&str], chess960: bool) -> Result<Board, String> {
+    let moves_at = args.iter().position(|&arg| arg == "moves");
+    let (position, moves) = match moves_at {
+        Some(index) => (&args[..index], &args[index + 1..]),
+        None => (args, &[][..]),
+    };
+
+    let mut board = match position.split_first() {
+        Some((&"startpos", [])) => Board::startpos(),
+        Some((&"fen", fen)) => Board::from_str(&fen.join(" ")).map_err(|err| err.to_string())?,
+        _ => return Err(String::from("position should be startpos or fen <fen>")),
+    };
+
+    board
+        .apply_uci_moves(moves, chess960)
+        .map_err(|err| err.to_string())?;
+
+    Ok(board)
+}
+
+/// parse_go parses the arguments of a `go` command into the SearchLimits of
+/// the search it starts from the position on the Board. The moves after
+/// `searchmoves` are parsed like the ones of a `position` command, with
+/// castling moves as the king capturing its own rook if chess960 is set.
+/// Unknown or malformed parameters are ignored.
+fn parse_go(args: &[&str], board: &mut Board, chess960: bool) -> SearchLimits {
+    let mut limits = SearchLimits::default();
+
+    let mut args = args.iter().peekable();
+    while let Some(&param) = args.next() {
+        // Parameters without values.
+        match param {
+            "infinite" => {
+                limits.infinite = true;
+                continue;
+            }
+
+            "ponder" => continue,
+
+            // The moves go on until the first argument which isn't a legal move.
+            "searchmoves" => {
+                while let Some(chessmove) =
+                    args.peek().and_then(|uci| board.parse_uci(uci, chess960))
+                {
+                    if !limits.search_moves.contains(&chessmove) {
+                        limits.search_moves.push(chessmove);
+                    }
+
+                    args.next();
+                }
+
+                continue;
+            }
+
+            _ => {}
+        }
+
+        let Some(value) = args.next().and_then(|value| value.parse::<i64>().ok()) else {
+            continue;
+        };
+
+        // Clock times are in milliseconds, and may be negative if the
+        // engine is already out of time, which is treated as no time.
+        let value = value.max(0) as u64;
+        let millis = Duration::from_millis(value);
+
+        match param {
+            "wtime" => limits.time[Color::White as usize] = Some(millis),
+            "btime" => limits.time[Color::Black as usize] = Some(millis),
+            "winc" => limits.increment[Color::White as usize] = millis,
+            "binc" => limits.increment[Color::Black as usize] = millis,
+            "movestogo" => limits.moves_to_go = Some(value as u32),
+            "depth" => limits.depth = Some(value as u32),
+            "nodes" => limits.nodes = Some(value),
+            "movetime" => limits.movetime = Some(millis),
+            "mate" => limits.mate = Some(value as u32),
+            _ => {}
+        }
+    }
+
+    limits
+}
+
+/// format_go formats the given SearchLimits as the `go` command which starts
+/// a search within them. Infinite searches ignore all of their other limits.
+pub fn format_go(limits: &SearchLimits) -> String {
+    if limits.infinite {
+        return String::from("go infinite");
+    }
+
+    let millis = |time: Duration| time.as_millis() as u64;
+    let increment = |color: Color| {
+        Some(limits.increment[color as usize])
+            .filter(|increment| !increment.is_zero())
+            .map(millis)
+    };
+
+    let params = [
+        ("wtime", limits.time[Color::White as usize].map(millis)),
+        ("btime", limits.time[Color::Black as usize].map(millis)),
+        ("winc", increment(Color::White)),
+        ("binc", increment(Color::Black)),
+        ("movestogo", limits.moves_to_go.map(u64::from)),
+        ("depth", limits.depth.map(u64::from)),
+        ("nodes", limits.nodes),
+        ("movetime", limits.movetime.map(millis)),
+        ("mate", limits.mate.map(u64::from)),
+    ];
+
+    let mut command = String::from("go");
+    for (param, value) in params {
+        if let Some(value) = value {
+            command += &format!(" {param} {value}");
+        }
+    }
+
+    command
+}
+
+/// Reporter reports the information about a search to the GUI, writing
+/// castling moves as the king capturing its own rook if chess960 is set.
+struct Reporter {
+    chess960: bool,
+}
+
+impl InfoListener for Reporter {
+    fn info(&mut self, info: &SearchInfo) {
+        println!("{}", format_info(info, self.chess960));
+    }
+
+    fn currmove(&mut self, depth: u8, chessmove: Move, number: usize) {
+        println!(
+            "info depth {depth} currmove {} currmovenumber {number}",
+            chessmove.to_uci(self.chess960)
+        );
+    }
+}
+
+/// format_info formats the given SearchInfo as an `info` command, with the
+/// score given in moves if it represents a forced mate. Castling moves are
+/// written as the king capturing its own rook if chess960 is set.
+fn format_info(info: &SearchInfo, chess960: bool) -> String {
+    let score = if search::is_mate(info.score) {
+        format!("mate {}", search::mate_moves(info.score))
+    } else {
+        format!("cp {}", info.score)
+    };
+
+    let pv: Vec<String> = info
+        .pv
+        .iter()
+        .map(|&chessmove| chessmove.to_uci(chess960))
+        .collect();
+
+    format!(
+        "info depth {} seldepth {} score {score} nodes {} nps {} hashfull {} tbhits {} time {} pv {}",
+        info.depth,
+        info.seldepth,
+        info.nodes,
+        info.nps(),
+        info.hashfull,
+        info.tbhits,
+        info.time.as_millis(),
+        pv.join(" ")
+    )
+}
+
+/// format_move formats the given Move in the UCI long algebraic notation,
+/// where castling moves are written as the king moving two squares, and
+/// promotions have a lower case suffix with the promotion piece.
+pub fn format_move(chessmove: Move) -> String {
+    chessmove.to_uci(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn go_searchmoves_restricts_the_root_moves() {
+        let mut board = Board::startpos();
+        let uci = |moves: &[Move]| -> Vec<String> {
+            moves
+                .iter()
+                .map(|chessmove| chessmove.to_uci(false))
+                .collect()
+        };
+
+        let args = ["searchmoves", "e2e4", "d2d4", "e2e4", "depth", "5"];
+        let limits = parse_go(&args, &mut board, false);
+        assert_eq!(uci(&limits.search_moves), ["e2e4", "d2d4"]);
+        assert_eq!(limits.depth, Some(5));
+
+        let args = ["wtime", "1000", "searchmoves", "g1f3"];
+        let limits = parse_go(&args, &mut board, false);
+        assert_eq!(uci(&limits.search_moves), ["g1f3"]);
+        assert_eq!(
+            limits.time[Color::White as usize],
+            Some(Duration::from_millis(1000))
+        );
+
+        // Illegal moves aren't searched.
+        let args = ["infinite", "searchmoves", "e2e5"];
+        let limits = parse_go(&args, &mut board, false);
+        assert!(limits.search_moves.is_empty());
+        assert!(limits.infinite);
+    }
+}
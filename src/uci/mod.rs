@@ -0,0 +1,9 @@
+// Non-namespaced modules.
+mod client;
+mod options;
+
+// Make the contents of the non-namespaced
+// modules public, so they can be accessed
+// without their parent namespace.
+pub use self::client::*;
+pub use self::options::*;
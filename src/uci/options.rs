@@ -0,0 +1,285 @@
+// Copyright © 2023 Rak Laptudirm <rak@laptudirm.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{error::Error, fmt};
+
+/// Options is a registry of an engine's UCI options. It advertises the
+/// options to the GUI, parses `setoption` commands, and keeps the current
+/// value of each option, which can be read through the typed getters.
+#[derive(Default)]
+pub struct Options {
+    options: Vec<EngineOption>,
+}
+
+/// Value is the value of a UCI option. Buttons don't have a value, and only
+/// trigger their change callbacks when they are pressed.
+#[derive(Clone, PartialEq, Eq)]
+pub enum Value {
+    Spin(i64),
+    Check(bool),
+    String(String),
+    Combo(String),
+    Button,
+}
+
+/// Callback is a function which is called with the new value of an option
+/// whenever it is changed by the GUI.
+pub type Callback = Box<dyn FnMut(&Value) + Send>;
+
+struct EngineOption {
+    name: String,
+    value: Value,
+    default: Value,
+
+    // Limits of spin options.
+    min: i64,
+    max: i64,
+
+    // Allowed values of combo options.
+    vars: Vec<String>,
+
+    callbacks: Vec<Callback>,
+}
+
+#[derive(Debug)]
+pub enum OptionError {
+    UnknownOption(String),
+    MissingName,
+    MissingValue,
+    InvalidValue(String),
+    Unusable(String, String),
+}
+
+impl fmt::Display for OptionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OptionError::UnknownOption(name) => write!(f, "unknown option {name}"),
+            OptionError::MissingName => write!(f, "missing option name"),
+            OptionError::MissingValue => write!(f, "missing option value"),
+            OptionError::InvalidValue(value) => write!(f, "invalid option value {value}"),
+            OptionError::Unusable(value, reason) => {
+                write!(f, "can't use option value {value}: {reason}")
+            }
+        }
+    }
+}
+
+impl Error for OptionError {}
+
+impl Options {
+    /// new creates an empty options registry.
+    pub fn new() -> Options {
+        Options::default()
+    }
+
+    /// add_spin adds a spin option, which is an integer between min and max.
+    pub fn add_spin(&mut self, name: &str, default: i64, min: i64, max: i64) -> &mut Options {
+        self.add(name, Value::Spin(default), min, max, Vec::new())
+    }
+
+    /// add_check adds a check option, which is either true or false.
+    pub fn add_check(&mut self, name: &str, default: bool) -> &mut Options {
+        self.add(name, Value::Check(default), 0, 0, Vec::new())
+    }
+
+    /// add_string adds a string option, which can have any value.
+    pub fn add_string(&mut self, name: &str, default: &str) -> &mut Options {
+        self.add(name, Value::String(default.to_string()), 0, 0, Vec::new())
+    }
+
+    /// add_combo adds a combo option, which is one of the given values.
+    pub fn add_combo(&mut self, name: &str, default: &str, vars: &[&str]) -> &mut Options {
+        let vars = vars.iter().map(|var| var.to_string()).collect();
+        self.add(name, Value::Combo(default.to_string()), 0, 0, vars)
+    }
+
+    /// add_button adds a button option, which triggers its change callbacks
+    /// whenever it is pressed.
+    pub fn add_button(&mut self, name: &str) -> &mut Options {
+        self.add(name, Value::Button, 0, 0, Vec::new())
+    }
+
+    /// on_change adds a callback which is called with the option's new value
+    /// whenever the option with the given name is changed.
+    pub fn on_change<F: FnMut(&Value) + Send + 'static>(
+        &mut self,
+        name: &str,
+        callback: F,
+    ) -> Result<&mut Options, OptionError> {
+        self.find_mut(name)?.callbacks.push(Box::new(callback));
+        Ok(self)
+    }
+
+    /// get returns the current value of the option with the given name.
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        self.find(name).map(|option| &option.value)
+    }
+
+    /// spin returns the value of the spin option with the given name.
+    pub fn spin(&self, name: &str) -> Option<i64> {
+        match self.get(name)? {
+            Value::Spin(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// check returns the value of the check option with the given name.
+    pub fn check(&self, name: &str) -> Option<bool> {
+        match self.get(name)? {
+            Value::Check(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// string returns the value of the string or combo option with the
+    /// given name.
+    pub fn string(&self, name: &str) -> Option<&str> {
+        match self.get(name)? {
+            Value::String(value) | Value::Combo(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// set parses and sets the value of the option with the given name, and
+    /// calls its change callbacks. The value is ignored for buttons.
+    pub fn set(&mut self, name: &str, value: &str) -> Result<(), OptionError> {
+        let option = self.find_mut(name)?;
+        let invalid = || OptionError::InvalidValue(value.to_string());
+
+        option.value = match option.default {
+            Value::Spin(_) => {
+                let value: i64 = value.parse().map_err(|_| invalid())?;
+                if value < option.min || value > option.max {
+                    return Err(invalid());
+                }
+
+                Value::Spin(value)
+            }
+
+            Value::Check(_) => Value::Check(value.parse().map_err(|_| invalid())?),
+            Value::String(_) => Value::String(value.to_string()),
+
+            Value::Combo(_) => {
+                let var = option
+                    .vars
+                    .iter()
+                    .find(|var| var.eq_ignore_ascii_case(value));
+                Value::Combo(var.ok_or_else(invalid)?.clone())
+            }
+
+            Value::Button => Value::Button,
+        };
+
+        for callback in &mut option.callbacks {
+            callback(&option.value);
+        }
+
+        Ok(())
+    }
+
+    /// set_option parses the arguments of a `setoption` command, which are
+    /// of the form `name <name> [value <value>]`, and sets the option.
+    pub fn set_option(&mut self, args: &[&str]) -> Result<(), OptionError> {
+        let Some((&"name", args)) = args.split_first() else {
+            return Err(OptionError::MissingName);
+        };
+
+        // Both the option's name and its value may contain spaces.
+        let value_at = args.iter().position(|&arg| arg == "value");
+        let (name, value) = match value_at {
+            Some(index) => (args[..index].join(" "), Some(args[index + 1..].join(" "))),
+            None => (args.join(" "), None),
+        };
+
+        match (value, &self.find(&name).map(|option| &option.default)) {
+            (Some(value), _) => self.set(&name, &value),
+            (None, Some(Value::Button)) => self.set(&name, ""),
+            (None, Some(_)) => Err(OptionError::MissingValue),
+            (None, None) => Err(OptionError::UnknownOption(name)),
+        }
+    }
+
+    fn add(
+        &mut self,
+        name: &str,
+        default: Value,
+        min: i64,
+        max: i64,
+        vars: Vec<String>,
+    ) -> &mut Options {
+        self.options.push(EngineOption {
+            name: name.to_string(),
+            value: default.clone(),
+            default,
+            min,
+            max,
+            vars,
+            callbacks: Vec::new(),
+        });
+
+        self
+    }
+
+    // Option names are case insensitive in UCI.
+
+    fn find(&self, name: &str) -> Option<&EngineOption> {
+        self.options
+            .iter()
+            .find(|option| option.name.eq_ignore_ascii_case(name))
+    }
+
+    fn find_mut(&mut self, name: &str) -> Result<&mut EngineOption, OptionError> {
+        self.options
+            .iter_mut()
+            .find(|option| option.name.eq_ignore_ascii_case(name))
+            .ok_or_else(|| OptionError::UnknownOption(name.to_string()))
+    }
+}
+
+impl fmt::Display for Options {
+    /// fmt writes the `option` advertisement of each option, one per line.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for option in &self.options {
+            write!(f, "option name {} type ", option.name)?;
+
+            match &option.default {
+                Value::Spin(default) => write!(
+                    f,
+                    "spin default {default} min {} max {}",
+                    option.min, option.max
+                )?,
+                Value::Check(default) => write!(f, "check default {default}")?,
+                Value::String(default) => {
+                    // Empty strings are advertised as <empty>.
+                    let default = if default.is_empty() {
+                        "<empty>"
+                    } else {
+                        default
+                    };
+                    write!(f, "string default {default}")?
+                }
+                Value::Combo(default) => {
+                    write!(f, "combo default {default}")?;
+                    for var in &option.vars {
+                        write!(f, " var {var}")?;
+                    }
+                }
+                Value::Button => write!(f, "button")?,
+            }
+
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
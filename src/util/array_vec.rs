@@ -0,0 +1,162 @@
+// Copyright © 2023 Rak Laptudirm <rak@laptudirm.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::ops::{Deref, DerefMut};
+
+/// ArrayVec is a list with a fixed capacity of N elements, which are stored
+/// inline rather than on the heap. It is used wherever a list needs to be
+/// built without allocating, like the move lists of the move generator and
+/// the search. Pushing an element into a full ArrayVec panics.
+#[derive(Clone, Copy)]
+pub struct ArrayVec<T: Copy + Default, const N: usize> {
+    items: [T; N],
+    len: usize,
+}
+
+impl<T: Copy + Default, const N: usize> ArrayVec<T, N> {
+    /// CAPACITY is the maximum number of elements in the ArrayVec.
+    pub const CAPACITY: usize = N;
+
+    /// new creates an empty ArrayVec.
+    #[inline(always)]
+    pub fn new() -> ArrayVec<T, N> {
+        ArrayVec {
+            items: [T::default(); N],
+            len: 0,
+        }
+    }
+
+    /// push adds the given element to the end of the ArrayVec. It panics if
+    /// the ArrayVec is full.
+    #[inline(always)]
+    pub fn push(&mut self, item: T) {
+        assert!(self.len < N, "array vec: capacity of {N} exceeded");
+        self.items[self.len] = item;
+        self.len += 1;
+    }
+
+    /// pop removes the last element of the ArrayVec and returns it, if any.
+    #[inline(always)]
+    pub fn pop(&mut self) -> Option<T> {
+        self.len = self.len.checked_sub(1)?;
+        Some(self.items[self.len])
+    }
+
+    /// clear removes all of the elements of the ArrayVec.
+    #[inline(always)]
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    /// truncate removes all but the first len elements of the ArrayVec.
+    #[inline(always)]
+    pub fn truncate(&mut self, len: usize) {
+        self.len = self.len.min(len);
+    }
+
+    /// extend_from_slice adds the given elements to the end of the ArrayVec.
+    /// It panics if they don't fit in it.
+    pub fn extend_from_slice(&mut self, items: &[T]) {
+        let len = self.len + items.len();
+        assert!(len <= N, "array vec: capacity of {N} exceeded");
+        self.items[self.len..len].copy_from_slice(items);
+        self.len = len;
+    }
+
+    /// retain removes the elements of the ArrayVec for which the given
+    /// function returns false, keeping the order of the other elements.
+    pub fn retain(&mut self, mut keep: impl FnMut(&T) -> bool) {
+        let mut len = 0;
+        for index in 0..self.len {
+            if keep(&self.items[index]) {
+                self.items[len] = self.items[index];
+                len += 1;
+            }
+        }
+
+        self.len = len;
+    }
+}
+
+impl<T: Copy + Default, const N: usize> Default for ArrayVec<T, N> {
+    fn default() -> Self {
+        ArrayVec::new()
+    }
+}
+
+impl<T: Copy + Default, const N: usize> Deref for ArrayVec<T, N> {
+    type Target = [T];
+
+    #[inline(always)]
+    fn deref(&self) -> &[T] {
+        &self.items[..self.len]
+    }
+}
+
+impl<T: Copy + Default, const N: usize> DerefMut for ArrayVec<T, N> {
+    #[inline(always)]
+    fn deref_mut(&mut self) -> &mut [T] {
+        &mut self.items[..self.len]
+    }
+}
+
+impl<T: Copy + Default, const N: usize> IntoIterator for ArrayVec<T, N> {
+    type Item = T;
+    type IntoIter = std::iter::Take<std::array::IntoIter<T, N>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.items.into_iter().take(self.len)
+    }
+}
+
+impl<'a, T: Copy + Default, const N: usize> IntoIterator for &'a ArrayVec<T, N> {
+    type Item = &'a T;
+    type IntoIter = std::slice::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T: Copy + Default, const N: usize> FromIterator<T> for ArrayVec<T, N> {
+    /// from_iter collects the given elements into an ArrayVec. It panics if
+    /// there are more than N of them.
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut list = ArrayVec::new();
+        for item in iter {
+            list.push(item);
+        }
+
+        list
+    }
+}
+
+impl<T: Copy + Default, const N: usize> Extend<T> for ArrayVec<T, N> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for item in iter {
+            self.push(item);
+        }
+    }
+}
+
+impl<T: Copy + Default + PartialEq, const N: usize> PartialEq for ArrayVec<T, N> {
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+impl<T: Copy + Default + std::fmt::Debug, const N: usize> std::fmt::Debug for ArrayVec<T, N> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
@@ -1 +1,2 @@
+pub mod array_vec;
 pub mod type_macros;
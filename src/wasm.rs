@@ -0,0 +1,163 @@
+// Copyright © 2023 Rak Laptudirm <rak@laptudirm.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use wasm_bindgen::prelude::*;
+
+use crate::chess::{variant, Board, Color, Move, Outcome, FEN};
+use crate::uci::format_move;
+
+/// WasmBoard exposes a Board to JavaScript. Moves are passed to and from
+/// it as strings, either in the UCI long algebraic notation or in the
+/// Standard Algebraic Notation.
+#[wasm_bindgen]
+pub struct WasmBoard {
+    board: Board,
+}
+
+#[wasm_bindgen]
+impl WasmBoard {
+    /// new creates a WasmBoard from the given FEN string, whose game is
+    /// played by the variant with the given name, or by the standard rules
+    /// if no variant is given.
+    #[wasm_bindgen(constructor)]
+    pub fn new(fen: &str, variant: Option<String>) -> Result<WasmBoard, JsError> {
+        let variant = match variant {
+            Some(name) => {
+                variant::from_name(&name).ok_or_else(|| JsError::new("unknown variant"))?
+            }
+            None => &variant::Standard,
+        };
+
+        let board = Board::from_fen(fen, variant)?;
+        Ok(WasmBoard { board })
+    }
+
+    /// fen returns the FEN string of the current position.
+    pub fn fen(&self) -> String {
+        FEN::from(&self.board).to_string()
+    }
+
+    /// side_to_move returns the side to move, as `w` or `b`.
+    #[wasm_bindgen(js_name = sideToMove)]
+    pub fn side_to_move(&self) -> String {
+        self.board.side_to_move().to_string()
+    }
+
+    /// legal_moves returns the legal moves in the UCI notation.
+    #[wasm_bindgen(js_name = legalMoves)]
+    pub fn legal_moves(&mut self) -> Vec<String> {
+        self.board
+            .generate_legal_moves()
+            .into_iter()
+            .map(format_move)
+            .collect()
+    }
+
+    /// legal_moves_san returns the legal moves in the Standard Algebraic
+    /// Notation.
+    #[wasm_bindgen(js_name = legalMovesSan)]
+    pub fn legal_moves_san(&mut self) -> Vec<String> {
+        let moves = self.board.generate_legal_moves();
+        moves
+            .into_iter()
+            .map(|chessmove| self.board.to_san(chessmove))
+            .collect()
+    }
+
+    /// is_legal checks if the given move in the UCI notation is legal.
+    #[wasm_bindgen(js_name = isLegal)]
+    pub fn is_legal(&mut self, uci_move: &str) -> bool {
+        self.parse_uci(uci_move).is_some()
+    }
+
+    /// make_move makes the given move in the UCI notation.
+    #[wasm_bindgen(js_name = makeMove)]
+    pub fn make_move(&mut self, uci_move: &str) -> Result<(), JsError> {
+        let chessmove = self
+            .parse_uci(uci_move)
+            .ok_or_else(|| JsError::new("illegal move"))?;
+
+        self.board.make_move(chessmove);
+        Ok(())
+    }
+
+    /// make_san_move makes the given move in the Standard Algebraic Notation,
+    /// and returns it in the UCI notation.
+    #[wasm_bindgen(js_name = makeSanMove)]
+    pub fn make_san_move(&mut self, san: &str) -> Result<String, JsError> {
+        let chessmove = self
+            .board
+            .parse_san(san)
+            .ok_or_else(|| JsError::new("illegal move"))?;
+
+        self.board.make_move(chessmove);
+        Ok(format_move(chessmove))
+    }
+
+    /// undo_move takes back the last move, and returns it in the UCI
+    /// notation, or undefined if no moves have been made.
+    #[wasm_bindgen(js_name = undoMove)]
+    pub fn undo_move(&mut self) -> Option<String> {
+        let chessmove = self.board.last_move()?;
+        self.board.undo_move();
+        Some(format_move(chessmove))
+    }
+
+    /// to_san converts the given legal move from the UCI notation to the
+    /// Standard Algebraic Notation.
+    #[wasm_bindgen(js_name = toSan)]
+    pub fn to_san(&mut self, uci_move: &str) -> Result<String, JsError> {
+        let chessmove = self
+            .parse_uci(uci_move)
+            .ok_or_else(|| JsError::new("illegal move"))?;
+
+        Ok(self.board.to_san(chessmove))
+    }
+
+    /// to_figurine_san converts the given legal move from the UCI notation
+    /// to the Figurine Algebraic Notation, which writes the pieces of the
+    /// Standard Algebraic Notation as Unicode chess symbols.
+    #[wasm_bindgen(js_name = toFigurineSan)]
+    pub fn to_figurine_san(&mut self, uci_move: &str) -> Result<String, JsError> {
+        let chessmove = self
+            .parse_uci(uci_move)
+            .ok_or_else(|| JsError::new("illegal move"))?;
+
+        Ok(self.board.to_figurine_san(chessmove))
+    }
+
+    /// is_check checks if the side to move is in check.
+    #[wasm_bindgen(js_name = isCheck)]
+    pub fn is_check(&self) -> bool {
+        self.board.is_check()
+    }
+
+    /// outcome returns the result of the game as `1-0`, `0-1` or `1/2-1/2`
+    /// if it is over, or undefined if it isn't.
+    pub fn outcome(&mut self) -> Option<String> {
+        let result = match self.board.outcome()? {
+            Outcome::Win(Color::White) => "1-0",
+            Outcome::Win(_) => "0-1",
+            Outcome::Draw => "1/2-1/2",
+        };
+
+        Some(result.to_string())
+    }
+}
+
+impl WasmBoard {
+    /// parse_uci returns the legal move with the given UCI notation, if any.
+    fn parse_uci(&mut self, uci_move: &str) -> Option<Move> {
+        self.board.parse_uci(uci_move, false)
+    }
+}